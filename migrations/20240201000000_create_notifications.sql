@@ -0,0 +1,10 @@
+-- Audit log of every notification sent, regardless of delivery channel
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TEXT NOT NULL,  -- ISO 8601 formatted UTC timestamp (RFC3339)
+    title TEXT NOT NULL,
+    body TEXT NOT NULL
+);
+
+-- Index for efficient time-range queries
+CREATE INDEX IF NOT EXISTS idx_notifications_timestamp ON notifications(timestamp);
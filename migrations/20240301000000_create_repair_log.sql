@@ -0,0 +1,5 @@
+-- Days processed by the data repairer, so repeated runs can skip them
+CREATE TABLE IF NOT EXISTS repair_log (
+    date TEXT PRIMARY KEY,  -- Local calendar date (YYYY-MM-DD)
+    repaired_at TEXT NOT NULL  -- ISO 8601 formatted UTC timestamp (RFC3339)
+);
@@ -0,0 +1,15 @@
+-- Occupancy totals per hour, so weekday/hour averages don't rescan raw data.
+-- Hours are TIMESTAMPTZ buckets (date_trunc of the RFC3339 text timestamps).
+CREATE TABLE IF NOT EXISTS hourly_averages (
+    hour_start TIMESTAMPTZ PRIMARY KEY,
+    percentage_sum DOUBLE PRECISION NOT NULL,
+    sample_count BIGINT NOT NULL
+);
+
+-- Range of hours the cache is complete for, and when it was last refreshed
+CREATE TABLE IF NOT EXISTS hourly_averages_coverage (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    covered_from TIMESTAMPTZ NOT NULL,
+    covered_until TIMESTAMPTZ NOT NULL,
+    refreshed_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,24 @@
+-- Gym each record belongs to; existing single-gym data keeps the default id
+ALTER TABLE occupancy_logs ADD COLUMN IF NOT EXISTS gym_id TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_occupancy_logs_gym_timestamp ON occupancy_logs(gym_id, timestamp);
+
+-- The hourly averages cache is derived data: recreate it keyed by gym
+DROP TABLE IF EXISTS hourly_averages;
+DROP TABLE IF EXISTS hourly_averages_coverage;
+
+CREATE TABLE hourly_averages (
+    gym_id TEXT NOT NULL,
+    hour_start TIMESTAMPTZ NOT NULL,
+    percentage_sum DOUBLE PRECISION NOT NULL,
+    sample_count BIGINT NOT NULL,
+    PRIMARY KEY (gym_id, hour_start)
+);
+
+-- Range of hours the cache is complete for per gym, and when it was refreshed
+CREATE TABLE hourly_averages_coverage (
+    gym_id TEXT PRIMARY KEY,
+    covered_from TIMESTAMPTZ NOT NULL,
+    covered_until TIMESTAMPTZ NOT NULL,
+    refreshed_at TIMESTAMPTZ NOT NULL
+);
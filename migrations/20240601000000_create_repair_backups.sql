@@ -0,0 +1,17 @@
+-- Repair runs whose affected records were backed up, so they can be undone
+CREATE TABLE IF NOT EXISTS repair_runs (
+    repair_id BIGSERIAL PRIMARY KEY,
+    gym_id TEXT NOT NULL,
+    range_start TEXT NOT NULL,  -- First backed up timestamp (RFC3339, inclusive)
+    range_end TEXT NOT NULL,  -- Last backed up timestamp (RFC3339, inclusive)
+    created_at TEXT NOT NULL  -- ISO 8601 formatted UTC timestamp (RFC3339)
+);
+
+-- Occupancy records as they were before each repair run
+CREATE TABLE IF NOT EXISTS repair_backups (
+    repair_id BIGINT NOT NULL REFERENCES repair_runs(repair_id) ON DELETE CASCADE,
+    record_id BIGINT NOT NULL,  -- Original occupancy_logs id
+    timestamp TEXT NOT NULL,
+    percentage DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (repair_id, record_id)
+);
@@ -0,0 +1,12 @@
+-- One record per gym and timestamp: drop duplicates from restarts or a second
+-- daemon instance, keeping the latest inserted value
+DELETE FROM occupancy_logs older
+USING occupancy_logs newer
+WHERE older.gym_id = newer.gym_id
+  AND older.timestamp = newer.timestamp
+  AND older.id < newer.id;
+
+-- Replaces the plain index on the same columns
+DROP INDEX IF EXISTS idx_occupancy_logs_gym_timestamp;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_occupancy_logs_gym_timestamp_unique
+    ON occupancy_logs(gym_id, timestamp);
@@ -0,0 +1,5 @@
+-- Track repaired days per gym; existing entries belong to the default gym
+ALTER TABLE repair_log ADD COLUMN IF NOT EXISTS gym_id TEXT NOT NULL DEFAULT 'default';
+
+ALTER TABLE repair_log DROP CONSTRAINT IF EXISTS repair_log_pkey;
+ALTER TABLE repair_log ADD PRIMARY KEY (gym_id, date);
@@ -4,13 +4,18 @@ use chrono::{
     DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, Offset, TimeZone, Timelike,
     Utc,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::{db::HourlyAverage, schedule::GymSchedule, traits::Clock};
+use crate::{
+    db::{HourlyAverage, OccupancyLog},
+    schedule::GymSchedule,
+    traits::Clock,
+};
 
 // ==================== Comparison Types ====================
 
 /// Mode for comparing time periods.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ComparisonMode {
     /// Compare current week to previous week
     WeekOverWeek,
@@ -20,8 +25,21 @@ pub enum ComparisonMode {
     CustomRange,
 }
 
+impl ComparisonMode {
+    /// How far before the current period the baseline period starts.
+    ///
+    /// Returns `None` for custom ranges, where the caller picks both periods.
+    pub fn baseline_offset(&self) -> Option<ChronoDuration> {
+        match self {
+            ComparisonMode::WeekOverWeek => Some(ChronoDuration::weeks(1)),
+            ComparisonMode::MonthOverMonth => Some(ChronoDuration::weeks(4)),
+            ComparisonMode::CustomRange => None,
+        }
+    }
+}
+
 /// Direction of a trend.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum TrendDirection {
     /// Occupancy is increasing
     Increasing,
@@ -56,7 +74,7 @@ impl TrendDirection {
 }
 
 /// Comparison of occupancy for a specific hour between two periods.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HourlyComparison {
     /// Day of week (0=Monday, 6=Sunday)
     pub weekday: i32,
@@ -76,16 +94,24 @@ pub struct HourlyComparison {
     pub current_samples: i64,
 }
 
+/// Default stable band (in percent change) for a single hour.
+pub const HOURLY_STABLE_BAND: f64 = 5.0;
+
 impl HourlyComparison {
     /// Returns the trend direction for this hour.
     pub fn trend(&self) -> TrendDirection {
+        self.trend_with_band(HOURLY_STABLE_BAND)
+    }
+
+    /// Returns the trend direction, treating changes within ±`stable_band`
+    /// percent as stable.
+    pub fn trend_with_band(&self, stable_band: f64) -> TrendDirection {
         if self.baseline_samples < 2 || self.current_samples < 2 {
             return TrendDirection::Insufficient;
         }
-        // Use 5% as threshold for "stable"
-        if self.percent_change > 5.0 {
+        if self.percent_change > stable_band {
             TrendDirection::Increasing
-        } else if self.percent_change < -5.0 {
+        } else if self.percent_change < -stable_band {
             TrendDirection::Decreasing
         } else {
             TrendDirection::Stable
@@ -93,8 +119,26 @@ impl HourlyComparison {
     }
 }
 
+/// Sensitivity settings for the overall trend classification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrendThresholds {
+    /// Average percent change within ±this value counts as stable
+    pub stable_band: f64,
+    /// Minimum number of comparable slots needed for a verdict
+    pub min_valid_slots: usize,
+}
+
+impl Default for TrendThresholds {
+    fn default() -> Self {
+        Self {
+            stable_band: 3.0,
+            min_valid_slots: 5,
+        }
+    }
+}
+
 /// Comparison between two time periods.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PeriodComparison {
     /// Mode used for this comparison
     pub mode: ComparisonMode,
@@ -112,17 +156,29 @@ pub struct PeriodComparison {
     pub biggest_increases: Vec<(i32, i32, f64)>, // (weekday, hour, change%)
     /// Hours with biggest decreases
     pub biggest_decreases: Vec<(i32, i32, f64)>, // (weekday, hour, change%)
+    /// Sample-weighted day-level rollup of the hourly comparisons
+    pub per_weekday: Vec<(i32, f64, f64, f64)>, // (weekday, baseline, current, change%)
 }
 
 // ==================== Statistical Analysis ====================
 
 /// Statistical summary of occupancy data.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OccupancyStats {
     /// Arithmetic mean of occupancy
     pub mean: f64,
     /// Median occupancy
     pub median: f64,
+    /// 25th percentile
+    pub p25: f64,
+    /// 50th percentile (same as median)
+    pub p50: f64,
+    /// 75th percentile
+    pub p75: f64,
+    /// 90th percentile
+    pub p90: f64,
+    /// 95th percentile
+    pub p95: f64,
     /// Standard deviation
     pub std_dev: f64,
     /// Minimum occupancy
@@ -136,7 +192,7 @@ pub struct OccupancyStats {
 }
 
 /// Represents a peak or quiet period.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct TimePeriod {
     /// Day of week (0=Monday, 6=Sunday)
     pub weekday: i32,
@@ -149,7 +205,7 @@ pub struct TimePeriod {
 }
 
 /// Day-of-week analysis result.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DayAnalysis {
     /// Day of week (0=Monday, 6=Sunday)
     pub weekday: i32,
@@ -169,8 +225,31 @@ pub struct DayAnalysis {
     pub sample_count: i64,
 }
 
+/// Which days of the week an aggregate includes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeekdayFilter {
+    /// All seven days
+    #[default]
+    All,
+    /// Monday to Friday
+    Weekdays,
+    /// Saturday and Sunday
+    Weekends,
+}
+
+impl WeekdayFilter {
+    /// Whether `weekday` (0=Monday, 6=Sunday) is included.
+    pub fn includes(&self, weekday: i32) -> bool {
+        match self {
+            WeekdayFilter::All => true,
+            WeekdayFilter::Weekdays => weekday < 5,
+            WeekdayFilter::Weekends => weekday >= 5,
+        }
+    }
+}
+
 /// Generated insight about occupancy patterns.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Insight {
     /// Category of the insight
     pub category: InsightCategory,
@@ -185,7 +264,7 @@ pub struct Insight {
 }
 
 /// Categories of insights.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum InsightCategory {
     /// Trend-related insight
     Trend,
@@ -201,6 +280,63 @@ pub enum InsightCategory {
     Consistency,
 }
 
+/// End-of-week digest of occupancy, e.g. for a scheduled notification.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklySummary {
+    /// Sample-weighted average occupancy of the week
+    pub overall_avg: f64,
+    /// Day with the highest average occupancy
+    pub busiest_day: Option<DayAnalysis>,
+    /// Day with the lowest average occupancy
+    pub quietest_day: Option<DayAnalysis>,
+    /// Quietest window of consecutive quiet hours
+    pub best_window: Option<TimePeriod>,
+    /// Trend compared to the previous week
+    pub trend: TrendDirection,
+    /// Overall change compared to the previous week in percent
+    pub change_percent: f64,
+    /// Most important generated insight
+    pub top_insight: Option<Insight>,
+}
+
+/// Language for user-facing analytics text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+/// A prediction for an upcoming hour with a ±1 standard deviation band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetailedPrediction {
+    /// Start of the predicted hour
+    pub timestamp: DateTime<Utc>,
+    /// Predicted occupancy percentage
+    pub percentage: f64,
+    /// Standard deviation of the slot across the baseline
+    pub std_dev: f64,
+    /// Lower edge of the band, clamped to 0-100
+    pub lower: f64,
+    /// Upper edge of the band, clamped to 0-100
+    pub upper: f64,
+}
+
+/// A run of identical consecutive readings, which usually means the portal's
+/// sensor is stuck rather than the gym being perfectly steady.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StuckRun {
+    /// Timestamp of the first reading in the run
+    pub start: DateTime<Utc>,
+    /// Timestamp of the last reading in the run
+    pub end: DateTime<Utc>,
+    /// The repeated value
+    pub value: f64,
+    /// Number of readings in the run
+    pub length: usize,
+}
+
 pub fn midnight_utc(date: NaiveDate) -> DateTime<Utc> {
     date.and_hms_opt(0, 0, 0)
         .expect("midnight (0,0,0) is always valid")
@@ -219,6 +355,9 @@ pub fn midnight_local_as_utc(date: NaiveDate) -> DateTime<Utc> {
         .with_timezone(&Utc)
 }
 
+/// Number of hours ahead predicted when no horizon is given.
+pub const DEFAULT_PREDICTION_HORIZON_HOURS: i64 = 2;
+
 /// Calculate predictions using the system clock.
 /// This is a convenience wrapper for backwards compatibility.
 pub fn calculate_predictions(baseline: &[HourlyAverage]) -> Vec<(DateTime<Utc>, f64)> {
@@ -235,12 +374,65 @@ pub fn calculate_predictions_with_schedule(
 }
 
 /// Calculate predictions with a custom schedule and clock.
-/// This is the core implementation that allows for testability.
+/// This is a thin wrapper around the detailed variant that drops the band.
 pub fn calculate_predictions_with_clock<C: Clock>(
     baseline: &[HourlyAverage],
     schedule: &GymSchedule,
     clock: &C,
 ) -> Vec<(DateTime<Utc>, f64)> {
+    calculate_predictions_with_horizon(baseline, schedule, clock, DEFAULT_PREDICTION_HORIZON_HOURS)
+}
+
+/// Calculate predictions for the next `horizon_hours` hours.
+///
+/// Closed hours are skipped and slots without baseline data are left as gaps,
+/// so the result may contain fewer than `horizon_hours` entries.
+pub fn calculate_predictions_with_horizon<C: Clock + ?Sized>(
+    baseline: &[HourlyAverage],
+    schedule: &GymSchedule,
+    clock: &C,
+    horizon_hours: i64,
+) -> Vec<(DateTime<Utc>, f64)> {
+    calculate_predictions_detailed_with_horizon(baseline, schedule, clock, horizon_hours)
+        .into_iter()
+        .map(|p| (p.timestamp, p.percentage))
+        .collect()
+}
+
+/// Calculate predictions with uncertainty bands using the system clock.
+/// This is a convenience wrapper using the default schedule.
+pub fn calculate_predictions_detailed(baseline: &[HourlyAverage]) -> Vec<DetailedPrediction> {
+    calculate_predictions_detailed_with_clock(
+        baseline,
+        &GymSchedule::default(),
+        &crate::traits::SystemClock,
+    )
+}
+
+/// Calculate predictions with uncertainty bands using a custom schedule and
+/// clock.
+pub fn calculate_predictions_detailed_with_clock<C: Clock>(
+    baseline: &[HourlyAverage],
+    schedule: &GymSchedule,
+    clock: &C,
+) -> Vec<DetailedPrediction> {
+    calculate_predictions_detailed_with_horizon(
+        baseline,
+        schedule,
+        clock,
+        DEFAULT_PREDICTION_HORIZON_HOURS,
+    )
+}
+
+/// Calculate predictions with uncertainty bands for the next `horizon_hours`
+/// hours.
+/// This is the core implementation that allows for testability.
+pub fn calculate_predictions_detailed_with_horizon<C: Clock + ?Sized>(
+    baseline: &[HourlyAverage],
+    schedule: &GymSchedule,
+    clock: &C,
+    horizon_hours: i64,
+) -> Vec<DetailedPrediction> {
     let mut predictions = Vec::new();
     if baseline.is_empty() {
         return predictions;
@@ -248,7 +440,7 @@ pub fn calculate_predictions_with_clock<C: Clock>(
 
     let now = clock.now_utc();
 
-    for i in 1..=2 {
+    for i in 1..=horizon_hours {
         let target_time = now + ChronoDuration::hours(i);
         let target_hour = target_time.hour() as i32;
         let target_weekday = target_time.weekday().num_days_from_monday() as i32;
@@ -270,12 +462,103 @@ pub fn calculate_predictions_with_clock<C: Clock>(
                 .with_nanosecond(0)
                 .unwrap();
 
-            predictions.push((plot_time, avg.avg_percentage));
+            let std_dev = slot_std_dev(baseline, target_hour);
+            predictions.push(DetailedPrediction {
+                timestamp: plot_time,
+                percentage: avg.avg_percentage,
+                std_dev,
+                lower: (avg.avg_percentage - std_dev).clamp(0.0, 100.0),
+                upper: (avg.avg_percentage + std_dev).clamp(0.0, 100.0),
+            });
         }
     }
     predictions
 }
 
+/// Standard deviation of an hour slot across all weekdays in the baseline.
+///
+/// `HourlyAverage` only carries the mean per slot, so the spread of the same
+/// hour across days is used as the uncertainty estimate.
+fn slot_std_dev(baseline: &[HourlyAverage], hour: i32) -> f64 {
+    let values: Vec<f64> = baseline
+        .iter()
+        .filter(|h| h.hour == hour)
+        .map(|h| h.avg_percentage)
+        .collect();
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+// ==================== Quiet Time Probability ====================
+
+/// Probability that a reading falls below `goal`, assuming a normal
+/// distribution with the given mean and standard deviation truncated to the
+/// valid 0-100 range.
+///
+/// A zero standard deviation degenerates to a step at the mean.
+pub fn probability_below(mean: f64, std_dev: f64, goal: f64) -> f64 {
+    let goal = goal.clamp(0.0, 100.0);
+    if std_dev <= 0.0 {
+        return if mean < goal { 1.0 } else { 0.0 };
+    }
+
+    let cdf = |x: f64| normal_cdf((x - mean) / std_dev);
+    let (lower, upper) = (cdf(0.0), cdf(100.0));
+    let mass = upper - lower;
+    if mass <= f64::EPSILON {
+        // The whole distribution sits outside 0-100; fall back to the step
+        return if mean < goal { 1.0 } else { 0.0 };
+    }
+
+    ((cdf(goal) - lower) / mass).clamp(0.0, 1.0)
+}
+
+/// Per-slot probability of being below `goal`, indexed `[weekday][hour]` in
+/// UTC like the source data.
+///
+/// Slots without data are `None`. The spread of each hour across weekdays is
+/// used as the slot's standard deviation, as in the prediction bands.
+pub fn probability_heatmap(data: &[HourlyAverage], goal: f64) -> [[Option<f64>; 24]; 7] {
+    let mut grid = [[None; 24]; 7];
+
+    for slot in data {
+        let (Ok(weekday), Ok(hour)) = (usize::try_from(slot.weekday), usize::try_from(slot.hour))
+        else {
+            continue;
+        };
+        if weekday < 7 && hour < 24 {
+            let std_dev = slot_std_dev(data, slot.hour);
+            grid[weekday][hour] = Some(probability_below(slot.avg_percentage, std_dev, goal));
+        }
+    }
+
+    grid
+}
+
+/// Standard normal cumulative distribution function.
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Error function (Abramowitz & Stegun 7.1.26, max error ~1.5e-7).
+fn erf(x: f64) -> f64 {
+    let sign = x.signum();
+    let x = x.abs();
+
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
 /// Find the best time today using the system clock.
 /// This is a convenience wrapper for backwards compatibility.
 pub fn find_best_time_today(data: &[HourlyAverage]) -> Option<(i32, f64)> {
@@ -291,8 +574,55 @@ pub fn find_best_time_today_with_clock<C: Clock>(
     let now = clock.now_local();
     let today_idx = now.weekday().num_days_from_monday() as i32;
 
+    find_best_time_for_weekday_with_clock(data, today_idx, clock)
+}
+
+/// Find the best time today using the system clock, with the slot's sample
+/// count as `(hour, avg, sample_count)`.
+pub fn find_best_time_today_detailed(data: &[HourlyAverage]) -> Option<(i32, f64, i64)> {
+    find_best_time_today_detailed_with_clock(data, &crate::traits::SystemClock)
+}
+
+/// Find the best time today with a custom clock, with the slot's sample count.
+///
+/// A low sample count means the recommendation rests on few readings and
+/// should not be trusted much.
+pub fn find_best_time_today_detailed_with_clock<C: Clock>(
+    data: &[HourlyAverage],
+    clock: &C,
+) -> Option<(i32, f64, i64)> {
+    let today_idx = clock.now_local().weekday().num_days_from_monday() as i32;
+
+    best_slot_for_weekday(data, today_idx, clock)
+}
+
+/// Find the best time on an arbitrary local weekday (0=Monday) using the
+/// system clock.
+/// This is a convenience wrapper for backwards compatibility.
+pub fn find_best_time_for_weekday(data: &[HourlyAverage], weekday: i32) -> Option<(i32, f64)> {
+    find_best_time_for_weekday_with_clock(data, weekday, &crate::traits::SystemClock)
+}
+
+/// Find the best time on an arbitrary local weekday with a custom clock.
+///
+/// Applies the same UTC to local conversion as the "today" variant, using the
+/// clock's current offset, and returns the *local* hour.
+pub fn find_best_time_for_weekday_with_clock<C: Clock>(
+    data: &[HourlyAverage],
+    weekday: i32,
+    clock: &C,
+) -> Option<(i32, f64)> {
+    best_slot_for_weekday(data, weekday, clock).map(|(hour, avg, _)| (hour, avg))
+}
+
+/// Quietest slot on a local weekday as `(local hour, avg, sample_count)`.
+fn best_slot_for_weekday<C: Clock>(
+    data: &[HourlyAverage],
+    weekday: i32,
+    clock: &C,
+) -> Option<(i32, f64, i64)> {
     // Logic Fix: Data is UTC, but we need to find the best time in Local terms.
-    let offset_seconds = now.offset().fix().local_minus_utc();
+    let offset_seconds = clock.now_local().offset().fix().local_minus_utc();
     let seconds_per_week = 7 * 24 * 3600;
 
     data.iter()
@@ -309,11 +639,90 @@ pub fn find_best_time_today_with_clock<C: Clock>(
             let local_w = (wrapped_local / 3600) / 24;
             let local_h = (wrapped_local / 3600) % 24;
 
-            (local_w as i32, local_h as i32, d.avg_percentage)
+            (
+                local_w as i32,
+                local_h as i32,
+                d.avg_percentage,
+                d.sample_count,
+            )
         })
-        .filter(|(w, _, _)| *w == today_idx) // Filter for *Local* today
+        .filter(|(w, _, _, _)| *w == weekday) // Filter for the *Local* target day
         .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
-        .map(|(_, h, avg)| (h, avg)) // Return *Local* hour
+        .map(|(_, h, avg, samples)| (h, avg, samples)) // Return *Local* hour
+}
+
+/// Decide whether to send a heads-up about an upcoming quiet period.
+///
+/// Returns the first prediction later today (local time) below `threshold`
+/// once it is at most `lead_minutes` away. At most one alert is sent per day:
+/// nothing is returned if `last_alert` is already today's date.
+pub fn predictive_quiet_alert<C: Clock + ?Sized>(
+    predictions: &[(DateTime<Utc>, f64)],
+    threshold: f64,
+    lead_minutes: i64,
+    last_alert: Option<NaiveDate>,
+    clock: &C,
+) -> Option<(DateTime<Utc>, f64)> {
+    let now = clock.now_utc();
+    let today = clock.now_local().date_naive();
+    if last_alert == Some(today) {
+        return None;
+    }
+
+    let (time, value) = predictions
+        .iter()
+        .filter(|(t, _)| *t > now && t.with_timezone(&Local).date_naive() == today)
+        .find(|(_, v)| *v < threshold)?;
+
+    (*time - now <= ChronoDuration::minutes(lead_minutes)).then_some((*time, *value))
+}
+
+// ==================== Busyness Classification ====================
+
+/// How busy the gym is, relative to the configured occupancy thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum BusynessLevel {
+    /// Below the low threshold
+    Quiet,
+    /// From the low threshold up to (excluding) the high threshold
+    Moderate,
+    /// From the high threshold up to (excluding) 100%
+    Busy,
+    /// At or above capacity
+    Full,
+}
+
+impl BusynessLevel {
+    /// Classify a percentage. Each threshold belongs to the busier level.
+    pub fn from_percentage(pct: f64, low: f64, high: f64) -> Self {
+        if pct < low {
+            BusynessLevel::Quiet
+        } else if pct < high {
+            BusynessLevel::Moderate
+        } else if pct < 100.0 {
+            BusynessLevel::Busy
+        } else {
+            BusynessLevel::Full
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BusynessLevel::Quiet => "Not Busy",
+            BusynessLevel::Moderate => "Moderate",
+            BusynessLevel::Busy => "Crowded",
+            BusynessLevel::Full => "Full",
+        }
+    }
+
+    /// Suggested color: 0 = green, 1 = orange, 2 = red.
+    pub fn color_index(&self) -> usize {
+        match self {
+            BusynessLevel::Quiet => 0,
+            BusynessLevel::Moderate => 1,
+            BusynessLevel::Busy | BusynessLevel::Full => 2,
+        }
+    }
 }
 
 // ==================== Comparative Analytics ====================
@@ -378,16 +787,49 @@ pub fn build_hourly_comparisons(
     comparisons
 }
 
+/// Which of two hourly-average series is quieter in a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum QuieterSide {
+    First,
+    Second,
+    /// Both averages are equal
+    Tie,
+}
+
+/// Compare two series covering the same range slot by slot.
+///
+/// Only slots with data in both series are returned, as
+/// `(weekday, hour, quieter side)`, sorted by weekday and hour.
+pub fn quieter_per_slot(
+    first: &[HourlyAverage],
+    second: &[HourlyAverage],
+) -> Vec<(i32, i32, QuieterSide)> {
+    build_hourly_comparisons(first, second)
+        .into_iter()
+        .filter(|c| c.baseline_samples > 0 && c.current_samples > 0)
+        .map(|c| {
+            let side = match c.baseline_avg.partial_cmp(&c.current_avg) {
+                Some(std::cmp::Ordering::Less) => QuieterSide::First,
+                Some(std::cmp::Ordering::Greater) => QuieterSide::Second,
+                _ => QuieterSide::Tie,
+            };
+            (c.weekday, c.hour, side)
+        })
+        .collect()
+}
+
 /// Compare two time periods and generate a comprehensive comparison.
 ///
 /// # Arguments
 /// * `baseline` - Hourly averages from the baseline/previous period
 /// * `current` - Hourly averages from the current/comparison period
 /// * `mode` - The comparison mode used
+/// * `thresholds` - Trend sensitivity override, `None` for the defaults
 pub fn compare_periods(
     baseline: &[HourlyAverage],
     current: &[HourlyAverage],
     mode: ComparisonMode,
+    thresholds: Option<TrendThresholds>,
 ) -> PeriodComparison {
     let hourly_comparisons = build_hourly_comparisons(baseline, current);
 
@@ -420,7 +862,8 @@ pub fn compare_periods(
         0.0
     };
 
-    let overall_trend = determine_trend(&hourly_comparisons);
+    let overall_trend =
+        determine_trend_with_thresholds(&hourly_comparisons, thresholds.unwrap_or_default());
 
     // Find biggest changes
     let mut sorted_by_increase: Vec<_> = hourly_comparisons
@@ -444,6 +887,8 @@ pub fn compare_periods(
         .map(|c| (c.weekday, c.hour, c.percent_change))
         .collect();
 
+    let per_weekday = rollup_per_weekday(&hourly_comparisons);
+
     PeriodComparison {
         mode,
         baseline_overall_avg,
@@ -453,17 +898,126 @@ pub fn compare_periods(
         hourly_comparisons,
         biggest_increases,
         biggest_decreases,
+        per_weekday,
+    }
+}
+
+/// Combine hourly comparisons into one sample-weighted entry per weekday.
+fn rollup_per_weekday(comparisons: &[HourlyComparison]) -> Vec<(i32, f64, f64, f64)> {
+    // weekday -> (baseline sum, baseline samples, current sum, current samples)
+    let mut days: HashMap<i32, (f64, i64, f64, i64)> = HashMap::new();
+    for c in comparisons {
+        let entry = days.entry(c.weekday).or_insert((0.0, 0, 0.0, 0));
+        entry.0 += c.baseline_avg * c.baseline_samples as f64;
+        entry.1 += c.baseline_samples;
+        entry.2 += c.current_avg * c.current_samples as f64;
+        entry.3 += c.current_samples;
+    }
+
+    let mut rollup: Vec<(i32, f64, f64, f64)> = days
+        .into_iter()
+        .map(|(weekday, (b_sum, b_count, c_sum, c_count))| {
+            let baseline_avg = if b_count > 0 {
+                b_sum / b_count as f64
+            } else {
+                0.0
+            };
+            let current_avg = if c_count > 0 {
+                c_sum / c_count as f64
+            } else {
+                0.0
+            };
+            let percent_change = if baseline_avg > 0.0 {
+                (current_avg - baseline_avg) / baseline_avg * 100.0
+            } else if current_avg > 0.0 {
+                100.0
+            } else {
+                0.0
+            };
+            (weekday, baseline_avg, current_avg, percent_change)
+        })
+        .collect();
+    rollup.sort_by_key(|(weekday, ..)| *weekday);
+    rollup
+}
+
+/// Aggregate raw logs into (weekday, hour) averages keyed in UTC.
+///
+/// Mirrors `Database::get_averages_range` so in-memory and database
+/// aggregates can be compared directly. Logs with unparseable timestamps are
+/// skipped.
+pub fn aggregate_hourly(logs: &[OccupancyLog]) -> Vec<HourlyAverage> {
+    let mut slots: HashMap<(i32, i32), (f64, i64)> = HashMap::new();
+
+    for log in logs {
+        if let Some(dt) = log.datetime() {
+            let key = (dt.weekday().num_days_from_monday() as i32, dt.hour() as i32);
+            let entry = slots.entry(key).or_insert((0.0, 0));
+            entry.0 += log.percentage;
+            entry.1 += 1;
+        }
     }
+
+    let mut averages: Vec<HourlyAverage> = slots
+        .into_iter()
+        .map(|((weekday, hour), (sum, count))| HourlyAverage {
+            weekday,
+            hour,
+            avg_percentage: sum / count as f64,
+            sample_count: count,
+        })
+        .collect();
+    averages.sort_by_key(|h| (h.weekday, h.hour));
+    averages
+}
+
+/// Compare the week starting at `current_start` against the baseline week
+/// selected by `mode`.
+///
+/// WeekOverWeek uses the week directly before, MonthOverMonth the same week
+/// four weeks earlier, so each slot is compared with the same weekday and hour
+/// in the intended baseline week. CustomRange falls back to the previous week;
+/// use `compare_periods` directly for arbitrary ranges.
+pub fn compare_periods_from_logs(
+    logs: &[OccupancyLog],
+    current_start: DateTime<Utc>,
+    mode: ComparisonMode,
+) -> PeriodComparison {
+    let week = ChronoDuration::weeks(1);
+    let baseline_start = current_start - mode.baseline_offset().unwrap_or(week);
+
+    let logs_in = |start: DateTime<Utc>| -> Vec<OccupancyLog> {
+        logs.iter()
+            .filter(|l| {
+                l.datetime()
+                    .is_some_and(|dt| dt >= start && dt < start + week)
+            })
+            .cloned()
+            .collect()
+    };
+
+    let baseline = aggregate_hourly(&logs_in(baseline_start));
+    let current = aggregate_hourly(&logs_in(current_start));
+
+    compare_periods(&baseline, &current, mode, None)
 }
 
 /// Determine the overall trend direction from hourly comparisons.
 pub fn determine_trend(comparisons: &[HourlyComparison]) -> TrendDirection {
+    determine_trend_with_thresholds(comparisons, TrendThresholds::default())
+}
+
+/// Determine the overall trend direction with custom sensitivity.
+pub fn determine_trend_with_thresholds(
+    comparisons: &[HourlyComparison],
+    thresholds: TrendThresholds,
+) -> TrendDirection {
     let valid_comparisons: Vec<_> = comparisons
         .iter()
         .filter(|c| c.baseline_samples >= 2 && c.current_samples >= 2)
         .collect();
 
-    if valid_comparisons.len() < 5 {
+    if valid_comparisons.len() < thresholds.min_valid_slots.max(1) {
         return TrendDirection::Insufficient;
     }
 
@@ -473,10 +1027,9 @@ pub fn determine_trend(comparisons: &[HourlyComparison]) -> TrendDirection {
         .sum::<f64>()
         / valid_comparisons.len() as f64;
 
-    // Use 3% as threshold for overall trend
-    if avg_change > 3.0 {
+    if avg_change > thresholds.stable_band {
         TrendDirection::Increasing
-    } else if avg_change < -3.0 {
+    } else if avg_change < -thresholds.stable_band {
         TrendDirection::Decreasing
     } else {
         TrendDirection::Stable
@@ -498,7 +1051,7 @@ pub fn calculate_stats(data: &[HourlyAverage]) -> Option<OccupancyStats> {
 
     let mut sorted = percentages.clone();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    let median = if n % 2 == 0 {
+    let median = if n.is_multiple_of(2) {
         (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
     } else {
         sorted[n / 2]
@@ -515,6 +1068,11 @@ pub fn calculate_stats(data: &[HourlyAverage]) -> Option<OccupancyStats> {
     Some(OccupancyStats {
         mean,
         median,
+        p25: percentile(&sorted, 25.0),
+        p50: percentile(&sorted, 50.0),
+        p75: percentile(&sorted, 75.0),
+        p90: percentile(&sorted, 90.0),
+        p95: percentile(&sorted, 95.0),
         std_dev,
         min,
         max,
@@ -523,6 +1081,56 @@ pub fn calculate_stats(data: &[HourlyAverage]) -> Option<OccupancyStats> {
     })
 }
 
+/// Calculate statistics weighting each slot by its `sample_count`.
+///
+/// Mean, variance, standard deviation and coefficient of variation use the
+/// sample counts as weights, so well-sampled slots dominate. Min, max, median
+/// and percentiles are the same as in [`calculate_stats`]. Falls back to the
+/// unweighted stats if no slot has any samples.
+pub fn calculate_stats_weighted(data: &[HourlyAverage]) -> Option<OccupancyStats> {
+    let mut stats = calculate_stats(data)?;
+
+    let total_weight: f64 = data.iter().map(|h| h.sample_count.max(0) as f64).sum();
+    if total_weight == 0.0 {
+        return Some(stats);
+    }
+
+    let mean = data
+        .iter()
+        .map(|h| h.avg_percentage * h.sample_count.max(0) as f64)
+        .sum::<f64>()
+        / total_weight;
+    let variance = data
+        .iter()
+        .map(|h| (h.avg_percentage - mean).powi(2) * h.sample_count.max(0) as f64)
+        .sum::<f64>()
+        / total_weight;
+
+    stats.mean = mean;
+    stats.std_dev = variance.sqrt();
+    stats.coefficient_of_variation = if mean > 0.0 {
+        stats.std_dev / mean
+    } else {
+        0.0
+    };
+    Some(stats)
+}
+
+/// Percentile of already-sorted values, linearly interpolating between ranks.
+///
+/// Uses the midpoint (Hazen) definition, where each value sits at the center
+/// of its rank, so the 50th percentile always equals the median. `p` is in
+/// the range 0-100 and the caller must ensure `sorted` is non-empty.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let max_rank = (sorted.len() - 1) as f64;
+    let rank = ((p / 100.0) * sorted.len() as f64 - 0.5).clamp(0.0, max_rank);
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
 /// Analyze patterns for each day of the week.
 pub fn analyze_days(data: &[HourlyAverage]) -> Vec<DayAnalysis> {
     const DAY_NAMES: [&str; 7] = [
@@ -572,13 +1180,79 @@ pub fn analyze_days(data: &[HourlyAverage]) -> Vec<DayAnalysis> {
         .collect()
 }
 
+/// Sample-weighted average occupancy per hour of day across all weekdays.
+///
+/// Collapses the 168 weekday/hour slots into a "typical day" of up to 24
+/// `(hour, average)` points, sorted by hour. Hours without samples are left
+/// out.
+pub fn average_by_hour(data: &[HourlyAverage]) -> Vec<(i32, f64)> {
+    average_by_hour_with_filter(data, WeekdayFilter::All)
+}
+
+/// Like [`average_by_hour`], but only over the days included by `filter`,
+/// e.g. to plot weekdays and weekends separately.
+pub fn average_by_hour_with_filter(
+    data: &[HourlyAverage],
+    filter: WeekdayFilter,
+) -> Vec<(i32, f64)> {
+    let mut sums = [(0.0, 0i64); 24];
+    for h in data
+        .iter()
+        .filter(|h| filter.includes(h.weekday) && (0..24).contains(&h.hour))
+    {
+        sums[h.hour as usize].0 += h.avg_percentage * h.sample_count as f64;
+        sums[h.hour as usize].1 += h.sample_count;
+    }
+
+    sums.iter()
+        .enumerate()
+        .filter(|(_, (_, count))| *count > 0)
+        .map(|(hour, (sum, count))| (hour as i32, sum / *count as f64))
+        .collect()
+}
+
+/// Infer each day's opening hours from when the gym actually has visitors.
+///
+/// Returns `(weekday, open_hour, close_hour)` from the first and last hour
+/// whose average occupancy exceeds `min_occupancy`, with `close_hour`
+/// exclusive like in `ScheduleHours`. Hours are in the same zone as `data`.
+/// Days without such an hour are left out. Comparing the result to the
+/// configured schedule shows when it has gone stale.
+pub fn infer_open_hours(data: &[HourlyAverage], min_occupancy: f64) -> Vec<(i32, i32, i32)> {
+    (0..7)
+        .filter_map(|weekday| {
+            let busy_hours = data
+                .iter()
+                .filter(|h| h.weekday == weekday && h.avg_percentage > min_occupancy)
+                .map(|h| h.hour);
+            let (open, last) = busy_hours.fold(None, |range, hour| match range {
+                None => Some((hour, hour)),
+                Some((open, last)) => Some((hour.min(open), hour.max(last))),
+            })?;
+            Some((weekday, open, last + 1))
+        })
+        .collect()
+}
+
+/// Default minimum samples for a slot to count as a peak or quiet hour.
+pub const DEFAULT_MIN_SAMPLES: i64 = 2;
+
 /// Find peak hours across the week.
 ///
 /// Returns the top N hours with highest average occupancy.
 pub fn find_peak_hours(data: &[HourlyAverage], top_n: usize) -> Vec<(i32, i32, f64)> {
+    find_peak_hours_with_min(data, top_n, DEFAULT_MIN_SAMPLES)
+}
+
+/// Find peak hours, ignoring slots with fewer than `min_samples` samples.
+pub fn find_peak_hours_with_min(
+    data: &[HourlyAverage],
+    top_n: usize,
+    min_samples: i64,
+) -> Vec<(i32, i32, f64)> {
     let mut sorted: Vec<_> = data
         .iter()
-        .filter(|h| h.sample_count >= 2)
+        .filter(|h| h.sample_count >= min_samples)
         .map(|h| (h.weekday, h.hour, h.avg_percentage))
         .collect();
 
@@ -587,13 +1261,61 @@ pub fn find_peak_hours(data: &[HourlyAverage], top_n: usize) -> Vec<(i32, i32, f
     sorted
 }
 
+/// Default z-score beyond which a slot is reported as unusual.
+pub const DEFAULT_ANOMALY_Z_THRESHOLD: f64 = 2.5;
+
+/// Find hours whose occupancy is unusually high or low.
+///
+/// Computes the mean and standard deviation across all slots with at least 2
+/// samples and returns `(weekday, hour, z_score)` for every slot deviating by
+/// more than `z_threshold` standard deviations, most extreme first.
+pub fn detect_anomalies(data: &[HourlyAverage], z_threshold: f64) -> Vec<(i32, i32, f64)> {
+    let slots: Vec<_> = data.iter().filter(|h| h.sample_count >= 2).collect();
+    if slots.len() < 2 {
+        return Vec::new();
+    }
+
+    let n = slots.len() as f64;
+    let mean = slots.iter().map(|h| h.avg_percentage).sum::<f64>() / n;
+    let variance = slots
+        .iter()
+        .map(|h| (h.avg_percentage - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let std_dev = variance.sqrt();
+    if std_dev == 0.0 {
+        return Vec::new();
+    }
+
+    let mut anomalies: Vec<_> = slots
+        .iter()
+        .map(|h| (h.weekday, h.hour, (h.avg_percentage - mean) / std_dev))
+        .filter(|(_, _, z)| z.abs() > z_threshold)
+        .collect();
+
+    anomalies.sort_by(|a, b| b.2.abs().partial_cmp(&a.2.abs()).unwrap());
+    anomalies
+}
+
 /// Find quiet hours across the week.
 ///
 /// Returns the top N hours with lowest average occupancy.
 pub fn find_quiet_hours(data: &[HourlyAverage], top_n: usize) -> Vec<(i32, i32, f64)> {
+    find_quiet_hours_with_min(data, top_n, DEFAULT_MIN_SAMPLES)
+}
+
+/// Find quiet hours, ignoring slots with fewer than `min_samples` samples.
+///
+/// A slot with a single stray reading, e.g. early in the morning, would
+/// otherwise easily win "quietest".
+pub fn find_quiet_hours_with_min(
+    data: &[HourlyAverage],
+    top_n: usize,
+    min_samples: i64,
+) -> Vec<(i32, i32, f64)> {
     let mut sorted: Vec<_> = data
         .iter()
-        .filter(|h| h.sample_count >= 2 && h.avg_percentage > 0.0)
+        .filter(|h| h.sample_count >= min_samples && h.avg_percentage > 0.0)
         .map(|h| (h.weekday, h.hour, h.avg_percentage))
         .collect();
 
@@ -602,27 +1324,135 @@ pub fn find_quiet_hours(data: &[HourlyAverage], top_n: usize) -> Vec<(i32, i32,
     sorted
 }
 
+/// Percentage points an alternative slot must be below the preferred one to
+/// be recommended.
+pub const ALTERNATIVE_SLOT_MIN_DROP: f64 = 10.0;
+
+/// Suggest a quieter slot close to a usual `preferred` `(weekday, hour)`.
+///
+/// Candidates are up to 2 hours earlier or later, on the same day or the
+/// days before and after. Returns the nearest in time of those at least
+/// `ALTERNATIVE_SLOT_MIN_DROP` points quieter as `(weekday, hour, occupancy)`,
+/// the quieter one on a tie, or `None` if the preferred slot is already good
+/// or has too few samples to judge.
+pub fn recommend_alternative_slot(
+    current: &[HourlyAverage],
+    preferred: (i32, i32),
+) -> Option<(i32, i32, f64)> {
+    const WEEK_HOURS: i32 = 7 * 24;
+    let slot_index = |weekday: i32, hour: i32| weekday * 24 + hour;
+    let preferred_index = slot_index(preferred.0, preferred.1);
+
+    let preferred_pct = current
+        .iter()
+        .find(|h| (h.weekday, h.hour) == preferred && h.sample_count >= DEFAULT_MIN_SAMPLES)?
+        .avg_percentage;
+
+    current
+        .iter()
+        .filter(|h| h.sample_count >= DEFAULT_MIN_SAMPLES)
+        .filter(|h| h.avg_percentage <= preferred_pct - ALTERNATIVE_SLOT_MIN_DROP)
+        .filter_map(|h| {
+            // Signed distance in hours, wrapping around the week
+            let offset = (slot_index(h.weekday, h.hour) - preferred_index + WEEK_HOURS / 2)
+                .rem_euclid(WEEK_HOURS)
+                - WEEK_HOURS / 2;
+            let days = (offset as f64 / 24.0).round() as i32;
+            let hours = offset - days * 24;
+            (days.abs() <= 1 && hours.abs() <= 2).then_some((offset.abs(), h))
+        })
+        .min_by(|(a_offset, a), (b_offset, b)| {
+            a_offset
+                .cmp(b_offset)
+                .then(a.avg_percentage.total_cmp(&b.avg_percentage))
+        })
+        .map(|(_, h)| (h.weekday, h.hour, h.avg_percentage))
+}
+
+/// Occupancy at or below which an hour counts towards a quiet window.
+const QUIET_WINDOW_THRESHOLD: f64 = 40.0;
+
+/// Minimum length of a reported quiet window in hours.
+const QUIET_WINDOW_MIN_HOURS: usize = 2;
+
 /// Find continuous quiet windows (consecutive hours below threshold).
 pub fn find_quiet_windows(
     data: &[HourlyAverage],
     threshold: f64,
     min_hours: usize,
 ) -> Vec<TimePeriod> {
-    let mut windows = Vec::new();
-
-    for weekday in 0i32..7 {
-        let mut day_hours: Vec<_> = data
-            .iter()
-            .filter(|h| h.weekday == weekday && h.sample_count >= 2)
-            .collect();
-        day_hours.sort_by_key(|h| h.hour);
+    let mut windows = find_windows(data, min_hours, |pct| pct <= threshold);
+    windows.sort_by(|a, b| a.avg_occupancy.partial_cmp(&b.avg_occupancy).unwrap());
+    windows
+}
 
-        let mut window_start: Option<i32> = None;
+/// Find quiet windows in local time, e.g. for "best workout windows".
+///
+/// The UTC hourly averages are shifted to local weekday/hour using the clock's
+/// current offset (like [`find_best_time_for_weekday_with_clock`]) before
+/// grouping, so windows crossing UTC midnight stay in one piece and
+/// `start_hour`/`end_hour` are local hours.
+pub fn find_quiet_windows_local<C: Clock>(
+    data: &[HourlyAverage],
+    threshold: f64,
+    min_hours: usize,
+    clock: &C,
+) -> Vec<TimePeriod> {
+    let offset_seconds = clock.now_local().offset().fix().local_minus_utc() as i64;
+    find_quiet_windows(&shift_to_local(data, offset_seconds), threshold, min_hours)
+}
+
+/// Shift UTC hourly averages by `offset_seconds`, wrapping around the week.
+fn shift_to_local(data: &[HourlyAverage], offset_seconds: i64) -> Vec<HourlyAverage> {
+    let seconds_per_week = 7 * 24 * 3600;
+
+    data.iter()
+        .map(|d| {
+            let utc_seconds = (d.weekday as i64 * 24 + d.hour as i64) * 3600;
+            let local_hours = (utc_seconds + offset_seconds).rem_euclid(seconds_per_week) / 3600;
+            HourlyAverage {
+                weekday: (local_hours / 24) as i32,
+                hour: (local_hours % 24) as i32,
+                ..d.clone()
+            }
+        })
+        .collect()
+}
+
+/// Find sustained busy windows (consecutive hours above threshold).
+///
+/// The busiest window comes first.
+pub fn find_peak_windows(
+    data: &[HourlyAverage],
+    threshold: f64,
+    min_hours: usize,
+) -> Vec<TimePeriod> {
+    let mut windows = find_windows(data, min_hours, |pct| pct > threshold);
+    windows.sort_by(|a, b| b.avg_occupancy.partial_cmp(&a.avg_occupancy).unwrap());
+    windows
+}
+
+/// Group consecutive hours matching `in_window` into windows per weekday.
+fn find_windows(
+    data: &[HourlyAverage],
+    min_hours: usize,
+    in_window: impl Fn(f64) -> bool,
+) -> Vec<TimePeriod> {
+    let mut windows = Vec::new();
+
+    for weekday in 0i32..7 {
+        let mut day_hours: Vec<_> = data
+            .iter()
+            .filter(|h| h.weekday == weekday && h.sample_count >= 2)
+            .collect();
+        day_hours.sort_by_key(|h| h.hour);
+
+        let mut window_start: Option<i32> = None;
         let mut window_sum = 0.0;
         let mut window_count = 0;
 
         for h in &day_hours {
-            if h.avg_percentage <= threshold {
+            if in_window(h.avg_percentage) {
                 if window_start.is_none() {
                     window_start = Some(h.hour);
                     window_sum = 0.0;
@@ -631,69 +1461,269 @@ pub fn find_quiet_windows(
                 window_sum += h.avg_percentage;
                 window_count += 1;
             } else {
-                if let Some(start) = window_start {
-                    if window_count >= min_hours {
-                        windows.push(TimePeriod {
-                            weekday,
-                            start_hour: start,
-                            end_hour: h.hour,
-                            avg_occupancy: window_sum / window_count as f64,
-                        });
-                    }
+                if let Some(start) = window_start
+                    && window_count >= min_hours
+                {
+                    windows.push(TimePeriod {
+                        weekday,
+                        start_hour: start,
+                        end_hour: h.hour,
+                        avg_occupancy: window_sum / window_count as f64,
+                    });
                 }
                 window_start = None;
             }
         }
 
         // Handle window extending to end of day
-        if let Some(start) = window_start {
-            if window_count >= min_hours {
-                windows.push(TimePeriod {
-                    weekday,
-                    start_hour: start,
-                    end_hour: 24,
-                    avg_occupancy: window_sum / window_count as f64,
+        if let Some(start) = window_start
+            && window_count >= min_hours
+        {
+            windows.push(TimePeriod {
+                weekday,
+                start_hour: start,
+                end_hour: 24,
+                avg_occupancy: window_sum / window_count as f64,
+            });
+        }
+    }
+
+    windows
+}
+
+/// Minimum number of days on each side of a level shift.
+pub const LEVEL_SHIFT_MIN_DAYS: usize = 7;
+
+/// Minimum change in daily mean occupancy (percentage points) for a shift.
+pub const LEVEL_SHIFT_MIN_PERCENT: f64 = 5.0;
+
+/// Find a sustained change in the overall occupancy level.
+///
+/// Splits the series of daily means (UTC dates, closed-hour zeros excluded) at
+/// every point leaving at least [`LEVEL_SHIFT_MIN_DAYS`] on both sides and
+/// picks the split with the largest two-sample t statistic. Returns the first
+/// day of the new level and the change in mean, if the change is at least
+/// [`LEVEL_SHIFT_MIN_PERCENT`] and clearly larger than the day-to-day noise.
+pub fn detect_level_shift(logs: &[OccupancyLog]) -> Option<(NaiveDate, f64)> {
+    const MIN_T_STATISTIC: f64 = 5.0;
+
+    let mut days: HashMap<NaiveDate, (f64, usize)> = HashMap::new();
+    for log in logs.iter().filter(|l| l.percentage != 0.0) {
+        if let Some(dt) = log.datetime() {
+            let entry = days.entry(dt.date_naive()).or_insert((0.0, 0));
+            entry.0 += log.percentage;
+            entry.1 += 1;
+        }
+    }
+
+    let mut daily: Vec<(NaiveDate, f64)> = days
+        .into_iter()
+        .map(|(date, (sum, count))| (date, sum / count as f64))
+        .collect();
+    daily.sort_by_key(|(date, _)| *date);
+
+    let n = daily.len();
+    if n < 2 * LEVEL_SHIFT_MIN_DAYS {
+        return None;
+    }
+
+    let mean_and_ss = |values: &[(NaiveDate, f64)]| {
+        let mean = values.iter().map(|(_, v)| v).sum::<f64>() / values.len() as f64;
+        let ss = values.iter().map(|(_, v)| (v - mean).powi(2)).sum::<f64>();
+        (mean, ss)
+    };
+
+    let mut best: Option<(usize, f64, f64)> = None;
+    for split in LEVEL_SHIFT_MIN_DAYS..=n - LEVEL_SHIFT_MIN_DAYS {
+        let (before, after) = daily.split_at(split);
+        let (mean_before, ss_before) = mean_and_ss(before);
+        let (mean_after, ss_after) = mean_and_ss(after);
+        let shift = mean_after - mean_before;
+
+        let pooled_std = ((ss_before + ss_after) / (n - 2) as f64).sqrt();
+        let std_err = pooled_std * (1.0 / before.len() as f64 + 1.0 / after.len() as f64).sqrt();
+        let t = if std_err > 0.0 {
+            shift.abs() / std_err
+        } else if shift != 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+
+        if best.is_none_or(|(_, _, best_t)| t > best_t) {
+            best = Some((split, shift, t));
+        }
+    }
+
+    let (split, shift, t) = best?;
+    (shift.abs() >= LEVEL_SHIFT_MIN_PERCENT && t >= MIN_T_STATISTIC)
+        .then(|| (daily[split].0, shift))
+}
+
+/// Centered moving average of a reading series.
+///
+/// Each point is replaced by the mean of all readings within
+/// `window_minutes / 2` on either side of it. Only readings actually present
+/// are averaged, so gaps in the data don't pull the value towards zero and
+/// points near either end use the part of the window that is available.
+pub fn smooth_moving_average(
+    logs: &[OccupancyLog],
+    window_minutes: i64,
+) -> Vec<(DateTime<Utc>, f64)> {
+    let mut points: Vec<(DateTime<Utc>, f64)> = logs
+        .iter()
+        .filter_map(|l| l.datetime().map(|d| (d, l.percentage)))
+        .collect();
+    points.sort_by_key(|(d, _)| *d);
+
+    let half_window = ChronoDuration::seconds(window_minutes.max(0) * 30);
+    let mut prefix = Vec::with_capacity(points.len() + 1);
+    prefix.push(0.0);
+    for (_, v) in &points {
+        prefix.push(prefix.last().unwrap() + v);
+    }
+
+    let (mut lo, mut hi) = (0, 0);
+    points
+        .iter()
+        .map(|&(time, _)| {
+            while points[lo].0 < time - half_window {
+                lo += 1;
+            }
+            while hi < points.len() && points[hi].0 <= time + half_window {
+                hi += 1;
+            }
+            (time, (prefix[hi] - prefix[lo]) / (hi - lo) as f64)
+        })
+        .collect()
+}
+
+/// Find runs of at least `min_run_length` identical consecutive readings.
+///
+/// Logs are expected in timestamp order. Runs of 0% are ignored since those are
+/// produced by the repairer for closed hours.
+pub fn detect_stuck_runs(logs: &[OccupancyLog], min_run_length: usize) -> Vec<StuckRun> {
+    let mut runs = Vec::new();
+    let mut current: Option<StuckRun> = None;
+
+    let mut close_run = |run: Option<StuckRun>| {
+        if let Some(run) = run
+            && run.length >= min_run_length
+            && run.value != 0.0
+        {
+            runs.push(run);
+        }
+    };
+
+    for log in logs {
+        let Some(dt) = log.datetime() else {
+            continue;
+        };
+
+        match current.as_mut() {
+            Some(run) if run.value == log.percentage => {
+                run.end = dt;
+                run.length += 1;
+            }
+            _ => {
+                close_run(current.take());
+                current = Some(StuckRun {
+                    start: dt,
+                    end: dt,
+                    value: log.percentage,
+                    length: 1,
                 });
             }
         }
     }
+    close_run(current);
 
-    windows.sort_by(|a, b| a.avg_occupancy.partial_cmp(&b.avg_occupancy).unwrap());
-    windows
+    runs
 }
 
 // ==================== Insight Generation ====================
 
+/// Build a warning insight for a suspected stuck sensor.
+pub fn stuck_sensor_insight(run: &StuckRun) -> Insight {
+    let local_start = run.start.with_timezone(&Local);
+    let local_end = run.end.with_timezone(&Local);
+
+    Insight {
+        category: InsightCategory::Anomaly,
+        importance: 5,
+        title: "Possible stuck sensor".to_string(),
+        description: format!(
+            "The portal reported exactly {:.1}% for {} readings in a row ({} to {}). Analytics \
+             for this period may not reflect real occupancy.",
+            run.value,
+            run.length,
+            local_start.format("%a %H:%M"),
+            local_end.format("%a %H:%M")
+        ),
+        data: None,
+    }
+}
+
+/// Serialize insights to a JSON array for external consumers.
+///
+/// Categories are written as their variant names, e.g. `"QuietTime"`.
+pub fn insights_to_json(insights: &[Insight]) -> serde_json::Result<String> {
+    serde_json::to_string(insights)
+}
+
 /// Generate human-readable insights from occupancy data.
 ///
 /// Analyzes the data and produces actionable insights about patterns,
-/// trends, and recommendations.
+/// trends, and recommendations. Text is in English; use
+/// [`generate_insights_lang`] for other languages.
 pub fn generate_insights(
     current: &[HourlyAverage],
     baseline: Option<&[HourlyAverage]>,
 ) -> Vec<Insight> {
+    generate_insights_lang(current, baseline, Language::English)
+}
+
+/// Generate insights with titles and descriptions in the given language.
+pub fn generate_insights_lang(
+    current: &[HourlyAverage],
+    baseline: Option<&[HourlyAverage]>,
+    lang: Language,
+) -> Vec<Insight> {
+    use Language::{English, German};
+
     let mut insights = Vec::new();
 
     // Get statistics
     if let Some(stats) = calculate_stats(current) {
         // Consistency insight
-        let consistency_level = if stats.coefficient_of_variation < 0.3 {
-            "very consistent"
-        } else if stats.coefficient_of_variation < 0.5 {
-            "moderately consistent"
-        } else {
-            "highly variable"
+        let consistency_level = match (lang, stats.coefficient_of_variation) {
+            (English, cv) if cv < 0.3 => "very consistent",
+            (English, cv) if cv < 0.5 => "moderately consistent",
+            (English, _) => "highly variable",
+            (German, cv) if cv < 0.3 => "sehr gleichmäßig",
+            (German, cv) if cv < 0.5 => "mäßig gleichmäßig",
+            (German, _) => "stark schwankend",
         };
 
         insights.push(Insight {
             category: InsightCategory::Consistency,
             importance: 2,
-            title: format!("Occupancy is {}", consistency_level),
-            description: format!(
-                "Average occupancy is {:.1}% with a standard deviation of {:.1}%. Range: {:.1}% \
-                 to {:.1}%.",
-                stats.mean, stats.std_dev, stats.min, stats.max
-            ),
+            title: match lang {
+                English => format!("Occupancy is {}", consistency_level),
+                German => format!("Auslastung ist {}", consistency_level),
+            },
+            description: match lang {
+                English => format!(
+                    "Average occupancy is {:.1}% with a standard deviation of {:.1}%. Range: \
+                     {:.1}% to {:.1}%.",
+                    stats.mean, stats.std_dev, stats.min, stats.max
+                ),
+                German => format!(
+                    "Durchschnittliche Auslastung {:.1}% bei einer Standardabweichung von {:.1}%. \
+                     Bereich: {:.1}% bis {:.1}%.",
+                    stats.mean, stats.std_dev, stats.min, stats.max
+                ),
+            },
             data: None,
         });
     }
@@ -703,26 +1733,30 @@ pub fn generate_insights(
     if let Some(busiest_day) = day_analysis
         .iter()
         .max_by(|a, b| a.avg_occupancy.partial_cmp(&b.avg_occupancy).unwrap())
+        && busiest_day.sample_count >= 5
     {
-        if busiest_day.sample_count >= 5 {
-            insights.push(Insight {
-                category: InsightCategory::DayPattern,
-                importance: 3,
-                title: format!("{} is the busiest day", busiest_day.day_name),
-                description: format!(
+        let day_name = weekday_name_lang(busiest_day.weekday, lang);
+        let peak_hour = busiest_day.peak_hour.unwrap_or(0);
+        insights.push(Insight {
+            category: InsightCategory::DayPattern,
+            importance: 3,
+            title: match lang {
+                English => format!("{} is the busiest day", day_name),
+                German => format!("{} ist der vollste Tag", day_name),
+            },
+            description: match lang {
+                English => format!(
                     "Average occupancy on {} is {:.1}%, peaking at {:.1}% around {}:00.",
-                    busiest_day.day_name,
-                    busiest_day.avg_occupancy,
-                    busiest_day.peak_occupancy,
-                    busiest_day.peak_hour.unwrap_or(0)
+                    day_name, busiest_day.avg_occupancy, busiest_day.peak_occupancy, peak_hour
                 ),
-                data: Some((
-                    busiest_day.weekday,
-                    busiest_day.peak_hour.unwrap_or(0),
-                    busiest_day.avg_occupancy,
-                )),
-            });
-        }
+                German => format!(
+                    "Durchschnittliche Auslastung am {} liegt bei {:.1}%, mit Spitze von \
+                     {:.1}% gegen {}:00 Uhr.",
+                    day_name, busiest_day.avg_occupancy, busiest_day.peak_occupancy, peak_hour
+                ),
+            },
+            data: Some((busiest_day.weekday, peak_hour, busiest_day.avg_occupancy)),
+        });
     }
 
     if let Some(quietest_day) = day_analysis
@@ -730,20 +1764,35 @@ pub fn generate_insights(
         .filter(|d| d.sample_count >= 5)
         .min_by(|a, b| a.avg_occupancy.partial_cmp(&b.avg_occupancy).unwrap())
     {
+        let day_name = weekday_name_lang(quietest_day.weekday, lang);
+        let quietest_hour = quietest_day.quietest_hour.unwrap_or(0);
         insights.push(Insight {
             category: InsightCategory::QuietTime,
             importance: 4,
-            title: format!("{} is the quietest day", quietest_day.day_name),
-            description: format!(
-                "Average occupancy on {} is only {:.1}%. Best time: around {}:00 ({:.1}%).",
-                quietest_day.day_name,
-                quietest_day.avg_occupancy,
-                quietest_day.quietest_hour.unwrap_or(0),
-                quietest_day.quietest_occupancy
-            ),
+            title: match lang {
+                English => format!("{} is the quietest day", day_name),
+                German => format!("{} ist der ruhigste Tag", day_name),
+            },
+            description: match lang {
+                English => format!(
+                    "Average occupancy on {} is only {:.1}%. Best time: around {}:00 ({:.1}%).",
+                    day_name,
+                    quietest_day.avg_occupancy,
+                    quietest_hour,
+                    quietest_day.quietest_occupancy
+                ),
+                German => format!(
+                    "Durchschnittliche Auslastung am {} nur {:.1}%. Beste Zeit: gegen {}:00 Uhr \
+                     ({:.1}%).",
+                    day_name,
+                    quietest_day.avg_occupancy,
+                    quietest_hour,
+                    quietest_day.quietest_occupancy
+                ),
+            },
             data: Some((
                 quietest_day.weekday,
-                quietest_day.quietest_hour.unwrap_or(0),
+                quietest_hour,
                 quietest_day.quietest_occupancy,
             )),
         });
@@ -752,38 +1801,94 @@ pub fn generate_insights(
     // Peak hours insight
     let peaks = find_peak_hours(current, 3);
     if !peaks.is_empty() {
-        const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
         let peak_desc: Vec<String> = peaks
             .iter()
-            .map(|(w, h, p)| format!("{} {}:00 ({:.0}%)", DAY_NAMES[*w as usize], h, p))
+            .map(|(w, h, p)| format!("{} {}:00 ({:.0}%)", weekday_short_lang(*w, lang), h, p))
             .collect();
 
         insights.push(Insight {
             category: InsightCategory::Peak,
             importance: 3,
-            title: "Busiest times to avoid".to_string(),
-            description: format!("Peak hours: {}", peak_desc.join(", ")),
+            title: match lang {
+                English => "Busiest times to avoid".to_string(),
+                German => "Diese Zeiten besser meiden".to_string(),
+            },
+            description: match lang {
+                English => format!("Peak hours: {}", peak_desc.join(", ")),
+                German => format!("Stoßzeiten: {}", peak_desc.join(", ")),
+            },
             data: Some(peaks[0]),
         });
     }
 
+    // Unusual hours insight
+    let anomalies = detect_anomalies(current, DEFAULT_ANOMALY_Z_THRESHOLD);
+    if let Some(&(w, h, z)) = anomalies.first() {
+        let day = weekday_short_lang(w, lang);
+        insights.push(Insight {
+            category: InsightCategory::Anomaly,
+            importance: 3,
+            title: match lang {
+                English => format!("Unusual hour: {} {}:00", day, h),
+                German => format!("Ungewöhnliche Stunde: {} {}:00", day, h),
+            },
+            description: match lang {
+                English => format!(
+                    "{} at {}:00 is {:.1} standard deviations {} than a typical hour. {} unusual \
+                     hours found in total.",
+                    day,
+                    h,
+                    z.abs(),
+                    if z > 0.0 { "busier" } else { "quieter" },
+                    anomalies.len()
+                ),
+                German => format!(
+                    "{} um {}:00 Uhr ist {:.1} Standardabweichungen {} als eine typische Stunde. \
+                     Insgesamt {} ungewöhnliche Stunden.",
+                    day,
+                    h,
+                    z.abs(),
+                    if z > 0.0 { "voller" } else { "ruhiger" },
+                    anomalies.len()
+                ),
+            },
+            data: Some((w, h, z)),
+        });
+    }
+
     // Quiet windows insight
-    let quiet_windows = find_quiet_windows(current, 40.0, 2);
+    let quiet_windows = find_quiet_windows(current, QUIET_WINDOW_THRESHOLD, QUIET_WINDOW_MIN_HOURS);
     if !quiet_windows.is_empty() {
-        const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
         let best_window = &quiet_windows[0];
+        let day = weekday_short_lang(best_window.weekday, lang);
+        let more = quiet_windows.len().saturating_sub(1);
         insights.push(Insight {
             category: InsightCategory::QuietTime,
             importance: 5,
-            title: "Best workout window".to_string(),
-            description: format!(
-                "{} {}:00-{}:00 averages only {:.1}% occupancy. {} more quiet windows available.",
-                DAY_NAMES[best_window.weekday as usize],
-                best_window.start_hour,
-                best_window.end_hour,
-                best_window.avg_occupancy,
-                quiet_windows.len().saturating_sub(1)
-            ),
+            title: match lang {
+                English => "Best workout window".to_string(),
+                German => "Bestes Trainingsfenster".to_string(),
+            },
+            description: match lang {
+                English => format!(
+                    "{} {}:00-{}:00 averages only {:.1}% occupancy. {} more quiet windows \
+                     available.",
+                    day,
+                    best_window.start_hour,
+                    best_window.end_hour,
+                    best_window.avg_occupancy,
+                    more
+                ),
+                German => format!(
+                    "{} {}:00-{}:00 Uhr liegt im Schnitt bei nur {:.1}% Auslastung. {} weitere \
+                     ruhige Zeitfenster verfügbar.",
+                    day,
+                    best_window.start_hour,
+                    best_window.end_hour,
+                    best_window.avg_occupancy,
+                    more
+                ),
+            },
             data: Some((
                 best_window.weekday,
                 best_window.start_hour,
@@ -794,28 +1899,42 @@ pub fn generate_insights(
 
     // Trend insights (if baseline provided)
     if let Some(baseline_data) = baseline {
-        let comparison = compare_periods(baseline_data, current, ComparisonMode::WeekOverWeek);
-
-        let trend_desc = match comparison.overall_trend {
-            TrendDirection::Increasing => {
-                format!(
-                    "Occupancy has increased by {:.1}% compared to the previous period. Consider \
-                     adjusting your workout times.",
-                    comparison.overall_change_percent.abs()
-                )
-            }
-            TrendDirection::Decreasing => {
-                format!(
-                    "Good news! Occupancy has decreased by {:.1}% compared to the previous period.",
-                    comparison.overall_change_percent.abs()
-                )
-            }
-            TrendDirection::Stable => {
+        let comparison =
+            compare_periods(baseline_data, current, ComparisonMode::WeekOverWeek, None);
+        let change = comparison.overall_change_percent.abs();
+
+        let trend_desc = match (lang, comparison.overall_trend) {
+            (English, TrendDirection::Increasing) => format!(
+                "Occupancy has increased by {:.1}% compared to the previous period. Consider \
+                 adjusting your workout times.",
+                change
+            ),
+            (English, TrendDirection::Decreasing) => format!(
+                "Good news! Occupancy has decreased by {:.1}% compared to the previous period.",
+                change
+            ),
+            (English, TrendDirection::Stable) => {
                 "Occupancy patterns are stable compared to the previous period.".to_string()
             }
-            TrendDirection::Insufficient => {
+            (English, TrendDirection::Insufficient) => {
                 "Not enough data to determine occupancy trends.".to_string()
             }
+            (German, TrendDirection::Increasing) => format!(
+                "Die Auslastung ist im Vergleich zum Vorzeitraum um {:.1}% gestiegen. Eventuell \
+                 lohnt es sich, die Trainingszeiten anzupassen.",
+                change
+            ),
+            (German, TrendDirection::Decreasing) => format!(
+                "Gute Nachrichten! Die Auslastung ist im Vergleich zum Vorzeitraum um {:.1}% \
+                 gesunken.",
+                change
+            ),
+            (German, TrendDirection::Stable) => {
+                "Die Auslastung ist im Vergleich zum Vorzeitraum stabil.".to_string()
+            }
+            (German, TrendDirection::Insufficient) => {
+                "Zu wenige Daten, um einen Trend zu bestimmen.".to_string()
+            }
         };
 
         let importance = match comparison.overall_trend {
@@ -824,40 +1943,100 @@ pub fn generate_insights(
             _ => 2,
         };
 
+        let title = match (lang, comparison.overall_trend) {
+            (English, trend) => format!("Gym is {}", trend.description()),
+            (German, TrendDirection::Increasing) => "Studio wird voller".to_string(),
+            (German, TrendDirection::Decreasing) => "Studio wird leerer".to_string(),
+            (German, TrendDirection::Stable) => "Auslastung ist stabil".to_string(),
+            (German, TrendDirection::Insufficient) => "Zu wenige Daten".to_string(),
+        };
+
         insights.push(Insight {
             category: InsightCategory::Trend,
             importance,
-            title: format!("Gym is {}", comparison.overall_trend.description()),
+            title,
             description: trend_desc,
             data: None,
         });
 
         // Biggest changes
         if !comparison.biggest_increases.is_empty() {
-            const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
             let (w, h, change) = comparison.biggest_increases[0];
+            let day = weekday_short_lang(w, lang);
             insights.push(Insight {
                 category: InsightCategory::Anomaly,
                 importance: 3,
-                title: "Significant occupancy increase".to_string(),
-                description: format!(
-                    "{} at {}:00 has seen a {:.0}% increase in occupancy. You may want to avoid \
-                     this time slot.",
-                    DAY_NAMES[w as usize], h, change
-                ),
+                title: match lang {
+                    English => "Significant occupancy increase".to_string(),
+                    German => "Deutlicher Anstieg der Auslastung".to_string(),
+                },
+                description: match lang {
+                    English => format!(
+                        "{} at {}:00 has seen a {:.0}% increase in occupancy. You may want to \
+                         avoid this time slot.",
+                        day, h, change
+                    ),
+                    German => format!(
+                        "{} um {}:00 Uhr ist die Auslastung um {:.0}% gestiegen. Diese Zeit \
+                         besser meiden.",
+                        day, h, change
+                    ),
+                },
                 data: Some((w, h, change)),
             });
         }
     }
 
     // Sort by importance (highest first)
-    insights.sort_by(|a, b| b.importance.cmp(&a.importance));
+    insights.sort_by_key(|insight| std::cmp::Reverse(insight.importance));
     insights
 }
 
+/// Summarize a week of hourly averages against the previous week.
+///
+/// Days without samples are ignored for the busiest and quietest day. The best
+/// window uses the same threshold as the "Best workout window" insight.
+pub fn weekly_summary(current: &[HourlyAverage], previous: &[HourlyAverage]) -> WeeklySummary {
+    let comparison = compare_periods(previous, current, ComparisonMode::WeekOverWeek, None);
+
+    let days: Vec<_> = analyze_days(current)
+        .into_iter()
+        .filter(|d| d.sample_count > 0)
+        .collect();
+    let by_avg = |a: &&DayAnalysis, b: &&DayAnalysis| {
+        a.avg_occupancy
+            .partial_cmp(&b.avg_occupancy)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    };
+
+    WeeklySummary {
+        overall_avg: comparison.current_overall_avg,
+        busiest_day: days.iter().max_by(by_avg).cloned(),
+        quietest_day: days.iter().min_by(by_avg).cloned(),
+        best_window: find_quiet_windows(current, QUIET_WINDOW_THRESHOLD, QUIET_WINDOW_MIN_HOURS)
+            .into_iter()
+            .next(),
+        trend: comparison.overall_trend,
+        change_percent: comparison.overall_change_percent,
+        top_insight: generate_insights(current, Some(previous))
+            .into_iter()
+            .next(),
+    }
+}
+
 /// Get the weekday name from index (0=Monday).
 pub fn weekday_name(weekday: i32) -> &'static str {
-    const DAY_NAMES: [&str; 7] = [
+    weekday_name_lang(weekday, Language::English)
+}
+
+/// Get the short weekday name from index (0=Monday).
+pub fn weekday_short(weekday: i32) -> &'static str {
+    weekday_short_lang(weekday, Language::English)
+}
+
+/// Get the weekday name from index (0=Monday) in the given language.
+pub fn weekday_name_lang(weekday: i32, lang: Language) -> &'static str {
+    const ENGLISH: [&str; 7] = [
         "Monday",
         "Tuesday",
         "Wednesday",
@@ -866,13 +2045,31 @@ pub fn weekday_name(weekday: i32) -> &'static str {
         "Saturday",
         "Sunday",
     ];
-    DAY_NAMES.get(weekday as usize).unwrap_or(&"Unknown")
+    const GERMAN: [&str; 7] = [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ];
+    let (names, unknown) = match lang {
+        Language::English => (ENGLISH, "Unknown"),
+        Language::German => (GERMAN, "Unbekannt"),
+    };
+    names.get(weekday as usize).copied().unwrap_or(unknown)
 }
 
-/// Get the short weekday name from index (0=Monday).
-pub fn weekday_short(weekday: i32) -> &'static str {
-    const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
-    DAY_NAMES.get(weekday as usize).unwrap_or(&"???")
+/// Get the short weekday name from index (0=Monday) in the given language.
+pub fn weekday_short_lang(weekday: i32, lang: Language) -> &'static str {
+    const ENGLISH: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const GERMAN: [&str; 7] = ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"];
+    let names = match lang {
+        Language::English => ENGLISH,
+        Language::German => GERMAN,
+    };
+    names.get(weekday as usize).copied().unwrap_or("???")
 }
 
 #[cfg(test)]
@@ -1101,86 +2298,312 @@ mod tests {
         }
 
         #[test]
-        fn test_predictions_clock_advances_correctly() {
-            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
-            let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        fn test_detailed_predictions_band_from_slot_spread() {
+            // Monday 09:00 UTC, so +1h targets Monday 10:00
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 9, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24); // 24/7 open
 
             let baseline = vec![
                 HourlyAverage {
                     weekday: 0,
-                    hour: 11,
-                    avg_percentage: 25.0,
-                    sample_count: 5,
-                },
-                HourlyAverage {
-                    weekday: 0,
-                    hour: 12,
-                    avg_percentage: 45.0,
-                    sample_count: 5,
+                    hour: 10,
+                    avg_percentage: 40.0,
+                    sample_count: 10,
                 },
                 HourlyAverage {
-                    weekday: 0,
-                    hour: 13,
-                    avg_percentage: 65.0,
-                    sample_count: 5,
+                    weekday: 1,
+                    hour: 10,
+                    avg_percentage: 60.0,
+                    sample_count: 10,
                 },
             ];
 
-            // At 10:00, should get predictions for 11:00 and 12:00
-            let predictions1 = calculate_predictions_with_clock(&baseline, &schedule, &clock);
-            assert_eq!(predictions1.len(), 2);
-            assert_eq!(predictions1[0].1, 25.0);
-            assert_eq!(predictions1[1].1, 45.0);
-
-            // Advance clock by 1 hour to 11:00
-            clock.advance(ChronoDuration::hours(1));
+            let predictions =
+                calculate_predictions_detailed_with_clock(&baseline, &schedule, &clock);
 
-            // Now should get predictions for 12:00 and 13:00
-            let predictions2 = calculate_predictions_with_clock(&baseline, &schedule, &clock);
-            assert_eq!(predictions2.len(), 2);
-            assert_eq!(predictions2[0].1, 45.0);
-            assert_eq!(predictions2[1].1, 65.0);
+            assert_eq!(predictions.len(), 1);
+            let p = predictions[0];
+            assert_eq!(p.percentage, 40.0);
+            assert!((p.std_dev - 10.0).abs() < 1e-9);
+            assert!((p.lower - 30.0).abs() < 1e-9);
+            assert!((p.upper - 50.0).abs() < 1e-9);
         }
 
         #[test]
-        fn test_find_best_time_with_mock_clock() {
-            // Set clock to Monday
-            let fixed_time = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap(); // Monday
-            let clock = MockClock::new(fixed_time);
+        fn test_detailed_predictions_band_is_clamped() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 9, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
 
-            // Data for Monday (weekday 0 in UTC)
-            let data = vec![
-                HourlyAverage {
-                    weekday: 0,
-                    hour: 8,
-                    avg_percentage: 60.0,
-                    sample_count: 5,
-                },
+            let baseline = vec![
                 HourlyAverage {
                     weekday: 0,
-                    hour: 14,
-                    avg_percentage: 15.0, // Lowest
-                    sample_count: 5,
+                    hour: 10,
+                    avg_percentage: 5.0,
+                    sample_count: 10,
                 },
                 HourlyAverage {
-                    weekday: 0,
-                    hour: 18,
-                    avg_percentage: 80.0,
-                    sample_count: 5,
+                    weekday: 1,
+                    hour: 10,
+                    avg_percentage: 95.0,
+                    sample_count: 10,
                 },
             ];
 
-            let result = find_best_time_today_with_clock(&data, &clock);
-            assert!(result.is_some());
-            let (_, avg) = result.unwrap();
-            // The best time should have the lowest percentage
-            assert_eq!(avg, 15.0);
-        }
-    }
+            let predictions =
+                calculate_predictions_detailed_with_clock(&baseline, &schedule, &clock);
 
-    // ==================== Week Boundary Tests ====================
+            assert_eq!(predictions.len(), 1);
+            // 5 ± 45 must stay within 0-100
+            assert_eq!(predictions[0].lower, 0.0);
+            assert!((predictions[0].upper - 50.0).abs() < 1e-9);
+        }
 
-    mod week_boundary_tests {
+        #[test]
+        fn test_tuple_predictions_match_detailed() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+            let baseline: Vec<HourlyAverage> = (0..24)
+                .map(|hour| HourlyAverage {
+                    weekday: 0,
+                    hour,
+                    avg_percentage: hour as f64 * 3.0,
+                    sample_count: 10,
+                })
+                .collect();
+
+            let tuples = calculate_predictions_with_clock(&baseline, &schedule, &clock);
+            let detailed = calculate_predictions_detailed_with_clock(&baseline, &schedule, &clock);
+
+            assert_eq!(tuples.len(), detailed.len());
+            for (t, d) in tuples.iter().zip(&detailed) {
+                assert_eq!(*t, (d.timestamp, d.percentage));
+            }
+        }
+
+        fn full_week_baseline() -> Vec<HourlyAverage> {
+            (0..7)
+                .flat_map(|weekday| {
+                    (0..24).map(move |hour| HourlyAverage {
+                        weekday,
+                        hour,
+                        avg_percentage: 40.0,
+                        sample_count: 10,
+                    })
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_predictions_with_horizon_full_data() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24); // 24/7 open
+
+            let predictions =
+                calculate_predictions_with_horizon(&full_week_baseline(), &schedule, &clock, 6);
+
+            assert_eq!(predictions.len(), 6);
+            for (i, (time, _)) in predictions.iter().enumerate() {
+                assert_eq!(*time, clock.now_utc() + ChronoDuration::hours(i as i64 + 1));
+            }
+        }
+
+        #[test]
+        fn test_predictions_with_horizon_keeps_gaps() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+
+            // Drop Monday 13:00 (the +3h slot) from the baseline
+            let baseline: Vec<HourlyAverage> = full_week_baseline()
+                .into_iter()
+                .filter(|h| !(h.weekday == 0 && h.hour == 13))
+                .collect();
+
+            let predictions = calculate_predictions_with_horizon(&baseline, &schedule, &clock, 6);
+
+            assert_eq!(predictions.len(), 5);
+            assert!(predictions.iter().all(|(time, _)| time.hour() != 13));
+        }
+
+        #[test]
+        fn test_predictions_with_horizon_skips_closed_hours() {
+            // Off the hour, so no target lands on the inclusive 00:00 "closing" minute
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 0, 0, 0); // Always closed
+
+            let predictions =
+                calculate_predictions_with_horizon(&full_week_baseline(), &schedule, &clock, 6);
+
+            assert!(predictions.is_empty());
+        }
+
+        #[test]
+        fn test_predictions_clock_advances_correctly() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+
+            let baseline = vec![
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 11,
+                    avg_percentage: 25.0,
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 12,
+                    avg_percentage: 45.0,
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 13,
+                    avg_percentage: 65.0,
+                    sample_count: 5,
+                },
+            ];
+
+            // At 10:00, should get predictions for 11:00 and 12:00
+            let predictions1 = calculate_predictions_with_clock(&baseline, &schedule, &clock);
+            assert_eq!(predictions1.len(), 2);
+            assert_eq!(predictions1[0].1, 25.0);
+            assert_eq!(predictions1[1].1, 45.0);
+
+            // Advance clock by 1 hour to 11:00
+            clock.advance(ChronoDuration::hours(1));
+
+            // Now should get predictions for 12:00 and 13:00
+            let predictions2 = calculate_predictions_with_clock(&baseline, &schedule, &clock);
+            assert_eq!(predictions2.len(), 2);
+            assert_eq!(predictions2[0].1, 45.0);
+            assert_eq!(predictions2[1].1, 65.0);
+        }
+
+        #[test]
+        fn test_find_best_time_for_weekday_ignores_other_days() {
+            // Clock is on a Monday, but we're planning for Wednesday
+            let fixed_time = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap();
+            let clock = MockClock::new(fixed_time);
+
+            let data = vec![
+                HourlyAverage {
+                    weekday: 0, // Monday, quieter but the wrong day
+                    hour: 12,
+                    avg_percentage: 5.0,
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 2,
+                    hour: 10,
+                    avg_percentage: 40.0,
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 2,
+                    hour: 12,
+                    avg_percentage: 20.0, // Lowest on Wednesday
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 2,
+                    hour: 14,
+                    avg_percentage: 60.0,
+                    sample_count: 5,
+                },
+            ];
+
+            let result = find_best_time_for_weekday_with_clock(&data, 2, &clock);
+            let (_, avg) = result.expect("Wednesday has data");
+            assert_eq!(avg, 20.0);
+
+            // Same result as asking for "today" on a Wednesday
+            let wednesday = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 19, 10, 0, 0).unwrap());
+            assert_eq!(
+                find_best_time_today_with_clock(&data, &wednesday),
+                find_best_time_for_weekday_with_clock(&data, 2, &wednesday)
+            );
+        }
+
+        #[test]
+        fn test_find_best_time_today_detailed_reports_slot_samples() {
+            // Wednesday, with a different sample count in every slot
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 19, 10, 0, 0).unwrap());
+            let data: Vec<HourlyAverage> = (0..7)
+                .flat_map(|weekday| {
+                    (0..24).map(move |hour| HourlyAverage {
+                        weekday,
+                        hour,
+                        avg_percentage: 30.0 + ((weekday * 24 + hour) % 17) as f64,
+                        sample_count: (weekday * 24 + hour) as i64 + 1,
+                    })
+                })
+                .collect();
+
+            let (hour, avg, samples) =
+                find_best_time_today_detailed_with_clock(&data, &clock).expect("Today has data");
+
+            let chosen = data
+                .iter()
+                .find(|h| h.sample_count == samples)
+                .expect("Sample count belongs to a slot");
+            assert_eq!(chosen.avg_percentage, avg);
+            assert_eq!(
+                find_best_time_today_with_clock(&data, &clock),
+                Some((hour, avg))
+            );
+        }
+
+        #[test]
+        fn test_find_best_time_for_weekday_no_data() {
+            let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let data = vec![HourlyAverage {
+                weekday: 0,
+                hour: 12,
+                avg_percentage: 30.0,
+                sample_count: 5,
+            }];
+
+            assert!(find_best_time_for_weekday_with_clock(&data, 4, &clock).is_none());
+        }
+
+        #[test]
+        fn test_find_best_time_with_mock_clock() {
+            // Set clock to Monday
+            let fixed_time = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap(); // Monday
+            let clock = MockClock::new(fixed_time);
+
+            // Data for Monday (weekday 0 in UTC)
+            let data = vec![
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 8,
+                    avg_percentage: 60.0,
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 14,
+                    avg_percentage: 15.0, // Lowest
+                    sample_count: 5,
+                },
+                HourlyAverage {
+                    weekday: 0,
+                    hour: 18,
+                    avg_percentage: 80.0,
+                    sample_count: 5,
+                },
+            ];
+
+            let result = find_best_time_today_with_clock(&data, &clock);
+            assert!(result.is_some());
+            let (_, avg) = result.unwrap();
+            // The best time should have the lowest percentage
+            assert_eq!(avg, 15.0);
+        }
+    }
+
+    // ==================== Week Boundary Tests ====================
+
+    mod week_boundary_tests {
         use chrono::TimeZone;
 
         use super::*;
@@ -1440,7 +2863,7 @@ mod tests {
                 // At 09:00, should predict for 10:00 (now + 1h) if data exists
                 if !predictions.is_empty() {
                     // The percentage should match the day's data
-                    let expected_weekday = (day as u32) % 7;
+                    let expected_weekday = day % 7;
                     let expected_pct = (expected_weekday as f64) * 10.0 + 5.0;
                     assert_eq!(
                         predictions[0].1, expected_pct,
@@ -1533,86 +2956,370 @@ mod tests {
         }
     }
 
-    // ==================== Comparative Analytics Tests ====================
+    // ==================== Busyness Classification Tests ====================
 
-    mod comparative_tests {
+    mod busyness_tests {
         use super::*;
 
-        fn make_hourly_avg(weekday: i32, hour: i32, pct: f64, samples: i64) -> HourlyAverage {
-            HourlyAverage {
-                weekday,
-                hour,
-                avg_percentage: pct,
-                sample_count: samples,
-            }
+        const LOW: f64 = 40.0;
+        const HIGH: f64 = 75.0;
+
+        #[test]
+        fn test_busyness_levels() {
+            assert_eq!(
+                BusynessLevel::from_percentage(0.0, LOW, HIGH),
+                BusynessLevel::Quiet
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(55.0, LOW, HIGH),
+                BusynessLevel::Moderate
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(90.0, LOW, HIGH),
+                BusynessLevel::Busy
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(120.0, LOW, HIGH),
+                BusynessLevel::Full
+            );
         }
 
         #[test]
-        fn test_build_hourly_comparisons_empty() {
-            let result = build_hourly_comparisons(&[], &[]);
-            assert!(result.is_empty());
+        fn test_busyness_low_boundary_is_moderate() {
+            assert_eq!(
+                BusynessLevel::from_percentage(39.9, LOW, HIGH),
+                BusynessLevel::Quiet
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(40.0, LOW, HIGH),
+                BusynessLevel::Moderate
+            );
         }
 
         #[test]
-        fn test_build_hourly_comparisons_basic() {
-            let baseline = vec![make_hourly_avg(0, 10, 40.0, 5)];
-            let current = vec![make_hourly_avg(0, 10, 50.0, 5)];
+        fn test_busyness_high_boundary_is_busy() {
+            assert_eq!(
+                BusynessLevel::from_percentage(74.9, LOW, HIGH),
+                BusynessLevel::Moderate
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(75.0, LOW, HIGH),
+                BusynessLevel::Busy
+            );
+        }
 
-            let result = build_hourly_comparisons(&baseline, &current);
+        #[test]
+        fn test_busyness_full_at_capacity() {
+            assert_eq!(
+                BusynessLevel::from_percentage(99.9, LOW, HIGH),
+                BusynessLevel::Busy
+            );
+            assert_eq!(
+                BusynessLevel::from_percentage(100.0, LOW, HIGH),
+                BusynessLevel::Full
+            );
+        }
 
-            assert_eq!(result.len(), 1);
-            assert_eq!(result[0].weekday, 0);
-            assert_eq!(result[0].hour, 10);
-            assert_eq!(result[0].baseline_avg, 40.0);
-            assert_eq!(result[0].current_avg, 50.0);
-            assert_eq!(result[0].absolute_change, 10.0);
-            assert!((result[0].percent_change - 25.0).abs() < 0.01); // 10/40 = 25%
+        #[test]
+        fn test_busyness_labels_and_colors() {
+            assert_eq!(BusynessLevel::Quiet.label(), "Not Busy");
+            assert_eq!(BusynessLevel::Full.label(), "Full");
+            assert_eq!(BusynessLevel::Quiet.color_index(), 0);
+            assert_eq!(BusynessLevel::Moderate.color_index(), 1);
+            assert_eq!(BusynessLevel::Busy.color_index(), 2);
+            assert_eq!(BusynessLevel::Full.color_index(), 2);
+        }
+    }
+
+    // ==================== Predictive Alert Tests ====================
+
+    mod predictive_alert_tests {
+        use super::*;
+        use crate::traits::MockClock;
+
+        fn clock() -> MockClock {
+            MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap())
+        }
+
+        fn hourly(clock: &MockClock, values: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (clock.now_utc() + ChronoDuration::hours(i as i64 + 1), v))
+                .collect()
         }
 
         #[test]
-        fn test_build_hourly_comparisons_missing_baseline() {
-            let baseline = vec![];
-            let current = vec![make_hourly_avg(0, 10, 50.0, 5)];
+        fn test_predictive_alert_fires_within_lead_time() {
+            let clock = clock();
+            let predictions = hourly(&clock, &[25.0, 50.0]);
 
-            let result = build_hourly_comparisons(&baseline, &current);
+            let alert = predictive_quiet_alert(&predictions, 30.0, 60, None, &clock);
 
-            assert_eq!(result.len(), 1);
-            assert_eq!(result[0].baseline_avg, 0.0);
-            assert_eq!(result[0].current_avg, 50.0);
-            assert_eq!(result[0].percent_change, 100.0); // From 0 to something
+            assert_eq!(alert, Some(predictions[0]));
         }
 
         #[test]
-        fn test_build_hourly_comparisons_missing_current() {
-            let baseline = vec![make_hourly_avg(0, 10, 50.0, 5)];
-            let current = vec![];
+        fn test_predictive_alert_picks_first_quiet_slot() {
+            let clock = clock();
+            let predictions = hourly(&clock, &[60.0, 20.0, 10.0]);
 
-            let result = build_hourly_comparisons(&baseline, &current);
+            let alert = predictive_quiet_alert(&predictions, 30.0, 120, None, &clock);
 
-            assert_eq!(result.len(), 1);
-            assert_eq!(result[0].baseline_avg, 50.0);
-            assert_eq!(result[0].current_avg, 0.0);
-            assert_eq!(result[0].percent_change, -100.0);
+            assert_eq!(alert, Some(predictions[1]));
         }
 
         #[test]
-        fn test_compare_periods_basic() {
-            let baseline = vec![
-                make_hourly_avg(0, 10, 40.0, 10),
-                make_hourly_avg(0, 11, 50.0, 10),
+        fn test_predictive_alert_silent_without_quiet_forecast() {
+            let clock = clock();
+            let predictions = hourly(&clock, &[45.0, 50.0, 35.0]);
+
+            assert!(predictive_quiet_alert(&predictions, 30.0, 180, None, &clock).is_none());
+        }
+
+        #[test]
+        fn test_predictive_alert_waits_for_lead_time() {
+            let clock = clock();
+            let predictions = hourly(&clock, &[50.0, 50.0, 20.0]);
+
+            // Quiet in 3 hours, lead time only 1 hour
+            assert!(predictive_quiet_alert(&predictions, 30.0, 60, None, &clock).is_none());
+
+            clock.advance(ChronoDuration::hours(2));
+            let alert = predictive_quiet_alert(&predictions, 30.0, 60, None, &clock);
+            assert_eq!(alert, Some(predictions[2]));
+        }
+
+        #[test]
+        fn test_predictive_alert_once_per_day() {
+            let clock = clock();
+            let predictions = hourly(&clock, &[20.0]);
+            let today = clock.now_local().date_naive();
+
+            let alert = predictive_quiet_alert(&predictions, 30.0, 60, Some(today), &clock);
+            assert!(alert.is_none());
+
+            let yesterday = today - ChronoDuration::days(1);
+            let alert = predictive_quiet_alert(&predictions, 30.0, 60, Some(yesterday), &clock);
+            assert!(alert.is_some());
+        }
+    }
+
+    // ==================== Comparative Analytics Tests ====================
+
+    mod comparative_tests {
+        use super::*;
+
+        fn make_hourly_avg(weekday: i32, hour: i32, pct: f64, samples: i64) -> HourlyAverage {
+            HourlyAverage {
+                weekday,
+                hour,
+                avg_percentage: pct,
+                sample_count: samples,
+            }
+        }
+
+        #[test]
+        fn test_build_hourly_comparisons_empty() {
+            let result = build_hourly_comparisons(&[], &[]);
+            assert!(result.is_empty());
+        }
+
+        #[test]
+        fn test_quieter_per_slot() {
+            let first = vec![
+                make_hourly_avg(0, 10, 30.0, 5),
+                make_hourly_avg(0, 18, 80.0, 5),
+                make_hourly_avg(2, 7, 45.0, 5),
+                make_hourly_avg(4, 12, 50.0, 5),
+            ];
+            let second = vec![
+                make_hourly_avg(0, 10, 55.0, 5),
+                make_hourly_avg(0, 18, 60.0, 5),
+                make_hourly_avg(2, 7, 45.0, 5),
+                make_hourly_avg(5, 9, 20.0, 5),
+            ];
+
+            let result = quieter_per_slot(&first, &second);
+
+            assert_eq!(
+                result,
+                vec![
+                    (0, 10, QuieterSide::First),
+                    (0, 18, QuieterSide::Second),
+                    (2, 7, QuieterSide::Tie),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_build_hourly_comparisons_basic() {
+            let baseline = vec![make_hourly_avg(0, 10, 40.0, 5)];
+            let current = vec![make_hourly_avg(0, 10, 50.0, 5)];
+
+            let result = build_hourly_comparisons(&baseline, &current);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].weekday, 0);
+            assert_eq!(result[0].hour, 10);
+            assert_eq!(result[0].baseline_avg, 40.0);
+            assert_eq!(result[0].current_avg, 50.0);
+            assert_eq!(result[0].absolute_change, 10.0);
+            assert!((result[0].percent_change - 25.0).abs() < 0.01); // 10/40 = 25%
+        }
+
+        #[test]
+        fn test_build_hourly_comparisons_missing_baseline() {
+            let baseline = vec![];
+            let current = vec![make_hourly_avg(0, 10, 50.0, 5)];
+
+            let result = build_hourly_comparisons(&baseline, &current);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].baseline_avg, 0.0);
+            assert_eq!(result[0].current_avg, 50.0);
+            assert_eq!(result[0].percent_change, 100.0); // From 0 to something
+        }
+
+        #[test]
+        fn test_build_hourly_comparisons_missing_current() {
+            let baseline = vec![make_hourly_avg(0, 10, 50.0, 5)];
+            let current = vec![];
+
+            let result = build_hourly_comparisons(&baseline, &current);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].baseline_avg, 50.0);
+            assert_eq!(result[0].current_avg, 0.0);
+            assert_eq!(result[0].percent_change, -100.0);
+        }
+
+        #[test]
+        fn test_compare_periods_basic() {
+            let baseline = vec![
+                make_hourly_avg(0, 10, 40.0, 10),
+                make_hourly_avg(0, 11, 50.0, 10),
             ];
             let current = vec![
                 make_hourly_avg(0, 10, 45.0, 10),
                 make_hourly_avg(0, 11, 55.0, 10),
             ];
 
-            let result = compare_periods(&baseline, &current, ComparisonMode::WeekOverWeek);
+            let result = compare_periods(&baseline, &current, ComparisonMode::WeekOverWeek, None);
 
             assert_eq!(result.mode, ComparisonMode::WeekOverWeek);
             assert!(result.current_overall_avg > result.baseline_overall_avg);
             assert!(result.overall_change_percent > 0.0);
         }
 
+        #[test]
+        fn test_compare_periods_per_weekday_rollup() {
+            let mut baseline = Vec::new();
+            let mut current = Vec::new();
+            for weekday in 0..3 {
+                baseline.push(make_hourly_avg(weekday, 10, 40.0, 10));
+                baseline.push(make_hourly_avg(weekday, 18, 60.0, 30));
+                current.push(make_hourly_avg(weekday, 10, 40.0, 10));
+                current.push(make_hourly_avg(weekday, 18, 60.0, 30));
+            }
+            // Tuesdays get much busier at 18:00
+            current[3] = make_hourly_avg(1, 18, 80.0, 30);
+
+            let result = compare_periods(&baseline, &current, ComparisonMode::WeekOverWeek, None);
+
+            assert_eq!(result.per_weekday.len(), 3);
+            for &(weekday, baseline_avg, current_avg, change) in &result.per_weekday {
+                // Weighted by samples: (40 * 10 + 60 * 30) / 40 = 55
+                assert!((baseline_avg - 55.0).abs() < 1e-9);
+                if weekday == 1 {
+                    // (40 * 10 + 80 * 30) / 40 = 70
+                    assert!((current_avg - 70.0).abs() < 1e-9);
+                    assert!((change - 300.0 / 11.0).abs() < 1e-9);
+                } else {
+                    assert!((current_avg - 55.0).abs() < 1e-9);
+                    assert_eq!(change, 0.0);
+                }
+            }
+        }
+
+        fn make_log(timestamp: DateTime<Utc>, percentage: f64) -> OccupancyLog {
+            OccupancyLog {
+                id: 0,
+                timestamp: timestamp.to_rfc3339(),
+                percentage,
+            }
+        }
+
+        /// Three readings in each of the Monday 10:00 and 11:00 slots of the
+        /// week starting at `week_start`.
+        fn week_of_logs(week_start: DateTime<Utc>, at_10: f64, at_11: f64) -> Vec<OccupancyLog> {
+            (0..3)
+                .flat_map(|i| {
+                    let minute = ChronoDuration::minutes(i * 10);
+                    [
+                        make_log(week_start + ChronoDuration::hours(10) + minute, at_10),
+                        make_log(week_start + ChronoDuration::hours(11) + minute, at_11),
+                    ]
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_comparison_mode_baseline_offset() {
+            assert_eq!(
+                ComparisonMode::WeekOverWeek.baseline_offset(),
+                Some(ChronoDuration::weeks(1))
+            );
+            assert_eq!(
+                ComparisonMode::MonthOverMonth.baseline_offset(),
+                Some(ChronoDuration::weeks(4))
+            );
+            assert_eq!(ComparisonMode::CustomRange.baseline_offset(), None);
+        }
+
+        #[test]
+        fn test_aggregate_hourly() {
+            // Monday 2024-06-10
+            let monday = Utc.with_ymd_and_hms(2024, 6, 10, 0, 0, 0).unwrap();
+            let logs = week_of_logs(monday, 30.0, 60.0);
+
+            let result = aggregate_hourly(&logs);
+
+            assert_eq!(result.len(), 2);
+            assert_eq!((result[0].weekday, result[0].hour), (0, 10));
+            assert_eq!(result[0].avg_percentage, 30.0);
+            assert_eq!(result[0].sample_count, 3);
+            assert_eq!((result[1].weekday, result[1].hour), (0, 11));
+            assert_eq!(result[1].avg_percentage, 60.0);
+        }
+
+        #[test]
+        fn test_month_over_month_uses_four_week_baseline() {
+            let current_start = Utc.with_ymd_and_hms(2024, 6, 10, 0, 0, 0).unwrap();
+
+            let mut logs = week_of_logs(current_start, 50.0, 50.0);
+            // Last week: 10:00 was quiet, 11:00 unchanged
+            logs.extend(week_of_logs(
+                current_start - ChronoDuration::weeks(1),
+                25.0,
+                50.0,
+            ));
+            // Four weeks ago: 11:00 was quiet, 10:00 unchanged
+            logs.extend(week_of_logs(
+                current_start - ChronoDuration::weeks(4),
+                50.0,
+                25.0,
+            ));
+
+            let wow = compare_periods_from_logs(&logs, current_start, ComparisonMode::WeekOverWeek);
+            let mom =
+                compare_periods_from_logs(&logs, current_start, ComparisonMode::MonthOverMonth);
+
+            assert_eq!(wow.biggest_increases, vec![(0, 10, 100.0)]);
+            assert_eq!(mom.biggest_increases, vec![(0, 11, 100.0)]);
+            assert_eq!(mom.mode, ComparisonMode::MonthOverMonth);
+        }
+
         #[test]
         fn test_determine_trend_insufficient_data() {
             let comparisons = vec![HourlyComparison {
@@ -1687,6 +3394,65 @@ mod tests {
             assert_eq!(result, TrendDirection::Stable);
         }
 
+        /// Ten comparable slots that all moved from 50% to 52%.
+        fn slightly_busier_periods() -> (Vec<HourlyAverage>, Vec<HourlyAverage>) {
+            let baseline = (8..18).map(|h| make_hourly_avg(0, h, 50.0, 10)).collect();
+            let current = (8..18).map(|h| make_hourly_avg(0, h, 52.0, 10)).collect();
+            (baseline, current)
+        }
+
+        #[test]
+        fn test_compare_periods_threshold_override() {
+            let (baseline, current) = slightly_busier_periods();
+
+            // +4% is outside the default ±3% band
+            let default = compare_periods(&baseline, &current, ComparisonMode::WeekOverWeek, None);
+            assert_eq!(default.overall_trend, TrendDirection::Increasing);
+
+            let relaxed = TrendThresholds {
+                stable_band: 5.0,
+                ..Default::default()
+            };
+            let result = compare_periods(
+                &baseline,
+                &current,
+                ComparisonMode::WeekOverWeek,
+                Some(relaxed),
+            );
+            assert_eq!(result.overall_trend, TrendDirection::Stable);
+        }
+
+        #[test]
+        fn test_determine_trend_with_thresholds_min_slots() {
+            let (baseline, current) = slightly_busier_periods();
+            let comparisons = build_hourly_comparisons(&baseline, &current);
+
+            let strict = TrendThresholds {
+                min_valid_slots: 20,
+                ..Default::default()
+            };
+            assert_eq!(
+                determine_trend_with_thresholds(&comparisons, strict),
+                TrendDirection::Insufficient
+            );
+            assert_eq!(
+                determine_trend_with_thresholds(&comparisons, TrendThresholds::default()),
+                determine_trend(&comparisons)
+            );
+        }
+
+        #[test]
+        fn test_hourly_comparison_trend_with_band() {
+            let comparison = build_hourly_comparisons(
+                &[make_hourly_avg(0, 10, 50.0, 10)],
+                &[make_hourly_avg(0, 10, 52.0, 10)],
+            )
+            .remove(0);
+
+            assert_eq!(comparison.trend(), TrendDirection::Stable);
+            assert_eq!(comparison.trend_with_band(2.0), TrendDirection::Increasing);
+        }
+
         #[test]
         fn test_hourly_comparison_trend() {
             let increasing = HourlyComparison {
@@ -1758,6 +3524,54 @@ mod tests {
             assert!(result.is_none());
         }
 
+        #[test]
+        fn test_calculate_stats_weighted_favors_high_count_slots() {
+            // Well-sampled slots are quiet, sparsely sampled ones busy
+            let data = vec![
+                make_hourly_avg(0, 10, 20.0, 100),
+                make_hourly_avg(0, 11, 20.0, 100),
+                make_hourly_avg(0, 12, 80.0, 2),
+                make_hourly_avg(0, 13, 80.0, 2),
+            ];
+
+            let unweighted = calculate_stats(&data).unwrap();
+            let weighted = calculate_stats_weighted(&data).unwrap();
+
+            assert_eq!(unweighted.mean, 50.0);
+            // (20 * 200 + 80 * 4) / 204
+            assert!((weighted.mean - 4320.0 / 204.0).abs() < 1e-9);
+            assert!(weighted.mean < unweighted.mean);
+            assert!(weighted.std_dev < unweighted.std_dev);
+            assert!(
+                (weighted.coefficient_of_variation - weighted.std_dev / weighted.mean).abs()
+                    < 1e-12
+            );
+
+            // Range is still reported unweighted
+            assert_eq!(weighted.min, 20.0);
+            assert_eq!(weighted.max, 80.0);
+        }
+
+        #[test]
+        fn test_calculate_stats_weighted_equal_counts_match_unweighted() {
+            let data = vec![
+                make_hourly_avg(0, 10, 30.0, 5),
+                make_hourly_avg(0, 11, 50.0, 5),
+                make_hourly_avg(0, 12, 70.0, 5),
+            ];
+
+            let unweighted = calculate_stats(&data).unwrap();
+            let weighted = calculate_stats_weighted(&data).unwrap();
+
+            assert!((weighted.mean - unweighted.mean).abs() < 1e-9);
+            assert!((weighted.std_dev - unweighted.std_dev).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_calculate_stats_weighted_empty() {
+            assert!(calculate_stats_weighted(&[]).is_none());
+        }
+
         #[test]
         fn test_calculate_stats_single_value() {
             let data = vec![make_hourly_avg(0, 10, 50.0, 5)];
@@ -1769,6 +3583,13 @@ mod tests {
             assert_eq!(result.min, 50.0);
             assert_eq!(result.max, 50.0);
             assert_eq!(result.sample_count, 1);
+
+            // Every percentile collapses to the single value
+            assert_eq!(result.p25, 50.0);
+            assert_eq!(result.p50, 50.0);
+            assert_eq!(result.p75, 50.0);
+            assert_eq!(result.p90, 50.0);
+            assert_eq!(result.p95, 50.0);
         }
 
         #[test]
@@ -1789,6 +3610,24 @@ mod tests {
             assert!(result.std_dev > 0.0);
         }
 
+        #[test]
+        fn test_calculate_stats_percentiles() {
+            let data = vec![
+                make_hourly_avg(0, 10, 80.0, 5),
+                make_hourly_avg(0, 11, 20.0, 5),
+                make_hourly_avg(0, 12, 60.0, 5),
+                make_hourly_avg(0, 13, 40.0, 5),
+            ];
+            let result = calculate_stats(&data).unwrap();
+
+            // Rank for p75 is 0.75 * 4 - 0.5 = 2.5 -> halfway between 60 and 80
+            assert!((result.p75 - 70.0).abs() < 1e-9);
+            assert!((result.p25 - 30.0).abs() < 1e-9);
+            assert_eq!(result.p50, result.median);
+            assert!(result.p90 <= result.p95);
+            assert!(result.p95 <= result.max);
+        }
+
         #[test]
         fn test_analyze_days() {
             let data = vec![
@@ -1842,6 +3681,134 @@ mod tests {
             assert_eq!(result[1], (1, 10, 20.0));
         }
 
+        #[test]
+        fn test_find_quiet_hours_with_min_excludes_low_sample_outlier() {
+            let data = vec![
+                make_hourly_avg(0, 6, 2.0, 3), // One early visitor
+                make_hourly_avg(0, 10, 15.0, 20),
+                make_hourly_avg(1, 10, 25.0, 12),
+            ];
+
+            assert_eq!(find_quiet_hours(&data, 1)[0], (0, 6, 2.0));
+
+            let result = find_quiet_hours_with_min(&data, 2, 10);
+            assert_eq!(result, vec![(0, 10, 15.0), (1, 10, 25.0)]);
+        }
+
+        #[test]
+        fn test_recommend_alternative_slot_finds_quiet_hour_before() {
+            let data = vec![
+                make_hourly_avg(0, 16, 35.0, 10),
+                make_hourly_avg(0, 17, 40.0, 10), // Quiet an hour earlier
+                make_hourly_avg(0, 18, 85.0, 10), // Preferred, crowded
+                make_hourly_avg(0, 19, 80.0, 10),
+                make_hourly_avg(1, 18, 30.0, 10), // Quieter, but a day later
+            ];
+
+            assert_eq!(
+                recommend_alternative_slot(&data, (0, 18)),
+                Some((0, 17, 40.0))
+            );
+        }
+
+        #[test]
+        fn test_recommend_alternative_slot_none_when_preferred_is_good() {
+            let data = vec![
+                make_hourly_avg(0, 17, 30.0, 10),
+                make_hourly_avg(0, 18, 35.0, 10), // Only 5 points higher
+                make_hourly_avg(0, 21, 5.0, 10),  // Too far away
+                make_hourly_avg(2, 18, 5.0, 10),  // Two days later
+            ];
+
+            assert_eq!(recommend_alternative_slot(&data, (0, 18)), None);
+            // No data for the preferred slot
+            assert_eq!(recommend_alternative_slot(&data, (3, 9)), None);
+        }
+
+        #[test]
+        fn test_recommend_alternative_slot_wraps_around_week() {
+            let data = vec![
+                make_hourly_avg(6, 23, 20.0, 10), // Sunday night
+                make_hourly_avg(0, 0, 70.0, 10),  // Preferred, Monday midnight
+            ];
+
+            assert_eq!(
+                recommend_alternative_slot(&data, (0, 0)),
+                Some((6, 23, 20.0))
+            );
+        }
+
+        #[test]
+        fn test_find_peak_hours_with_min_excludes_low_sample_outlier() {
+            let data = vec![
+                make_hourly_avg(0, 22, 95.0, 2), // A class let out once
+                make_hourly_avg(0, 18, 80.0, 20),
+                make_hourly_avg(2, 17, 70.0, 15),
+            ];
+
+            assert_eq!(find_peak_hours(&data, 1)[0], (0, 22, 95.0));
+
+            let result = find_peak_hours_with_min(&data, 2, 10);
+            assert_eq!(result, vec![(0, 18, 80.0), (2, 17, 70.0)]);
+        }
+
+        #[test]
+        fn test_average_by_hour_weights_by_samples() {
+            let data = vec![
+                make_hourly_avg(0, 9, 20.0, 10),
+                make_hourly_avg(1, 9, 50.0, 30),
+                make_hourly_avg(0, 17, 60.0, 5),
+                make_hourly_avg(1, 17, 80.0, 5),
+                make_hourly_avg(1, 3, 0.0, 0), // No samples
+            ];
+
+            let result = average_by_hour(&data);
+
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].0, 9);
+            assert!((result[0].1 - 42.5).abs() < 1e-9);
+            assert_eq!(result[1].0, 17);
+            assert!((result[1].1 - 70.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_average_by_hour_with_filter_separates_weekends() {
+            let data = vec![
+                make_hourly_avg(2, 10, 60.0, 5),
+                make_hourly_avg(5, 10, 20.0, 5),
+                make_hourly_avg(6, 10, 30.0, 5),
+            ];
+
+            assert_eq!(
+                average_by_hour_with_filter(&data, WeekdayFilter::Weekdays),
+                vec![(10, 60.0)]
+            );
+            assert_eq!(
+                average_by_hour_with_filter(&data, WeekdayFilter::Weekends),
+                vec![(10, 25.0)]
+            );
+        }
+
+        #[test]
+        fn test_infer_open_hours_from_data_span() {
+            // Monday and Saturday with visitors 08:00-20:00, empty readings
+            // around it, and no data on the other days
+            let data: Vec<HourlyAverage> = [0, 5]
+                .into_iter()
+                .flat_map(|weekday| {
+                    (6..22).map(move |hour| {
+                        let pct = if (8..20).contains(&hour) { 35.0 } else { 0.0 };
+                        make_hourly_avg(weekday, hour, pct, 5)
+                    })
+                })
+                .collect();
+
+            let result = infer_open_hours(&data, 1.0);
+
+            assert_eq!(result, vec![(0, 8, 20), (5, 8, 20)]);
+            assert!(infer_open_hours(&[], 1.0).is_empty());
+        }
+
         #[test]
         fn test_find_quiet_windows() {
             let data = vec![
@@ -1860,6 +3827,447 @@ mod tests {
             assert_eq!(window.start_hour, 6);
             assert!(window.end_hour >= 8);
         }
+
+        #[test]
+        fn test_find_quiet_windows_local_shifts_hours() {
+            // Sunday 22:00 to Monday 00:00 UTC is Monday 00:00 to 02:00 at UTC+2
+            let data = vec![
+                make_hourly_avg(6, 21, 80.0, 5),
+                make_hourly_avg(6, 22, 20.0, 5),
+                make_hourly_avg(6, 23, 25.0, 5),
+                make_hourly_avg(0, 0, 30.0, 5),
+                make_hourly_avg(0, 1, 70.0, 5),
+            ];
+
+            let result = find_quiet_windows(&shift_to_local(&data, 2 * 3600), 40.0, 2);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].weekday, 0);
+            assert_eq!(result[0].start_hour, 0);
+            assert_eq!(result[0].end_hour, 3);
+            assert!((result[0].avg_occupancy - 25.0).abs() < 1e-9);
+
+            // Negative offsets wrap back into the previous week
+            let shifted = shift_to_local(&data[3..4], -3600);
+            assert_eq!((shifted[0].weekday, shifted[0].hour), (6, 23));
+        }
+
+        #[test]
+        fn test_find_quiet_windows_local_uses_clock_offset() {
+            let clock =
+                crate::traits::MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap());
+            let offset = clock.now_local().offset().fix().local_minus_utc() as i64;
+            let data = vec![
+                make_hourly_avg(2, 6, 20.0, 5),
+                make_hourly_avg(2, 7, 25.0, 5),
+                make_hourly_avg(2, 8, 30.0, 5),
+            ];
+
+            assert_eq!(
+                find_quiet_windows_local(&data, 40.0, 2, &clock),
+                find_quiet_windows(&shift_to_local(&data, offset), 40.0, 2)
+            );
+        }
+
+        #[test]
+        fn test_find_peak_windows() {
+            let data = vec![
+                make_hourly_avg(0, 15, 30.0, 5),
+                make_hourly_avg(0, 16, 40.0, 5),
+                make_hourly_avg(0, 17, 70.0, 5),
+                make_hourly_avg(0, 18, 80.0, 5),
+                make_hourly_avg(0, 19, 75.0, 5),
+                make_hourly_avg(0, 20, 45.0, 5), // Break
+                make_hourly_avg(0, 21, 20.0, 5),
+            ];
+
+            let result = find_peak_windows(&data, 60.0, 2);
+
+            assert_eq!(result.len(), 1);
+            let window = &result[0];
+            assert_eq!(window.weekday, 0);
+            assert_eq!(window.start_hour, 17);
+            assert_eq!(window.end_hour, 20);
+            assert_eq!(window.avg_occupancy, 75.0);
+        }
+
+        #[test]
+        fn test_find_peak_windows_extends_to_end_of_day() {
+            let data = vec![
+                make_hourly_avg(4, 20, 30.0, 5),
+                make_hourly_avg(4, 21, 65.0, 5),
+                make_hourly_avg(4, 22, 70.0, 5),
+                make_hourly_avg(4, 23, 75.0, 5),
+            ];
+
+            let result = find_peak_windows(&data, 60.0, 2);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].start_hour, 21);
+            assert_eq!(result[0].end_hour, 24);
+        }
+
+        #[test]
+        fn test_find_peak_windows_sorted_busiest_first() {
+            let data = vec![
+                // Monday 17-19 at ~70%
+                make_hourly_avg(0, 17, 70.0, 5),
+                make_hourly_avg(0, 18, 70.0, 5),
+                // Tuesday 17-19 at ~90%
+                make_hourly_avg(1, 17, 90.0, 5),
+                make_hourly_avg(1, 18, 90.0, 5),
+                // Wednesday: a single busy hour is too short
+                make_hourly_avg(2, 18, 95.0, 5),
+            ];
+
+            let result = find_peak_windows(&data, 60.0, 2);
+
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0].weekday, 1);
+            assert_eq!(result[1].weekday, 0);
+        }
+    }
+
+    // ==================== Quiet Time Probability Tests ====================
+
+    mod probability_tests {
+        use super::*;
+
+        fn make_hourly_avg(weekday: i32, hour: i32, pct: f64) -> HourlyAverage {
+            HourlyAverage {
+                weekday,
+                hour,
+                avg_percentage: pct,
+                sample_count: 10,
+            }
+        }
+
+        #[test]
+        fn test_normal_cdf_reference_values() {
+            assert!((normal_cdf(0.0) - 0.5).abs() < 1e-6);
+            assert!((normal_cdf(1.0) - 0.841_344_7).abs() < 1e-6);
+            assert!((normal_cdf(-1.96) - 0.024_997_9).abs() < 1e-6);
+        }
+
+        #[test]
+        fn test_probability_below_zero_std_is_step() {
+            assert_eq!(probability_below(20.0, 0.0, 30.0), 1.0);
+            assert_eq!(probability_below(40.0, 0.0, 30.0), 0.0);
+        }
+
+        #[test]
+        fn test_probability_below_at_mean_is_about_half() {
+            // Symmetric around 50, so truncation doesn't skew it
+            let p = probability_below(50.0, 10.0, 50.0);
+            assert!((p - 0.5).abs() < 1e-6);
+        }
+
+        #[test]
+        fn test_probability_heatmap_high_and_low_slots() {
+            // Hour 10 spreads across days (std = 10); Monday is well below the goal,
+            // Tuesday well above
+            let data = vec![make_hourly_avg(0, 10, 15.0), make_hourly_avg(1, 10, 35.0)];
+
+            let grid = probability_heatmap(&data, 30.0);
+
+            let quiet = grid[0][10].unwrap();
+            let busy = grid[1][10].unwrap();
+            assert!(quiet > 0.9, "quiet slot probability was {}", quiet);
+            assert!(busy < 0.35, "busy slot probability was {}", busy);
+        }
+
+        #[test]
+        fn test_probability_heatmap_missing_slots_are_none() {
+            let data = vec![make_hourly_avg(0, 10, 15.0)];
+            let grid = probability_heatmap(&data, 30.0);
+
+            assert!(grid[0][10].is_some());
+            assert!(grid[0][11].is_none());
+            assert!(grid[6][23].is_none());
+        }
+    }
+
+    // ==================== Level Shift Tests ====================
+
+    mod level_shift_tests {
+        use super::*;
+
+        /// One hour of minute readings per day starting 2024-05-01, with the
+        /// daily level given by `level(day_index)`.
+        fn daily_logs(days: i64, level: impl Fn(i64) -> f64) -> Vec<OccupancyLog> {
+            let start = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+            (0..days)
+                .flat_map(|day| {
+                    let pct = level(day);
+                    (0..60).map(move |minute| OccupancyLog {
+                        id: day * 60 + minute,
+                        timestamp: (start
+                            + ChronoDuration::days(day)
+                            + ChronoDuration::minutes(minute))
+                        .to_rfc3339(),
+                        percentage: pct,
+                    })
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_detect_level_shift_step_up() {
+            // 40-42% for 18 days, then 55-57%
+            let logs = daily_logs(30, |day| {
+                let noise = (day % 3) as f64;
+                if day < 18 { 40.0 + noise } else { 55.0 + noise }
+            });
+
+            let (date, shift) = detect_level_shift(&logs).expect("shift should be detected");
+
+            assert_eq!(date, NaiveDate::from_ymd_opt(2024, 5, 19).unwrap());
+            assert!((shift - 15.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_detect_level_shift_step_down() {
+            let logs = daily_logs(28, |day| if day < 14 { 60.0 } else { 45.0 });
+
+            let (date, shift) = detect_level_shift(&logs).expect("shift should be detected");
+
+            assert_eq!(date, NaiveDate::from_ymd_opt(2024, 5, 15).unwrap());
+            assert_eq!(shift, -15.0);
+        }
+
+        #[test]
+        fn test_detect_level_shift_stable_series() {
+            let logs = daily_logs(30, |day| 40.0 + (day % 7) as f64);
+            assert!(detect_level_shift(&logs).is_none());
+        }
+
+        #[test]
+        fn test_detect_level_shift_ignores_short_excursions() {
+            // A jump in the last few days is not a sustained shift yet
+            let logs = daily_logs(30, |day| if day < 27 { 40.0 } else { 70.0 });
+            assert!(detect_level_shift(&logs).is_none());
+        }
+
+        #[test]
+        fn test_detect_level_shift_too_few_days() {
+            let logs = daily_logs(10, |day| if day < 5 { 40.0 } else { 60.0 });
+            assert!(detect_level_shift(&logs).is_none());
+        }
+    }
+
+    // ==================== Smoothing Tests ====================
+
+    mod smoothing_tests {
+        use super::*;
+
+        fn minute_logs(values: &[f64]) -> Vec<OccupancyLog> {
+            let start = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap();
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &percentage)| OccupancyLog {
+                    id: i as i64,
+                    timestamp: (start + ChronoDuration::minutes(i as i64)).to_rfc3339(),
+                    percentage,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_smooth_flat_series_unchanged() {
+            let logs = minute_logs(&[42.0; 30]);
+            let smoothed = smooth_moving_average(&logs, 10);
+
+            assert_eq!(smoothed.len(), 30);
+            for ((time, value), log) in smoothed.iter().zip(&logs) {
+                assert_eq!(Some(*time), log.datetime());
+                assert!((value - 42.0).abs() < 1e-9);
+            }
+        }
+
+        #[test]
+        fn test_smooth_attenuates_spike() {
+            let mut values = vec![20.0; 21];
+            values[10] = 90.0;
+            let smoothed = smooth_moving_average(&minute_logs(&values), 4);
+
+            // Window of +/-2 minutes: the spike is averaged with 4 neighbours
+            assert!((smoothed[10].1 - 34.0).abs() < 1e-9);
+            assert!((smoothed[8].1 - 34.0).abs() < 1e-9);
+            assert!((smoothed[7].1 - 20.0).abs() < 1e-9);
+            let peak = smoothed.iter().map(|(_, v)| *v).fold(f64::MIN, f64::max);
+            assert!(peak < 90.0);
+        }
+
+        #[test]
+        fn test_smooth_edges_use_available_points() {
+            let smoothed = smooth_moving_average(&minute_logs(&[10.0, 20.0, 30.0, 40.0]), 2);
+
+            assert!((smoothed[0].1 - 15.0).abs() < 1e-9);
+            assert!((smoothed[1].1 - 20.0).abs() < 1e-9);
+            assert!((smoothed[3].1 - 35.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_smooth_skips_gaps() {
+            // Readings at minutes 0, 1 and 30; the gap is not filled with zeros
+            let mut logs = minute_logs(&[50.0, 60.0]);
+            logs.extend(minute_logs(&[0.0; 31]).into_iter().skip(30).map(|mut l| {
+                l.percentage = 80.0;
+                l
+            }));
+            let smoothed = smooth_moving_average(&logs, 10);
+
+            assert_eq!(smoothed.len(), 3);
+            assert!((smoothed[0].1 - 55.0).abs() < 1e-9);
+            assert!((smoothed[2].1 - 80.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_smooth_empty() {
+            assert!(smooth_moving_average(&[], 15).is_empty());
+        }
+    }
+
+    // ==================== Stuck Sensor Tests ====================
+
+    mod stuck_sensor_tests {
+        use super::*;
+
+        fn minute_logs(values: &[f64]) -> Vec<OccupancyLog> {
+            let start = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap();
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &percentage)| OccupancyLog {
+                    id: i as i64,
+                    timestamp: (start + ChronoDuration::minutes(i as i64)).to_rfc3339(),
+                    percentage,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_detect_stuck_runs_flags_long_identical_run() {
+            let logs = minute_logs(&[42.0; 60]);
+
+            let runs = detect_stuck_runs(&logs, 60);
+
+            assert_eq!(runs.len(), 1);
+            assert_eq!(runs[0].value, 42.0);
+            assert_eq!(runs[0].length, 60);
+            assert_eq!(runs[0].end - runs[0].start, ChronoDuration::minutes(59));
+        }
+
+        #[test]
+        fn test_detect_stuck_runs_ignores_normal_variation() {
+            let values: Vec<f64> = (0..120).map(|i| 40.0 + (i % 5) as f64).collect();
+            let logs = minute_logs(&values);
+
+            assert!(detect_stuck_runs(&logs, 60).is_empty());
+        }
+
+        #[test]
+        fn test_detect_stuck_runs_below_threshold() {
+            let logs = minute_logs(&[42.0; 59]);
+            assert!(detect_stuck_runs(&logs, 60).is_empty());
+        }
+
+        #[test]
+        fn test_detect_stuck_runs_ignores_closed_zeros() {
+            let logs = minute_logs(&[0.0; 90]);
+            assert!(detect_stuck_runs(&logs, 60).is_empty());
+        }
+
+        #[test]
+        fn test_stuck_sensor_insight() {
+            let logs = minute_logs(&[42.0; 60]);
+            let run = &detect_stuck_runs(&logs, 60)[0];
+
+            let insight = stuck_sensor_insight(run);
+
+            assert_eq!(insight.category, InsightCategory::Anomaly);
+            assert!(insight.description.contains("60 readings"));
+        }
+    }
+
+    // ==================== Anomaly Detection Tests ====================
+
+    mod anomaly_tests {
+        use super::*;
+
+        fn make_hourly_avg(weekday: i32, hour: i32, pct: f64, samples: i64) -> HourlyAverage {
+            HourlyAverage {
+                weekday,
+                hour,
+                avg_percentage: pct,
+                sample_count: samples,
+            }
+        }
+
+        /// Monday 06:00-23:00 at a flat 30% with one slot replaced.
+        fn flat_day_with(hour: i32, pct: f64, samples: i64) -> Vec<HourlyAverage> {
+            (6..24)
+                .map(|h| {
+                    if h == hour {
+                        make_hourly_avg(0, h, pct, samples)
+                    } else {
+                        make_hourly_avg(0, h, 30.0, 10)
+                    }
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_detect_anomalies_finds_outlier() {
+            let data = flat_day_with(18, 90.0, 10);
+
+            let result = detect_anomalies(&data, 3.0);
+
+            assert_eq!(result.len(), 1);
+            let (weekday, hour, z) = result[0];
+            assert_eq!((weekday, hour), (0, 18));
+            // A single outlier among n equal values has z = sqrt(n - 1)
+            assert!((z - 17.0_f64.sqrt()).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_detect_anomalies_negative_outlier() {
+            let data = flat_day_with(12, 0.5, 10);
+
+            let result = detect_anomalies(&data, 3.0);
+
+            assert_eq!(result.len(), 1);
+            assert_eq!((result[0].0, result[0].1), (0, 12));
+            assert!(result[0].2 < 0.0);
+        }
+
+        #[test]
+        fn test_detect_anomalies_ignores_low_sample_slots() {
+            let data = flat_day_with(18, 90.0, 1);
+            assert!(detect_anomalies(&data, 3.0).is_empty());
+        }
+
+        #[test]
+        fn test_detect_anomalies_uniform_data() {
+            let data = flat_day_with(18, 30.0, 10);
+            assert!(detect_anomalies(&data, 1.0).is_empty());
+            assert!(detect_anomalies(&[], 1.0).is_empty());
+        }
+
+        #[test]
+        fn test_generate_insights_reports_anomaly() {
+            let data = flat_day_with(18, 90.0, 10);
+
+            let insights = generate_insights(&data, None);
+            let anomaly = insights
+                .iter()
+                .find(|i| i.category == InsightCategory::Anomaly)
+                .expect("outlier should produce an anomaly insight");
+
+            assert!(anomaly.title.contains("Mon 18:00"));
+            assert!(anomaly.description.contains("busier"));
+        }
     }
 
     // ==================== Insight Generation Tests ====================
@@ -1876,6 +4284,42 @@ mod tests {
             }
         }
 
+        #[test]
+        fn test_weekly_summary_days_match_data() {
+            // Tuesday busiest, Saturday quietest, Sunday without samples
+            let current: Vec<HourlyAverage> = (0..6)
+                .flat_map(|weekday| {
+                    let base = match weekday {
+                        1 => 70.0,
+                        5 => 15.0,
+                        _ => 45.0,
+                    };
+                    (8..12).map(move |hour| make_hourly_avg(weekday, hour, base + hour as f64, 10))
+                })
+                .collect();
+
+            // Same as the previous week, so the trend is stable
+            let summary = weekly_summary(&current, &current);
+
+            let days = analyze_days(&current);
+            let busiest = summary.busiest_day.as_ref().unwrap();
+            let quietest = summary.quietest_day.as_ref().unwrap();
+            assert_eq!(busiest.weekday, 1);
+            assert_eq!(busiest.avg_occupancy, days[1].avg_occupancy);
+            assert_eq!(quietest.weekday, 5);
+            assert_eq!(quietest.avg_occupancy, days[5].avg_occupancy);
+
+            let window = summary.best_window.as_ref().unwrap();
+            assert_eq!(
+                (window.weekday, window.start_hour, window.end_hour),
+                (5, 8, 24)
+            );
+            assert_eq!(summary.trend, TrendDirection::Stable);
+            assert_eq!(summary.change_percent, 0.0);
+            assert!(summary.top_insight.is_some());
+            assert!(serde_json::to_string(&summary).is_ok());
+        }
+
         #[test]
         fn test_generate_insights_empty_data() {
             let result = generate_insights(&[], None);
@@ -1929,6 +4373,75 @@ mod tests {
             assert!(result.iter().any(|i| i.category == InsightCategory::Trend));
         }
 
+        #[test]
+        fn test_generate_insights_lang() {
+            let data: Vec<HourlyAverage> = (0..7)
+                .flat_map(|weekday| {
+                    (8..20).map(move |hour| {
+                        make_hourly_avg(weekday, hour, (20 + hour * 3 + weekday) as f64, 10)
+                    })
+                })
+                .collect();
+
+            let english = generate_insights(&data, None);
+            let german = generate_insights_lang(&data, None, Language::German);
+
+            assert_eq!(english.len(), german.len());
+            assert!(
+                english
+                    .iter()
+                    .any(|i| i.title == "Sunday is the busiest day")
+            );
+            assert!(
+                german
+                    .iter()
+                    .any(|i| i.title == "Sonntag ist der vollste Tag")
+            );
+            assert!(
+                german
+                    .iter()
+                    .any(|i| i.title == "Montag ist der ruhigste Tag")
+            );
+        }
+
+        #[test]
+        fn test_insights_to_json() {
+            let insight = Insight {
+                category: InsightCategory::QuietTime,
+                importance: 5,
+                title: "Best workout window".to_string(),
+                description: "Quiet".to_string(),
+                data: Some((0, 10, 12.5)),
+            };
+
+            let json = insights_to_json(&[insight]).unwrap();
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+            let first = &value[0];
+            assert_eq!(first["category"], "QuietTime");
+            assert_eq!(first["importance"], 5);
+            assert_eq!(first["title"], "Best workout window");
+            assert_eq!(first["data"], serde_json::json!([0, 10, 12.5]));
+        }
+
+        #[test]
+        fn test_period_comparison_serializes() {
+            let baseline = vec![make_hourly_avg(0, 10, 40.0, 10)];
+            let current = vec![make_hourly_avg(0, 10, 50.0, 10)];
+            let comparison =
+                compare_periods(&baseline, &current, ComparisonMode::WeekOverWeek, None);
+
+            let value = serde_json::to_value(&comparison).unwrap();
+
+            assert_eq!(value["mode"], "WeekOverWeek");
+            assert_eq!(value["overall_trend"], "Insufficient");
+            assert_eq!(value["hourly_comparisons"][0]["percent_change"], 25.0);
+            assert!(value.get("biggest_increases").is_some());
+
+            let stats = serde_json::to_value(calculate_stats(&current).unwrap()).unwrap();
+            assert_eq!(stats["mean"], 50.0);
+        }
+
         #[test]
         fn test_insights_sorted_by_importance() {
             let data: Vec<HourlyAverage> = (0..7)
@@ -1975,5 +4488,26 @@ mod tests {
             assert_eq!(weekday_short(6), "Sun");
             assert_eq!(weekday_short(7), "???");
         }
+
+        #[test]
+        fn test_weekday_name_lang() {
+            assert_eq!(weekday_name_lang(0, Language::German), "Montag");
+            assert_eq!(weekday_name_lang(6, Language::German), "Sonntag");
+            assert_eq!(weekday_name_lang(7, Language::German), "Unbekannt");
+            assert_eq!(weekday_name_lang(0, Language::English), weekday_name(0));
+        }
+
+        #[test]
+        fn test_weekday_short_lang() {
+            assert_eq!(weekday_short_lang(0, Language::German), "Mo");
+            assert_eq!(weekday_short_lang(3, Language::German), "Do");
+            assert_eq!(weekday_short_lang(6, Language::German), "So");
+            assert_eq!(weekday_short_lang(2, Language::English), weekday_short(2));
+        }
+
+        #[test]
+        fn test_language_default_is_english() {
+            assert_eq!(Language::default(), Language::English);
+        }
     }
 }
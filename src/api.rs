@@ -1,9 +1,13 @@
-use std::time::Duration;
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, ensure};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
 use serde::Deserialize;
 
-use crate::config::NetworkConfig;
+use crate::config::{GymConfig, GymEntry, NetworkConfig};
+
+/// User-Agent identifying the monitor to gym operators.
+pub const DEFAULT_USER_AGENT: &str = concat!("hardy-monitor/", env!("CARGO_PKG_VERSION"));
 
 /// Response structure from the gym API.
 /// Fields preserved for API contract completeness even if not currently used.
@@ -25,6 +29,131 @@ impl GymResponse {
             .parse::<f64>()
             .context("Failed to parse occupancy percentage from numval")
     }
+
+    /// Parse the occupancy value like `occupancy_percentage`, clamped to
+    /// 0-100 so API glitches cannot skew averages and predictions.
+    pub fn occupancy_percentage_clamped(&self) -> Result<f64> {
+        clamp_percentage(self.occupancy_percentage()?)
+    }
+}
+
+/// Clamp a parsed occupancy percentage to 0-100.
+///
+/// Non-finite values (`NaN`, `inf`) carry no information and are rejected.
+pub fn clamp_percentage(raw: f64) -> Result<f64> {
+    ensure!(
+        raw.is_finite(),
+        "Occupancy percentage is not a finite number: {}",
+        raw
+    );
+    Ok(raw.clamp(0.0, 100.0))
+}
+
+/// Tracks consecutive identical readings to spot a frozen gym portal.
+#[derive(Debug, Clone, Default)]
+pub struct StaleDetector {
+    last: Option<f64>,
+    /// Readings in a row equal to `last`, including it
+    repeats: usize,
+}
+
+impl StaleDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a raw reading and return whether it is stale: the same value
+    /// for at least `threshold` consecutive fetches. A `threshold` of 0
+    /// disables detection.
+    pub fn observe(&mut self, value: f64, threshold: usize) -> bool {
+        if self.last == Some(value) {
+            self.repeats += 1;
+        } else {
+            self.last = Some(value);
+            self.repeats = 1;
+        }
+        threshold > 0 && self.repeats >= threshold
+    }
+
+    /// Number of consecutive identical readings so far.
+    pub fn repeats(&self) -> usize {
+        self.repeats
+    }
+}
+
+/// Extracts the occupancy percentage from a gym API response body.
+pub trait OccupancyParser: Debug + Send + Sync {
+    fn parse(&self, body: &str) -> Result<f64>;
+}
+
+/// Aidoo portal JSON with the percentage in `numval`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AidooParser;
+
+impl OccupancyParser for AidooParser {
+    fn parse(&self, body: &str) -> Result<f64> {
+        serde_json::from_str::<GymResponse>(body)
+            .context("Failed to parse gym API response")?
+            .occupancy_percentage()
+    }
+}
+
+/// A bare percentage such as `45.5` or `"45.5%"`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawPercentParser;
+
+impl OccupancyParser for RawPercentParser {
+    fn parse(&self, body: &str) -> Result<f64> {
+        body.trim()
+            .trim_matches('"')
+            .trim_end_matches('%')
+            .trim()
+            .parse::<f64>()
+            .context("Failed to parse raw occupancy percentage")
+    }
+}
+
+/// JSON with visitor `count` and `capacity`, converted to a percentage.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountCapacityParser;
+
+#[derive(Deserialize)]
+struct CountCapacity {
+    count: f64,
+    capacity: f64,
+}
+
+impl OccupancyParser for CountCapacityParser {
+    fn parse(&self, body: &str) -> Result<f64> {
+        let CountCapacity { count, capacity } =
+            serde_json::from_str(body).context("Failed to parse count/capacity response")?;
+        ensure!(
+            capacity > 0.0,
+            "Gym capacity must be positive, got {}",
+            capacity
+        );
+        Ok(count / capacity * 100.0)
+    }
+}
+
+/// Response format of a gym API, selecting its parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiFormat {
+    #[default]
+    Aidoo,
+    RawPercent,
+    CountCapacity,
+}
+
+impl ApiFormat {
+    pub fn parser(self) -> Box<dyn OccupancyParser> {
+        match self {
+            ApiFormat::Aidoo => Box::new(AidooParser),
+            ApiFormat::RawPercent => Box::new(RawPercentParser),
+            ApiFormat::CountCapacity => Box::new(CountCapacityParser),
+        }
+    }
 }
 
 /// API client for fetching gym data.
@@ -32,45 +161,163 @@ impl GymResponse {
 pub struct GymApiClient {
     client: reqwest::Client,
     url: String,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    parser: Arc<dyn OccupancyParser>,
+}
+
+/// Configured headers and bearer token, marked sensitive so that
+/// reqwest redacts their values in debug output.
+fn request_headers(network_config: &NetworkConfig) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &network_config.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name: {}", name))?;
+        let mut value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header {}", name))?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    if let Some(token) = &network_config.bearer_token {
+        let mut value =
+            HeaderValue::from_str(&format!("Bearer {}", token)).context("Invalid bearer token")?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    Ok(headers)
+}
+
+/// A failed request attempt.
+struct AttemptError {
+    error: anyhow::Error,
+    /// Timeouts, connection failures and 5xx/429 responses may succeed later
+    retryable: bool,
+}
+
+impl AttemptError {
+    fn new(error: anyhow::Error, retryable: bool) -> Self {
+        Self { error, retryable }
+    }
 }
 
 impl GymApiClient {
-    /// Create a new API client with configurable timeouts.
+    /// Create a new API client with configurable timeouts and retries.
     pub fn new(url: String, network_config: &NetworkConfig) -> Result<Self> {
-        let client = reqwest::Client::builder()
+        let mut builder = reqwest::Client::builder()
             .timeout(Duration::from_secs(network_config.request_timeout_secs))
             .connect_timeout(Duration::from_secs(network_config.connect_timeout_secs))
-            .build()
-            .context("Failed to create HTTP client")?;
+            .default_headers(request_headers(network_config)?);
+        if let Some(user_agent) = &network_config.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        let client = builder.build().context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            url,
+            max_retries: network_config.max_retries,
+            retry_base_delay: Duration::from_millis(network_config.retry_base_delay_ms),
+            parser: Arc::new(AidooParser),
+        })
+    }
+
+    /// Parse responses with `parser` instead of the Aidoo format.
+    pub fn with_parser(mut self, parser: Box<dyn OccupancyParser>) -> Self {
+        self.parser = Arc::from(parser);
+        self
+    }
 
-        Ok(Self { client, url })
+    /// Create one client per configured gym, paired with its entry.
+    pub fn for_gyms(
+        gym_config: &GymConfig,
+        network_config: &NetworkConfig,
+    ) -> Result<Vec<(GymEntry, Self)>> {
+        gym_config
+            .entries()
+            .into_iter()
+            .map(|entry| {
+                let client = Self::new(entry.api_url.clone(), network_config)
+                    .with_context(|| format!("Failed to create API client for gym {}", entry.id))?
+                    .with_parser(gym_config.api_format.parser());
+                Ok((entry, client))
+            })
+            .collect()
     }
 
-    /// Fetch the current gym occupancy data.
+    /// Fetch the current gym occupancy data in the Aidoo format.
     pub async fn fetch_occupancy(&self) -> Result<GymResponse> {
-        let response = self
-            .client
-            .get(&self.url)
-            .send()
-            .await
-            .context("Failed to send request to gym API")?;
+        let body = self.fetch_body().await?;
+        serde_json::from_str(&body).context("Failed to parse gym API response")
+    }
+
+    /// Fetch the current occupancy percentage using the configured parser.
+    pub async fn fetch_percentage(&self) -> Result<f64> {
+        let body = self.fetch_body().await?;
+        self.parser.parse(&body)
+    }
+
+    /// Fetch the raw response body.
+    ///
+    /// Transient failures are retried up to `max_retries` times with
+    /// exponential backoff; client errors fail at once.
+    async fn fetch_body(&self) -> Result<String> {
+        let mut attempt = 0;
+        loop {
+            match self.try_fetch().await {
+                Ok(data) => return Ok(data),
+                Err(failure) if failure.retryable && attempt < self.max_retries => {
+                    let delay = self
+                        .retry_base_delay
+                        .saturating_mul(2u32.saturating_pow(attempt));
+                    attempt += 1;
+                    tracing::debug!(
+                        "Gym API request failed ({:#}), retry {}/{} in {:?}",
+                        failure.error,
+                        attempt,
+                        self.max_retries,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(failure) => return Err(failure.error),
+            }
+        }
+    }
+
+    /// Make a single request to the gym API.
+    async fn try_fetch(&self) -> Result<String, AttemptError> {
+        let response = self.client.get(&self.url).send().await.map_err(|e| {
+            let retryable = e.is_timeout() || e.is_connect();
+            AttemptError::new(
+                anyhow::Error::new(e).context("Failed to send request to gym API"),
+                retryable,
+            )
+        })?;
 
         let status = response.status();
         if !status.is_success() {
-            anyhow::bail!("API returned error status: {}", status);
+            let retryable =
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+            return Err(AttemptError::new(
+                anyhow::anyhow!("API returned error status: {}", status),
+                retryable,
+            ));
         }
 
-        let data = response
-            .json::<GymResponse>()
-            .await
-            .context("Failed to parse gym API response")?;
-
-        Ok(data)
+        response.text().await.map_err(|e| {
+            let retryable = e.is_timeout();
+            AttemptError::new(
+                anyhow::Error::new(e).context("Failed to read gym API response"),
+                retryable,
+            )
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
 
     // ==================== GymResponse Parsing Tests ====================
@@ -155,6 +402,33 @@ mod tests {
         assert_eq!(result.unwrap(), -5.0);
     }
 
+    #[test]
+    fn test_occupancy_percentage_clamped_negative() {
+        let response = make_response("-10.5");
+        assert_eq!(response.occupancy_percentage_clamped().unwrap(), 0.0);
+        // The raw value stays available
+        assert_eq!(response.occupancy_percentage().unwrap(), -10.5);
+    }
+
+    #[test]
+    fn test_occupancy_percentage_clamped_over_hundred() {
+        let response = make_response("9999.99");
+        assert_eq!(response.occupancy_percentage_clamped().unwrap(), 100.0);
+    }
+
+    #[test]
+    fn test_occupancy_percentage_clamped_in_range() {
+        let response = make_response("42.5");
+        assert_eq!(response.occupancy_percentage_clamped().unwrap(), 42.5);
+    }
+
+    #[test]
+    fn test_occupancy_percentage_clamped_rejects_non_finite() {
+        assert!(make_response("NaN").occupancy_percentage_clamped().is_err());
+        assert!(make_response("inf").occupancy_percentage_clamped().is_err());
+        assert!(make_response("abc").occupancy_percentage_clamped().is_err());
+    }
+
     #[test]
     fn test_occupancy_percentage_scientific_notation() {
         let response = make_response("1e2");
@@ -163,6 +437,89 @@ mod tests {
         assert_eq!(result.unwrap(), 100.0);
     }
 
+    // ==================== Stale Detection Tests ====================
+
+    #[test]
+    fn test_stale_detector_flags_run_of_identical_readings() {
+        let mut detector = StaleDetector::new();
+
+        let stale: Vec<bool> = [42.0; 5].iter().map(|&v| detector.observe(v, 3)).collect();
+
+        assert_eq!(stale, [false, false, true, true, true]);
+        assert_eq!(detector.repeats(), 5);
+    }
+
+    #[test]
+    fn test_stale_detector_resets_on_change() {
+        let mut detector = StaleDetector::new();
+        for _ in 0..3 {
+            detector.observe(42.0, 3);
+        }
+
+        assert!(!detector.observe(43.0, 3));
+        assert_eq!(detector.repeats(), 1);
+        assert!(!detector.observe(43.0, 3));
+        assert!(detector.observe(43.0, 3));
+    }
+
+    #[test]
+    fn test_stale_detector_disabled_with_zero_threshold() {
+        let mut detector = StaleDetector::new();
+        assert!((0..100).all(|_| !detector.observe(42.0, 0)));
+    }
+
+    // ==================== Response Parser Tests ====================
+
+    #[test]
+    fn test_aidoo_parser() {
+        let body = r#"{"gym": 3, "name": "Hardy's", "workload": "45%", "numval": "45.5"}"#;
+        assert_eq!(AidooParser.parse(body).unwrap(), 45.5);
+    }
+
+    #[test]
+    fn test_aidoo_parser_rejects_other_shape() {
+        assert!(
+            AidooParser
+                .parse(r#"{"count": 42, "capacity": 120}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_raw_percent_parser() {
+        assert_eq!(RawPercentParser.parse("45.5").unwrap(), 45.5);
+        assert_eq!(RawPercentParser.parse(" 72\n").unwrap(), 72.0);
+        assert_eq!(RawPercentParser.parse(r#""30%""#).unwrap(), 30.0);
+    }
+
+    #[test]
+    fn test_raw_percent_parser_rejects_text() {
+        assert!(RawPercentParser.parse("busy").is_err());
+    }
+
+    #[test]
+    fn test_count_capacity_parser() {
+        let body = r#"{"count": 42, "capacity": 120, "updated": "2024-06-11T10:00:00Z"}"#;
+        assert!((CountCapacityParser.parse(body).unwrap() - 35.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_count_capacity_parser_rejects_zero_capacity() {
+        assert!(
+            CountCapacityParser
+                .parse(r#"{"count": 5, "capacity": 0}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_api_format_selects_parser() {
+        let body = r#"{"count": 30, "capacity": 60}"#;
+        assert_eq!(ApiFormat::CountCapacity.parser().parse(body).unwrap(), 50.0);
+        assert!(ApiFormat::Aidoo.parser().parse(body).is_err());
+        assert_eq!(ApiFormat::RawPercent.parser().parse("12").unwrap(), 12.0);
+    }
+
     // ==================== GymApiClient Construction Tests ====================
 
     #[test]
@@ -170,6 +527,7 @@ mod tests {
         let config = NetworkConfig {
             request_timeout_secs: 30,
             connect_timeout_secs: 10,
+            ..Default::default()
         };
         let result = GymApiClient::new("https://example.com/api".to_string(), &config);
         assert!(result.is_ok());
@@ -180,8 +538,31 @@ mod tests {
         let config = NetworkConfig {
             request_timeout_secs: 60,
             connect_timeout_secs: 20,
+            ..Default::default()
         };
         let result = GymApiClient::new("https://test.example.com".to_string(), &config);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_api_client_debug_redacts_credentials() {
+        let config = NetworkConfig {
+            headers: HashMap::from([("X-Api-Key".to_string(), "key-123".to_string())]),
+            bearer_token: Some("token-456".to_string()),
+            ..Default::default()
+        };
+        let client = GymApiClient::new("https://example.com/api".to_string(), &config).unwrap();
+        let debug_str = format!("{:?}", client);
+        assert!(!debug_str.contains("key-123"));
+        assert!(!debug_str.contains("token-456"));
+    }
+
+    #[test]
+    fn test_api_client_rejects_invalid_header_name() {
+        let config = NetworkConfig {
+            headers: HashMap::from([("Bad Header".to_string(), "x".to_string())]),
+            ..Default::default()
+        };
+        assert!(GymApiClient::new("https://example.com/api".to_string(), &config).is_err());
+    }
 }
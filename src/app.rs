@@ -3,20 +3,30 @@ use std::{path::PathBuf, sync::Arc, time::Duration};
 use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, Utc};
 use hardy_monitor::{
     analytics::{
-        self, ComparisonMode, DayAnalysis, Insight, OccupancyStats, TrendDirection, analyze_days,
-        calculate_stats, compare_periods, find_peak_hours, find_quiet_hours, generate_insights,
+        self, BusynessLevel, ComparisonMode, DayAnalysis, Insight, OccupancyStats,
+        PeriodComparison, QuieterSide, TrendDirection, analyze_days, calculate_stats,
+        compare_periods, find_peak_hours, find_quiet_hours, generate_insights_lang,
         midnight_local_as_utc, midnight_utc,
     },
-    config::AppConfig,
-    db::{Database, HourlyAverage, OccupancyLog},
+    config::{AppConfig, GymEntry, ThemeMode},
+    daemon::{self, PredictiveAlert},
+    db::{Database, HourlyAverage, ImportSummary, OccupancyLog, estimated_count, write_csv},
     repair::DataRepairer,
     schedule::GymSchedule,
-    style,
-    traits::{Clock, Notifier},
-    widgets::{gauge::GaugeWidget, heatmap::HeatmapWidget, history_chart::HistoryChart},
+    style::Palette,
+    traits::{AlertSound, AsyncNotifier, Clock, NotificationGate, SoundSink, ThresholdAlerts},
+    widgets::{
+        comparison_chart::ComparisonChartWidget,
+        gauge::{GaugeWidget, get_status_color},
+        heatmap::{HeatmapMode, HeatmapWidget},
+        history_chart::{ConfidencePoint, HistoryChart, HistoryChartImage},
+        sparkline::{level_rgba, render_sparkline, render_status_dot, status_rgba},
+    },
 };
 use iced::{
     Alignment, Border, Color, Element, Length, Shadow, Subscription, Task, Theme, Vector,
+    futures::SinkExt,
+    keyboard, theme,
     widget::{
         Space, button,
         canvas::{Cache, Canvas},
@@ -26,7 +36,8 @@ use iced::{
 };
 use muda::MenuEvent;
 use thiserror::Error;
-use tray_icon::{TrayIcon, TrayIconEvent};
+use tokio::sync::mpsc;
+use tray_icon::{Icon, TrayIcon, TrayIconEvent};
 
 /// Typed Application Errors
 #[derive(Debug, Clone, Error)]
@@ -43,6 +54,22 @@ pub enum AppError {
     Unknown(String),
 }
 
+/// History ranges longer than this are downsampled for the chart.
+const FULL_RESOLUTION_DAYS: i64 = 7;
+
+/// Points drawn for a downsampled history range.
+const HISTORY_CHART_POINTS: usize = 1000;
+
+/// Edge length of generated tray icons in pixels.
+const TRAY_ICON_SIZE: u32 = 32;
+
+/// Best times based on fewer samples are shown grayed out.
+const CONFIDENT_SAMPLE_COUNT: i64 = 10;
+
+/// Size of the image written by "Export PNG".
+const CHART_EXPORT_WIDTH: u32 = 1600;
+const CHART_EXPORT_HEIGHT: u32 = 800;
+
 // --- STATE STRUCTS ---
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -51,6 +78,7 @@ pub enum ViewMode {
     Dashboard,
     WeeklyPattern,
     Insights,
+    Comparison,
     DataRepair,
 }
 
@@ -63,6 +91,27 @@ pub enum AnalyticsRange {
     Last8Weeks,
 }
 
+impl AnalyticsRange {
+    /// The range covering `weeks` weeks, falling back to this week.
+    fn from_weeks(weeks: u32) -> Self {
+        match weeks {
+            2 => Self::Last2Weeks,
+            4 => Self::Last4Weeks,
+            8 => Self::Last8Weeks,
+            _ => Self::ThisWeek,
+        }
+    }
+
+    fn weeks(self) -> u32 {
+        match self {
+            Self::ThisWeek => 1,
+            Self::Last2Weeks => 2,
+            Self::Last4Weeks => 4,
+            Self::Last8Weeks => 8,
+        }
+    }
+}
+
 use hardy_monitor::repair::{RepairProgress, RepairSummary};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -76,6 +125,7 @@ struct RepairState {
     start_date: String,
     end_date: String,
     is_running: bool,
+    skip_repaired: bool,
     progress: Option<RepairProgress>,
     last_result: Option<Result<RepairSummary, AppError>>,
 }
@@ -83,11 +133,18 @@ struct RepairState {
 struct MonitorState {
     occupancy: Option<f64>,
     history: Vec<OccupancyLog>,
+    smoothed_history: Vec<(DateTime<Utc>, f64)>,
     last_update: Option<DateTime<Utc>>,
     analytics_data: Vec<HourlyAverage>,
-    best_time_today: Option<(i32, f64)>,
+    /// Whether the database holds any record for the gym. Assumed until the
+    /// first count, so the onboarding panel never flashes for existing users.
+    has_any_data: bool,
+    /// Quietest hour today as `(hour, avg, sample_count)`
+    best_time_today: Option<(i32, f64, i64)>,
     prediction_baseline: Vec<HourlyAverage>,
     predictions: Vec<(DateTime<Utc>, f64)>,
+    /// Predictions with their ±1 standard deviation band
+    prediction_band: Vec<ConfidencePoint>,
     // Insights data
     insights: Vec<Insight>,
     stats: Option<OccupancyStats>,
@@ -96,6 +153,11 @@ struct MonitorState {
     quiet_hours: Vec<(i32, i32, f64)>,
     trend: Option<TrendDirection>,
     baseline_for_comparison: Vec<HourlyAverage>,
+    /// Latest period comparison for the comparison view
+    comparison: Option<PeriodComparison>,
+    /// Quieter of the first two configured gyms per `(weekday, hour)` slot
+    gym_comparison: Vec<(i32, i32, QuieterSide)>,
+    stuck_run: Option<analytics::StuckRun>,
 }
 
 struct UiState {
@@ -105,22 +167,40 @@ struct UiState {
     gauge_cache: Cache,
     heatmap_cache: Cache,
     heatmap_tooltip_cache: Cache,
+    heatmap_mode: HeatmapMode,
+    comparison_cache: Cache,
+    comparison_tooltip_cache: Cache,
+    comparison_mode: ComparisonMode,
     current_view: ViewMode,
     analytics_range: AnalyticsRange,
     history_start_date: String,
     history_end_date: String,
     history_days_preset: Option<i64>,
     is_window_visible: bool,
+    /// Color index of the status icon currently in the tray
+    tray_color_index: Option<usize>,
+    last_schedule_check: DateTime<Local>,
+    theme_mode: ThemeMode,
+    /// Last reported OS preference, used by `ThemeMode::System`
+    system_theme: theme::Mode,
 }
 
 struct NotificationState {
     threshold: f64,
     enabled: bool,
-    was_below_threshold: bool,
+    alerts: ThresholdAlerts,
+    /// Cooldown between "empty" alerts
+    gate: NotificationGate,
+    /// Cooldown between "busy" alerts
+    busy_gate: NotificationGate,
+    /// Also sent by the daemon; the GUI adds a desktop notification
+    predictive_alert: PredictiveAlert,
 }
 
 struct ExportState {
     status: Option<String>,
+    /// CSV file to backfill records from
+    import_path: String,
 }
 
 pub struct HardyMonitorApp {
@@ -128,8 +208,13 @@ pub struct HardyMonitorApp {
     config: Arc<AppConfig>,
     schedule: GymSchedule,
     clock: Arc<dyn Clock>,
-    notifier: Arc<dyn Notifier>,
-    _tray_icon: TrayIcon,
+    notifier: Arc<dyn AsyncNotifier>,
+    /// Played along with threshold alerts if `notifications.play_sound` is set
+    alert_sound: AlertSound,
+    tray_icon: TrayIcon,
+    /// Green, orange and red status icons, indexed by
+    /// `BusynessLevel::color_index`
+    tray_status_icons: Vec<Icon>,
     error: Option<AppError>,
 
     // Grouped State
@@ -150,22 +235,40 @@ pub enum Message {
 
     // Data Results
     FetchCompleted(Result<f64, AppError>),
-    HistoryLoaded(Result<Vec<OccupancyLog>, AppError>),
+    RecordCountLoaded(Result<i64, AppError>),
+    HistoryLoaded {
+        logs: Result<Vec<OccupancyLog>, AppError>,
+        /// Whether `logs` are bucket averages rather than raw readings
+        downsampled: bool,
+    },
     AnalyticsLoaded(Result<Vec<HourlyAverage>, AppError>),
     PredictionBaselineLoaded(Result<Vec<HourlyAverage>, AppError>),
+    SparklineLoaded(Result<Vec<OccupancyLog>, AppError>),
     InsightsDataLoaded {
         current: Result<Vec<HourlyAverage>, AppError>,
         baseline: Result<Vec<HourlyAverage>, AppError>,
     },
+    ComparisonDataLoaded {
+        mode: ComparisonMode,
+        current: Result<Vec<HourlyAverage>, AppError>,
+        baseline: Result<Vec<HourlyAverage>, AppError>,
+    },
+    GymComparisonLoaded(Result<Vec<(i32, i32, QuieterSide)>, AppError>),
 
     // Notifications
     NotificationThresholdChanged(f64),
     NotificationToggled(bool),
     NotificationSent,
+    SaveSettings,
+    SettingsSaved(Result<PathBuf, AppError>),
 
     // Navigation & View
     SwitchView(ViewMode),
     SwitchAnalyticsRange(AnalyticsRange),
+    SwitchComparisonMode(ComparisonMode),
+    ToggleHeatmapMode,
+    ToggleTheme,
+    SystemThemeChanged(theme::Mode),
     HistoryStartDateChanged(String),
     HistoryEndDateChanged(String),
     HistoryPresetSelected(i64),
@@ -173,7 +276,11 @@ pub enum Message {
 
     // Export & System
     ExportCsv,
+    ExportChartPng,
     ExportCompleted(Result<String, AppError>),
+    ImportPathChanged(String),
+    ImportCsv,
+    ImportCompleted(Result<ImportSummary, AppError>),
     ClearExportStatus,
     TrayCheck,
     WindowCloseRequested,
@@ -182,6 +289,7 @@ pub enum Message {
     RepairStartDateChanged(String),
     RepairEndDateChanged(String),
     RepairPresetSelected(RepairPreset),
+    RepairSkipToggled(bool),
     StartRepairJob,
     RepairProgress(RepairProgress),
     RepairCompleted(Result<RepairSummary, AppError>),
@@ -193,7 +301,8 @@ impl HardyMonitorApp {
         tray_icon: TrayIcon,
         config: Arc<AppConfig>,
         clock: Arc<dyn Clock>,
-        notifier: Arc<dyn Notifier>,
+        notifier: Arc<dyn AsyncNotifier>,
+        sound: Arc<dyn SoundSink>,
     ) -> (Self, Task<Message>) {
         let db = Arc::new(db);
         let now = clock.now_utc();
@@ -210,16 +319,21 @@ impl HardyMonitorApp {
             schedule,
             clock: clock.clone(),
             notifier,
-            _tray_icon: tray_icon,
+            alert_sound: AlertSound::new(sound, config.notifications.play_sound),
+            tray_icon,
+            tray_status_icons: render_tray_status_icons(),
             error: None,
             data: MonitorState {
                 occupancy: None,
                 history: Vec::new(),
+                smoothed_history: Vec::new(),
                 last_update: None,
                 analytics_data: Vec::new(),
+                has_any_data: true,
                 best_time_today: None,
                 prediction_baseline: Vec::new(),
                 predictions: Vec::new(),
+                prediction_band: Vec::new(),
                 insights: Vec::new(),
                 stats: None,
                 day_analysis: Vec::new(),
@@ -227,6 +341,9 @@ impl HardyMonitorApp {
                 quiet_hours: Vec::new(),
                 trend: None,
                 baseline_for_comparison: Vec::new(),
+                comparison: None,
+                gym_comparison: Vec::new(),
+                stuck_run: None,
             },
             ui: UiState {
                 is_loading: false,
@@ -235,38 +352,66 @@ impl HardyMonitorApp {
                 gauge_cache: Cache::new(),
                 heatmap_cache: Cache::new(),
                 heatmap_tooltip_cache: Cache::new(),
+                heatmap_mode: HeatmapMode::default(),
+                comparison_cache: Cache::new(),
+                comparison_tooltip_cache: Cache::new(),
+                comparison_mode: ComparisonMode::WeekOverWeek,
                 current_view: ViewMode::default(),
-                analytics_range: AnalyticsRange::default(),
+                analytics_range: AnalyticsRange::from_weeks(config.analytics.range_weeks),
                 history_start_date: today_str.clone(),
                 history_end_date: tomorrow_str.clone(),
                 history_days_preset: Some(1),
                 is_window_visible: true,
+                tray_color_index: None,
+                last_schedule_check: clock.now_local(),
+                theme_mode: config.ui.theme,
+                system_theme: theme::Mode::None,
             },
             notifications: NotificationState {
                 threshold: config.notifications.threshold_percent,
                 enabled: config.notifications.enabled,
-                was_below_threshold: false,
+                alerts: ThresholdAlerts::default(),
+                gate: NotificationGate::new(
+                    config.notifications.min_notification_interval_secs,
+                    clock.clone(),
+                ),
+                busy_gate: NotificationGate::new(
+                    config.notifications.min_notification_interval_secs,
+                    clock.clone(),
+                ),
+                predictive_alert: PredictiveAlert::default(),
+            },
+            export: ExportState {
+                status: None,
+                import_path: String::new(),
             },
-            export: ExportState { status: None },
             repair: RepairState {
                 start_date: today_str.clone(),
                 end_date: tomorrow_str,
                 is_running: false,
+                skip_repaired: true,
                 progress: None,
                 last_result: None,
             },
         };
 
         let prediction_days = config.analytics.prediction_window_days;
+        let adaptive_baseline = config.analytics.adaptive_baseline;
         let clock_for_tasks = clock.clone();
         let initial_tasks = vec![
+            Self::load_record_count(db.clone()),
             Self::load_history(db.clone()),
             Self::load_analytics(
                 db.clone(),
-                AnalyticsRange::ThisWeek,
+                AnalyticsRange::from_weeks(config.analytics.range_weeks),
                 clock_for_tasks.clone(),
             ),
-            Self::load_prediction_baseline(db.clone(), prediction_days, clock_for_tasks),
+            Self::load_prediction_baseline(
+                db.clone(),
+                prediction_days,
+                clock_for_tasks,
+                adaptive_baseline,
+            ),
         ];
 
         let seconds_to_next_minute = 60 - now.timestamp() % 60;
@@ -277,18 +422,44 @@ impl HardyMonitorApp {
             |_| Message::FetchAlignmentComplete,
         );
 
+        let system_theme_task = iced::system::theme().map(Message::SystemThemeChanged);
+
         (
             app,
-            Task::batch([Task::batch(initial_tasks), alignment_task]),
+            Task::batch([
+                Task::batch(initial_tasks),
+                alignment_task,
+                system_theme_task,
+            ]),
         )
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Tick => {
-                self.data.predictions =
-                    analytics::calculate_predictions(&self.data.prediction_baseline);
-                Task::none()
+                self.refresh_predictions();
+
+                // Don't leave the dashboard showing "closed" until the next aligned fetch
+                let now = self.clock.now_local();
+                let just_opened = self.config.refresh.prefetch_on_open
+                    && self
+                        .schedule
+                        .opened_between(&self.ui.last_schedule_check, &now);
+                self.ui.last_schedule_check = now;
+
+                let mut tasks = Vec::new();
+                if just_opened {
+                    self.ui.is_loading = true;
+                    tasks.push(Self::fetch_latest_from_db(self.db.clone()));
+                }
+                if self.config.window.tray_sparkline {
+                    tasks.push(Self::load_sparkline(self.db.clone()));
+                }
+                // Notice when the daemon stores its first readings
+                if !self.data.has_any_data {
+                    tasks.push(Self::load_record_count(self.db.clone()));
+                }
+                Task::batch(tasks)
             }
             Message::ChartInteraction => Task::none(),
             Message::FetchAlignmentComplete => {
@@ -299,6 +470,7 @@ impl HardyMonitorApp {
                 } else {
                     self.data.occupancy = None;
                     self.ui.is_loading = false;
+                    self.update_tray_tooltip();
                     Task::none()
                 }
             }
@@ -309,6 +481,7 @@ impl HardyMonitorApp {
                 } else {
                     self.data.occupancy = None;
                     self.ui.is_loading = false;
+                    self.update_tray_tooltip();
                     Task::none()
                 }
             }
@@ -316,7 +489,9 @@ impl HardyMonitorApp {
                 self.ui.is_loading = true;
                 self.error = None;
                 let prediction_days = self.config.analytics.prediction_window_days;
+                let adaptive_baseline = self.config.analytics.adaptive_baseline;
                 Task::batch([
+                    Self::load_record_count(self.db.clone()),
                     Self::fetch_latest_from_db(self.db.clone()),
                     Self::load_history(self.db.clone()),
                     Self::load_analytics(
@@ -328,6 +503,7 @@ impl HardyMonitorApp {
                         self.db.clone(),
                         prediction_days,
                         self.clock.clone(),
+                        adaptive_baseline,
                     ),
                 ])
             }
@@ -339,13 +515,20 @@ impl HardyMonitorApp {
                         self.data.last_update = Some(self.clock.now_utc());
                         self.error = None;
                         self.ui.gauge_cache.clear();
+                        self.update_tray_tooltip();
+                        if !self.config.window.tray_sparkline {
+                            self.update_tray_status_icon(percentage);
+                        }
 
                         // Update predictions
-                        self.data.predictions =
-                            analytics::calculate_predictions(&self.data.prediction_baseline);
+                        self.refresh_predictions();
 
                         // Notifications
-                        let is_below = percentage < self.notifications.threshold;
+                        let crossings = self.notifications.alerts.update(
+                            percentage,
+                            self.notifications.threshold,
+                            self.config.notifications.high_threshold_percent,
+                        );
 
                         // NEW: Always refresh history AND analytics on new data
                         // This ensures the view is always up to date, including at hour marks
@@ -359,21 +542,42 @@ impl HardyMonitorApp {
                         ];
 
                         if self.notifications.enabled
-                            && is_below
-                            && !self.notifications.was_below_threshold
+                            && crossings.empty
+                            && self.notifications.gate.allow()
                         {
                             let notifier = self.notifier.clone();
+                            let sound = self.alert_sound.clone();
                             tasks.push(Task::perform(
                                 async move {
-                                    let _ = notifier.notify(
-                                        "Hardy's Gym Monitor",
-                                        &format!("Gym is empty! {:.0}%", percentage),
+                                    let body = format!("Gym is empty! {:.0}%", percentage);
+                                    let _ = tokio::join!(
+                                        notifier.notify_async("Hardy's Gym Monitor", &body),
+                                        sound.play()
                                     );
                                 },
                                 |_| Message::NotificationSent,
                             ));
                         }
-                        self.notifications.was_below_threshold = is_below;
+                        if self.notifications.enabled
+                            && self.config.notifications.high_threshold_alert
+                            && crossings.busy
+                            && self.notifications.busy_gate.allow()
+                        {
+                            let notifier = self.notifier.clone();
+                            let sound = self.alert_sound.clone();
+                            tasks.push(Task::perform(
+                                async move {
+                                    let body = format!("Gym is busy! {:.0}%", percentage);
+                                    let _ = tokio::join!(
+                                        notifier.notify_async("Hardy's Gym Monitor", &body),
+                                        sound.play()
+                                    );
+                                },
+                                |_| Message::NotificationSent,
+                            ));
+                        }
+
+                        tasks.extend(self.predictive_alert_task());
                         Task::batch(tasks)
                     }
                     Err(e) => {
@@ -382,12 +586,51 @@ impl HardyMonitorApp {
                     }
                 }
             }
-            Message::HistoryLoaded(result) => {
+            Message::RecordCountLoaded(result) => {
+                match result {
+                    Ok(count) => {
+                        let had_data = self.data.has_any_data;
+                        self.data.has_any_data = count > 0;
+                        // The first readings arrived, so load everything
+                        if !had_data && self.data.has_any_data {
+                            return self.update(Message::RefreshNow);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to count records: {}", e),
+                }
+                Task::none()
+            }
+            Message::HistoryLoaded {
+                logs: result,
+                downsampled,
+            } => {
                 if let Ok(logs) = result {
+                    // Bucket averages don't repeat like a stuck sensor does
+                    self.data.stuck_run = if downsampled {
+                        None
+                    } else {
+                        analytics::detect_stuck_runs(
+                            &logs,
+                            self.config.analytics.stuck_sensor_min_readings,
+                        )
+                        .pop()
+                    };
+                    if let Some(run) = &self.data.stuck_run {
+                        tracing::warn!(
+                            "Possible stuck sensor: {:.1}% repeated {} times",
+                            run.value,
+                            run.length
+                        );
+                    }
+                    let window = self.config.analytics.smoothing_window_minutes;
+                    self.data.smoothed_history = if window > 0 {
+                        analytics::smooth_moving_average(&logs, window)
+                    } else {
+                        Vec::new()
+                    };
                     self.data.history = logs;
                     self.ui.chart_cache.clear();
-                    self.data.predictions =
-                        analytics::calculate_predictions(&self.data.prediction_baseline);
+                    self.refresh_predictions();
                 } else if let Err(e) = result {
                     self.error = Some(e);
                 }
@@ -398,7 +641,7 @@ impl HardyMonitorApp {
                     self.data.analytics_data = data;
                     self.ui.heatmap_cache.clear();
                     self.data.best_time_today =
-                        analytics::find_best_time_today(&self.data.analytics_data);
+                        analytics::find_best_time_today_detailed(&self.data.analytics_data);
                 } else if let Err(e) = result {
                     self.error = Some(e);
                 }
@@ -407,8 +650,15 @@ impl HardyMonitorApp {
             Message::PredictionBaselineLoaded(result) => {
                 if let Ok(data) = result {
                     self.data.prediction_baseline = data;
-                    self.data.predictions =
-                        analytics::calculate_predictions(&self.data.prediction_baseline);
+                    self.refresh_predictions();
+                }
+                Task::none()
+            }
+            Message::SparklineLoaded(result) => {
+                match result {
+                    Ok(logs) if !logs.is_empty() => self.update_tray_sparkline(&logs),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to load sparkline data: {}", e),
                 }
                 Task::none()
             }
@@ -429,41 +679,177 @@ impl HardyMonitorApp {
                     if let Some(ref bl) = baseline_opt {
                         self.data.baseline_for_comparison = bl.clone();
                         let comparison =
-                            compare_periods(bl, &current_data, ComparisonMode::WeekOverWeek);
+                            compare_periods(bl, &current_data, ComparisonMode::WeekOverWeek, None);
                         self.data.trend = Some(comparison.overall_trend);
-                        self.data.insights = generate_insights(&current_data, Some(bl));
+                        self.data.insights = generate_insights_lang(
+                            &current_data,
+                            Some(bl),
+                            self.config.window.language,
+                        );
                     } else {
-                        self.data.insights = generate_insights(&current_data, None);
+                        self.data.insights = generate_insights_lang(
+                            &current_data,
+                            None,
+                            self.config.window.language,
+                        );
                         self.data.trend = None;
                     }
+
+                    if let Some(run) = &self.data.stuck_run {
+                        self.data
+                            .insights
+                            .insert(0, analytics::stuck_sensor_insight(run));
+                    }
+                }
+                Task::none()
+            }
+            Message::ComparisonDataLoaded {
+                mode,
+                current,
+                baseline,
+            } => {
+                // Ignore results for a mode that was switched away from meanwhile
+                if mode != self.ui.comparison_mode {
+                    return Task::none();
+                }
+                match (current, baseline) {
+                    (Ok(current), Ok(baseline)) => {
+                        self.data.comparison =
+                            Some(compare_periods(&baseline, &current, mode, None));
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        self.data.comparison = None;
+                        self.error = Some(e);
+                    }
+                }
+                self.ui.comparison_cache.clear();
+                self.ui.comparison_tooltip_cache.clear();
+                Task::none()
+            }
+            Message::GymComparisonLoaded(result) => {
+                match result {
+                    Ok(slots) => self.data.gym_comparison = slots,
+                    Err(e) => {
+                        self.data.gym_comparison.clear();
+                        self.error = Some(e);
+                    }
                 }
                 Task::none()
             }
             Message::NotificationThresholdChanged(val) => {
                 self.notifications.threshold = val;
+                // The probability heatmap is relative to the threshold
+                if let HeatmapMode::QuietProbability { .. } = self.ui.heatmap_mode {
+                    self.ui.heatmap_mode = HeatmapMode::QuietProbability { goal: val };
+                    self.ui.heatmap_cache.clear();
+                }
                 Task::none()
             }
             Message::NotificationToggled(enabled) => {
                 self.notifications.enabled = enabled;
-                self.notifications.was_below_threshold =
-                    self.data.occupancy.unwrap_or(100.0) < self.notifications.threshold;
+                self.notifications.alerts.reset(
+                    self.data.occupancy,
+                    self.notifications.threshold,
+                    self.config.notifications.high_threshold_percent,
+                );
                 Task::none()
             }
             Message::NotificationSent => Task::none(),
+            Message::SaveSettings => {
+                let mut config = (*self.config).clone();
+                config.notifications.enabled = self.notifications.enabled;
+                config.notifications.threshold_percent = self.notifications.threshold;
+                config.analytics.range_weeks = self.ui.analytics_range.weeks();
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            let path = AppConfig::user_config_path();
+                            config
+                                .save(&path)
+                                .map(|_| path)
+                                .map_err(|e| AppError::Io(format!("{:#}", e)))
+                        })
+                        .await
+                        .map_err(|e| AppError::Unknown(e.to_string()))?
+                    },
+                    Message::SettingsSaved,
+                )
+            }
+            Message::SettingsSaved(result) => {
+                match result {
+                    Ok(path) => {
+                        self.export.status = Some(format!("Settings saved to {}", path.display()))
+                    }
+                    Err(e) => {
+                        self.error = Some(e);
+                        self.export.status = Some("Saving settings failed".to_string());
+                    }
+                }
+                Task::perform(
+                    async {
+                        tokio::time::sleep(Duration::from_secs(4)).await;
+                    },
+                    |_| Message::ClearExportStatus,
+                )
+            }
             Message::SwitchView(mode) => {
                 self.ui.current_view = mode;
-                if mode == ViewMode::Insights {
+                match mode {
                     // Load data for insights when switching to that view
-                    Self::load_insights_data(self.db.clone(), self.clock.clone())
-                } else {
-                    Task::none()
+                    ViewMode::Insights => {
+                        Self::load_insights_data(self.db.clone(), self.clock.clone())
+                    }
+                    ViewMode::Comparison => Task::batch([
+                        Self::load_comparison_data(
+                            self.db.clone(),
+                            self.clock.clone(),
+                            self.ui.comparison_mode,
+                        ),
+                        Self::load_gym_comparison(
+                            self.db.clone(),
+                            self.clock.clone(),
+                            &self.config.gym.gyms,
+                        ),
+                    ]),
+                    _ => Task::none(),
                 }
             }
+            Message::SwitchComparisonMode(mode) => {
+                self.ui.comparison_mode = mode;
+                Self::load_comparison_data(self.db.clone(), self.clock.clone(), mode)
+            }
             Message::SwitchAnalyticsRange(range) => {
                 self.ui.analytics_range = range;
                 self.ui.heatmap_cache.clear();
                 Self::load_analytics(self.db.clone(), range, self.clock.clone())
             }
+            Message::ToggleHeatmapMode => {
+                self.ui.heatmap_mode = match self.ui.heatmap_mode {
+                    HeatmapMode::Occupancy => HeatmapMode::QuietProbability {
+                        goal: self.notifications.threshold,
+                    },
+                    HeatmapMode::QuietProbability { .. } => HeatmapMode::Occupancy,
+                };
+                self.ui.heatmap_cache.clear();
+                self.ui.heatmap_tooltip_cache.clear();
+                Task::none()
+            }
+            Message::ToggleTheme => {
+                self.ui.theme_mode = if self.theme().extended_palette().is_dark {
+                    ThemeMode::Light
+                } else {
+                    ThemeMode::Dark
+                };
+                self.clear_canvas_caches();
+                Task::none()
+            }
+            Message::SystemThemeChanged(mode) => {
+                self.ui.system_theme = mode;
+                if self.ui.theme_mode == ThemeMode::System {
+                    self.clear_canvas_caches();
+                }
+                Task::none()
+            }
             Message::HistoryStartDateChanged(d) => {
                 self.ui.history_start_date = d;
                 self.ui.history_days_preset = None;
@@ -536,6 +922,8 @@ impl HardyMonitorApp {
                 self.export.status = Some("Exporting...".to_string());
                 let db = self.db.clone();
                 let clock = self.clock.clone();
+                let columns = self.config.export.columns.clone();
+                let options = self.config.export.options();
                 Task::perform(
                     async move {
                         let logs = db
@@ -551,13 +939,51 @@ impl HardyMonitorApp {
                                     "hardy_monitor_export_{}.csv",
                                     export_time.format("%Y%m%d_%H%M%S")
                                 ));
-                                let mut wtr = csv::Writer::from_path(&path)
+                                let file = std::fs::File::create(&path)
+                                    .map_err(|e| AppError::Io(e.to_string()))?;
+                                write_csv(file, &logs, &columns, &options)
                                     .map_err(|e| AppError::Io(e.to_string()))?;
-                                for log in logs {
-                                    wtr.serialize(log)
-                                        .map_err(|e| AppError::Io(e.to_string()))?;
+                                Ok(path)
+                            })
+                            .await
+                            .map_err(|e| AppError::Unknown(e.to_string()))??;
+                        Ok(path.to_string_lossy().to_string())
+                    },
+                    Message::ExportCompleted,
+                )
+            }
+            Message::ExportChartPng => {
+                self.ui.is_loading = true;
+                self.export.status = Some("Exporting...".to_string());
+                let history = self.data.history.clone();
+                let smoothed = self.data.smoothed_history.clone();
+                let predictions = self.data.predictions.clone();
+                let (range_start, range_end) = self.history_chart_range();
+                let palette = self.palette();
+                let export_time = self.clock.now_utc();
+                Task::perform(
+                    async move {
+                        let path =
+                            tokio::task::spawn_blocking(move || -> Result<PathBuf, AppError> {
+                                let img = HistoryChartImage {
+                                    history: &history,
+                                    smoothed: &smoothed,
+                                    predictions: &predictions,
+                                    range_start,
+                                    range_end,
                                 }
-                                wtr.flush().map_err(|e| AppError::Io(e.to_string()))?;
+                                .render(
+                                    &palette,
+                                    CHART_EXPORT_WIDTH,
+                                    CHART_EXPORT_HEIGHT,
+                                );
+                                let mut path =
+                                    dirs::download_dir().unwrap_or_else(|| PathBuf::from("."));
+                                path.push(format!(
+                                    "hardy_monitor_chart_{}.png",
+                                    export_time.format("%Y%m%d_%H%M%S")
+                                ));
+                                img.save(&path).map_err(|e| AppError::Io(e.to_string()))?;
                                 Ok(path)
                             })
                             .await
@@ -583,6 +1009,45 @@ impl HardyMonitorApp {
                     |_| Message::ClearExportStatus,
                 )
             }
+            Message::ImportPathChanged(path) => {
+                self.export.import_path = path;
+                Task::none()
+            }
+            Message::ImportCsv => {
+                let path = PathBuf::from(self.export.import_path.trim());
+                if path.as_os_str().is_empty() {
+                    self.error = Some(AppError::Validation("No CSV file selected".into()));
+                    return Task::none();
+                }
+                self.ui.is_loading = true;
+                self.export.status = Some("Importing...".to_string());
+                let db = self.db.clone();
+                let delimiter = self.config.export.options().delimiter;
+                Task::perform(
+                    async move {
+                        db.import_csv_with_delimiter(&path, delimiter)
+                            .await
+                            .map_err(|e| AppError::Io(format!("{:#}", e)))
+                    },
+                    Message::ImportCompleted,
+                )
+            }
+            Message::ImportCompleted(result) => {
+                self.ui.is_loading = false;
+                match result {
+                    Ok(summary) => self.export.status = Some(import_status(&summary)),
+                    Err(e) => {
+                        self.error = Some(e);
+                        self.export.status = Some("Import failed".to_string());
+                    }
+                }
+                Task::perform(
+                    async {
+                        tokio::time::sleep(Duration::from_secs(4)).await;
+                    },
+                    |_| Message::ClearExportStatus,
+                )
+            }
             Message::ClearExportStatus => {
                 self.export.status = None;
                 Task::none()
@@ -617,8 +1082,15 @@ impl HardyMonitorApp {
                 }
                 Task::none()
             }
+            Message::RepairSkipToggled(skip) => {
+                self.repair.skip_repaired = skip;
+                Task::none()
+            }
             Message::StartRepairJob => {
                 if self.repair.is_running {
+                    self.error = Some(AppError::Validation(
+                        "A repair is already running; wait for it to finish".into(),
+                    ));
                     return Task::none();
                 }
 
@@ -651,16 +1123,28 @@ impl HardyMonitorApp {
 
                 let db = self.db.clone();
                 let schedule = self.schedule.clone();
-                Task::perform(
-                    async move {
-                        let repairer = DataRepairer::new(db, schedule);
-                        repairer.repair_date_range(start, end, None).await
-                    },
-                    |r| match r {
-                        Ok(summary) => Message::RepairCompleted(Ok(summary)),
-                        Err(e) => Message::RepairCompleted(Err(AppError::Database(e.to_string()))),
-                    },
-                )
+                let skip_repaired = self.repair.skip_repaired;
+                Task::stream(iced::stream::channel(16, async move |mut output| {
+                    let (progress_tx, mut progress_rx) = mpsc::channel(16);
+                    let repairer =
+                        DataRepairer::new(db, schedule).with_skip_repaired(skip_repaired);
+
+                    // Forward progress while the repair runs; the sender is
+                    // dropped when the repair finishes, ending the loop
+                    let repair = repairer.repair_date_range(start, end, Some(progress_tx));
+                    let forward = async {
+                        while let Some(progress) = progress_rx.recv().await {
+                            let _ = output.send(Message::RepairProgress(progress)).await;
+                        }
+                    };
+                    let (result, ()) = tokio::join!(repair, forward);
+
+                    let _ = output
+                        .send(Message::RepairCompleted(
+                            result.map_err(|e| AppError::Database(e.to_string())),
+                        ))
+                        .await;
+                }))
             }
             Message::RepairProgress(progress) => {
                 self.repair.progress = Some(progress);
@@ -675,12 +1159,18 @@ impl HardyMonitorApp {
     }
 
     pub fn view(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let sidebar = self.view_sidebar();
-        let content = match self.ui.current_view {
-            ViewMode::Dashboard => self.view_dashboard(),
-            ViewMode::WeeklyPattern => self.view_weekly_pattern(),
-            ViewMode::Insights => self.view_insights(),
-            ViewMode::DataRepair => self.view_data_repair(),
+        let content = if !self.data.has_any_data {
+            self.view_onboarding()
+        } else {
+            match self.ui.current_view {
+                ViewMode::Dashboard => self.view_dashboard(),
+                ViewMode::WeeklyPattern => self.view_weekly_pattern(),
+                ViewMode::Insights => self.view_insights(),
+                ViewMode::Comparison => self.view_comparison(),
+                ViewMode::DataRepair => self.view_data_repair(),
+            }
         };
 
         let main_area = container(column![
@@ -697,14 +1187,14 @@ impl HardyMonitorApp {
             .height(Length::Fill);
 
         if let Some(msg) = &self.export.status {
-            let toast = container(text(msg).size(14).color(style::TEXT_BRIGHT))
+            let toast = container(text(msg).size(14).color(palette.text))
                 .padding([12, 24])
-                .style(|_| container::Style {
-                    background: Some(style::BG_CARD.into()),
+                .style(move |_| container::Style {
+                    background: Some(palette.card.into()),
                     border: Border {
                         radius: 20.0.into(),
                         width: 1.0,
-                        color: style::ACCENT_GREEN,
+                        color: palette.accent_green,
                     },
                     shadow: Shadow {
                         color: Color::from_rgba(0.0, 0.0, 0.0, 0.5),
@@ -737,6 +1227,9 @@ impl HardyMonitorApp {
             subs.push(iced::time::every(data_interval).map(|_| Message::FetchTick));
         }
         subs.push(iced::time::every(tray_interval).map(|_| Message::TrayCheck));
+        if self.ui.theme_mode == ThemeMode::System {
+            subs.push(iced::system::theme_changes().map(Message::SystemThemeChanged));
+        }
         subs.push(iced::event::listen_with(|event, _status, _window_id| {
             if let iced::Event::Window(window::Event::CloseRequested) = event {
                 Some(Message::WindowCloseRequested)
@@ -744,37 +1237,74 @@ impl HardyMonitorApp {
                 None
             }
         }));
+        subs.push(iced::event::listen_with(|event, status, _window_id| {
+            // A focused text input captures the keys typed into it
+            if status == iced::event::Status::Captured {
+                return None;
+            }
+            match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+                    if modifiers.is_empty() =>
+                {
+                    shortcut_message(key.as_ref())
+                }
+                _ => None,
+            }
+        }));
         Subscription::batch(subs)
     }
 
     pub fn theme(&self) -> Theme {
-        Theme::Dark
+        match self.ui.theme_mode {
+            ThemeMode::Dark => Theme::Dark,
+            ThemeMode::Light => Theme::Light,
+            ThemeMode::System => match self.ui.system_theme {
+                theme::Mode::Light => Theme::Light,
+                theme::Mode::Dark | theme::Mode::None => Theme::Dark,
+            },
+        }
+    }
+
+    /// Colors of the active theme.
+    fn palette(&self) -> Palette {
+        Palette::of(&self.theme())
+    }
+
+    /// Drop cached canvas drawings so they are redrawn in the new colors.
+    fn clear_canvas_caches(&self) {
+        self.ui.chart_cache.clear();
+        self.ui.gauge_cache.clear();
+        self.ui.heatmap_cache.clear();
+        self.ui.heatmap_tooltip_cache.clear();
+        self.ui.comparison_cache.clear();
+        self.ui.comparison_tooltip_cache.clear();
     }
 
     // --- VIEW COMPONENTS ---
 
     fn view_sidebar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let sidebar_width = self.config.window.sidebar_width;
 
         let brand = column![
             text("HARDY")
                 .size(32)
                 .font(iced::font::Font::MONOSPACE)
-                .color(style::ACCENT_BLUE),
-            text("MONITOR").size(14).color(style::TEXT_MUTED),
+                .color(palette.accent_blue),
+            text("MONITOR").size(14).color(palette.text_muted),
         ];
 
         let nav_btn = |label: &str, mode: ViewMode| {
             let is_active = self.ui.current_view == mode;
             let bg = if is_active {
-                style::ACCENT_BLUE
+                palette.accent_blue
             } else {
                 Color::TRANSPARENT
             };
             let txt = if is_active {
-                style::BG_DARK
+                palette.background
             } else {
-                style::TEXT_MUTED
+                palette.text_muted
             };
             button(text(label.to_string()).color(txt).size(16))
                 .on_press(Message::SwitchView(mode))
@@ -791,6 +1321,25 @@ impl HardyMonitorApp {
                 .padding(12)
         };
 
+        let shortcut_row = |keys: &str, action: &str| {
+            row![
+                text(keys.to_string())
+                    .size(11)
+                    .color(palette.text)
+                    .width(Length::Fixed(40.0)),
+                text(action.to_string()).size(11).color(palette.text_muted),
+            ]
+        };
+        let shortcuts = column![
+            text("SHORTCUTS").size(11).color(palette.text_muted),
+            Space::new().height(6),
+            shortcut_row("1–5", "Switch view"),
+            shortcut_row("R", "Refresh now"),
+            shortcut_row("E", "Export CSV"),
+            shortcut_row("Esc", "Hide to tray"),
+        ]
+        .spacing(2);
+
         container(column![
             brand,
             Space::new().height(40),
@@ -800,14 +1349,18 @@ impl HardyMonitorApp {
             Space::new().height(10),
             nav_btn("Insights", ViewMode::Insights),
             Space::new().height(10),
+            nav_btn("Comparison", ViewMode::Comparison),
+            Space::new().height(10),
             nav_btn("Data Repair", ViewMode::DataRepair),
+            Space::new().height(Length::Fill),
+            shortcuts,
         ])
         .width(Length::Fixed(sidebar_width))
         .height(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(style::BG_CARD.into()),
+        .style(move |_| container::Style {
+            background: Some(palette.card.into()),
             border: Border {
-                color: style::STROKE_DIM,
+                color: palette.stroke,
                 width: 1.0,
                 ..Default::default()
             },
@@ -818,6 +1371,7 @@ impl HardyMonitorApp {
     }
 
     fn view_header(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let last_update = self
             .data
             .last_update
@@ -826,30 +1380,30 @@ impl HardyMonitorApp {
 
         let status = if self.ui.is_loading {
             row![
-                text("Updating").size(14).color(style::TEXT_MUTED),
-                text("...").size(14).color(style::ACCENT_BLUE)
+                text("Updating").size(14).color(palette.text_muted),
+                text("...").size(14).color(palette.accent_blue)
             ]
             .spacing(5)
         } else if let Some(e) = &self.error {
             row![
-                container(text("!").size(12).color(style::BG_DARK))
+                container(text("!").size(12).color(palette.background))
                     .padding([2, 6])
-                    .style(|_| container::Style {
-                        background: Some(style::ACCENT_RED.into()),
+                    .style(move |_| container::Style {
+                        background: Some(palette.accent_red.into()),
                         border: Border {
                             radius: 10.0.into(),
                             ..Default::default()
                         },
                         ..Default::default()
                     }),
-                text(e.to_string()).size(14).color(style::ACCENT_RED)
+                text(e.to_string()).size(14).color(palette.accent_red)
             ]
             .spacing(8)
             .align_y(Alignment::Center)
         } else {
             row![
-                container(Space::new().width(8).height(8)).style(|_| container::Style {
-                    background: Some(style::ACCENT_GREEN.into()),
+                container(Space::new().width(8).height(8)).style(move |_| container::Style {
+                    background: Some(palette.accent_green.into()),
                     border: Border {
                         radius: 4.0.into(),
                         ..Default::default()
@@ -858,7 +1412,7 @@ impl HardyMonitorApp {
                 }),
                 text(format!("Last Update: {}", last_update))
                     .size(14)
-                    .color(style::TEXT_MUTED)
+                    .color(palette.text_muted)
             ]
             .spacing(8)
             .align_y(Alignment::Center)
@@ -869,19 +1423,40 @@ impl HardyMonitorApp {
                 ViewMode::Dashboard => "Dashboard",
                 ViewMode::WeeklyPattern => "Weekly Heatmap",
                 ViewMode::Insights => "Insights",
+                ViewMode::Comparison => "Comparison",
                 ViewMode::DataRepair => "Data Repair",
             })
             .size(28)
-            .color(style::TEXT_BRIGHT),
+            .color(palette.text),
             Space::new().width(Length::Fill),
             status,
             Space::new().width(10),
+            button(
+                text(if palette == Palette::DARK {
+                    "☀"
+                } else {
+                    "☾"
+                })
+                .size(18)
+            )
+            .on_press(Message::ToggleTheme)
+            .padding(10)
+            .style(move |_, _| button::Style {
+                background: Some(palette.card.into()),
+                text_color: palette.text,
+                border: Border {
+                    radius: 8.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            Space::new().width(10),
             button(text("↻").size(18))
                 .on_press(Message::RefreshNow)
                 .padding(10)
-                .style(|_, _| button::Style {
-                    background: Some(style::BG_CARD.into()),
-                    text_color: style::TEXT_BRIGHT,
+                .style(move |_, _| button::Style {
+                    background: Some(palette.card.into()),
+                    text_color: palette.text,
                     border: Border {
                         radius: 8.0.into(),
                         ..Default::default()
@@ -893,7 +1468,34 @@ impl HardyMonitorApp {
         .into()
     }
 
+    /// Shown instead of every view while the database is empty.
+    fn view_onboarding(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+
+        let content = column![
+            text("📭").size(48),
+            Space::new().height(15),
+            text("No data yet").size(24).color(palette.text),
+            Space::new().height(10),
+            text("The daemon needs to run during open hours to collect occupancy readings.")
+                .size(14)
+                .color(palette.text_muted),
+            text("Start it with `hardy-monitor --daemon`; this window fills in on its own.")
+                .size(14)
+                .color(palette.text_muted),
+        ]
+        .align_x(Alignment::Center);
+
+        container(card_container(content))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .into()
+    }
+
     fn view_dashboard(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let low_threshold = self.config.thresholds.low_occupancy_percent;
         let high_threshold = self.config.thresholds.high_occupancy_percent;
 
@@ -909,24 +1511,24 @@ impl HardyMonitorApp {
 
         let is_checked = self.notifications.enabled;
         let active_rail = if is_checked {
-            style::ACCENT_BLUE
+            palette.accent_blue
         } else {
-            style::TEXT_MUTED
+            palette.text_muted
         };
         let handle_bg = if is_checked {
-            style::ACCENT_BLUE
+            palette.accent_blue
         } else {
-            style::TEXT_MUTED
+            palette.text_muted
         };
         let text_color = if is_checked {
-            style::TEXT_BRIGHT
+            palette.text
         } else {
-            style::TEXT_MUTED
+            palette.text_muted
         };
 
         let slider_section: Element<'_, Message> = column![
             row![
-                text("Threshold:").size(12).color(style::TEXT_MUTED),
+                text("Threshold:").size(12).color(palette.text_muted),
                 text(format!("{:.0}%", self.notifications.threshold))
                     .size(12)
                     .color(text_color)
@@ -940,7 +1542,7 @@ impl HardyMonitorApp {
             .step(5.0)
             .style(move |_: &Theme, _| slider::Style {
                 rail: slider::Rail {
-                    backgrounds: (active_rail.into(), style::BG_DARK.into()),
+                    backgrounds: (active_rail.into(), palette.background.into()),
                     width: 4.0,
                     border: Border {
                         radius: 2.0.into(),
@@ -964,71 +1566,113 @@ impl HardyMonitorApp {
                     .on_toggle(Message::NotificationToggled)
                     .size(14)
                     .style(move |_theme, _status| checkbox::Style {
-                        icon_color: style::TEXT_BRIGHT,
+                        icon_color: palette.text,
                         background: if is_checked {
-                            style::ACCENT_BLUE.into()
+                            palette.accent_blue.into()
                         } else {
-                            style::BG_DARK.into()
+                            palette.background.into()
                         },
                         border: Border {
                             radius: 4.0.into(),
                             width: 1.0,
-                            color: style::STROKE_DIM
+                            color: palette.stroke
                         },
                         text_color: None,
                     }),
                 text("Notify when empty").size(14).color(if is_checked {
-                    style::TEXT_BRIGHT
+                    palette.text
                 } else {
-                    style::TEXT_MUTED
+                    palette.text_muted
                 })
             ]
             .spacing(8)
             .align_y(Alignment::Center),
-            slider_section
+            slider_section,
+            button(text("Save settings").size(12))
+                .on_press(Message::SaveSettings)
+                .padding([6, 12])
+                .style(secondary_btn_style)
         ]
         .spacing(10)
         .max_width(220);
 
+        // Estimated head count, only when the gym's capacity is configured
+        let capacity = self.config.gym.capacity;
+        let head_count: Element<'_, Message> = match capacity.zip(self.data.occupancy) {
+            Some((capacity, percentage)) => text(format!(
+                "≈ {} people",
+                estimated_count(percentage, capacity)
+            ))
+            .size(14)
+            .color(palette.text_muted)
+            .into(),
+            None => Space::new().height(0).into(),
+        };
+
+        let closing_warning: Element<'_, Message> =
+            match self.schedule.minutes_until_close(&self.clock.now_local()) {
+                Some(minutes) if minutes <= self.config.thresholds.closing_warning_minutes => {
+                    text(format!("Closes in {} min", minutes))
+                        .size(14)
+                        .color(palette.accent_orange)
+                        .into()
+                }
+                _ => Space::new().height(0).into(),
+            };
+
         let current_card = card_container(column![
-            text("Current Status").size(16).color(style::TEXT_MUTED),
+            text("Current Status").size(16).color(palette.text_muted),
             Space::new().height(10),
             center(gauge),
+            container(head_count).center_x(Length::Fill),
+            container(closing_warning).center_x(Length::Fill),
             Space::new().height(20),
             notify_controls
         ]);
 
-        let rec_content = if let Some((hour, avg)) = self.data.best_time_today {
+        let rec_content = if let Some((hour, avg, samples)) = self.data.best_time_today {
+            // Gray out recommendations that rest on only a few readings
+            let accent = if samples >= CONFIDENT_SAMPLE_COUNT {
+                palette.accent_cyan
+            } else {
+                palette.text_muted
+            };
             column![
                 text(format!("Best time on {}s", Local::now().format("%A")))
                     .size(16)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
                 Space::new().height(20),
-                text(format!("{:02}:00", hour))
-                    .size(36)
-                    .color(style::ACCENT_CYAN),
+                text(format!("{:02}:00", hour)).size(36).color(accent),
                 Space::new().height(10),
                 container(
                     text(format!("~{:.0}% load", avg))
                         .size(14)
-                        .color(style::BG_DARK)
+                        .color(palette.background)
                 )
                 .padding([6, 12])
-                .style(|_| container::Style {
-                    background: Some(style::ACCENT_CYAN.into()),
+                .style(move |_| container::Style {
+                    background: Some(accent.into()),
                     border: Border {
                         radius: 12.0.into(),
                         ..Default::default()
                     },
                     ..Default::default()
-                })
+                }),
+                Space::new().height(10),
+                text(format!(
+                    "(based on {} sample{})",
+                    samples,
+                    if samples == 1 { "" } else { "s" }
+                ))
+                .size(12)
+                .color(palette.text_muted)
             ]
             .align_x(Alignment::Center)
         } else {
             column![
-                text("Best Time Today").size(16).color(style::TEXT_MUTED),
+                text("Best Time Today").size(16).color(palette.text_muted),
                 Space::new().height(20),
-                text("Collecting Data...").color(style::TEXT_MUTED)
+                text("Collecting Data...").color(palette.text_muted)
             ]
             .align_x(Alignment::Center)
         };
@@ -1046,7 +1690,7 @@ impl HardyMonitorApp {
                 &self.ui.history_start_date,
                 Message::HistoryStartDateChanged
             ),
-            text("-").color(style::TEXT_MUTED),
+            text("-").color(palette.text_muted),
             styled_input(&self.ui.history_end_date, Message::HistoryEndDateChanged),
             button(text("Go").size(12))
                 .on_press(Message::ApplyDateRange)
@@ -1056,36 +1700,22 @@ impl HardyMonitorApp {
             button(text("Export CSV").size(12))
                 .on_press(Message::ExportCsv)
                 .padding([8, 12])
+                .style(secondary_btn_style),
+            button(text("Export PNG").size(12))
+                .on_press(Message::ExportChartPng)
+                .padding([8, 12])
                 .style(secondary_btn_style)
         ]
         .spacing(10)
         .align_y(Alignment::Center);
 
-        // Use local time for chart boundaries so "Today" means local today
-        let (chart_start, chart_end) = if let Some(days) = self.ui.history_days_preset {
-            let local_today = Local::now().date_naive();
-            let end_aligned = midnight_local_as_utc(local_today + ChronoDuration::days(1));
-            let start_aligned = midnight_local_as_utc(local_today + ChronoDuration::days(1 - days));
-            (start_aligned, end_aligned)
-        } else {
-            match (
-                parse_date(&self.ui.history_start_date),
-                parse_date(&self.ui.history_end_date),
-            ) {
-                (Some(s), Some(e)) => {
-                    if s == e {
-                        (s, s + ChronoDuration::days(1))
-                    } else {
-                        (s, e)
-                    }
-                }
-                _ => (Utc::now() - ChronoDuration::days(1), Utc::now()),
-            }
-        };
+        let (chart_start, chart_end) = self.history_chart_range();
 
         let chart = Canvas::new(HistoryChart {
             history: &self.data.history,
+            smoothed: &self.data.smoothed_history,
             predictions: &self.data.predictions,
+            confidence: &self.data.prediction_band,
             range_start: chart_start,
             range_end: chart_end,
             cache: &self.ui.chart_cache,
@@ -1100,7 +1730,7 @@ impl HardyMonitorApp {
             top_row,
             card_container(column![
                 row![
-                    text("Occupancy Trends").size(16).color(style::TEXT_MUTED),
+                    text("Occupancy Trends").size(16).color(palette.text_muted),
                     Space::new().width(Length::Fill),
                     controls
                 ]
@@ -1115,16 +1745,17 @@ impl HardyMonitorApp {
     }
 
     fn view_weekly_pattern(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let range_btn = |label: &str, range: AnalyticsRange| {
             let active = self.ui.analytics_range == range;
             button(text(label.to_string()).size(14))
                 .on_press(Message::SwitchAnalyticsRange(range))
                 .padding([8, 16])
-                .style(move |_, _| {
+                .style(move |theme, status| {
                     if active {
-                        primary_btn_style(&Theme::Dark, iced::widget::button::Status::Active)
+                        primary_btn_style(theme, status)
                     } else {
-                        secondary_btn_style(&Theme::Dark, iced::widget::button::Status::Active)
+                        secondary_btn_style(theme, status)
                     }
                 })
         };
@@ -1136,8 +1767,31 @@ impl HardyMonitorApp {
         ]
         .spacing(10);
 
+        let is_probability = matches!(self.ui.heatmap_mode, HeatmapMode::QuietProbability { .. });
+        let mode_btn = button(
+            text(if is_probability {
+                format!("P(< {:.0}%)", self.notifications.threshold)
+            } else {
+                "Occupancy".to_string()
+            })
+            .size(14),
+        )
+        .on_press(Message::ToggleHeatmapMode)
+        .padding([8, 16])
+        .style(move |theme, status| {
+            if is_probability {
+                primary_btn_style(theme, status)
+            } else {
+                secondary_btn_style(theme, status)
+            }
+        });
+        let controls = controls.push(mode_btn);
+
         let heatmap = Canvas::new(HeatmapWidget {
             data: &self.data.analytics_data,
+            mode: self.ui.heatmap_mode,
+            low_threshold: self.config.thresholds.low_occupancy_percent,
+            high_threshold: self.config.thresholds.high_occupancy_percent,
             cache: &self.ui.heatmap_cache,
             tooltip_cache: &self.ui.heatmap_tooltip_cache,
         })
@@ -1158,21 +1812,26 @@ impl HardyMonitorApp {
                     },
                     ..Default::default()
                 }),
-                text(label.to_string()).size(12).color(style::TEXT_MUTED)
+                text(label.to_string()).size(12).color(palette.text_muted)
             ]
             .spacing(6)
             .align_y(Alignment::Center)
         };
-        let legend = row![
-            legend_item(style::ACCENT_GREEN, "Low"),
-            legend_item(style::ACCENT_ORANGE, "Busy"),
-            legend_item(style::ACCENT_RED, "Full")
+        let legend_labels = if is_probability {
+            ["Likely quiet", "Uncertain", "Unlikely"]
+        } else {
+            ["Low", "Busy", "Full"]
+        };
+        let legend = row![
+            legend_item(palette.accent_green, legend_labels[0]),
+            legend_item(palette.accent_orange, legend_labels[1]),
+            legend_item(palette.accent_red, legend_labels[2])
         ]
         .spacing(15);
 
         let mut row_content = row![].spacing(15);
-        for (idx, day_name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
-            .iter()
+        for (idx, day_name) in (0..7)
+            .map(|w| analytics::weekday_short_lang(w, self.config.window.language))
             .enumerate()
         {
             if let Some(b) = self
@@ -1188,10 +1847,12 @@ impl HardyMonitorApp {
             {
                 row_content = row_content.push(
                     column![
-                        text(day_name.to_string()).size(12).color(style::TEXT_MUTED),
+                        text(day_name.to_string())
+                            .size(12)
+                            .color(palette.text_muted),
                         text(format!("{:02}:00", b.hour))
                             .size(14)
-                            .color(style::ACCENT_CYAN)
+                            .color(palette.accent_cyan)
                     ]
                     .spacing(2),
                 );
@@ -1202,7 +1863,7 @@ impl HardyMonitorApp {
             row![
                 text("Weekly Occupancy Heatmap")
                     .size(16)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
                 Space::new().width(Length::Fill),
                 controls
             ]
@@ -1223,19 +1884,22 @@ impl HardyMonitorApp {
     }
 
     fn view_insights(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let lang = self.config.window.language;
+
         // Trend card
         let trend_card = {
             let (trend_icon, trend_text, trend_color) = match self.data.trend {
-                Some(TrendDirection::Increasing) => ("📈", "Getting Busier", style::ACCENT_RED),
-                Some(TrendDirection::Decreasing) => ("📉", "Getting Quieter", style::ACCENT_GREEN),
-                Some(TrendDirection::Stable) => ("➡️", "Staying Stable", style::ACCENT_CYAN),
+                Some(TrendDirection::Increasing) => ("📈", "Getting Busier", palette.accent_red),
+                Some(TrendDirection::Decreasing) => ("📉", "Getting Quieter", palette.accent_green),
+                Some(TrendDirection::Stable) => ("➡️", "Staying Stable", palette.accent_cyan),
                 Some(TrendDirection::Insufficient) | None => {
-                    ("❓", "Collecting Data", style::TEXT_MUTED)
+                    ("❓", "Collecting Data", palette.text_muted)
                 }
             };
 
             card_container(column![
-                text("Overall Trend").size(14).color(style::TEXT_MUTED),
+                text("Overall Trend").size(14).color(palette.text_muted),
                 Space::new().height(15),
                 row![
                     text(trend_icon).size(32),
@@ -1244,7 +1908,7 @@ impl HardyMonitorApp {
                         text(trend_text).size(20).color(trend_color),
                         text("vs previous 4 weeks")
                             .size(12)
-                            .color(style::TEXT_MUTED),
+                            .color(palette.text_muted),
                     ]
                 ]
                 .align_y(Alignment::Center)
@@ -1255,75 +1919,79 @@ impl HardyMonitorApp {
         // Statistics card
         let stats_card = if let Some(ref stats) = self.data.stats {
             let consistency = if stats.coefficient_of_variation < 0.3 {
-                ("Very Predictable", style::ACCENT_GREEN)
+                ("Very Predictable", palette.accent_green)
             } else if stats.coefficient_of_variation < 0.5 {
-                ("Moderately Predictable", style::ACCENT_ORANGE)
+                ("Moderately Predictable", palette.accent_orange)
             } else {
-                ("Highly Variable", style::ACCENT_RED)
+                ("Highly Variable", palette.accent_red)
             };
 
             card_container(column![
-                text("Statistics").size(14).color(style::TEXT_MUTED),
+                text("Statistics").size(14).color(palette.text_muted),
                 Space::new().height(15),
                 row![
                     column![
-                        text("Average").size(12).color(style::TEXT_MUTED),
+                        text("Average").size(12).color(palette.text_muted),
                         text(format!("{:.1}%", stats.mean))
                             .size(24)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
                     ],
                     Space::new().width(30),
                     column![
-                        text("Range").size(12).color(style::TEXT_MUTED),
+                        text("Range").size(12).color(palette.text_muted),
                         text(format!("{:.0}% - {:.0}%", stats.min, stats.max))
                             .size(18)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
                     ],
                 ]
                 .align_y(Alignment::End),
                 Space::new().height(15),
                 row![
-                    text("Consistency: ").size(12).color(style::TEXT_MUTED),
+                    text("Consistency: ").size(12).color(palette.text_muted),
                     text(consistency.0).size(12).color(consistency.1),
                 ]
             ])
             .width(Length::FillPortion(1))
         } else {
             card_container(column![
-                text("Statistics").size(14).color(style::TEXT_MUTED),
+                text("Statistics").size(14).color(palette.text_muted),
                 Space::new().height(20),
-                text("Loading...").color(style::TEXT_MUTED),
+                text("Loading...").color(palette.text_muted),
             ])
             .width(Length::FillPortion(1))
         };
 
         // Peak hours card
         let peak_card = card_container(column![
-            text("Busiest Times").size(14).color(style::TEXT_MUTED),
+            text("Busiest Times").size(14).color(palette.text_muted),
             Space::new().height(15),
             {
                 let mut peak_col = column![].spacing(8);
                 for (weekday, hour, pct) in self.data.peak_hours.iter().take(5) {
                     peak_col = peak_col.push(
                         row![
-                            container(text(format!("{:.0}%", pct)).size(12).color(style::BG_DARK))
-                                .padding([4, 8])
-                                .style(|_| container::Style {
-                                    background: Some(style::ACCENT_RED.into()),
-                                    border: Border {
-                                        radius: 4.0.into(),
-                                        ..Default::default()
-                                    },
+                            container(
+                                text(format!("{:.0}%", pct))
+                                    .size(12)
+                                    .color(palette.background)
+                            )
+                            .padding([4, 8])
+                            .style(move |_| container::Style {
+                                background: Some(palette.accent_red.into()),
+                                border: Border {
+                                    radius: 4.0.into(),
                                     ..Default::default()
-                                }),
+                                },
+                                ..Default::default()
+                            }),
                             Space::new().width(10),
                             text(format!(
                                 "{} {:02}:00",
-                                analytics::weekday_short(*weekday),
+                                analytics::weekday_short_lang(*weekday, lang),
                                 hour
                             ))
                             .size(14)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
                         ]
                         .align_y(Alignment::Center),
                     );
@@ -1335,31 +2003,35 @@ impl HardyMonitorApp {
 
         // Quiet hours card
         let quiet_card = card_container(column![
-            text("Quietest Times").size(14).color(style::TEXT_MUTED),
+            text("Quietest Times").size(14).color(palette.text_muted),
             Space::new().height(15),
             {
                 let mut quiet_col = column![].spacing(8);
                 for (weekday, hour, pct) in self.data.quiet_hours.iter().take(5) {
                     quiet_col = quiet_col.push(
                         row![
-                            container(text(format!("{:.0}%", pct)).size(12).color(style::BG_DARK))
-                                .padding([4, 8])
-                                .style(|_| container::Style {
-                                    background: Some(style::ACCENT_GREEN.into()),
-                                    border: Border {
-                                        radius: 4.0.into(),
-                                        ..Default::default()
-                                    },
+                            container(
+                                text(format!("{:.0}%", pct))
+                                    .size(12)
+                                    .color(palette.background)
+                            )
+                            .padding([4, 8])
+                            .style(move |_| container::Style {
+                                background: Some(palette.accent_green.into()),
+                                border: Border {
+                                    radius: 4.0.into(),
                                     ..Default::default()
-                                }),
+                                },
+                                ..Default::default()
+                            }),
                             Space::new().width(10),
                             text(format!(
                                 "{} {:02}:00",
-                                analytics::weekday_short(*weekday),
+                                analytics::weekday_short_lang(*weekday, lang),
                                 hour
                             ))
                             .size(14)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
                         ]
                         .align_y(Alignment::Center),
                     );
@@ -1371,7 +2043,7 @@ impl HardyMonitorApp {
 
         // Day analysis card
         let days_card = card_container(column![
-            text("Daily Patterns").size(14).color(style::TEXT_MUTED),
+            text("Daily Patterns").size(14).color(palette.text_muted),
             Space::new().height(15),
             {
                 let mut days_row = row![].spacing(30); // Increased spacing
@@ -1379,13 +2051,12 @@ impl HardyMonitorApp {
                     if day.sample_count > 0 {
                         // Increased multiplier for visibility in full-width view
                         let bar_height = (day.avg_occupancy * 1.5).max(5.0);
-                        let color = if day.avg_occupancy < 40.0 {
-                            style::ACCENT_GREEN
-                        } else if day.avg_occupancy < 60.0 {
-                            style::ACCENT_ORANGE
-                        } else {
-                            style::ACCENT_RED
-                        };
+                        let color = get_status_color(
+                            &palette,
+                            day.avg_occupancy,
+                            self.config.thresholds.low_occupancy_percent,
+                            self.config.thresholds.high_occupancy_percent,
+                        );
 
                         days_row = days_row.push(
                             column![
@@ -1403,10 +2074,12 @@ impl HardyMonitorApp {
                                     ..Default::default()
                                 }),
                                 Space::new().height(8),
-                                text(&day.day_name[..3]).size(12).color(style::TEXT_MUTED),
+                                text(analytics::weekday_short_lang(day.weekday, lang))
+                                    .size(12)
+                                    .color(palette.text_muted),
                                 text(format!("{:.0}%", day.avg_occupancy))
                                     .size(12)
-                                    .color(style::TEXT_BRIGHT),
+                                    .color(palette.text),
                             ]
                             .align_x(Alignment::Center),
                         );
@@ -1421,16 +2094,16 @@ impl HardyMonitorApp {
 
         // Insights list
         let insights_card = card_container(column![
-            text("Key Insights").size(14).color(style::TEXT_MUTED),
+            text("Key Insights").size(14).color(palette.text_muted),
             Space::new().height(15),
             {
                 let mut insights_col = column![].spacing(12);
                 for insight in self.data.insights.iter().take(6) {
                     let importance_color = match insight.importance {
-                        5 => style::ACCENT_GREEN,
-                        4 => style::ACCENT_CYAN,
-                        3 => style::ACCENT_ORANGE,
-                        _ => style::TEXT_MUTED,
+                        5 => palette.accent_green,
+                        4 => palette.accent_cyan,
+                        3 => palette.accent_orange,
+                        _ => palette.text_muted,
                     };
 
                     insights_col = insights_col.push(
@@ -1439,7 +2112,7 @@ impl HardyMonitorApp {
                                 container(
                                     text(format!("{}", insight.importance))
                                         .size(10)
-                                        .color(style::BG_DARK)
+                                        .color(palette.background)
                                 )
                                 .padding([2, 6])
                                 .style(move |_| container::Style {
@@ -1451,15 +2124,17 @@ impl HardyMonitorApp {
                                     ..Default::default()
                                 }),
                                 Space::new().width(10),
-                                text(&insight.title).size(14).color(style::TEXT_BRIGHT),
+                                text(&insight.title).size(14).color(palette.text),
                             ]
                             .align_y(Alignment::Center),
                             Space::new().height(4),
-                            text(&insight.description).size(12).color(style::TEXT_MUTED),
+                            text(&insight.description)
+                                .size(12)
+                                .color(palette.text_muted),
                         ])
                         .padding(12)
-                        .style(|_| container::Style {
-                            background: Some(style::BG_DARK.into()),
+                        .style(move |_| container::Style {
+                            background: Some(palette.background.into()),
                             border: Border {
                                 radius: 8.0.into(),
                                 ..Default::default()
@@ -1473,7 +2148,7 @@ impl HardyMonitorApp {
                     insights_col = insights_col.push(
                         text("No insights yet. Keep collecting data!")
                             .size(14)
-                            .color(style::TEXT_MUTED),
+                            .color(palette.text_muted),
                     );
                 }
 
@@ -1507,7 +2182,247 @@ impl HardyMonitorApp {
             .into()
     }
 
+    fn view_comparison(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let lang = self.config.window.language;
+
+        let mode_btn = |label: &str, mode: ComparisonMode| {
+            let active = self.ui.comparison_mode == mode;
+            button(text(label.to_string()).size(14))
+                .on_press(Message::SwitchComparisonMode(mode))
+                .padding([8, 16])
+                .style(move |theme, status| {
+                    if active {
+                        primary_btn_style(theme, status)
+                    } else {
+                        secondary_btn_style(theme, status)
+                    }
+                })
+        };
+        let controls = row![
+            mode_btn("Week over Week", ComparisonMode::WeekOverWeek),
+            mode_btn("Month over Month", ComparisonMode::MonthOverMonth),
+        ]
+        .spacing(10);
+
+        let Some(comparison) = &self.data.comparison else {
+            return column![
+                controls,
+                card_container(text("Loading...").color(palette.text_muted)).width(Length::Fill),
+            ]
+            .spacing(20)
+            .into();
+        };
+
+        let baseline_label = match comparison.mode {
+            ComparisonMode::MonthOverMonth => "vs the same week 4 weeks ago",
+            _ => "vs the previous week",
+        };
+        let (trend_text, trend_color) = match comparison.overall_trend {
+            TrendDirection::Increasing => ("Getting Busier", palette.accent_red),
+            TrendDirection::Decreasing => ("Getting Quieter", palette.accent_green),
+            TrendDirection::Stable => ("Staying Stable", palette.accent_cyan),
+            TrendDirection::Insufficient => ("Collecting Data", palette.text_muted),
+        };
+
+        let overall_card = card_container(column![
+            text("Overall Change").size(14).color(palette.text_muted),
+            Space::new().height(15),
+            row![
+                text(format!("{:+.1}%", comparison.overall_change_percent))
+                    .size(32)
+                    .color(trend_color),
+                Space::new().width(15),
+                column![
+                    text(trend_text).size(16).color(trend_color),
+                    text(format!(
+                        "{:.1}% → {:.1}% average, {}",
+                        comparison.baseline_overall_avg,
+                        comparison.current_overall_avg,
+                        baseline_label
+                    ))
+                    .size(12)
+                    .color(palette.text_muted),
+                ]
+            ]
+            .align_y(Alignment::Center)
+        ])
+        .width(Length::Fill);
+
+        let change_list = |title: &str, changes: &[(i32, i32, f64)], color: Color| {
+            let mut list = column![].spacing(8);
+            for (weekday, hour, pct) in changes.iter().take(5) {
+                list = list.push(
+                    row![
+                        container(
+                            text(format!("{:+.0}%", pct))
+                                .size(12)
+                                .color(palette.background)
+                        )
+                        .padding([4, 8])
+                        .style(move |_| container::Style {
+                            background: Some(color.into()),
+                            border: Border {
+                                radius: 4.0.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }),
+                        Space::new().width(10),
+                        text(format!(
+                            "{} {:02}:00",
+                            analytics::weekday_short_lang(*weekday, lang),
+                            hour
+                        ))
+                        .size(14)
+                        .color(palette.text),
+                    ]
+                    .align_y(Alignment::Center),
+                );
+            }
+            if changes.is_empty() {
+                list = list.push(
+                    text("No notable changes")
+                        .size(14)
+                        .color(palette.text_muted),
+                );
+            }
+            card_container(column![
+                text(title.to_string()).size(14).color(palette.text_muted),
+                Space::new().height(15),
+                list,
+            ])
+            .width(Length::FillPortion(1))
+        };
+
+        let chart = Canvas::new(ComparisonChartWidget {
+            data: &comparison.hourly_comparisons,
+            cache: &self.ui.comparison_cache,
+            tooltip_cache: &self.ui.comparison_tooltip_cache,
+        })
+        .width(Length::Fill)
+        .height(Length::Fixed(260.0));
+        let chart_element = Element::from(chart).map(|_: ()| Message::ChartInteraction);
+
+        let chart_card = card_container(column![
+            text("Change by Hour (percentage points)")
+                .size(14)
+                .color(palette.text_muted),
+            Space::new().height(15),
+            chart_element,
+        ])
+        .width(Length::Fill);
+
+        let content = column![
+            controls,
+            overall_card,
+            chart_card,
+            self.view_gym_comparison(),
+            row![
+                change_list(
+                    "Biggest Increases",
+                    &comparison.biggest_increases,
+                    palette.accent_red
+                ),
+                change_list(
+                    "Biggest Decreases",
+                    &comparison.biggest_decreases,
+                    palette.accent_green
+                ),
+            ]
+            .spacing(20),
+        ]
+        .spacing(20)
+        .padding(10);
+
+        scrollable(content)
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Hours in which each of the first two configured gyms is quieter, per
+    /// weekday.
+    fn view_gym_comparison(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let lang = self.config.window.language;
+        let [first, second, ..] = self.config.gym.gyms.as_slice() else {
+            return Space::new().into();
+        };
+
+        let hours = |weekday: i32, side: QuieterSide| {
+            let hours: Vec<String> = self
+                .data
+                .gym_comparison
+                .iter()
+                .filter(|(w, _, s)| *w == weekday && *s == side)
+                .map(|(_, hour, _)| format!("{:02}", hour))
+                .collect();
+            if hours.is_empty() {
+                "-".to_string()
+            } else {
+                hours.join(", ")
+            }
+        };
+
+        let mut rows = column![
+            row![
+                text("").width(Length::Fixed(50.0)),
+                text(format!("{} quieter", first.name))
+                    .size(12)
+                    .color(palette.accent_green)
+                    .width(Length::FillPortion(1)),
+                text(format!("{} quieter", second.name))
+                    .size(12)
+                    .color(palette.accent_cyan)
+                    .width(Length::FillPortion(1)),
+            ]
+            .spacing(10)
+        ]
+        .spacing(8);
+        for weekday in 0..7 {
+            rows = rows.push(
+                row![
+                    text(analytics::weekday_short_lang(weekday, lang))
+                        .size(14)
+                        .color(palette.text)
+                        .width(Length::Fixed(50.0)),
+                    text(hours(weekday, QuieterSide::First))
+                        .size(14)
+                        .color(palette.text)
+                        .width(Length::FillPortion(1)),
+                    text(hours(weekday, QuieterSide::Second))
+                        .size(14)
+                        .color(palette.text)
+                        .width(Length::FillPortion(1)),
+                ]
+                .spacing(10),
+            );
+        }
+        if self.data.gym_comparison.is_empty() {
+            rows = rows.push(
+                text("No hours with data for both gyms")
+                    .size(14)
+                    .color(palette.text_muted),
+            );
+        }
+
+        card_container(column![
+            text(format!(
+                "Head to Head: {} vs {} (last 7 days)",
+                first.name, second.name
+            ))
+            .size(14)
+            .color(palette.text_muted),
+            Space::new().height(15),
+            rows,
+        ])
+        .width(Length::Fill)
+        .into()
+    }
+
     fn view_data_repair(&self) -> Element<'_, Message> {
+        let palette = self.palette();
         let preset_btn = |label: &str, preset: RepairPreset| {
             button(text(label.to_string()).size(12))
                 .on_press(Message::RepairPresetSelected(preset))
@@ -1517,7 +2432,7 @@ impl HardyMonitorApp {
 
         let date_inputs = row![
             styled_input(&self.repair.start_date, Message::RepairStartDateChanged),
-            text("to").color(style::TEXT_MUTED).size(14),
+            text("to").color(palette.text_muted).size(14),
             styled_input(&self.repair.end_date, Message::RepairEndDateChanged),
         ]
         .spacing(10)
@@ -1530,12 +2445,23 @@ impl HardyMonitorApp {
         ]
         .spacing(10);
 
+        let skip_toggle = row![
+            checkbox(self.repair.skip_repaired)
+                .on_toggle(Message::RepairSkipToggled)
+                .size(14),
+            text("Skip days that were already repaired")
+                .size(13)
+                .color(palette.text_muted),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
         let start_button = if self.repair.is_running {
             button(text("Running...").size(14))
                 .padding([12, 24])
-                .style(|_, _| button::Style {
-                    background: Some(style::TEXT_MUTED.into()),
-                    text_color: style::BG_DARK,
+                .style(move |_, _| button::Style {
+                    background: Some(palette.text_muted.into()),
+                    text_color: palette.background,
                     border: Border {
                         radius: 8.0.into(),
                         ..Default::default()
@@ -1558,13 +2484,11 @@ impl HardyMonitorApp {
                 };
                 column![
                     text(format!(
-                        "Processing: {} (Day {} of {})",
-                        progress.current_day,
-                        progress.processed_days + 1,
-                        progress.total_days
+                        "Processed: {} (Day {} of {})",
+                        progress.current_day, progress.processed_days, progress.total_days
                     ))
                     .size(14)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
                     Space::new().height(10),
                     container(
                         container(
@@ -1572,8 +2496,8 @@ impl HardyMonitorApp {
                                 .width(Length::FillPortion((pct as u16).max(1)))
                                 .height(8)
                         )
-                        .style(|_| container::Style {
-                            background: Some(style::ACCENT_BLUE.into()),
+                        .style(move |_| container::Style {
+                            background: Some(palette.accent_blue.into()),
                             border: Border {
                                 radius: 4.0.into(),
                                 ..Default::default()
@@ -1582,8 +2506,8 @@ impl HardyMonitorApp {
                         })
                     )
                     .width(Length::Fill)
-                    .style(|_| container::Style {
-                        background: Some(style::BG_DARK.into()),
+                    .style(move |_| container::Style {
+                        background: Some(palette.background.into()),
                         border: Border {
                             radius: 4.0.into(),
                             ..Default::default()
@@ -1595,7 +2519,7 @@ impl HardyMonitorApp {
             } else {
                 text("Starting repair job...")
                     .size(14)
-                    .color(style::TEXT_MUTED)
+                    .color(palette.text_muted)
                     .into()
             }
         } else {
@@ -1608,45 +2532,65 @@ impl HardyMonitorApp {
                 Ok(summary) => card_container(column![
                     text("Last Repair Results")
                         .size(16)
-                        .color(style::ACCENT_GREEN),
+                        .color(palette.accent_green),
                     Space::new().height(15),
                     row![
-                        text("Days processed:").size(14).color(style::TEXT_MUTED),
+                        text("Days processed:").size(14).color(palette.text_muted),
                         Space::new().width(10),
                         text(summary.days_processed.to_string())
                             .size(14)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
+                    ],
+                    Space::new().height(5),
+                    row![
+                        text("Already repaired (skipped):")
+                            .size(14)
+                            .color(palette.text_muted),
+                        Space::new().width(10),
+                        text(summary.days_skipped.to_string())
+                            .size(14)
+                            .color(palette.text),
                     ],
                     Space::new().height(5),
                     row![
-                        text("Gaps filled:").size(14).color(style::TEXT_MUTED),
+                        text("Gaps filled:").size(14).color(palette.text_muted),
                         Space::new().width(10),
                         text(summary.gaps_filled.to_string())
                             .size(14)
-                            .color(style::ACCENT_CYAN),
+                            .color(palette.accent_cyan),
                     ],
                     Space::new().height(5),
                     row![
-                        text("Records zeroed:").size(14).color(style::TEXT_MUTED),
+                        text("Records zeroed:").size(14).color(palette.text_muted),
                         Space::new().width(10),
                         text(summary.records_zeroed.to_string())
                             .size(14)
-                            .color(style::ACCENT_ORANGE),
+                            .color(palette.accent_orange),
                     ],
                     Space::new().height(5),
                     row![
-                        text("End entries added:").size(14).color(style::TEXT_MUTED),
+                        text("End entries added:")
+                            .size(14)
+                            .color(palette.text_muted),
                         Space::new().width(10),
                         text(summary.end_entries_added.to_string())
                             .size(14)
-                            .color(style::TEXT_BRIGHT),
+                            .color(palette.text),
+                    ],
+                    Space::new().height(5),
+                    row![
+                        text("Backup id:").size(14).color(palette.text_muted),
+                        Space::new().width(10),
+                        text(summary.repair_id.to_string())
+                            .size(14)
+                            .color(palette.text),
                     ],
                 ])
                 .into(),
                 Err(e) => card_container(column![
-                    text("Repair Failed").size(16).color(style::ACCENT_RED),
+                    text("Repair Failed").size(16).color(palette.accent_red),
                     Space::new().height(10),
-                    text(e.to_string()).size(14).color(style::TEXT_MUTED),
+                    text(e.to_string()).size(14).color(palette.text_muted),
                 ])
                 .into(),
             }
@@ -1657,39 +2601,67 @@ impl HardyMonitorApp {
         let description = column![
             text("Repair occupancy data by:")
                 .size(14)
-                .color(style::TEXT_MUTED),
+                .color(palette.text_muted),
             Space::new().height(8),
             row![
-                text("•").color(style::ACCENT_CYAN),
+                text("•").color(palette.accent_cyan),
                 Space::new().width(8),
                 text("Filling gaps up to 5 minutes with linear interpolation")
                     .size(13)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
             ],
             Space::new().height(4),
             row![
-                text("•").color(style::ACCENT_CYAN),
+                text("•").color(palette.accent_cyan),
                 Space::new().width(8),
                 text("Setting values outside opening hours to 0")
                     .size(13)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
             ],
             Space::new().height(4),
             row![
-                text("•").color(style::ACCENT_CYAN),
+                text("•").color(palette.accent_cyan),
                 Space::new().width(8),
                 text("Adding end-of-day closure entries")
                     .size(13)
-                    .color(style::TEXT_MUTED),
+                    .color(palette.text_muted),
             ],
         ];
 
-        card_container(column![
-            text("Select Date Range").size(16).color(style::TEXT_BRIGHT),
+        let import_section = card_container(column![
+            text("Import CSV").size(16).color(palette.text),
+            Space::new().height(10),
+            text(
+                "Backfill readings from a CSV with timestamp and percentage columns, such as an \
+                 earlier export. Rows whose timestamp is already stored are skipped."
+            )
+            .size(13)
+            .color(palette.text_muted),
+            Space::new().height(15),
+            row![
+                text_input("Path to CSV file", &self.export.import_path)
+                    .on_input(Message::ImportPathChanged)
+                    .on_submit(Message::ImportCsv)
+                    .padding(8)
+                    .size(12)
+                    .style(input_style),
+                button(text("Import CSV").size(12))
+                    .on_press(Message::ImportCsv)
+                    .padding([8, 12])
+                    .style(secondary_btn_style),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        ]);
+
+        let repair_section = card_container(column![
+            text("Select Date Range").size(16).color(palette.text),
             Space::new().height(20),
             date_inputs,
             Space::new().height(15),
             presets,
+            Space::new().height(15),
+            skip_toggle,
             Space::new().height(25),
             description,
             Space::new().height(25),
@@ -1699,12 +2671,49 @@ impl HardyMonitorApp {
             Space::new().height(20),
             result_section,
         ])
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into()
+        .width(Length::Fill);
+
+        scrollable(column![repair_section, import_section].spacing(20))
+            .height(Length::Fill)
+            .into()
     }
 
     // --- LOGIC HELPERS ---
+    /// Recompute the upcoming predictions from the prediction baseline.
+    fn refresh_predictions(&mut self) {
+        let baseline = &self.data.prediction_baseline;
+        self.data.predictions = analytics::calculate_predictions(baseline);
+        self.data.prediction_band = analytics::calculate_predictions_detailed(baseline)
+            .iter()
+            .map(ConfidencePoint::from)
+            .collect();
+    }
+
+    /// Time range shown by the history chart.
+    fn history_chart_range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        // Use local time for chart boundaries so "Today" means local today
+        if let Some(days) = self.ui.history_days_preset {
+            let local_today = Local::now().date_naive();
+            let end_aligned = midnight_local_as_utc(local_today + ChronoDuration::days(1));
+            let start_aligned = midnight_local_as_utc(local_today + ChronoDuration::days(1 - days));
+            (start_aligned, end_aligned)
+        } else {
+            match (
+                parse_date(&self.ui.history_start_date),
+                parse_date(&self.ui.history_end_date),
+            ) {
+                (Some(s), Some(e)) => {
+                    if s == e {
+                        (s, s + ChronoDuration::days(1))
+                    } else {
+                        (s, e)
+                    }
+                }
+                _ => (Utc::now() - ChronoDuration::days(1), Utc::now()),
+            }
+        }
+    }
+
     /// Fetch the latest occupancy record from the database (read-only, no API calls).
     fn fetch_latest_from_db(db: Arc<Database>) -> Task<Message> {
         Task::perform(
@@ -1720,20 +2729,102 @@ impl HardyMonitorApp {
         )
     }
 
+    /// Show the latest reading in the tray tooltip and title.
+    fn update_tray_tooltip(&self) {
+        let last_update = self.data.last_update.map(|t| t.with_timezone(&Local));
+        let tooltip = tray_tooltip(self.data.occupancy, last_update);
+        if let Err(e) = self.tray_icon.set_tooltip(Some(&tooltip)) {
+            tracing::warn!("Failed to update tray tooltip: {}", e);
+        }
+        self.tray_icon
+            .set_title(self.data.occupancy.map(|pct| format!("{:.0}%", pct)));
+    }
+
+    /// Switch the tray icon to the status color of the given reading.
+    ///
+    /// The icons are rendered once at startup and only swapped when the
+    /// color actually changes.
+    fn update_tray_status_icon(&mut self, percentage: f64) {
+        let index = BusynessLevel::from_percentage(
+            percentage,
+            self.config.thresholds.low_occupancy_percent,
+            self.config.thresholds.high_occupancy_percent,
+        )
+        .color_index();
+        if self.ui.tray_color_index == Some(index) {
+            return;
+        }
+        let Some(icon) = self.tray_status_icons.get(index) else {
+            return;
+        };
+        match self.tray_icon.set_icon(Some(icon.clone())) {
+            Ok(()) => self.ui.tray_color_index = Some(index),
+            Err(e) => tracing::warn!("Failed to update tray icon: {}", e),
+        }
+    }
+
+    /// Redraw the tray icon as a sparkline of the given records.
+    fn update_tray_sparkline(&self, logs: &[OccupancyLog]) {
+        let values: Vec<f64> = logs.iter().map(|l| l.percentage).collect();
+        let latest = values.last().copied().unwrap_or(0.0);
+        let rgba = status_rgba(
+            latest,
+            self.config.thresholds.low_occupancy_percent,
+            self.config.thresholds.high_occupancy_percent,
+        );
+
+        let img = render_sparkline(&values, TRAY_ICON_SIZE, TRAY_ICON_SIZE, rgba);
+        match Icon::from_rgba(img.into_raw(), TRAY_ICON_SIZE, TRAY_ICON_SIZE) {
+            Ok(icon) => {
+                if let Err(e) = self.tray_icon.set_icon(Some(icon)) {
+                    tracing::warn!("Failed to update tray icon: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to build sparkline icon: {}", e),
+        }
+    }
+
+    fn load_record_count(db: Arc<Database>) -> Task<Message> {
+        Task::perform(
+            async move { db.count_records().await },
+            |r: Result<i64, anyhow::Error>| {
+                Message::RecordCountLoaded(r.map_err(|e| AppError::Database(e.to_string())))
+            },
+        )
+    }
+
+    fn load_sparkline(db: Arc<Database>) -> Task<Message> {
+        Task::perform(
+            async move { db.get_recent_records(60).await },
+            |r: Result<Vec<OccupancyLog>, anyhow::Error>| {
+                Message::SparklineLoaded(r.map_err(|e| AppError::Database(e.to_string())))
+            },
+        )
+    }
+
     fn load_history(db: Arc<Database>) -> Task<Message> {
         Task::perform(
             async move { db.get_history(1).await },
-            |r: Result<Vec<OccupancyLog>, anyhow::Error>| {
-                Message::HistoryLoaded(r.map_err(|e| AppError::Database(e.to_string())))
+            |r: Result<Vec<OccupancyLog>, anyhow::Error>| Message::HistoryLoaded {
+                logs: r.map_err(|e| AppError::Database(e.to_string())),
+                downsampled: false,
             },
         )
     }
 
     fn load_history_range(db: Arc<Database>, s: DateTime<Utc>, e: DateTime<Utc>) -> Task<Message> {
+        let downsampled = e - s > ChronoDuration::days(FULL_RESOLUTION_DAYS);
         Task::perform(
-            async move { db.get_history_range(s, e).await },
-            |r: Result<Vec<OccupancyLog>, anyhow::Error>| {
-                Message::HistoryLoaded(r.map_err(|e| AppError::Database(e.to_string())))
+            async move {
+                if downsampled {
+                    db.get_history_downsampled(s, e, HISTORY_CHART_POINTS).await
+                } else {
+                    db.get_history_range(s, e).await
+                }
+            },
+            move |r: Result<Vec<OccupancyLog>, anyhow::Error>| Message::HistoryLoaded {
+                logs: r.map_err(|e| AppError::Database(e.to_string())),
+                downsampled,
             },
         )
     }
@@ -1761,17 +2852,37 @@ impl HardyMonitorApp {
         )
     }
 
+    /// Notify ahead of a predicted quiet period, at most once per day.
+    fn predictive_alert_task(&mut self) -> Option<Task<Message>> {
+        if !self.notifications.enabled || !self.config.notifications.predictive_alerts {
+            return None;
+        }
+
+        let body = self.notifications.predictive_alert.check(
+            &self.data.prediction_baseline,
+            &self.schedule,
+            self.notifications.threshold,
+            self.config.notifications.predictive_lead_minutes,
+            self.clock.as_ref(),
+        )?;
+
+        let notifier = self.notifier.clone();
+        Some(Task::perform(
+            async move {
+                let _ = notifier.notify_async("Hardy's Gym Monitor", &body).await;
+            },
+            |_| Message::NotificationSent,
+        ))
+    }
+
     fn load_prediction_baseline(
         db: Arc<Database>,
         days: i64,
         clock: Arc<dyn Clock>,
+        adaptive: bool,
     ) -> Task<Message> {
-        let now = clock.now_utc();
         Task::perform(
-            async move {
-                db.get_averages_range(now - ChronoDuration::days(days), now)
-                    .await
-            },
+            async move { daemon::load_prediction_baseline(&db, days, adaptive, clock.as_ref()).await },
             |r: Result<Vec<HourlyAverage>, anyhow::Error>| {
                 Message::PredictionBaselineLoaded(r.map_err(|e| AppError::Database(e.to_string())))
             },
@@ -1810,26 +2921,161 @@ impl HardyMonitorApp {
             },
         )
     }
+
+    /// Compare the first two configured gyms over the last week; does
+    /// nothing with fewer than two gyms.
+    fn load_gym_comparison(
+        db: Arc<Database>,
+        clock: Arc<dyn Clock>,
+        gyms: &[GymEntry],
+    ) -> Task<Message> {
+        let [first, second, ..] = gyms else {
+            return Task::none();
+        };
+        let (first, second) = (first.id.clone(), second.id.clone());
+        let now = clock.now_utc();
+        let start = now - ChronoDuration::weeks(1);
+
+        Task::perform(
+            async move { db.get_gym_averages_pair(&first, &second, start, now).await },
+            |r: Result<(Vec<HourlyAverage>, Vec<HourlyAverage>), anyhow::Error>| {
+                Message::GymComparisonLoaded(
+                    r.map(|(first, second)| analytics::quieter_per_slot(&first, &second))
+                        .map_err(|e| AppError::Database(e.to_string())),
+                )
+            },
+        )
+    }
+
+    fn load_comparison_data(
+        db: Arc<Database>,
+        clock: Arc<dyn Clock>,
+        mode: ComparisonMode,
+    ) -> Task<Message> {
+        let now = clock.now_utc();
+        // Current period: the last week. Baseline: the week `offset` earlier.
+        let current_start = now - ChronoDuration::weeks(1);
+        let offset = mode.baseline_offset().unwrap_or(ChronoDuration::weeks(1));
+        let baseline_start = current_start - offset;
+        let baseline_end = baseline_start + ChronoDuration::weeks(1);
+
+        let db_clone = db.clone();
+        Task::perform(
+            async move {
+                let current = db.get_averages_range(current_start, now).await;
+                let baseline = db_clone
+                    .get_averages_range(baseline_start, baseline_end)
+                    .await;
+                (current, baseline)
+            },
+            move |(current, baseline): (
+                Result<Vec<HourlyAverage>, anyhow::Error>,
+                Result<Vec<HourlyAverage>, anyhow::Error>,
+            )| {
+                Message::ComparisonDataLoaded {
+                    mode,
+                    current: current.map_err(|e| AppError::Database(e.to_string())),
+                    baseline: baseline.map_err(|e| AppError::Database(e.to_string())),
+                }
+            },
+        )
+    }
 }
 
 // --- HELPER FUNCTIONS ---
+/// Render the green, orange and red tray status icons.
+///
+/// Returns no icons if any fails to build, so indices always match
+/// `BusynessLevel::color_index`.
+fn render_tray_status_icons() -> Vec<Icon> {
+    [
+        BusynessLevel::Quiet,
+        BusynessLevel::Moderate,
+        BusynessLevel::Busy,
+    ]
+    .into_iter()
+    .map(|level| {
+        let img = render_status_dot(TRAY_ICON_SIZE, level_rgba(level));
+        Icon::from_rgba(img.into_raw(), TRAY_ICON_SIZE, TRAY_ICON_SIZE)
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to build tray status icons: {}", e);
+        Vec::new()
+    })
+}
+
+/// Tray tooltip for the latest reading, e.g. "Gym: 42% • 14:03".
+fn tray_tooltip(occupancy: Option<f64>, at: Option<DateTime<Local>>) -> String {
+    match (occupancy, at) {
+        (Some(pct), Some(at)) => format!("Gym: {:.0}% • {}", pct, at.format("%H:%M")),
+        (Some(pct), None) => format!("Gym: {:.0}%", pct),
+        (None, _) => "Gym closed".to_string(),
+    }
+}
+
+/// Import status, e.g. "Imported 40 records, skipped 2 duplicates and 1
+/// malformed row", followed by the first few malformed rows.
+fn import_status(summary: &ImportSummary) -> String {
+    const ERRORS_SHOWN: usize = 3;
+
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    let mut status = format!(
+        "Imported {} record{}, skipped {} duplicate{} and {} malformed row{}",
+        summary.inserted,
+        plural(summary.inserted),
+        summary.duplicates,
+        plural(summary.duplicates),
+        summary.malformed,
+        plural(summary.malformed)
+    );
+    for error in summary.errors.iter().take(ERRORS_SHOWN) {
+        status.push('\n');
+        status.push_str(error);
+    }
+    if summary.errors.len() > ERRORS_SHOWN {
+        status.push_str(&format!(
+            "\n...and {} more",
+            summary.errors.len() - ERRORS_SHOWN
+        ));
+    }
+    status
+}
+
 fn card_container<'a>(
     content: impl Into<Element<'a, Message>>,
 ) -> container::Container<'a, Message> {
-    container(content).padding(24).style(|_| container::Style {
-        background: Some(style::BG_CARD.into()),
-        border: Border {
-            color: Color::TRANSPARENT,
-            width: 0.0,
-            radius: 16.0.into(),
-        },
-        shadow: Shadow {
-            color: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
-            offset: Vector::new(0.0, 4.0),
-            blur_radius: 10.0,
-        },
-        ..Default::default()
-    })
+    container(content)
+        .padding(24)
+        .style(|theme| container::Style {
+            background: Some(Palette::of(theme).card.into()),
+            border: Border {
+                color: Color::TRANSPARENT,
+                width: 0.0,
+                radius: 16.0.into(),
+            },
+            shadow: Shadow {
+                color: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+                offset: Vector::new(0.0, 4.0),
+                blur_radius: 10.0,
+            },
+            ..Default::default()
+        })
+}
+
+/// Message for a keyboard shortcut, as listed in the sidebar.
+fn shortcut_message(key: keyboard::Key<&str>) -> Option<Message> {
+    match key {
+        keyboard::Key::Character("1") => Some(Message::SwitchView(ViewMode::Dashboard)),
+        keyboard::Key::Character("2") => Some(Message::SwitchView(ViewMode::WeeklyPattern)),
+        keyboard::Key::Character("3") => Some(Message::SwitchView(ViewMode::Insights)),
+        keyboard::Key::Character("4") => Some(Message::SwitchView(ViewMode::Comparison)),
+        keyboard::Key::Character("5") => Some(Message::SwitchView(ViewMode::DataRepair)),
+        keyboard::Key::Character("r") => Some(Message::RefreshNow),
+        keyboard::Key::Character("e") => Some(Message::ExportCsv),
+        keyboard::Key::Named(keyboard::key::Named::Escape) => Some(Message::WindowCloseRequested),
+        _ => None,
+    }
 }
 
 fn styled_input(
@@ -1841,48 +3087,51 @@ fn styled_input(
         .padding(8)
         .width(Length::Fixed(110.0))
         .size(12)
-        .style(|_, status| {
-            let border_color = if matches!(status, iced::widget::text_input::Status::Focused { .. })
-            {
-                style::ACCENT_BLUE
-            } else {
-                style::STROKE_DIM
-            };
-            text_input::Style {
-                background: style::BG_DARK.into(),
-                border: Border {
-                    color: border_color,
-                    width: 1.0,
-                    radius: 6.0.into(),
-                },
-                icon: style::TEXT_MUTED,
-                placeholder: style::TEXT_MUTED,
-                value: style::TEXT_BRIGHT,
-                selection: style::ACCENT_BLUE,
-            }
-        })
+        .style(input_style)
         .into()
 }
 
+fn input_style(theme: &Theme, status: text_input::Status) -> text_input::Style {
+    let palette = Palette::of(theme);
+    let border_color = if matches!(status, text_input::Status::Focused { .. }) {
+        palette.accent_blue
+    } else {
+        palette.stroke
+    };
+    text_input::Style {
+        background: palette.background.into(),
+        border: Border {
+            color: border_color,
+            width: 1.0,
+            radius: 6.0.into(),
+        },
+        icon: palette.text_muted,
+        placeholder: palette.text_muted,
+        value: palette.text,
+        selection: palette.accent_blue,
+    }
+}
+
 fn preset_btn(label: &str, days: i64, current: Option<i64>) -> Element<'_, Message> {
     let active = current == Some(days);
     button(text(label.to_string()).size(12))
         .on_press(Message::HistoryPresetSelected(days))
         .padding([6, 12])
-        .style(move |_, _| {
+        .style(move |theme, status| {
             if active {
-                primary_btn_style(&Theme::Dark, iced::widget::button::Status::Active)
+                primary_btn_style(theme, status)
             } else {
-                secondary_btn_style(&Theme::Dark, iced::widget::button::Status::Active)
+                secondary_btn_style(theme, status)
             }
         })
         .into()
 }
 
-fn primary_btn_style(_: &Theme, _: iced::widget::button::Status) -> button::Style {
+fn primary_btn_style(theme: &Theme, _: iced::widget::button::Status) -> button::Style {
+    let palette = Palette::of(theme);
     button::Style {
-        background: Some(style::ACCENT_BLUE.into()),
-        text_color: style::BG_DARK,
+        background: Some(palette.accent_blue.into()),
+        text_color: palette.background,
         border: Border {
             radius: 6.0.into(),
             ..Default::default()
@@ -1891,13 +3140,14 @@ fn primary_btn_style(_: &Theme, _: iced::widget::button::Status) -> button::Styl
     }
 }
 
-fn secondary_btn_style(_: &Theme, _: iced::widget::button::Status) -> button::Style {
+fn secondary_btn_style(theme: &Theme, _: iced::widget::button::Status) -> button::Style {
+    let palette = Palette::of(theme);
     button::Style {
-        background: Some(style::BG_DARK.into()),
-        text_color: style::TEXT_BRIGHT,
+        background: Some(palette.background.into()),
+        text_color: palette.text,
         border: Border {
             radius: 6.0.into(),
-            color: style::STROKE_DIM,
+            color: palette.stroke,
             width: 1.0,
         },
         ..Default::default()
@@ -1,36 +1,122 @@
-use std::path::PathBuf;
-
-use anyhow::{Context, Result};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, ensure};
+use chrono::{NaiveDate, Weekday};
 use config::{Config, Environment, File};
 use serde::Deserialize;
 
+use crate::{
+    analytics::Language,
+    api::{ApiFormat, DEFAULT_USER_AGENT},
+    db::{
+        DEFAULT_EXPORT_DATE_FORMAT, DEFAULT_GYM_ID, ExportColumn, ExportOptions,
+        is_valid_date_format,
+    },
+    ml::MlConfig,
+    schedule::GermanState,
+};
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub database: DatabaseConfig,
     pub gym: GymConfig,
     pub network: NetworkConfig,
     pub window: WindowConfig,
+    pub ui: UiConfig,
     pub refresh: RefreshConfig,
     pub notifications: NotificationConfig,
     pub thresholds: ThresholdsConfig,
     pub analytics: AnalyticsConfig,
+    pub export: ExportConfig,
     pub schedule: ScheduleConfig,
+    pub daemon: DaemonConfig,
+    pub ml: MlConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Skip inserts within this many seconds of the latest record (0 = off)
+    pub min_record_interval_secs: u64,
+    /// Size of the connection pool
+    pub max_connections: u32,
+    /// Seconds to wait for a free pooled connection
+    pub acquire_timeout_secs: u64,
+    /// Daemon deletes records older than this many days (0 = keep forever)
+    pub retention_days: u32,
+    /// Hours between pruning runs
+    pub prune_interval_hours: u64,
+    /// Vacuum after a pruning run removed at least this many rows (0 = never)
+    pub vacuum_min_rows: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct GymConfig {
+    /// Single-gym shorthand, used when `gyms` is empty
     pub api_url: String,
+    /// Response format of the gym APIs
+    #[serde(default)]
+    pub api_format: ApiFormat,
+    /// Gyms to monitor; records are tagged with each entry's id
+    #[serde(default)]
+    pub gyms: Vec<GymEntry>,
+    /// Identical consecutive readings after which the portal is considered
+    /// frozen (0 = off)
+    #[serde(default = "default_stale_detection_count")]
+    pub stale_detection_count: usize,
+    /// Don't store readings while the portal is considered frozen
+    #[serde(default)]
+    pub skip_stale_readings: bool,
+    /// Number of people the gym holds at 100%, for showing estimated counts
+    #[serde(default)]
+    pub capacity: Option<u32>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+fn default_stale_detection_count() -> usize {
+    30
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct GymEntry {
+    pub id: String,
+    pub name: String,
+    pub api_url: String,
+}
+
+impl GymConfig {
+    /// Configured gyms, or a single default gym built from `api_url`.
+    pub fn entries(&self) -> Vec<GymEntry> {
+        if !self.gyms.is_empty() {
+            return self.gyms.clone();
+        }
+        vec![GymEntry {
+            id: DEFAULT_GYM_ID.to_string(),
+            name: "Hardy's".to_string(),
+            api_url: self.api_url.clone(),
+        }]
+    }
+}
+
+#[derive(Deserialize, Clone)]
 pub struct NetworkConfig {
     pub request_timeout_secs: u64,
     pub connect_timeout_secs: u64,
+    /// Retries after a timeout, 5xx or 429 response (0 = no retries)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub retry_base_delay_ms: u64,
+    /// Extra headers sent with every gym API request (e.g. an API key)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>` with every gym API request
+    pub bearer_token: Option<String>,
+    /// User-Agent header for gym API requests (unset = none)
+    pub user_agent: Option<String>,
 }
 
 impl Default for NetworkConfig {
@@ -38,10 +124,35 @@ impl Default for NetworkConfig {
         Self {
             request_timeout_secs: 30,
             connect_timeout_secs: 10,
+            max_retries: 3,
+            retry_base_delay_ms: 200,
+            headers: HashMap::new(),
+            bearer_token: None,
+            user_agent: Some(DEFAULT_USER_AGENT.to_string()),
         }
     }
 }
 
+/// Header values and the token may be secrets, so only their presence is shown.
+impl fmt::Debug for NetworkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut header_names: Vec<_> = self.headers.keys().collect();
+        header_names.sort();
+        f.debug_struct("NetworkConfig")
+            .field("request_timeout_secs", &self.request_timeout_secs)
+            .field("connect_timeout_secs", &self.connect_timeout_secs)
+            .field("max_retries", &self.max_retries)
+            .field("retry_base_delay_ms", &self.retry_base_delay_ms)
+            .field("user_agent", &self.user_agent)
+            .field("headers", &header_names)
+            .field(
+                "bearer_token",
+                &self.bearer_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct WindowConfig {
     #[allow(dead_code)]
@@ -49,6 +160,10 @@ pub struct WindowConfig {
     pub width: f32,
     pub height: f32,
     pub sidebar_width: f32,
+    /// Replace the tray icon with a sparkline of the last hour's occupancy
+    pub tray_sparkline: bool,
+    /// Language for weekday names and insights
+    pub language: Language,
 }
 
 impl Default for WindowConfig {
@@ -58,15 +173,36 @@ impl Default for WindowConfig {
             width: 1200.0,
             height: 850.0,
             sidebar_width: 250.0,
+            tray_sparkline: true,
+            language: Language::English,
         }
     }
 }
 
+/// Color scheme of the GUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+    /// Follow the operating system's light/dark preference
+    System,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct UiConfig {
+    /// Color scheme on startup; can be toggled at runtime
+    pub theme: ThemeMode,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct RefreshConfig {
     pub ui_interval_secs: u64,
     pub data_fetch_interval_secs: u64,
     pub tray_poll_interval_ms: u64,
+    /// Fetch as soon as the gym opens instead of waiting for the next tick
+    pub prefetch_on_open: bool,
 }
 
 impl Default for RefreshConfig {
@@ -75,6 +211,7 @@ impl Default for RefreshConfig {
             ui_interval_secs: 30,
             data_fetch_interval_secs: 60,
             tray_poll_interval_ms: 50,
+            prefetch_on_open: true,
         }
     }
 }
@@ -85,6 +222,56 @@ pub struct NotificationConfig {
     pub threshold_percent: f64,
     /// Ntfy.sh topic for phone notifications (e.g., "hardys-occupancy-1993")
     pub ntfy_topic: Option<String>,
+    /// Notify ahead of a predicted drop below the threshold, once per day
+    pub predictive_alerts: bool,
+    /// How long before the predicted quiet time the alert is sent
+    pub predictive_lead_minutes: i64,
+    /// Minimum seconds between two threshold alerts (0 = no cooldown)
+    pub min_notification_interval_secs: u64,
+    /// Also alert when occupancy rises above `high_threshold_percent`
+    pub high_threshold_alert: bool,
+    pub high_threshold_percent: f64,
+    /// Play a short sound along with threshold alerts
+    #[serde(default)]
+    pub play_sound: bool,
+    /// SMTP settings for email alerts (unset = no email)
+    #[serde(default)]
+    pub email: Option<EmailConfig>,
+    /// URL that receives a JSON POST per notification, e.g. a Discord webhook
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// 465 uses implicit TLS, any other port STARTTLS
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// Login for servers that require authentication
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. "Hardy Monitor <alerts@example.com>"
+    pub from: String,
+    /// Recipient address
+    pub to: String,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// The password is a secret, so only its presence is shown.
+impl fmt::Debug for EmailConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmailConfig")
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
 }
 
 impl Default for NotificationConfig {
@@ -93,6 +280,14 @@ impl Default for NotificationConfig {
             enabled: false,
             threshold_percent: 30.0,
             ntfy_topic: None,
+            predictive_alerts: false,
+            predictive_lead_minutes: 60,
+            min_notification_interval_secs: 900,
+            high_threshold_alert: false,
+            high_threshold_percent: 75.0,
+            play_sound: false,
+            email: None,
+            webhook_url: None,
         }
     }
 }
@@ -101,6 +296,8 @@ impl Default for NotificationConfig {
 pub struct ThresholdsConfig {
     pub low_occupancy_percent: f64,
     pub high_occupancy_percent: f64,
+    /// Show a "closes soon" warning this many minutes before closing
+    pub closing_warning_minutes: u32,
 }
 
 impl Default for ThresholdsConfig {
@@ -108,6 +305,7 @@ impl Default for ThresholdsConfig {
         Self {
             low_occupancy_percent: 40.0,
             high_occupancy_percent: 75.0,
+            closing_warning_minutes: 30,
         }
     }
 }
@@ -115,12 +313,82 @@ impl Default for ThresholdsConfig {
 #[derive(Debug, Deserialize, Clone)]
 pub struct AnalyticsConfig {
     pub prediction_window_days: i64,
+    /// Identical consecutive readings before flagging a possible stuck sensor
+    pub stuck_sensor_min_readings: usize,
+    /// Start the prediction baseline at a detected level shift, if any
+    pub adaptive_baseline: bool,
+    /// Moving-average window for the history chart overlay (0 disables it)
+    pub smoothing_window_minutes: i64,
+    /// Weeks shown in the analytics view on startup
+    pub range_weeks: u32,
 }
 
 impl Default for AnalyticsConfig {
     fn default() -> Self {
         Self {
             prediction_window_days: 28,
+            stuck_sensor_min_readings: 60,
+            adaptive_baseline: false,
+            smoothing_window_minutes: 15,
+            range_weeks: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExportConfig {
+    /// Columns written to CSV exports, in order
+    pub columns: Vec<ExportColumn>,
+    /// Field separator of CSV exports and imports, e.g. ';' for German Excel
+    pub delimiter: char,
+    /// Append a local-time column to exports unless one is already selected
+    pub include_local_time: bool,
+    /// strftime format of the local-time column
+    pub date_format: String,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            columns: ExportColumn::DEFAULT.to_vec(),
+            delimiter: ',',
+            include_local_time: false,
+            date_format: DEFAULT_EXPORT_DATE_FORMAT.to_string(),
+        }
+    }
+}
+
+impl ExportConfig {
+    /// Options for [`crate::db::write_csv`]. The delimiter is validated to be
+    /// ASCII.
+    pub fn options(&self) -> ExportOptions {
+        ExportOptions {
+            delimiter: self.delimiter as u8,
+            include_local_time: self.include_local_time,
+            date_format: self.date_format.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DaemonConfig {
+    /// Port of the `/health` status endpoint (unset = no endpoint)
+    pub status_port: Option<u16>,
+    /// Address the status endpoint listens on; 0.0.0.0 for all interfaces
+    pub status_bind: IpAddr,
+    /// Day the weekly digest is sent on, e.g. "sunday" (unset = no digest)
+    pub digest_weekday: Option<Weekday>,
+    /// Local hour the weekly digest is sent at
+    pub digest_hour: u32,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            status_port: None,
+            status_bind: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            digest_weekday: None,
+            digest_hour: 20,
         }
     }
 }
@@ -129,6 +397,16 @@ impl Default for AnalyticsConfig {
 pub struct ScheduleConfig {
     pub weekday: ScheduleHours,
     pub weekend: ScheduleHours,
+    /// Hours for each day, Monday first; replaces weekday/weekend when set
+    pub days: Option<[ScheduleHours; 7]>,
+    /// State whose public holidays use the weekend hours
+    pub holidays: GermanState,
+    /// IANA time zone the hours are given in, or "local" for the system zone
+    pub timezone: String,
+    /// Extra dates the gym is closed all day (renovations, private events)
+    pub closed_dates: Vec<NaiveDate>,
+    /// ICS file with additional all-day holidays
+    pub holiday_calendar: Option<PathBuf>,
 }
 
 impl Default for ScheduleConfig {
@@ -137,23 +415,48 @@ impl Default for ScheduleConfig {
             weekday: ScheduleHours {
                 open_hour: 6,
                 close_hour: 23,
+                windows: None,
             },
             weekend: ScheduleHours {
                 open_hour: 9,
                 close_hour: 21,
+                windows: None,
             },
+            days: None,
+            holidays: GermanState::Bavaria,
+            timezone: "Europe/Berlin".into(),
+            closed_dates: Vec::new(),
+            holiday_calendar: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ScheduleHours {
     pub open_hour: u32,
     pub close_hour: u32,
+    /// Separate opening windows (e.g. `[[6, 13], [15, 23]]` for a midday
+    /// closure); replaces `open_hour`/`close_hour` when set
+    pub windows: Option<Vec<(u32, u32)>>,
+}
+
+impl ScheduleHours {
+    /// Opening windows as `(open_hour, close_hour)` pairs.
+    pub fn open_windows(&self) -> Vec<(u32, u32)> {
+        self.windows
+            .clone()
+            .unwrap_or_else(|| vec![(self.open_hour, self.close_hour)])
+    }
 }
 
 impl AppConfig {
     pub fn load() -> Result<Self> {
+        Self::load_from(&Self::user_config_path())
+    }
+
+    /// Load the config, reading user overrides from `user_config` instead of
+    /// the user config directory.
+    pub fn load_from(user_config: &Path) -> Result<Self> {
         // Load .env file (silently ignore if not present - production uses env vars directly)
         let _ = dotenvy::dotenv();
 
@@ -161,55 +464,335 @@ impl AppConfig {
         let database_url = std::env::var("DATABASE_URL")
             .context("DATABASE_URL must be set (via .env file or environment variable)")?;
 
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("hardy-monitor");
-
         let builder = Config::builder()
             // 1. Load default values
             // Database (loaded from environment above)
             .set_default("database.url", database_url)?
+            .set_default("database.min_record_interval_secs", 30)?
+            .set_default("database.max_connections", 5)?
+            .set_default("database.acquire_timeout_secs", 30)?
+            .set_default("database.retention_days", 0)?
+            .set_default("database.prune_interval_hours", 24)?
+            .set_default("database.vacuum_min_rows", 10_000)?
             // Gym
             .set_default("gym.api_url", "https://portal.aidoo-online.de/workload?mandant=202300180_fuerstenfeldbruck&stud_nr=3&jsonResponse=1")?
+            .set_default("gym.api_format", "aidoo")?
+            .set_default("gym.gyms", Vec::<String>::new())?
+            .set_default("gym.stale_detection_count", 30)?
+            .set_default("gym.skip_stale_readings", false)?
+            .set_default("gym.capacity", None::<u32>)?
             // Network
             .set_default("network.request_timeout_secs", 30)?
             .set_default("network.connect_timeout_secs", 10)?
+            .set_default("network.max_retries", 3)?
+            .set_default("network.retry_base_delay_ms", 200)?
+            .set_default("network.bearer_token", None::<String>)?
+            .set_default("network.user_agent", DEFAULT_USER_AGENT)?
             // Window
             .set_default("window.title", "Hardy's Gym Monitor")?
             .set_default("window.width", 1200.0)?
             .set_default("window.height", 850.0)?
             .set_default("window.sidebar_width", 250.0)?
+            .set_default("window.tray_sparkline", true)?
+            .set_default("window.language", "english")?
+            // UI
+            .set_default("ui.theme", "dark")?
             // Refresh
             .set_default("refresh.ui_interval_secs", 30)?
             .set_default("refresh.data_fetch_interval_secs", 60)?
             .set_default("refresh.tray_poll_interval_ms", 50)?
+            .set_default("refresh.prefetch_on_open", true)?
             // Notifications
             .set_default("notifications.enabled", false)?
             .set_default("notifications.threshold_percent", 30.0)?
             .set_default("notifications.ntfy_topic", None::<String>)?
+            .set_default("notifications.predictive_alerts", false)?
+            .set_default("notifications.predictive_lead_minutes", 60)?
+            .set_default("notifications.min_notification_interval_secs", 900)?
+            .set_default("notifications.high_threshold_alert", false)?
+            .set_default("notifications.high_threshold_percent", 75.0)?
+            .set_default("notifications.play_sound", false)?
+            .set_default("notifications.webhook_url", None::<String>)?
             // Thresholds
             .set_default("thresholds.low_occupancy_percent", 40.0)?
             .set_default("thresholds.high_occupancy_percent", 75.0)?
+            .set_default("thresholds.closing_warning_minutes", 30)?
             // Analytics
             .set_default("analytics.prediction_window_days", 28)?
+            .set_default("analytics.stuck_sensor_min_readings", 60)?
+            .set_default("analytics.adaptive_baseline", false)?
+            .set_default("analytics.smoothing_window_minutes", 15)?
+            .set_default("analytics.range_weeks", 1)?
+            // Export
+            .set_default("export.columns", vec!["id", "timestamp", "percentage"])?
+            .set_default("export.delimiter", ",")?
+            .set_default("export.include_local_time", false)?
+            .set_default("export.date_format", DEFAULT_EXPORT_DATE_FORMAT)?
             // Schedule
             .set_default("schedule.weekday.open_hour", 6)?
             .set_default("schedule.weekday.close_hour", 23)?
+            .set_default("schedule.weekday.windows", None::<Vec<String>>)?
             .set_default("schedule.weekend.open_hour", 9)?
             .set_default("schedule.weekend.close_hour", 21)?
+            .set_default("schedule.weekend.windows", None::<Vec<String>>)?
+            .set_default("schedule.days", None::<Vec<String>>)?
+            .set_default("schedule.holidays", "bavaria")?
+            .set_default("schedule.timezone", "Europe/Berlin")?
+            .set_default("schedule.closed_dates", Vec::<String>::new())?
+            .set_default("schedule.holiday_calendar", None::<String>)?
+            // Daemon
+            .set_default("daemon.status_port", None::<u16>)?
+            .set_default("daemon.status_bind", "127.0.0.1")?
+            .set_default("daemon.digest_weekday", None::<String>)?
+            .set_default("daemon.digest_hour", 20)?
+            // ML predictions
+            .set_default("ml.enabled", true)?
+            .set_default("ml.training_window_days", 56)?
+            .set_default("ml.retrain_interval_hours", 24)?
+            .set_default("ml.prediction_horizon_hours", 6)?
+            .set_default("ml.min_samples_for_training", 500)?
+            .set_default("ml.fallback_on_error", true)?
+            .set_default("ml.round_to_local_hour", true)?
+            .set_default("ml.model_kind", "gradient_boosted_trees")?
+            .set_default("ml.cv_folds", 5)?
+            .set_default("ml.half_life_days", 28.0)?
+            .set_default("ml.model_params.n_estimators", 100)?
+            .set_default("ml.model_params.learning_rate", 0.1)?
+            .set_default("ml.model_params.max_depth", 6)?
+            .set_default("ml.model_params.min_samples_split", 5)?
+            .set_default("ml.model_params.min_samples_leaf", 2)?
 
             // 2. Load from local config file (optional, lowest priority)
             .add_source(File::from(PathBuf::from("config.toml")).required(false))
 
             // 3. Load from user config directory (optional, overrides local)
-            .add_source(File::from(config_dir.join("config.toml")).required(false))
+            .add_source(File::from(user_config.to_path_buf()).required(false))
 
             // 4. Load from Environment variables (HARDY_DATABASE__PATH=...)
             .add_source(Environment::with_prefix("HARDY").separator("__"));
 
         let s = builder.build()?;
-        Ok(s.try_deserialize()?)
+        let config: Self = s.try_deserialize()?;
+        config.validate()?;
+        Ok(config)
     }
+
+    /// Reject values that would only fail confusingly at runtime.
+    ///
+    /// Errors name the offending field, e.g. `schedule.weekday.open_hour`.
+    pub fn validate(&self) -> Result<()> {
+        if self.gym.gyms.is_empty() {
+            validate_url("gym.api_url", &self.gym.api_url)?;
+        }
+        for (i, entry) in self.gym.gyms.iter().enumerate() {
+            validate_url(&format!("gym.gyms[{}].api_url", i), &entry.api_url)?;
+        }
+        if let Some(url) = &self.notifications.webhook_url {
+            validate_url("notifications.webhook_url", url)?;
+        }
+        if let Some(capacity) = self.gym.capacity {
+            validate_positive("gym.capacity", capacity.into())?;
+        }
+
+        ensure!(
+            self.thresholds.low_occupancy_percent < self.thresholds.high_occupancy_percent,
+            "thresholds.high_occupancy_percent ({}) must be above \
+             thresholds.low_occupancy_percent ({})",
+            self.thresholds.high_occupancy_percent,
+            self.thresholds.low_occupancy_percent
+        );
+        validate_percent(
+            "thresholds.low_occupancy_percent",
+            self.thresholds.low_occupancy_percent,
+        )?;
+        validate_percent(
+            "thresholds.high_occupancy_percent",
+            self.thresholds.high_occupancy_percent,
+        )?;
+        validate_percent(
+            "notifications.threshold_percent",
+            self.notifications.threshold_percent,
+        )?;
+        validate_percent(
+            "notifications.high_threshold_percent",
+            self.notifications.high_threshold_percent,
+        )?;
+        ensure!(
+            !self.notifications.high_threshold_alert
+                || self.notifications.threshold_percent < self.notifications.high_threshold_percent,
+            "notifications.high_threshold_percent ({}) must be above \
+             notifications.threshold_percent ({})",
+            self.notifications.high_threshold_percent,
+            self.notifications.threshold_percent
+        );
+
+        validate_positive(
+            "network.request_timeout_secs",
+            self.network.request_timeout_secs,
+        )?;
+        validate_positive(
+            "network.connect_timeout_secs",
+            self.network.connect_timeout_secs,
+        )?;
+        validate_positive("refresh.ui_interval_secs", self.refresh.ui_interval_secs)?;
+        validate_positive(
+            "refresh.data_fetch_interval_secs",
+            self.refresh.data_fetch_interval_secs,
+        )?;
+        validate_positive(
+            "refresh.tray_poll_interval_ms",
+            self.refresh.tray_poll_interval_ms,
+        )?;
+        ensure!(
+            self.analytics.prediction_window_days > 0,
+            "analytics.prediction_window_days must be positive, got {}",
+            self.analytics.prediction_window_days
+        );
+
+        ensure!(
+            self.export.delimiter.is_ascii() && !matches!(self.export.delimiter, '"' | '\n' | '\r'),
+            "export.delimiter must be a single ASCII character other than a quote or line break, \
+             got {:?}",
+            self.export.delimiter
+        );
+        ensure!(
+            is_valid_date_format(&self.export.date_format),
+            "export.date_format is not a valid strftime format: {:?}",
+            self.export.date_format
+        );
+
+        ensure!(
+            self.daemon.digest_hour < 24,
+            "daemon.digest_hour must be 0-23, got {}",
+            self.daemon.digest_hour
+        );
+
+        validate_hours("schedule.weekday", &self.schedule.weekday)?;
+        validate_hours("schedule.weekend", &self.schedule.weekend)?;
+        if let Some(days) = &self.schedule.days {
+            for (i, hours) in days.iter().enumerate() {
+                validate_hours(&format!("schedule.days[{}]", i), hours)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The per-user config file, which overrides the local `config.toml`.
+    pub fn user_config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hardy-monitor")
+            .join("config.toml")
+    }
+
+    /// Write the settings that can be changed in the GUI to `path`.
+    ///
+    /// Other keys already in the file are kept (its comments are not). Values
+    /// that may come from the environment, like the database URL, are never
+    /// written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut doc = match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .parse::<toml::Table>()
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        let notifications = table_mut(&mut doc, "notifications")?;
+        notifications.insert("enabled".into(), self.notifications.enabled.into());
+        notifications.insert(
+            "threshold_percent".into(),
+            self.notifications.threshold_percent.into(),
+        );
+        let analytics = table_mut(&mut doc, "analytics")?;
+        analytics.insert(
+            "range_weeks".into(),
+            i64::from(self.analytics.range_weeks).into(),
+        );
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(path, toml::to_string(&doc)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn validate_url(field: &str, url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)
+        .with_context(|| format!("{} is not a valid URL: {:?}", field, url))?;
+    ensure!(
+        matches!(parsed.scheme(), "http" | "https"),
+        "{} must be an http(s) URL, got {:?}",
+        field,
+        url
+    );
+    Ok(())
+}
+
+fn validate_percent(field: &str, value: f64) -> Result<()> {
+    ensure!(
+        (0.0..=100.0).contains(&value),
+        "{} must be between 0 and 100, got {}",
+        field,
+        value
+    );
+    Ok(())
+}
+
+fn validate_positive(field: &str, value: u64) -> Result<()> {
+    ensure!(value > 0, "{} must be positive", field);
+    Ok(())
+}
+
+fn validate_hours(field: &str, hours: &ScheduleHours) -> Result<()> {
+    let Some(windows) = &hours.windows else {
+        ensure!(
+            hours.open_hour <= 24,
+            "{}.open_hour must be between 0 and 24, got {}",
+            field,
+            hours.open_hour
+        );
+        ensure!(
+            hours.close_hour <= 24,
+            "{}.close_hour must be between 0 and 24, got {}",
+            field,
+            hours.close_hour
+        );
+        ensure!(
+            hours.open_hour < hours.close_hour,
+            "{}.open_hour ({}) must be before {}.close_hour ({})",
+            field,
+            hours.open_hour,
+            field,
+            hours.close_hour
+        );
+        return Ok(());
+    };
+
+    for (i, &(open, close)) in windows.iter().enumerate() {
+        ensure!(
+            open < close && close <= 24,
+            "{}.windows[{}] must satisfy open < close <= 24, got [{}, {}]",
+            field,
+            i,
+            open,
+            close
+        );
+    }
+    Ok(())
+}
+
+/// Get the `[key]` table of a TOML document, creating it if missing.
+fn table_mut<'a>(doc: &'a mut toml::Table, key: &str) -> Result<&'a mut toml::Table> {
+    doc.entry(key)
+        .or_insert_with(|| toml::Table::new().into())
+        .as_table_mut()
+        .with_context(|| format!("`{}` in the config file is not a table", key))
 }
 
 #[cfg(test)]
@@ -223,6 +806,12 @@ mod tests {
         let config = NetworkConfig::default();
         assert_eq!(config.request_timeout_secs, 30);
         assert_eq!(config.connect_timeout_secs, 10);
+        assert_eq!(config.max_retries, 3);
+        assert_eq!(config.retry_base_delay_ms, 200);
+        assert!(config.headers.is_empty());
+        assert!(config.bearer_token.is_none());
+        assert_eq!(config.user_agent.as_deref(), Some(DEFAULT_USER_AGENT));
+        assert!(DEFAULT_USER_AGENT.starts_with("hardy-monitor/"));
     }
 
     #[test]
@@ -232,6 +821,103 @@ mod tests {
         assert_eq!(config.width, 1200.0);
         assert_eq!(config.height, 850.0);
         assert_eq!(config.sidebar_width, 250.0);
+        assert!(config.tray_sparkline);
+        assert_eq!(config.language, Language::English);
+    }
+
+    #[test]
+    fn test_daemon_config_defaults() {
+        let config = DaemonConfig::default();
+        assert!(config.status_port.is_none());
+        assert_eq!(config.status_bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert!(config.digest_weekday.is_none());
+        assert_eq!(config.digest_hour, 20);
+    }
+
+    #[test]
+    fn test_load_digest_weekday_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[daemon]\ndigest_weekday = \"sunday\"\ndigest_hour = 19\n",
+        )
+        .unwrap();
+
+        let config = AppConfig::load_from(&path).expect("Config should load");
+
+        assert_eq!(config.daemon.digest_weekday, Some(Weekday::Sun));
+        assert_eq!(config.daemon.digest_hour, 19);
+    }
+
+    #[test]
+    fn test_load_status_bind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "[daemon]\nstatus_port = 8080\n").unwrap();
+        let config = AppConfig::load_from(&path).expect("Config should load");
+        assert_eq!(config.daemon.status_bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        fs::write(&path, "[daemon]\nstatus_bind = \"0.0.0.0\"\n").unwrap();
+        let config = AppConfig::load_from(&path).expect("Config should load");
+        assert_eq!(config.daemon.status_bind, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        fs::write(&path, "[daemon]\nstatus_bind = \"localhost\"\n").unwrap();
+        assert!(AppConfig::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_load_ml_model_params() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[ml]\nmodel_kind = \"linear\"\n\n[ml.model_params]\nmax_depth = 4\n",
+        )
+        .unwrap();
+
+        let config = AppConfig::load_from(&path).expect("Config should load");
+
+        assert_eq!(config.ml.model_kind, crate::ml::ModelKind::Linear);
+        assert_eq!(config.ml.model_params.max_depth, 4);
+        // Unset values keep their defaults
+        assert_eq!(config.ml.model_params.n_estimators, 100);
+        assert_eq!(config.ml.half_life_days, Some(28.0));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_capacity() {
+        let mut config = valid_config();
+        config.gym.capacity = Some(0);
+        assert_invalid(config, "gym.capacity");
+    }
+
+    #[test]
+    fn test_validate_rejects_digest_hour() {
+        let mut config = valid_config();
+        config.daemon.digest_hour = 24;
+        assert_invalid(config, "daemon.digest_hour");
+    }
+
+    #[test]
+    fn test_ui_config_defaults() {
+        let config = UiConfig::default();
+        assert_eq!(config.theme, ThemeMode::Dark);
+    }
+
+    #[test]
+    fn test_ui_theme_deserializes_lowercase() {
+        let config: UiConfig = Config::builder()
+            .add_source(File::from_str(
+                r#"theme = "system""#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("UI config should deserialize");
+
+        assert_eq!(config.theme, ThemeMode::System);
     }
 
     #[test]
@@ -240,6 +926,7 @@ mod tests {
         assert_eq!(config.ui_interval_secs, 30);
         assert_eq!(config.data_fetch_interval_secs, 60);
         assert_eq!(config.tray_poll_interval_ms, 50);
+        assert!(config.prefetch_on_open);
     }
 
     #[test]
@@ -247,6 +934,14 @@ mod tests {
         let config = NotificationConfig::default();
         assert!(!config.enabled);
         assert_eq!(config.threshold_percent, 30.0);
+        assert!(!config.predictive_alerts);
+        assert_eq!(config.predictive_lead_minutes, 60);
+        assert_eq!(config.min_notification_interval_secs, 900);
+        assert!(!config.high_threshold_alert);
+        assert_eq!(config.high_threshold_percent, 75.0);
+        assert!(!config.play_sound);
+        assert!(config.email.is_none());
+        assert!(config.webhook_url.is_none());
     }
 
     #[test]
@@ -254,12 +949,60 @@ mod tests {
         let config = ThresholdsConfig::default();
         assert_eq!(config.low_occupancy_percent, 40.0);
         assert_eq!(config.high_occupancy_percent, 75.0);
+        assert_eq!(config.closing_warning_minutes, 30);
     }
 
     #[test]
     fn test_analytics_config_defaults() {
         let config = AnalyticsConfig::default();
         assert_eq!(config.prediction_window_days, 28);
+        assert_eq!(config.stuck_sensor_min_readings, 60);
+        assert!(!config.adaptive_baseline);
+        assert_eq!(config.smoothing_window_minutes, 15);
+        assert_eq!(config.range_weeks, 1);
+    }
+
+    #[test]
+    fn test_export_config_defaults() {
+        let config = ExportConfig::default();
+        assert_eq!(
+            config.columns,
+            vec![
+                ExportColumn::Id,
+                ExportColumn::Timestamp,
+                ExportColumn::Percentage
+            ]
+        );
+        assert_eq!(config.options(), ExportOptions::default());
+    }
+
+    #[test]
+    fn test_load_export_semicolon_delimiter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[export]\ndelimiter = \";\"\ninclude_local_time = true\n",
+        )
+        .unwrap();
+
+        let config = AppConfig::load_from(&path).expect("Config should load");
+
+        let options = config.export.options();
+        assert_eq!(options.delimiter, b';');
+        assert!(options.include_local_time);
+        assert_eq!(options.date_format, DEFAULT_EXPORT_DATE_FORMAT);
+    }
+
+    #[test]
+    fn test_validate_rejects_export_options() {
+        let mut config = valid_config();
+        config.export.delimiter = 'ä';
+        assert_invalid(config, "export.delimiter");
+
+        let mut config = valid_config();
+        config.export.date_format = "%Q".to_string();
+        assert_invalid(config, "export.date_format");
     }
 
     #[test]
@@ -269,6 +1012,42 @@ mod tests {
         assert_eq!(config.weekday.close_hour, 23);
         assert_eq!(config.weekend.open_hour, 9);
         assert_eq!(config.weekend.close_hour, 21);
+        assert!(config.days.is_none());
+        assert_eq!(config.holidays, GermanState::Bavaria);
+        assert_eq!(config.timezone, "Europe/Berlin");
+        assert!(config.closed_dates.is_empty());
+        assert!(config.holiday_calendar.is_none());
+    }
+
+    #[test]
+    fn test_schedule_days_table_deserializes() {
+        let toml = r#"
+            holidays = "bavaria"
+            timezone = "Europe/Berlin"
+            closed_dates = []
+            weekday = { open_hour = 6, close_hour = 23 }
+            weekend = { open_hour = 9, close_hour = 21 }
+            days = [
+                { open_hour = 6, close_hour = 23 },
+                { open_hour = 6, close_hour = 23 },
+                { open_hour = 6, close_hour = 23 },
+                { open_hour = 6, close_hour = 23 },
+                { open_hour = 6, close_hour = 22 },
+                { open_hour = 8, close_hour = 20 },
+                { open_hour = 10, close_hour = 18 },
+            ]
+        "#;
+
+        let config: ScheduleConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Schedule should deserialize");
+
+        let days = config.days.expect("days should be set");
+        assert_eq!(days[4].close_hour, 22);
+        assert_eq!(days[5].open_hour, 8);
+        assert_eq!(days[6].open_hour, 10);
     }
 
     // ==================== Config Loading Tests ====================
@@ -298,16 +1077,128 @@ mod tests {
     // ==================== Struct Field Tests ====================
 
     #[test]
-    fn test_schedule_hours_copy() {
+    fn test_schedule_hours_clone() {
         let hours = ScheduleHours {
             open_hour: 8,
             close_hour: 20,
+            windows: None,
         };
-        let copy = hours; // Should work because it implements Copy
+        let copy = hours.clone();
         assert_eq!(copy.open_hour, 8);
         assert_eq!(copy.close_hour, 20);
     }
 
+    #[test]
+    fn test_schedule_hours_open_windows() {
+        let single = ScheduleHours {
+            open_hour: 8,
+            close_hour: 20,
+            windows: None,
+        };
+        assert_eq!(single.open_windows(), vec![(8, 20)]);
+
+        let split = ScheduleHours {
+            windows: Some(vec![(6, 13), (15, 23)]),
+            ..single
+        };
+        assert_eq!(split.open_windows(), vec![(6, 13), (15, 23)]);
+    }
+
+    #[test]
+    fn test_schedule_windows_deserialize() {
+        let toml = r#"
+            holidays = "bavaria"
+            timezone = "Europe/Berlin"
+            closed_dates = []
+            weekday = { open_hour = 6, close_hour = 23, windows = [[6, 13], [15, 23]] }
+            weekend = { open_hour = 9, close_hour = 21 }
+        "#;
+
+        let config: ScheduleConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Schedule should deserialize");
+
+        assert_eq!(config.weekday.windows, Some(vec![(6, 13), (15, 23)]));
+        assert!(config.weekend.windows.is_none());
+    }
+
+    #[test]
+    fn test_schedule_closed_dates_deserialize() {
+        let toml = r#"
+            holidays = "bavaria"
+            timezone = "Europe/Berlin"
+            closed_dates = ["2024-06-11", "2024-12-31"]
+            weekday = { open_hour = 6, close_hour = 23 }
+            weekend = { open_hour = 9, close_hour = 21 }
+        "#;
+
+        let config: ScheduleConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Schedule should deserialize");
+
+        assert_eq!(
+            config.closed_dates,
+            vec![
+                NaiveDate::from_ymd_opt(2024, 6, 11).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gym_shorthand_yields_default_entry() {
+        let toml = r#"
+            api_url = "https://example.com/api"
+        "#;
+
+        let config: GymConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Gym config should deserialize");
+
+        let entries = config.entries();
+        assert_eq!(config.api_format, ApiFormat::Aidoo);
+        assert_eq!(config.stale_detection_count, 30);
+        assert!(!config.skip_stale_readings);
+        assert!(config.capacity.is_none());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, DEFAULT_GYM_ID);
+        assert_eq!(entries[0].api_url, "https://example.com/api");
+    }
+
+    #[test]
+    fn test_gym_list_deserializes() {
+        let toml = r#"
+            api_url = "https://example.com/unused"
+            api_format = "count_capacity"
+
+            [[gyms]]
+            id = "ffb"
+            name = "Fürstenfeldbruck"
+            api_url = "https://example.com/ffb"
+
+            [[gyms]]
+            id = "gap"
+            name = "Garmisch"
+            api_url = "https://example.com/gap"
+        "#;
+
+        let config: GymConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Gym config should deserialize");
+
+        let ids: Vec<_> = config.entries().into_iter().map(|g| g.id).collect();
+        assert_eq!(ids, vec!["ffb", "gap"]);
+        assert_eq!(config.api_format, ApiFormat::CountCapacity);
+    }
+
     #[test]
     fn test_config_structs_are_clone() {
         let network = NetworkConfig::default();
@@ -330,6 +1221,51 @@ mod tests {
         assert!(debug_str.contains("request_timeout_secs"));
     }
 
+    #[test]
+    fn test_network_config_debug_redacts_secrets() {
+        let config = NetworkConfig {
+            headers: HashMap::from([("X-Api-Key".to_string(), "key-123".to_string())]),
+            bearer_token: Some("token-456".to_string()),
+            ..Default::default()
+        };
+        let debug_str = format!("{:?}", config);
+        assert!(debug_str.contains("X-Api-Key"));
+        assert!(!debug_str.contains("key-123"));
+        assert!(!debug_str.contains("token-456"));
+    }
+
+    #[test]
+    fn test_email_config_deserializes_with_default_port() {
+        let toml = r#"
+            enabled = true
+            threshold_percent = 25.0
+            predictive_alerts = false
+            predictive_lead_minutes = 60
+            min_notification_interval_secs = 900
+            high_threshold_alert = false
+            high_threshold_percent = 75.0
+
+            [email]
+            smtp_host = "smtp.example.com"
+            username = "alerts@example.com"
+            password = "hunter2"
+            from = "Hardy Monitor <alerts@example.com>"
+            to = "me@example.com"
+        "#;
+
+        let config: NotificationConfig = Config::builder()
+            .add_source(File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .expect("Notification config should deserialize");
+
+        let email = config.email.expect("Email config should be set");
+        assert_eq!(email.smtp_host, "smtp.example.com");
+        assert_eq!(email.smtp_port, 587);
+        assert_eq!(email.to, "me@example.com");
+        assert!(!format!("{:?}", email).contains("hunter2"));
+    }
+
     // ==================== Environment Variable Override Tests ====================
 
     /// Helper to safely set and remove environment variables in tests.
@@ -424,6 +1360,174 @@ mod tests {
         assert_eq!(config.notifications.threshold_percent, 15.5);
     }
 
+    #[test]
+    fn test_save_then_load_keeps_modified_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hardy-monitor").join("config.toml");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[gym]\napi_url = \"https://example.com/api\"\n").unwrap();
+
+        let mut config = AppConfig::load_from(&path).expect("Config should load");
+        config.notifications.threshold_percent = 42.0;
+        config.analytics.range_weeks = 4;
+        config.save(&path).expect("Config should save");
+
+        // Read the file alone, so env overrides from other tests can't interfere
+        let saved = Config::builder()
+            .add_source(File::from(path.clone()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            saved.get::<f64>("notifications.threshold_percent").unwrap(),
+            42.0
+        );
+        assert_eq!(saved.get::<u32>("analytics.range_weeks").unwrap(), 4);
+        assert_eq!(
+            saved.get::<String>("gym.api_url").unwrap(),
+            "https://example.com/api"
+        );
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            !contents.contains("database"),
+            "DATABASE_URL must not be written"
+        );
+    }
+
+    // ==================== Validate Tests ====================
+
+    fn valid_config() -> AppConfig {
+        AppConfig {
+            database: DatabaseConfig {
+                url: "postgres://localhost/hardy".to_string(),
+                min_record_interval_secs: 30,
+                max_connections: 5,
+                acquire_timeout_secs: 30,
+                retention_days: 0,
+                prune_interval_hours: 24,
+                vacuum_min_rows: 10_000,
+            },
+            gym: GymConfig {
+                api_url: "https://example.com/workload".to_string(),
+                api_format: ApiFormat::default(),
+                gyms: Vec::new(),
+                stale_detection_count: 30,
+                skip_stale_readings: false,
+                capacity: None,
+            },
+            network: NetworkConfig::default(),
+            window: WindowConfig::default(),
+            ui: UiConfig::default(),
+            refresh: RefreshConfig::default(),
+            notifications: NotificationConfig::default(),
+            thresholds: ThresholdsConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            export: ExportConfig::default(),
+            schedule: ScheduleConfig::default(),
+            daemon: DaemonConfig::default(),
+            ml: MlConfig::default(),
+        }
+    }
+
+    /// Assert that `config` fails validation with an error naming `field`.
+    fn assert_invalid(config: AppConfig, field: &str) {
+        let err = config
+            .validate()
+            .expect_err("Config should be rejected")
+            .to_string();
+        assert!(err.contains(field), "Error should name {}: {}", field, err);
+    }
+
+    #[test]
+    fn test_validate_accepts_defaults() {
+        assert!(valid_config().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_hour_out_of_range() {
+        let mut config = valid_config();
+        config.schedule.weekday.open_hour = 30;
+        assert_invalid(config, "schedule.weekday.open_hour");
+
+        let mut config = valid_config();
+        config.schedule.weekend.close_hour = 25;
+        assert_invalid(config, "schedule.weekend.close_hour");
+    }
+
+    #[test]
+    fn test_validate_rejects_open_after_close() {
+        let mut config = valid_config();
+        config.schedule.weekend.open_hour = 22;
+        config.schedule.weekend.close_hour = 9;
+        assert_invalid(config, "schedule.weekend.open_hour");
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_window() {
+        let mut config = valid_config();
+        config.schedule.weekday.windows = Some(vec![(6, 13), (15, 12)]);
+        assert_invalid(config, "schedule.weekday.windows[1]");
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_day_hours() {
+        let mut config = valid_config();
+        let hours = config.schedule.weekday.clone();
+        let mut days = std::array::from_fn(|_| hours.clone());
+        days[3].close_hour = 0;
+        config.schedule.days = Some(days);
+        assert_invalid(config, "schedule.days[3]");
+    }
+
+    #[test]
+    fn test_validate_rejects_swapped_thresholds() {
+        let mut config = valid_config();
+        config.thresholds.low_occupancy_percent = 80.0;
+        config.thresholds.high_occupancy_percent = 20.0;
+        assert_invalid(config, "thresholds.high_occupancy_percent");
+    }
+
+    #[test]
+    fn test_validate_rejects_percent_out_of_range() {
+        let mut config = valid_config();
+        config.notifications.threshold_percent = 120.0;
+        assert_invalid(config, "notifications.threshold_percent");
+    }
+
+    #[test]
+    fn test_validate_rejects_busy_threshold_below_empty_threshold() {
+        let mut config = valid_config();
+        config.notifications.high_threshold_alert = true;
+        config.notifications.high_threshold_percent = 20.0;
+        assert_invalid(config, "notifications.high_threshold_percent");
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_intervals() {
+        let mut config = valid_config();
+        config.refresh.data_fetch_interval_secs = 0;
+        assert_invalid(config, "refresh.data_fetch_interval_secs");
+
+        let mut config = valid_config();
+        config.network.request_timeout_secs = 0;
+        assert_invalid(config, "network.request_timeout_secs");
+
+        let mut config = valid_config();
+        config.analytics.prediction_window_days = 0;
+        assert_invalid(config, "analytics.prediction_window_days");
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_urls() {
+        let mut config = valid_config();
+        config.gym.api_url = "not a url".to_string();
+        assert_invalid(config, "gym.api_url");
+
+        let mut config = valid_config();
+        config.notifications.webhook_url = Some("ftp://example.com/hook".to_string());
+        assert_invalid(config, "notifications.webhook_url");
+    }
+
     // ==================== Config Value Validation Tests ====================
     // Note: Tests that set invalid env var values are avoided because they
     // can pollute other tests running in parallel.
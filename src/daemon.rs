@@ -0,0 +1,370 @@
+//! Daemon Module
+//!
+//! Holds everything the daemon builds from its config (schedule and API
+//! clients), so a reloaded config can be swapped in without restarting the
+//! fetch loop. Also contains the loop's fetch-and-store step, the live
+//! feed it publishes stored readings to, for applications embedding it, and
+//! the predictive quiet alert shared with the GUI.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, DurationRound, Local, NaiveDate, Utc};
+use tokio::sync::broadcast;
+
+use crate::{
+    analytics,
+    api::{self, GymApiClient},
+    config::{AppConfig, GymConfig, GymEntry},
+    db::{Database, HourlyAverage, OccupancyLog},
+    schedule::GymSchedule,
+    traits::{Clock, SystemClock},
+};
+
+/// Readings the live feed keeps for subscribers that fall behind.
+pub const LIVE_FEED_CAPACITY: usize = 64;
+
+/// Config-derived state of the daemon's fetch loop.
+pub struct DaemonSettings {
+    pub config: Arc<AppConfig>,
+    pub schedule: GymSchedule,
+    /// One API client per configured gym
+    pub gyms: Vec<(GymEntry, GymApiClient)>,
+}
+
+impl DaemonSettings {
+    /// Build the schedule and API clients for `config`.
+    pub fn new(config: Arc<AppConfig>) -> Result<Self> {
+        let gyms = GymApiClient::for_gyms(&config.gym, &config.network)?;
+        let schedule = GymSchedule::new(&config.schedule);
+        Ok(Self {
+            config,
+            schedule,
+            gyms,
+        })
+    }
+
+    /// Swap in a reloaded config.
+    ///
+    /// The database settings are kept, since the connection pool and the
+    /// background maintenance tasks are not rebuilt. On error the current
+    /// settings stay in place.
+    pub fn reload(&mut self, mut config: AppConfig) -> Result<()> {
+        config.database = self.config.database.clone();
+        *self = Self::new(Arc::new(config))?;
+        Ok(())
+    }
+}
+
+/// A reading stored by [`fetch_and_store`].
+#[derive(Debug, Clone)]
+pub struct LiveReading {
+    pub gym_id: String,
+    pub log: OccupancyLog,
+}
+
+/// Broadcast of stored readings, so consumers are pushed updates instead of
+/// polling the database.
+///
+/// Publishing never waits for subscribers. Each subscriber has its own
+/// buffer of [`LIVE_FEED_CAPACITY`] readings; one that falls further behind
+/// gets [`broadcast::error::RecvError::Lagged`] with the number of readings
+/// it missed, and then continues with the oldest one still buffered.
+/// Readings published while nobody is subscribed are dropped.
+#[derive(Debug, Clone)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveReading>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(LIVE_FEED_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive readings published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveReading> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, reading: LiveReading) {
+        // Only fails when nobody is subscribed
+        let _ = self.sender.send(reading);
+    }
+}
+
+/// Fetch current occupancy, store it in the database and publish it to
+/// `feed`.
+///
+/// Returns `None` if the reading was not stored because the portal looks
+/// frozen and `gym.skip_stale_readings` is set.
+pub async fn fetch_and_store(
+    api_client: &GymApiClient,
+    database: &Database,
+    stale: &mut api::StaleDetector,
+    gym: &GymConfig,
+    name: &str,
+    feed: &LiveFeed,
+) -> Result<Option<f64>> {
+    fetch_and_store_with_clock(api_client, database, stale, gym, name, feed, &SystemClock).await
+}
+
+/// [`fetch_and_store`] with the reading timestamped by `clock`.
+///
+/// The timestamp is truncated to the minute, so a second fetch within the
+/// same minute updates that minute's record instead of adding one.
+pub async fn fetch_and_store_with_clock<C: Clock>(
+    api_client: &GymApiClient,
+    database: &Database,
+    stale: &mut api::StaleDetector,
+    gym: &GymConfig,
+    name: &str,
+    feed: &LiveFeed,
+    clock: &C,
+) -> Result<Option<f64>> {
+    let raw = api_client.fetch_percentage().await?;
+    if stale.observe(raw, gym.stale_detection_count) {
+        // Warn once per run instead of every fetch
+        if stale.repeats() == gym.stale_detection_count {
+            tracing::warn!(
+                "{} reported {:.1}% for {} fetches in a row, the portal may be frozen",
+                name,
+                raw,
+                stale.repeats()
+            );
+        }
+        if gym.skip_stale_readings {
+            return Ok(None);
+        }
+    }
+    let percentage = api::clamp_percentage(raw)?;
+    if percentage != raw {
+        tracing::debug!("Clamped occupancy {:.2}% to {:.0}%", raw, percentage);
+    }
+    let now = clock.now_utc();
+    let timestamp = now
+        .duration_trunc(chrono::Duration::minutes(1))
+        .unwrap_or(now);
+    let id = database.insert_record(timestamp, percentage).await?;
+    feed.publish(LiveReading {
+        gym_id: database.gym_id().to_string(),
+        log: OccupancyLog {
+            id,
+            timestamp: timestamp.to_rfc3339(),
+            percentage,
+        },
+    });
+    Ok(Some(percentage))
+}
+
+/// Load the hourly averages predictions are based on.
+///
+/// Covers the past `days`, or only the time since the last level shift when
+/// `adaptive` is set.
+pub async fn load_prediction_baseline<C: Clock + ?Sized>(
+    database: &Database,
+    days: i64,
+    adaptive: bool,
+    clock: &C,
+) -> Result<Vec<HourlyAverage>> {
+    let now = clock.now_utc();
+    let mut start = now - chrono::Duration::days(days);
+    if adaptive {
+        let logs = database.get_history_range(start, now).await?;
+        if let Some((date, shift)) = analytics::detect_level_shift(&logs) {
+            tracing::info!(
+                "Occupancy level shifted by {:+.1}% on {}; baseline starts there",
+                shift,
+                date
+            );
+            start = start.max(analytics::midnight_utc(date));
+        }
+    }
+    database.get_averages_range(start, now).await
+}
+
+/// Alert ahead of a predicted quiet period, at most once per day.
+#[derive(Debug, Default)]
+pub struct PredictiveAlert {
+    last_sent: Option<NaiveDate>,
+}
+
+impl PredictiveAlert {
+    /// The alert message if the first quiet slot predicted today is within
+    /// `lead_minutes`. Today's alert counts as sent once this returns it.
+    pub fn check<C: Clock + ?Sized>(
+        &mut self,
+        baseline: &[HourlyAverage],
+        schedule: &GymSchedule,
+        threshold: f64,
+        lead_minutes: i64,
+        clock: &C,
+    ) -> Option<String> {
+        let upcoming = analytics::calculate_predictions_with_horizon(baseline, schedule, clock, 24);
+        let (time, predicted) = analytics::predictive_quiet_alert(
+            &upcoming,
+            threshold,
+            lead_minutes,
+            self.last_sent,
+            clock,
+        )?;
+        self.last_sent = Some(clock.now_local().date_naive());
+        Some(predictive_alert_body(threshold, time, predicted))
+    }
+}
+
+fn predictive_alert_body(threshold: f64, time: DateTime<Utc>, predicted: f64) -> String {
+    format!(
+        "The gym should drop below {:.0}% around {} ({:.0}% predicted)",
+        threshold,
+        time.with_timezone(&Local).format("%H:%M"),
+        predicted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::traits::MockClock;
+
+    fn write_config(path: &std::path::Path, weekday_close: u32) {
+        std::fs::write(
+            path,
+            format!(
+                "[schedule.weekday]\nopen_hour = 6\nclose_hour = {}\n",
+                weekday_close
+            ),
+        )
+        .unwrap();
+    }
+
+    fn reading(id: i64) -> LiveReading {
+        LiveReading {
+            gym_id: "ffb".to_string(),
+            log: OccupancyLog {
+                id,
+                timestamp: "2024-06-12T12:00:00+00:00".to_string(),
+                percentage: 40.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_live_feed_lagging_subscriber_skips_oldest() {
+        let feed = LiveFeed::new();
+        let mut rx = feed.subscribe();
+        for id in 0..LIVE_FEED_CAPACITY as i64 + 2 {
+            feed.publish(reading(id));
+        }
+
+        assert!(matches!(
+            rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(2))
+        ));
+        assert_eq!(rx.try_recv().unwrap().log.id, 2);
+    }
+
+    #[test]
+    fn test_live_feed_without_subscribers_drops_readings() {
+        let feed = LiveFeed::new();
+        feed.publish(reading(1));
+
+        let mut rx = feed.subscribe();
+        feed.publish(reading(2));
+        assert_eq!(rx.try_recv().unwrap().log.id, 2);
+        assert!(rx.try_recv().is_err());
+    }
+
+    fn flat_baseline(percentage: f64) -> Vec<HourlyAverage> {
+        (0..7)
+            .flat_map(|weekday| {
+                (0..24).map(move |hour| HourlyAverage {
+                    weekday,
+                    hour,
+                    avg_percentage: percentage,
+                    sample_count: 10,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_predictive_alert_sent_once_per_day() {
+        let clock = MockClock::new_zoned(Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap());
+        let schedule = GymSchedule::default();
+        let baseline = flat_baseline(20.0);
+        let mut alert = PredictiveAlert::default();
+
+        let body = alert
+            .check(&baseline, &schedule, 30.0, 60, &clock)
+            .expect("Quiet slot within the lead time");
+        assert!(body.contains("13:00"), "{}", body);
+        assert!(body.contains("20% predicted"), "{}", body);
+
+        clock.advance(chrono::Duration::minutes(30));
+        assert!(
+            alert
+                .check(&baseline, &schedule, 30.0, 60, &clock)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_predictive_alert_silent_when_busy() {
+        let clock = MockClock::new_zoned(Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap());
+        let mut alert = PredictiveAlert::default();
+
+        let body = alert.check(
+            &flat_baseline(50.0),
+            &GymSchedule::default(),
+            30.0,
+            60,
+            &clock,
+        );
+
+        assert!(body.is_none());
+    }
+
+    #[test]
+    fn test_reload_updates_schedule() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        // A Wednesday that is not a holiday
+        let noon = Local.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+
+        write_config(&path, 23);
+        let config = AppConfig::load_from(&path).expect("Config should load");
+        let mut settings = DaemonSettings::new(Arc::new(config)).unwrap();
+        assert!(settings.schedule.is_open(&noon));
+
+        write_config(&path, 10);
+        let reloaded = AppConfig::load_from(&path).expect("Config should load");
+        settings.reload(reloaded).unwrap();
+
+        assert!(!settings.schedule.is_open(&noon));
+        assert_eq!(settings.config.schedule.weekday.close_hour, 10);
+    }
+
+    #[test]
+    fn test_reload_keeps_database_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        write_config(&path, 23);
+        let config = AppConfig::load_from(&path).expect("Config should load");
+        let mut settings = DaemonSettings::new(Arc::new(config)).unwrap();
+
+        let mut reloaded = AppConfig::load_from(&path).expect("Config should load");
+        reloaded.database.url = "postgres://elsewhere/db".to_string();
+        settings.reload(reloaded).unwrap();
+
+        assert_ne!(settings.config.database.url, "postgres://elsewhere/db");
+    }
+}
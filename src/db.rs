@@ -1,11 +1,19 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, NaiveDate, TimeZone, Utc};
-use serde::Serialize;
-use sqlx::{FromRow, PgPool};
+use chrono::{DateTime, DurationRound, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool, postgres::PgPoolOptions};
 
-use crate::traits::Clock;
+use crate::{
+    config::DatabaseConfig,
+    schedule::GymSchedule,
+    traits::{Clock, NotificationStore},
+};
 
 /// Represents a single occupancy log entry from the database.
 #[derive(Debug, Clone, FromRow, Serialize)]
@@ -23,6 +31,202 @@ impl OccupancyLog {
             .ok()
             .map(|dt| dt.with_timezone(&Utc))
     }
+
+    /// Approximate number of people present in a gym holding `capacity`.
+    pub fn estimated_count(&self, capacity: u32) -> u32 {
+        estimated_count(self.percentage, capacity)
+    }
+}
+
+/// Approximate number of people at `percentage` of `capacity`, rounded to the
+/// nearest person.
+pub fn estimated_count(percentage: f64, capacity: u32) -> u32 {
+    (percentage.clamp(0.0, 100.0) / 100.0 * capacity as f64).round() as u32
+}
+
+/// A column that can be included in a CSV export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportColumn {
+    Id,
+    /// Stored timestamp (RFC 3339, UTC)
+    Timestamp,
+    /// Timestamp converted to the machine's local timezone
+    LocalTime,
+    Percentage,
+}
+
+impl ExportColumn {
+    /// Columns written when nothing else is configured.
+    pub const DEFAULT: [ExportColumn; 3] = [Self::Id, Self::Timestamp, Self::Percentage];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Timestamp => "timestamp",
+            Self::LocalTime => "local_time",
+            Self::Percentage => "percentage",
+        }
+    }
+
+    fn value(&self, log: &OccupancyLog, options: &ExportOptions) -> String {
+        match self {
+            Self::Id => log.id.to_string(),
+            Self::Timestamp => log.timestamp.clone(),
+            Self::LocalTime => log
+                .datetime()
+                .map(|dt| {
+                    dt.with_timezone(&Local)
+                        .format(&options.date_format)
+                        .to_string()
+                })
+                .unwrap_or_default(),
+            // Debug keeps the trailing ".0" like the serde-based export did
+            Self::Percentage => format!("{:?}", log.percentage),
+        }
+    }
+}
+
+/// Date format of the local-time column: RFC 3339, which [`read_csv`] parses.
+pub const DEFAULT_EXPORT_DATE_FORMAT: &str = "%+";
+
+/// How a CSV export is written, beyond its columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    /// Field separator, e.g. `b';'` for spreadsheets in a German locale
+    pub delimiter: u8,
+    /// Append a `local_time` column unless one is already selected
+    pub include_local_time: bool,
+    /// strftime format of the local-time column
+    pub date_format: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            include_local_time: false,
+            date_format: DEFAULT_EXPORT_DATE_FORMAT.to_string(),
+        }
+    }
+}
+
+/// Whether `format` is a valid strftime format string.
+pub fn is_valid_date_format(format: &str) -> bool {
+    !chrono::format::StrftimeItems::new(format).any(|item| item == chrono::format::Item::Error)
+}
+
+/// Write logs as CSV with the given columns, in order, plus a header row.
+pub fn write_csv<W: Write>(
+    writer: W,
+    logs: &[OccupancyLog],
+    columns: &[ExportColumn],
+    options: &ExportOptions,
+) -> Result<()> {
+    anyhow::ensure!(!columns.is_empty(), "No export columns selected");
+    anyhow::ensure!(
+        is_valid_date_format(&options.date_format),
+        "Invalid export date format {:?}",
+        options.date_format
+    );
+
+    let mut columns = columns.to_vec();
+    if options.include_local_time && !columns.contains(&ExportColumn::LocalTime) {
+        columns.push(ExportColumn::LocalTime);
+    }
+
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(writer);
+    wtr.write_record(columns.iter().map(|c| c.header()))
+        .context("Failed to write CSV header")?;
+
+    for log in logs {
+        wtr.write_record(columns.iter().map(|c| c.value(log, options)))
+            .context("Failed to serialize log entry")?;
+    }
+
+    wtr.flush().context("Failed to flush CSV writer")?;
+    Ok(())
+}
+
+/// Records read from an import CSV.
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    /// Valid rows in file order
+    pub records: Vec<(DateTime<Utc>, f64)>,
+    /// One message per malformed row, naming its line
+    pub errors: Vec<String>,
+}
+
+/// Outcome of [`Database::import_csv`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Records written to the database
+    pub inserted: usize,
+    /// Rows skipped because their timestamp is already stored or repeated
+    /// within the file
+    pub duplicates: usize,
+    /// Rows skipped because they could not be read
+    pub malformed: usize,
+    /// One message per malformed row, naming its line
+    pub errors: Vec<String>,
+}
+
+/// Read occupancy records from CSV in the format written by [`write_csv`],
+/// separated by `delimiter`.
+///
+/// Needs a `timestamp` or `local_time` column and a `percentage` column;
+/// other columns are ignored. Malformed rows are collected in
+/// [`CsvImport::errors`] instead of failing the whole read.
+pub fn read_csv<R: Read>(reader: R, delimiter: u8) -> Result<CsvImport> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader);
+    let headers = rdr.headers().context("Failed to read CSV header")?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+
+    let time_col = column(ExportColumn::Timestamp.header())
+        .or_else(|| column(ExportColumn::LocalTime.header()))
+        .context("CSV has no timestamp or local_time column")?;
+    let pct_col =
+        column(ExportColumn::Percentage.header()).context("CSV has no percentage column")?;
+
+    let mut import = CsvImport::default();
+    for (i, row) in rdr.records().enumerate() {
+        // Line 1 is the header
+        let line = i + 2;
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                import.errors.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        let field = |col: usize| row.get(col).unwrap_or_default().trim();
+
+        let Ok(timestamp) = DateTime::parse_from_rfc3339(field(time_col)) else {
+            import.errors.push(format!(
+                "line {}: invalid timestamp {:?}",
+                line,
+                field(time_col)
+            ));
+            continue;
+        };
+        match field(pct_col).parse::<f64>() {
+            Ok(percentage) if (0.0..=100.0).contains(&percentage) => {
+                import
+                    .records
+                    .push((timestamp.with_timezone(&Utc), percentage));
+            }
+            _ => import.errors.push(format!(
+                "line {}: invalid percentage {:?}",
+                line,
+                field(pct_col)
+            )),
+        }
+    }
+    Ok(import)
 }
 
 #[derive(Debug, Clone)]
@@ -37,11 +241,65 @@ pub struct HourlyAverage {
 #[derive(Clone, Debug)]
 pub struct Database {
     pool: PgPool,
+    /// Inserts closer than this to the latest record are skipped
+    min_record_interval: chrono::Duration,
+    /// Gym whose records are written and read
+    gym_id: String,
 }
 
+/// Gym ID of records written before multiple gyms were supported.
+pub const DEFAULT_GYM_ID: &str = "default";
+
+/// Pool size used when none (or an invalid one) is configured.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+/// Pool acquire timeout used when none (or an invalid one) is configured.
+pub const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// Repair runs per gym whose backups are kept for undoing; older ones are
+/// dropped when a new repair is backed up.
+pub const REPAIR_BACKUPS_KEPT: i64 = 10;
+
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = PgPool::connect(database_url)
+        Self::connect(database_url, PgPoolOptions::new()).await
+    }
+
+    /// Connect with the pool size, acquire timeout and record interval from
+    /// `config`. Pool settings below 1 fall back to the defaults.
+    pub async fn from_config(config: &DatabaseConfig) -> Result<Self> {
+        let max_connections = if config.max_connections >= 1 {
+            config.max_connections
+        } else {
+            tracing::warn!(
+                "Invalid database.max_connections {}, using {}",
+                config.max_connections,
+                DEFAULT_MAX_CONNECTIONS
+            );
+            DEFAULT_MAX_CONNECTIONS
+        };
+        let acquire_timeout_secs = if config.acquire_timeout_secs >= 1 {
+            config.acquire_timeout_secs
+        } else {
+            tracing::warn!(
+                "Invalid database.acquire_timeout_secs {}, using {}",
+                config.acquire_timeout_secs,
+                DEFAULT_ACQUIRE_TIMEOUT_SECS
+            );
+            DEFAULT_ACQUIRE_TIMEOUT_SECS
+        };
+
+        let options = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(std::time::Duration::from_secs(acquire_timeout_secs));
+        Ok(Self::connect(&config.url, options)
+            .await?
+            .with_min_record_interval(config.min_record_interval_secs))
+    }
+
+    async fn connect(database_url: &str, options: PgPoolOptions) -> Result<Self> {
+        let pool = options
+            .connect(database_url)
             .await
             .context("Failed to connect to PostgreSQL database")?;
 
@@ -50,22 +308,104 @@ impl Database {
             .await
             .context("Failed to run database migrations")?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            min_record_interval: chrono::Duration::zero(),
+            gym_id: DEFAULT_GYM_ID.to_string(),
+        })
+    }
+
+    /// Version of the latest applied migration in `migrations/`, or `None`
+    /// on an empty schema.
+    ///
+    /// Migrations are applied by [`Self::new`], each in its own transaction,
+    /// and already-applied versions are skipped.
+    pub async fn schema_version(&self) -> Result<Option<i64>> {
+        sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(version) FROM _sqlx_migrations WHERE success",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to read schema version")
+    }
+
+    /// Read and write the records of gym `gym_id`; the pool is shared.
+    pub fn with_gym(mut self, gym_id: impl Into<String>) -> Self {
+        self.gym_id = gym_id.into();
+        self
+    }
+
+    /// Gym this handle reads and writes.
+    pub fn gym_id(&self) -> &str {
+        &self.gym_id
+    }
+
+    /// Maximum number of pooled connections.
+    pub fn max_connections(&self) -> u32 {
+        self.pool.options().get_max_connections()
+    }
+
+    /// Skip inserts within `secs` seconds of the latest existing record.
+    ///
+    /// Prevents near-duplicate rows when the GUI and the daemon both record
+    /// the same minute. `0` disables deduplication.
+    pub fn with_min_record_interval(mut self, secs: u64) -> Self {
+        self.min_record_interval = chrono::Duration::seconds(secs as i64);
+        self
     }
 
+    /// Insert a new occupancy record.
+    ///
+    /// Timestamps are unique per gym: if a record already exists at exactly
+    /// `timestamp`, its percentage is replaced (the latest value wins) and its
+    /// ID is returned. If a minimum record interval is set and the latest
+    /// record is closer than that to `timestamp` (but not at exactly
+    /// `timestamp`), nothing is inserted and the latest record's ID is returned
+    /// instead.
     pub async fn insert_record(&self, timestamp: DateTime<Utc>, percentage: f64) -> Result<i64> {
-        let timestamp_str = timestamp.to_rfc3339();
+        if self.min_record_interval > chrono::Duration::zero()
+            && let Some(latest) = self.get_latest_record().await?
+            && let Some(latest_time) = latest.datetime()
+            && latest_time != timestamp
+            && (timestamp - latest_time).abs() < self.min_record_interval
+        {
+            tracing::debug!(
+                "Skipping insert at {}: within {}s of record {}",
+                timestamp,
+                self.min_record_interval.num_seconds(),
+                latest.id
+            );
+            return Ok(latest.id);
+        }
+
+        self.insert_unchecked(timestamp, percentage).await
+    }
+
+    async fn insert_unchecked(&self, timestamp: DateTime<Utc>, percentage: f64) -> Result<i64> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start insert transaction")?;
 
-        // Use RETURNING to get the inserted ID (PostgreSQL)
-        let result = sqlx::query_scalar!(
-            "INSERT INTO occupancy_logs (timestamp, percentage) VALUES ($1, $2) RETURNING id",
-            timestamp_str,
-            percentage
+        // Use RETURNING to get the inserted or updated ID (PostgreSQL)
+        let result = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO occupancy_logs (timestamp, percentage, gym_id) VALUES ($1, $2, $3) ON \
+             CONFLICT (gym_id, timestamp) DO UPDATE SET percentage = EXCLUDED.percentage \
+             RETURNING id",
         )
-        .fetch_one(&self.pool)
+        .bind(timestamp.to_rfc3339())
+        .bind(percentage)
+        .bind(&self.gym_id)
+        .fetch_one(&mut *tx)
         .await
         .context("Failed to insert occupancy record")?;
+        self.refresh_cached_hours(&mut tx, timestamp, timestamp)
+            .await?;
 
+        tx.commit()
+            .await
+            .context("Failed to commit occupancy record")?;
         Ok(result)
     }
 
@@ -74,20 +414,24 @@ impl Database {
         self.get_history_from(cutoff).await
     }
 
+    /// Get all records from the last `minutes` minutes, oldest first.
+    pub async fn get_recent_records(&self, minutes: i64) -> Result<Vec<OccupancyLog>> {
+        let cutoff = Utc::now() - chrono::Duration::minutes(minutes);
+        self.get_history_from(cutoff).await
+    }
+
     /// Get the most recent occupancy record.
     pub async fn get_latest_record(&self) -> Result<Option<OccupancyLog>> {
-        let log = sqlx::query_as!(
-            OccupancyLog,
+        let log = sqlx::query_as::<_, OccupancyLog>(
             r#"
-            SELECT
-                id as "id!",
-                timestamp as "timestamp!",
-                percentage as "percentage!"
+            SELECT id, timestamp, percentage
             FROM occupancy_logs
+            WHERE gym_id = $1
             ORDER BY timestamp DESC
             LIMIT 1
-            "#
+            "#,
         )
+        .bind(&self.gym_id)
         .fetch_optional(&self.pool)
         .await
         .context("Failed to fetch latest occupancy record")?;
@@ -95,28 +439,43 @@ impl Database {
         Ok(log)
     }
 
+    /// Count all of this gym's records.
+    pub async fn count_records(&self) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM occupancy_logs WHERE gym_id = $1")
+            .bind(&self.gym_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to count occupancy records")
+    }
+
+    /// Count this gym's records at or after `start`.
+    pub async fn count_records_since(&self, start: DateTime<Utc>) -> Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM occupancy_logs WHERE gym_id = $2 AND timestamp >= $1",
+        )
+        .bind(start.to_rfc3339())
+        .bind(&self.gym_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count occupancy records")
+    }
+
     pub async fn get_history_range(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<Vec<OccupancyLog>> {
-        let start_str = start.to_rfc3339();
-        let end_str = end.to_rfc3339();
-
-        let logs = sqlx::query_as!(
-            OccupancyLog,
+        let logs = sqlx::query_as::<_, OccupancyLog>(
             r#"
-            SELECT
-                id as "id!",
-                timestamp as "timestamp!",
-                percentage as "percentage!"
+            SELECT id, timestamp, percentage
             FROM occupancy_logs
-            WHERE timestamp >= $1 AND timestamp <= $2
+            WHERE gym_id = $3 AND timestamp >= $1 AND timestamp <= $2
             ORDER BY timestamp ASC
             "#,
-            start_str,
-            end_str
         )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(&self.gym_id)
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch occupancy history for date range")?;
@@ -124,22 +483,67 @@ impl Database {
         Ok(logs)
     }
 
-    async fn get_history_from(&self, cutoff: DateTime<Utc>) -> Result<Vec<OccupancyLog>> {
-        let cutoff_str = cutoff.to_rfc3339();
+    /// History over `[start, end]` averaged into `target_points` equal buckets.
+    ///
+    /// Each returned record is timestamped at its bucket's center and carries
+    /// the ID of the bucket's first record. Empty buckets are omitted.
+    pub async fn get_history_downsampled(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        target_points: usize,
+    ) -> Result<Vec<OccupancyLog>> {
+        let span_ms = (end - start).num_milliseconds().max(1);
+        let bucket_ms = (span_ms / target_points.max(1) as i64).max(1);
 
-        let logs = sqlx::query_as!(
-            OccupancyLog,
+        let rows = sqlx::query_as::<_, (i64, i64, f64)>(
             r#"
             SELECT
-                id as "id!",
-                timestamp as "timestamp!",
-                percentage as "percentage!"
+                MIN(id),
+                FLOOR(
+                    EXTRACT(EPOCH FROM timestamp::timestamptz - $3) * 1000 / $4
+                )::BIGINT as bucket,
+                AVG(percentage)
+            FROM occupancy_logs
+            WHERE gym_id = $5 AND timestamp >= $1 AND timestamp <= $2
+            GROUP BY bucket
+            ORDER BY bucket ASC
+            "#,
+        )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(start)
+        .bind(bucket_ms)
+        .bind(&self.gym_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch downsampled occupancy history")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, bucket, percentage)| {
+                let center =
+                    start + chrono::Duration::milliseconds(bucket * bucket_ms + bucket_ms / 2);
+                OccupancyLog {
+                    id,
+                    timestamp: center.to_rfc3339(),
+                    percentage,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_history_from(&self, cutoff: DateTime<Utc>) -> Result<Vec<OccupancyLog>> {
+        let logs = sqlx::query_as::<_, OccupancyLog>(
+            r#"
+            SELECT id, timestamp, percentage
             FROM occupancy_logs
-            WHERE timestamp >= $1
+            WHERE gym_id = $2 AND timestamp >= $1
             ORDER BY timestamp ASC
             "#,
-            cutoff_str
         )
+        .bind(cutoff.to_rfc3339())
+        .bind(&self.gym_id)
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch occupancy history")?;
@@ -147,45 +551,239 @@ impl Database {
         Ok(logs)
     }
 
+    /// Average occupancy per weekday and hour over `[start, end)`.
+    ///
+    /// Whole hours covered by the `hourly_averages` cache are read from it;
+    /// the rest is aggregated from the raw records. Inserts and prunes update
+    /// the covered hours they touch, so the result is the same whether or not
+    /// the cache is fresh.
     pub async fn get_averages_range(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<Vec<HourlyAverage>> {
-        let start_str = start.to_rfc3339();
-        let end_str = end.to_rfc3339();
+        let coverage = sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>)>(
+            "SELECT covered_from, covered_until FROM hourly_averages_coverage WHERE gym_id = $1",
+        )
+        .bind(&self.gym_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to read hourly averages coverage")?;
+
+        // Whole hours of the range the cache can answer; empty if none
+        let (cached_start, cached_end) = coverage
+            .map(|(from, until)| (ceil_hour(start).max(from), floor_hour(end).min(until)))
+            .filter(|(cached_start, cached_end)| cached_start < cached_end)
+            .unwrap_or((start, start));
 
         // PostgreSQL version:
         // - ISODOW returns 1=Monday through 7=Sunday, subtract 1 to get 0=Monday
         // - EXTRACT(HOUR ...) returns the hour (0-23)
         // - Cast timestamp TEXT to TIMESTAMPTZ for date functions
-        let logs = sqlx::query_as!(
-            HourlyAverage,
+        let rows = sqlx::query_as::<_, (i32, i32, f64, i64)>(
             r#"
             SELECT
-                weekday as "weekday!: i32",
-                hour as "hour!: i32",
-                AVG(percentage) as "avg_percentage!: f64",
-                COUNT(*) as "sample_count!: i64"
+                weekday,
+                hour,
+                SUM(total) / SUM(samples)::DOUBLE PRECISION,
+                SUM(samples)::BIGINT
             FROM (
+                SELECT
+                    (EXTRACT(ISODOW FROM hour_start)::INTEGER - 1) as weekday,
+                    EXTRACT(HOUR FROM hour_start)::INTEGER as hour,
+                    percentage_sum as total,
+                    sample_count as samples
+                FROM hourly_averages
+                WHERE gym_id = $7 AND hour_start >= $3 AND hour_start < $4
+                UNION ALL
                 SELECT
                     (EXTRACT(ISODOW FROM timestamp::timestamptz)::INTEGER - 1) as weekday,
                     EXTRACT(HOUR FROM timestamp::timestamptz)::INTEGER as hour,
-                    percentage
+                    percentage as total,
+                    1::BIGINT as samples
                 FROM occupancy_logs
-                WHERE timestamp >= $1 AND timestamp < $2
-            ) AS subquery
+                WHERE gym_id = $7
+                  AND ((timestamp >= $1 AND timestamp < $5)
+                   OR (timestamp >= $6 AND timestamp < $2))
+            ) AS buckets
             GROUP BY weekday, hour
             ORDER BY weekday, hour
             "#,
-            start_str,
-            end_str
         )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(cached_start)
+        .bind(cached_end)
+        .bind(cached_start.to_rfc3339())
+        .bind(cached_end.to_rfc3339())
+        .bind(&self.gym_id)
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch aggregated data")?;
 
-        Ok(logs)
+        Ok(rows
+            .into_iter()
+            .map(
+                |(weekday, hour, avg_percentage, sample_count)| HourlyAverage {
+                    weekday,
+                    hour,
+                    avg_percentage,
+                    sample_count,
+                },
+            )
+            .collect())
+    }
+
+    /// Averages of gyms `first` and `second` over the same `[start, end)`,
+    /// for comparing them head to head with
+    /// [`crate::analytics::quieter_per_slot`].
+    pub async fn get_gym_averages_pair(
+        &self,
+        first: &str,
+        second: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(Vec<HourlyAverage>, Vec<HourlyAverage>)> {
+        let first = self
+            .clone()
+            .with_gym(first)
+            .get_averages_range(start, end)
+            .await?;
+        let second = self
+            .clone()
+            .with_gym(second)
+            .get_averages_range(start, end)
+            .await?;
+        Ok((first, second))
+    }
+
+    /// Recompute cached hourly totals for every complete hour since `since`,
+    /// returning how many hours were written.
+    ///
+    /// The cache then covers `since` (or earlier, if it already did) up to
+    /// the start of the current hour.
+    pub async fn refresh_hourly_averages(&self, since: DateTime<Utc>) -> Result<u64> {
+        let from = floor_hour(since);
+        let until = floor_hour(Utc::now());
+        if from >= until {
+            return Ok(0);
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start hourly averages refresh")?;
+
+        let written = self.write_hourly_averages(&mut tx, from, until).await?;
+
+        // Extend the covered range if the refresh connects to it
+        sqlx::query(
+            r#"
+            INSERT INTO hourly_averages_coverage (gym_id, covered_from, covered_until, refreshed_at)
+            VALUES ($4, $1, $2, $3)
+            ON CONFLICT (gym_id) DO UPDATE
+            SET covered_from = CASE
+                    WHEN hourly_averages_coverage.covered_until >= EXCLUDED.covered_from
+                    THEN LEAST(hourly_averages_coverage.covered_from, EXCLUDED.covered_from)
+                    ELSE EXCLUDED.covered_from
+                END,
+                covered_until = EXCLUDED.covered_until,
+                refreshed_at = EXCLUDED.refreshed_at
+            "#,
+        )
+        .bind(from)
+        .bind(until)
+        .bind(Utc::now())
+        .bind(&self.gym_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update hourly averages coverage")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit hourly averages refresh")?;
+        Ok(written)
+    }
+
+    /// Recompute the cached totals of the whole hours in `[from, until)`.
+    async fn write_hourly_averages(
+        &self,
+        conn: &mut PgConnection,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<u64> {
+        // Clear first so hours whose records were pruned don't linger
+        sqlx::query(
+            "DELETE FROM hourly_averages WHERE gym_id = $3 AND hour_start >= $1 AND hour_start < \
+             $2",
+        )
+        .bind(from)
+        .bind(until)
+        .bind(&self.gym_id)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to clear hourly averages")?;
+
+        let written = sqlx::query(
+            r#"
+            INSERT INTO hourly_averages (gym_id, hour_start, percentage_sum, sample_count)
+            SELECT $3, date_trunc('hour', timestamp::timestamptz), SUM(percentage), COUNT(*)
+            FROM occupancy_logs
+            WHERE gym_id = $3 AND timestamp >= $1 AND timestamp < $2
+            GROUP BY 2
+            ON CONFLICT (gym_id, hour_start) DO UPDATE
+            SET percentage_sum = EXCLUDED.percentage_sum,
+                sample_count = EXCLUDED.sample_count
+            "#,
+        )
+        .bind(from.to_rfc3339())
+        .bind(until.to_rfc3339())
+        .bind(&self.gym_id)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to refresh hourly averages")?
+        .rows_affected();
+
+        Ok(written)
+    }
+
+    /// Bring the cached hours from `first` to `last` up to date after their
+    /// records changed. Hours outside the covered range are left alone.
+    async fn refresh_cached_hours(
+        &self,
+        conn: &mut PgConnection,
+        first: DateTime<Utc>,
+        last: DateTime<Utc>,
+    ) -> Result<()> {
+        let coverage = sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>)>(
+            "SELECT covered_from, covered_until FROM hourly_averages_coverage WHERE gym_id = $1",
+        )
+        .bind(&self.gym_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .context("Failed to read hourly averages coverage")?;
+        let Some((covered_from, covered_until)) = coverage else {
+            return Ok(());
+        };
+
+        let from = floor_hour(first).max(covered_from);
+        let until = (floor_hour(last) + chrono::Duration::hours(1)).min(covered_until);
+        if from < until {
+            self.write_hourly_averages(conn, from, until).await?;
+        }
+        Ok(())
+    }
+
+    /// When the hourly averages cache was last refreshed, if ever.
+    pub async fn hourly_averages_refreshed_at(&self) -> Result<Option<DateTime<Utc>>> {
+        sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT refreshed_at FROM hourly_averages_coverage WHERE gym_id = $1",
+        )
+        .bind(&self.gym_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to read hourly averages refresh time")
     }
 
     /// Export all occupancy logs to a CSV file.
@@ -196,10 +794,18 @@ impl Database {
     /// # Arguments
     /// * `output_dir` - Directory where the CSV file will be created
     /// * `clock` - Clock for generating the timestamp in the filename
+    /// * `columns` - Columns to write, in order
+    /// * `options` - Delimiter and local-time column, see [`ExportOptions`]
     ///
     /// # Returns
     /// The path to the created CSV file on success.
-    pub async fn export_to_csv<C: Clock>(&self, output_dir: &Path, clock: &C) -> Result<PathBuf> {
+    pub async fn export_to_csv<C: Clock>(
+        &self,
+        output_dir: &Path,
+        clock: &C,
+        columns: &[ExportColumn],
+        options: &ExportOptions,
+    ) -> Result<PathBuf> {
         let logs = self
             .get_history(365 * 10)
             .await
@@ -213,20 +819,14 @@ impl Database {
 
         let output_path = output_dir.join(&filename);
 
-        // Clone path and logs for the blocking task
+        // Clone path, columns and options for the blocking task
         let path = output_path.clone();
-        let logs_clone = logs;
+        let columns = columns.to_vec();
+        let options = options.clone();
 
         tokio::task::spawn_blocking(move || -> Result<()> {
-            let mut wtr = csv::Writer::from_path(&path).context("Failed to create CSV writer")?;
-
-            for log in logs_clone {
-                wtr.serialize(log)
-                    .context("Failed to serialize log entry")?;
-            }
-
-            wtr.flush().context("Failed to flush CSV writer")?;
-            Ok(())
+            let file = std::fs::File::create(&path).context("Failed to create CSV file")?;
+            write_csv(file, &logs, &columns, &options)
         })
         .await
         .context("CSV export task failed")??;
@@ -234,23 +834,92 @@ impl Database {
         Ok(output_path)
     }
 
+    /// Import records from a comma-separated file in the format written by
+    /// [`write_csv`].
+    ///
+    /// Rows whose timestamp is already stored, or repeated within the file,
+    /// are skipped. Malformed rows are logged and skipped. The rest are written
+    /// with [`Self::insert_records`], and the hourly averages cache is
+    /// refreshed from the earliest imported record on.
+    ///
+    /// # Returns
+    /// How many rows were inserted, and how many were skipped as duplicate or
+    /// malformed.
+    pub async fn import_csv(&self, path: &Path) -> Result<ImportSummary> {
+        self.import_csv_with_delimiter(path, b',').await
+    }
+
+    /// [`Self::import_csv`] for a file separated by `delimiter`, e.g. one
+    /// exported with [`ExportOptions::delimiter`].
+    pub async fn import_csv_with_delimiter(
+        &self,
+        path: &Path,
+        delimiter: u8,
+    ) -> Result<ImportSummary> {
+        let path = path.to_path_buf();
+        let import = tokio::task::spawn_blocking(move || -> Result<CsvImport> {
+            let file = std::fs::File::open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            read_csv(file, delimiter)
+        })
+        .await
+        .context("CSV import task failed")??;
+
+        for error in &import.errors {
+            tracing::warn!("Skipping malformed CSV row, {}", error);
+        }
+        let mut summary = ImportSummary {
+            malformed: import.errors.len(),
+            errors: import.errors,
+            ..Default::default()
+        };
+
+        let start = import.records.iter().map(|(t, _)| *t).min();
+        let end = import.records.iter().map(|(t, _)| *t).max();
+        let (Some(start), Some(end)) = (start, end) else {
+            return Ok(summary);
+        };
+
+        let mut seen: HashSet<DateTime<Utc>> = self
+            .get_history_range(start, end)
+            .await
+            .context("Failed to fetch existing records for import")?
+            .iter()
+            .filter_map(OccupancyLog::datetime)
+            .collect();
+        let rows = import.records.len();
+        let records: Vec<_> = import
+            .records
+            .into_iter()
+            .filter(|(timestamp, _)| seen.insert(*timestamp))
+            .collect();
+        summary.duplicates = rows - records.len();
+
+        summary.inserted = self.insert_records(&records).await?;
+        if let Some(earliest) = records.iter().map(|(t, _)| *t).min() {
+            self.refresh_hourly_averages(earliest)
+                .await
+                .context("Failed to refresh hourly averages after import")?;
+        }
+        Ok(summary)
+    }
+
     /// Get all records for a specific local date.
     ///
     /// This returns all occupancy logs where the timestamp falls within the
-    /// given date when converted to local time.
-    pub async fn get_records_for_date(&self, date: NaiveDate) -> Result<Vec<OccupancyLog>> {
+    /// given date in the schedule's time zone.
+    pub async fn get_records_for_date(
+        &self,
+        date: NaiveDate,
+        schedule: &GymSchedule,
+    ) -> Result<Vec<OccupancyLog>> {
         // Convert local date boundaries to UTC
-        let local_tz = chrono::Local;
-        let start_of_day = local_tz
-            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
-            .single()
-            .context("Invalid local datetime for start of day")?
-            .with_timezone(&Utc);
-        let end_of_day = local_tz
-            .from_local_datetime(&date.and_hms_opt(23, 59, 59).unwrap())
-            .single()
-            .context("Invalid local datetime for end of day")?
-            .with_timezone(&Utc);
+        let start_of_day = schedule
+            .day_start(date)
+            .context("Invalid local datetime for start of day")?;
+        let end_of_day = schedule
+            .wall_time_to_utc(&date.and_hms_opt(23, 59, 59).unwrap())
+            .context("Invalid local datetime for end of day")?;
 
         self.get_history_range(start_of_day, end_of_day).await
     }
@@ -269,26 +938,428 @@ impl Database {
     }
 
     /// Insert a record at a specific timestamp.
+    ///
+    /// Used for backfilling, so it bypasses the minimum record interval. A
+    /// record already at `timestamp` is replaced.
     pub async fn insert_at_timestamp(
         &self,
         timestamp: DateTime<Utc>,
         percentage: f64,
     ) -> Result<i64> {
-        self.insert_record(timestamp, percentage).await
+        self.insert_unchecked(timestamp, percentage).await
     }
 
-    /// Batch insert multiple records.
-    pub async fn batch_insert(&self, records: Vec<(DateTime<Utc>, f64)>) -> Result<()> {
-        for (timestamp, percentage) in records {
-            self.insert_record(timestamp, percentage).await?;
+    /// Intervals between consecutive records in `[start, end]` that are more
+    /// than `max_gap_minutes` apart.
+    ///
+    /// With a `schedule`, gaps that are not entirely within opening hours are
+    /// skipped, since no data is expected while the gym is closed.
+    pub async fn find_gaps(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_minutes: i64,
+        schedule: Option<&GymSchedule>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT previous, timestamp
+            FROM (
+                SELECT
+                    timestamp,
+                    LAG(timestamp) OVER (ORDER BY timestamp) AS previous
+                FROM occupancy_logs
+                WHERE gym_id = $4 AND timestamp >= $1 AND timestamp <= $2
+            ) AS pairs
+            WHERE previous IS NOT NULL
+              AND EXTRACT(EPOCH FROM timestamp::timestamptz - previous::timestamptz) > $3 * 60
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(max_gap_minutes)
+        .bind(&self.gym_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find gaps in occupancy records")?;
+
+        let parse = |s: &str| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc));
+        let mut gaps = Vec::with_capacity(rows.len());
+        for (from, to) in rows {
+            let (from, to) = (parse(&from)?, parse(&to)?);
+            if schedule.is_none_or(|schedule| open_throughout(schedule, from, to)) {
+                gaps.push((from, to));
+            }
+        }
+
+        Ok(gaps)
+    }
+
+    /// Delete this gym's occupancy records older than `cutoff`, returning
+    /// how many were removed.
+    ///
+    /// Cached hourly averages before `cutoff` are removed with them.
+    pub async fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start prune transaction")?;
+
+        let result = sqlx::query("DELETE FROM occupancy_logs WHERE gym_id = $1 AND timestamp < $2")
+            .bind(&self.gym_id)
+            .bind(cutoff.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to prune occupancy records")?;
+        sqlx::query("DELETE FROM hourly_averages WHERE gym_id = $1 AND hour_start < $2")
+            .bind(&self.gym_id)
+            .bind(floor_hour(cutoff))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to prune hourly averages")?;
+        // The hour containing the cutoff lost only part of its records
+        if floor_hour(cutoff) < cutoff {
+            self.refresh_cached_hours(&mut tx, cutoff, cutoff).await?;
         }
+
+        tx.commit()
+            .await
+            .context("Failed to commit pruned records")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Reclaim space left by deleted occupancy records.
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM ANALYZE occupancy_logs")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum occupancy records")?;
+        Ok(())
+    }
+
+    /// Record a sent notification in the audit log.
+    pub async fn insert_notification(
+        &self,
+        timestamp: DateTime<Utc>,
+        title: &str,
+        body: &str,
+    ) -> Result<i64> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO notifications (timestamp, title, body) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(timestamp.to_rfc3339())
+        .bind(title)
+        .bind(body)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to insert notification")?;
+
+        Ok(id)
+    }
+
+    /// Time of the most recent notification with the given title, if any.
+    pub async fn last_notification_at(&self, title: &str) -> Result<Option<DateTime<Utc>>> {
+        let timestamp = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT MAX(timestamp) FROM notifications WHERE title = $1",
+        )
+        .bind(title)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to read last notification")?;
+
+        timestamp
+            .map(|t| {
+                DateTime::parse_from_rfc3339(&t)
+                    .map(|t| t.with_timezone(&Utc))
+                    .with_context(|| format!("Invalid notification timestamp {:?}", t))
+            })
+            .transpose()
+    }
+
+    /// Record that a local date was repaired at `repaired_at`.
+    pub async fn mark_day_repaired(
+        &self,
+        date: NaiveDate,
+        repaired_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO repair_log (gym_id, date, repaired_at) VALUES ($1, $2, $3) ON CONFLICT \
+             (gym_id, date) DO UPDATE SET repaired_at = EXCLUDED.repaired_at",
+        )
+        .bind(&self.gym_id)
+        .bind(date.format("%Y-%m-%d").to_string())
+        .bind(repaired_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record repaired day")?;
+
+        Ok(())
+    }
+
+    /// Get the last repair time for each logged date between `start` and `end`
+    /// (inclusive).
+    pub async fn get_repair_log(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, DateTime<Utc>)>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT date, repaired_at FROM repair_log WHERE gym_id = $3 AND date >= $1 AND date \
+             <= $2 ORDER BY date",
+        )
+        .bind(start.format("%Y-%m-%d").to_string())
+        .bind(end.format("%Y-%m-%d").to_string())
+        .bind(&self.gym_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch repair log")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(date, repaired_at)| {
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+                let repaired_at = DateTime::parse_from_rfc3339(&repaired_at).ok()?;
+                Some((date, repaired_at.with_timezone(&Utc)))
+            })
+            .collect())
+    }
+
+    /// Back up this gym's records in `[start, end]` before a repair, returning
+    /// the id of the new repair run.
+    ///
+    /// Only the newest [`REPAIR_BACKUPS_KEPT`] runs of the gym are kept.
+    pub async fn backup_records(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<i64> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start repair backup")?;
+
+        let repair_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO repair_runs (gym_id, range_start, range_end, created_at) VALUES ($1, $2, \
+             $3, $4) RETURNING repair_id",
+        )
+        .bind(&self.gym_id)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to record repair run")?;
+
+        sqlx::query(
+            "INSERT INTO repair_backups (repair_id, record_id, timestamp, percentage) SELECT $1, \
+             id, timestamp, percentage FROM occupancy_logs WHERE gym_id = $2 AND timestamp >= $3 \
+             AND timestamp <= $4",
+        )
+        .bind(repair_id)
+        .bind(&self.gym_id)
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to back up records")?;
+
+        // Deleting a run cascades to its backed up records
+        sqlx::query(
+            "DELETE FROM repair_runs WHERE gym_id = $1 AND repair_id NOT IN (SELECT repair_id \
+             FROM repair_runs WHERE gym_id = $1 ORDER BY repair_id DESC LIMIT $2)",
+        )
+        .bind(&self.gym_id)
+        .bind(REPAIR_BACKUPS_KEPT)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to prune old repair backups")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit repair backup")?;
+
+        Ok(repair_id)
+    }
+
+    /// Replace the records covered by repair run `repair_id` with its backup,
+    /// returning the restored `(start, end)` range.
+    ///
+    /// Records stored after the backup was taken are kept, even inside the
+    /// range.
+    pub async fn restore_backup(&self, repair_id: i64) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start repair restore")?;
+
+        let (gym_id, range_start, range_end, created_at) =
+            sqlx::query_as::<_, (String, String, String, String)>(
+                "SELECT gym_id, range_start, range_end, created_at FROM repair_runs WHERE \
+                 repair_id = $1",
+            )
+            .bind(repair_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to fetch repair run")?
+            .with_context(|| format!("No backup for repair {}", repair_id))?;
+
+        sqlx::query(
+            "DELETE FROM occupancy_logs WHERE gym_id = $1 AND timestamp >= $2 AND timestamp <= $3 \
+             AND timestamp <= $4",
+        )
+        .bind(&gym_id)
+        .bind(&range_start)
+        .bind(&range_end)
+        .bind(&created_at)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to clear repaired records")?;
+
+        // Backups taken before timestamps were unique may hold duplicates
+        sqlx::query(
+            "INSERT INTO occupancy_logs (id, gym_id, timestamp, percentage) SELECT record_id, $2, \
+             timestamp, percentage FROM repair_backups WHERE repair_id = $1 ORDER BY record_id \
+             DESC ON CONFLICT (gym_id, timestamp) DO NOTHING",
+        )
+        .bind(repair_id)
+        .bind(&gym_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to restore backed up records")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit repair restore")?;
+
+        let parse = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .context("Invalid repair range")
+        };
+        Ok((parse(&range_start)?, parse(&range_end)?))
+    }
+
+    /// Forget that the days between `start` and `end` (inclusive) were
+    /// repaired.
+    pub async fn clear_repair_log(&self, start: NaiveDate, end: NaiveDate) -> Result<u64> {
+        let cleared =
+            sqlx::query("DELETE FROM repair_log WHERE gym_id = $3 AND date >= $1 AND date <= $2")
+                .bind(start.format("%Y-%m-%d").to_string())
+                .bind(end.format("%Y-%m-%d").to_string())
+                .bind(&self.gym_id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to clear repair log")?
+                .rows_affected();
+
+        Ok(cleared)
+    }
+
+    /// Insert records in a single transaction, returning how many were written.
+    ///
+    /// Like [`Self::insert_at_timestamp`], this bypasses the minimum record
+    /// interval and replaces records at the same timestamp. If any record
+    /// fails, none of the batch is written.
+    pub async fn insert_records(&self, records: &[(DateTime<Utc>, f64)]) -> Result<usize> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        // Dropping the transaction on an early return rolls it back
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start insert transaction")?;
+
+        for &(timestamp, percentage) in records {
+            anyhow::ensure!(
+                percentage.is_finite(),
+                "Invalid percentage {} at {}",
+                percentage,
+                timestamp
+            );
+            sqlx::query(
+                "INSERT INTO occupancy_logs (timestamp, percentage, gym_id) VALUES ($1, $2, $3) \
+                 ON CONFLICT (gym_id, timestamp) DO UPDATE SET percentage = EXCLUDED.percentage",
+            )
+            .bind(timestamp.to_rfc3339())
+            .bind(percentage)
+            .bind(&self.gym_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to insert occupancy record")?;
+        }
+        let first = records.iter().map(|(t, _)| *t).min();
+        let last = records.iter().map(|(t, _)| *t).max();
+        if let (Some(first), Some(last)) = (first, last) {
+            self.refresh_cached_hours(&mut tx, first, last).await?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit inserted records")?;
+        Ok(records.len())
+    }
+}
+
+/// Start of the hour containing `time`.
+fn floor_hour(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(chrono::Duration::hours(1))
+        .unwrap_or(time)
+}
+
+/// Start of the first hour beginning at or after `time`.
+fn ceil_hour(time: DateTime<Utc>) -> DateTime<Utc> {
+    let floor = floor_hour(time);
+    if floor == time {
+        time
+    } else {
+        floor + chrono::Duration::hours(1)
+    }
+}
+
+/// Whether the gym is open for the whole of `[from, to]`.
+fn open_throughout(schedule: &GymSchedule, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+    schedule.is_open(&from)
+        && schedule.is_open(&to)
+        && schedule
+            .next_transition(&from)
+            .is_none_or(|transition| transition.with_timezone(&Utc) >= to)
+}
+
+/// Notification store backed by the database.
+///
+/// `Notifier::notify` is synchronous, so inserts are spawned onto the given
+/// runtime instead of blocking the caller.
+#[derive(Clone, Debug)]
+pub struct DbNotificationStore {
+    db: Database,
+    handle: tokio::runtime::Handle,
+}
+
+impl DbNotificationStore {
+    pub fn new(db: Database, handle: tokio::runtime::Handle) -> Self {
+        Self { db, handle }
+    }
+}
+
+impl NotificationStore for DbNotificationStore {
+    fn record(&self, title: &str, body: &str) -> Result<()> {
+        let db = self.db.clone();
+        let (title, body) = (title.to_string(), body.to_string());
+        self.handle.spawn(async move {
+            if let Err(e) = db.insert_notification(Utc::now(), &title, &body).await {
+                tracing::warn!("Failed to store notification: {}", e);
+            }
+        });
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::{Datelike, Timelike};
+    use chrono::{Datelike, TimeZone, Timelike};
 
     use super::*;
 
@@ -376,6 +1447,204 @@ mod tests {
         // Just ensure it doesn't panic
     }
 
+    // ==================== CSV Export Tests ====================
+
+    fn export_to_string(logs: &[OccupancyLog], columns: &[ExportColumn]) -> String {
+        let mut buf = Vec::new();
+        write_csv(&mut buf, logs, columns, &ExportOptions::default()).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_write_csv_default_columns() {
+        let csv = export_to_string(
+            &[make_log("2024-06-15T14:30:00+00:00")],
+            &ExportColumn::DEFAULT,
+        );
+        assert_eq!(
+            csv,
+            "id,timestamp,percentage\n1,2024-06-15T14:30:00+00:00,50.0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_csv_restricted_and_reordered_columns() {
+        let logs = vec![
+            make_log("2024-06-15T14:30:00+00:00"),
+            OccupancyLog {
+                id: 2,
+                timestamp: "2024-06-15T14:31:00+00:00".to_string(),
+                percentage: 12.5,
+            },
+        ];
+
+        let csv = export_to_string(&logs, &[ExportColumn::Percentage, ExportColumn::Timestamp]);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "percentage,timestamp",
+                "50.0,2024-06-15T14:30:00+00:00",
+                "12.5,2024-06-15T14:31:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_csv_local_time_column() {
+        let log = make_log("2024-06-15T14:30:00+00:00");
+        let csv = export_to_string(std::slice::from_ref(&log), &[ExportColumn::LocalTime]);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "local_time");
+        let local = DateTime::parse_from_rfc3339(lines[1]).unwrap();
+        assert_eq!(local.with_timezone(&Utc), log.datetime().unwrap());
+    }
+
+    #[test]
+    fn test_write_csv_requires_columns() {
+        assert!(write_csv(Vec::new(), &[], &[], &ExportOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_round_trips_export() {
+        let logs = vec![
+            make_log("2024-06-15T14:30:00+00:00"),
+            OccupancyLog {
+                id: 2,
+                timestamp: "2024-06-15T14:31:00+00:00".to_string(),
+                percentage: 12.5,
+            },
+        ];
+        let csv = export_to_string(&logs, &ExportColumn::DEFAULT);
+
+        let import = read_csv(csv.as_bytes(), b',').unwrap();
+
+        assert!(import.errors.is_empty());
+        assert_eq!(
+            import.records,
+            logs.iter()
+                .map(|l| (l.datetime().unwrap(), l.percentage))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_csv_round_trips_semicolon_with_local_time() {
+        let logs = vec![
+            make_log("2024-06-15T14:30:00+00:00"),
+            OccupancyLog {
+                id: 2,
+                timestamp: "2024-06-15T14:31:00+00:00".to_string(),
+                percentage: 12.5,
+            },
+        ];
+        let options = ExportOptions {
+            delimiter: b';',
+            include_local_time: true,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        write_csv(&mut buf, &logs, &[ExportColumn::Percentage], &options).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+
+        assert!(csv.starts_with("percentage;local_time\n50.0;"), "{}", csv);
+        let import = read_csv(csv.as_bytes(), b';').unwrap();
+
+        assert!(import.errors.is_empty());
+        assert_eq!(
+            import.records,
+            logs.iter()
+                .map(|l| (l.datetime().unwrap(), l.percentage))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_write_csv_local_time_date_format() {
+        let options = ExportOptions {
+            include_local_time: true,
+            date_format: "%d.%m.%Y %H:%M".to_string(),
+            ..Default::default()
+        };
+        let log = make_log("2024-06-15T14:30:00+00:00");
+        let mut buf = Vec::new();
+        write_csv(
+            &mut buf,
+            std::slice::from_ref(&log),
+            &[ExportColumn::LocalTime],
+            &options,
+        )
+        .unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+
+        // Already selected, so not added a second time
+        let expected = log
+            .datetime()
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%d.%m.%Y %H:%M");
+        assert_eq!(csv, format!("local_time\n{}\n", expected));
+    }
+
+    #[test]
+    fn test_write_csv_rejects_invalid_date_format() {
+        let options = ExportOptions {
+            date_format: "%Q".to_string(),
+            ..Default::default()
+        };
+        assert!(write_csv(Vec::new(), &[], &ExportColumn::DEFAULT, &options).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_collects_malformed_rows() {
+        let csv = [
+            "timestamp,percentage",
+            "2024-06-15T14:30:00+00:00,50.0",
+            "yesterday,40.0",
+            "2024-06-15T14:32:00+00:00,abc",
+            "2024-06-15T14:33:00+00:00,120.0",
+            "2024-06-15T14:34:00+00:00,20.0",
+        ]
+        .join("\n");
+
+        let import = read_csv(csv.as_bytes(), b',').unwrap();
+
+        assert_eq!(import.records.len(), 2);
+        assert_eq!(import.errors.len(), 3);
+        assert!(import.errors[0].starts_with("line 3:"));
+    }
+
+    #[test]
+    fn test_read_csv_falls_back_to_local_time() {
+        let csv = "local_time,percentage\n2024-06-15T16:30:00+02:00,50.0\n";
+
+        let import = read_csv(csv.as_bytes(), b',').unwrap();
+
+        let expected = Utc.with_ymd_and_hms(2024, 6, 15, 14, 30, 0).unwrap();
+        assert_eq!(import.records, vec![(expected, 50.0)]);
+    }
+
+    #[test]
+    fn test_read_csv_requires_percentage_column() {
+        assert!(read_csv("timestamp\n2024-06-15T14:30:00+00:00\n".as_bytes(), b',').is_err());
+    }
+
+    #[test]
+    fn test_export_column_deserialize() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            columns: Vec<ExportColumn>,
+        }
+        let parsed: Wrapper =
+            serde_json::from_str(r#"{"columns": ["local_time", "percentage"]}"#).unwrap();
+        assert_eq!(
+            parsed.columns,
+            vec![ExportColumn::LocalTime, ExportColumn::Percentage]
+        );
+    }
+
     #[test]
     fn test_datetime_milliseconds() {
         let log = make_log("2024-06-15T14:30:00.123Z");
@@ -390,6 +1659,20 @@ mod tests {
         assert!(result.is_some());
     }
 
+    #[test]
+    fn test_estimated_count() {
+        let log = OccupancyLog {
+            id: 1,
+            timestamp: "2024-06-15T14:30:00Z".to_string(),
+            percentage: 42.0,
+        };
+        assert_eq!(log.estimated_count(200), 84);
+        // Rounded to the nearest person, out-of-range readings clamped
+        assert_eq!(estimated_count(33.3, 10), 3);
+        assert_eq!(estimated_count(105.0, 80), 80);
+        assert_eq!(estimated_count(-1.0, 80), 0);
+    }
+
     // ==================== HourlyAverage Struct Tests ====================
 
     #[test]
@@ -0,0 +1,230 @@
+//! Weekly Digest Module
+//!
+//! Decides when the daemon sends its weekly summary and renders the summary
+//! as a notification. Sending and persistence stay with the caller: the last
+//! send time is passed in, so a restarted daemon does not send twice.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Days, Duration as ChronoDuration, Utc, Weekday};
+
+use crate::{
+    analytics::{TrendDirection, WeeklySummary, weekday_name, weekday_short},
+    schedule::GymSchedule,
+    traits::Clock,
+};
+
+/// How long after the scheduled time a missed digest is still sent, e.g. when
+/// the daemon was down at the time.
+const CATCH_UP: ChronoDuration = ChronoDuration::days(1);
+
+/// Title of the digest notification for a gym, also used to find the last
+/// send in the notification log.
+pub fn digest_title(gym_name: &str) -> String {
+    format!("Weekly digest: {}", gym_name)
+}
+
+/// Render a weekly summary as a notification body.
+pub fn digest_body(summary: &WeeklySummary) -> String {
+    let mut lines = vec![match summary.trend {
+        TrendDirection::Insufficient => format!("Average {:.1}%", summary.overall_avg),
+        trend => format!(
+            "Average {:.1}% ({}, {:+.1}% vs. last week)",
+            summary.overall_avg,
+            trend.description(),
+            summary.change_percent
+        ),
+    }];
+    if let Some(day) = &summary.busiest_day {
+        lines.push(format!(
+            "Busiest: {} ({:.1}%)",
+            weekday_name(day.weekday),
+            day.avg_occupancy
+        ));
+    }
+    if let Some(day) = &summary.quietest_day {
+        lines.push(format!(
+            "Quietest: {} ({:.1}%)",
+            weekday_name(day.weekday),
+            day.avg_occupancy
+        ));
+    }
+    if let Some(window) = &summary.best_window {
+        lines.push(format!(
+            "Best window: {} {}:00-{}:00 ({:.1}%)",
+            weekday_short(window.weekday),
+            window.start_hour,
+            window.end_hour,
+            window.avg_occupancy
+        ));
+    }
+    if let Some(insight) = &summary.top_insight {
+        lines.push(insight.title.clone());
+    }
+    lines.join("\n")
+}
+
+/// Schedule of one weekly digest, e.g. Sunday 20:00 in the gym's time zone.
+#[derive(Clone)]
+pub struct DigestSchedule {
+    weekday: Weekday,
+    hour: u32,
+    last_sent: Option<DateTime<Utc>>,
+    /// Opening hours whose time zone `weekday` and `hour` are read in
+    gym_schedule: GymSchedule,
+    clock: Arc<dyn Clock>,
+}
+
+impl DigestSchedule {
+    /// Create a schedule for `weekday` at `hour`, given when the digest was
+    /// last sent (from a previous run).
+    pub fn new(
+        weekday: Weekday,
+        hour: u32,
+        last_sent: Option<DateTime<Utc>>,
+        gym_schedule: GymSchedule,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            weekday,
+            hour,
+            last_sent,
+            gym_schedule,
+            clock,
+        }
+    }
+
+    /// The most recent scheduled time at or before now.
+    fn last_trigger(&self) -> DateTime<Utc> {
+        let now = self.gym_schedule.now_local(self.clock.as_ref());
+        let days_back =
+            (now.weekday().num_days_from_monday() + 7 - self.weekday.num_days_from_monday()) % 7;
+        let date = now.date_naive() - Days::new(days_back as u64);
+        let trigger = self.local_hour(date);
+        if trigger > now.with_timezone(&Utc) {
+            self.local_hour(date - Days::new(7))
+        } else {
+            trigger
+        }
+    }
+
+    /// `hour`:00 on `date` in the gym's time zone, or an hour later if that
+    /// falls into a DST gap.
+    fn local_hour(&self, date: chrono::NaiveDate) -> DateTime<Utc> {
+        let naive = date
+            .and_hms_opt(self.hour, 0, 0)
+            .expect("hour is validated 0-23");
+        self.gym_schedule
+            .wall_time_to_utc(&naive)
+            .or_else(|| {
+                self.gym_schedule
+                    .wall_time_to_utc(&(naive + ChronoDuration::hours(1)))
+            })
+            .expect("only one hour is skipped at a DST change")
+    }
+
+    /// Whether the digest should be sent now: the scheduled time has passed
+    /// within the catch-up window and it was not sent since.
+    pub fn is_due(&self) -> bool {
+        let trigger = self.last_trigger();
+        self.clock.now_utc() - trigger < CATCH_UP
+            && self.last_sent.is_none_or(|sent| sent < trigger)
+    }
+
+    /// Record a send at the current time.
+    pub fn mark_sent(&mut self) {
+        self.last_sent = Some(self.clock.now_utc());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+    use crate::{analytics::weekly_summary, db::HourlyAverage, traits::MockClock};
+
+    #[test]
+    fn test_digest_sent_once_per_week() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 12, 9, 0, 0).unwrap());
+        // Three days ahead, so the last trigger is outside the catch-up window
+        let weekday = clock.now_local().weekday().succ().succ().succ();
+        let mut schedule = DigestSchedule::new(
+            weekday,
+            20,
+            None,
+            GymSchedule::default(),
+            Arc::new(clock.clone()),
+        );
+        assert!(!schedule.is_due());
+
+        let mut sent = Vec::new();
+        for _ in 0..14 * 24 {
+            clock.advance(ChronoDuration::hours(1));
+            if schedule.is_due() {
+                schedule.mark_sent();
+                sent.push(clock.now_local());
+            }
+        }
+
+        assert_eq!(sent.len(), 2);
+        for time in &sent {
+            assert_eq!(time.weekday(), weekday);
+        }
+        assert_eq!(sent[1] - sent[0], ChronoDuration::weeks(1));
+    }
+
+    #[test]
+    fn test_digest_not_resent_after_restart() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 12, 9, 0, 0).unwrap());
+        let weekday = clock.now_local().weekday();
+        let hour = clock.now_local().hour();
+        let mut schedule = DigestSchedule::new(
+            weekday,
+            hour,
+            None,
+            GymSchedule::default(),
+            Arc::new(clock.clone()),
+        );
+        assert!(schedule.is_due());
+        schedule.mark_sent();
+
+        // A new schedule loaded with the last send time, half an hour later
+        clock.advance(ChronoDuration::minutes(30));
+        let restarted = DigestSchedule::new(
+            weekday,
+            hour,
+            schedule.last_sent,
+            GymSchedule::default(),
+            Arc::new(clock.clone()),
+        );
+        assert!(!restarted.is_due());
+    }
+
+    #[test]
+    fn test_digest_body_lists_summary() {
+        let current: Vec<HourlyAverage> = [(1, 70.0), (5, 20.0)]
+            .into_iter()
+            .flat_map(|(weekday, pct)| {
+                (8..12).map(move |hour| HourlyAverage {
+                    weekday,
+                    hour,
+                    avg_percentage: pct,
+                    sample_count: 10,
+                })
+            })
+            .collect();
+
+        let body = digest_body(&weekly_summary(&current, &[]));
+
+        // No previous week to compare against
+        assert!(body.starts_with("Average 45.0%\n"), "{}", body);
+        assert!(body.contains("Busiest: Tuesday (70.0%)"), "{}", body);
+        assert!(body.contains("Quietest: Saturday (20.0%)"), "{}", body);
+        assert!(
+            body.contains("Best window: Sat 8:00-24:00 (20.0%)"),
+            "{}",
+            body
+        );
+    }
+}
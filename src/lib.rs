@@ -6,9 +6,13 @@
 pub mod analytics;
 pub mod api;
 pub mod config;
+pub mod daemon;
 pub mod db;
+pub mod digest;
+pub mod ml;
 pub mod repair;
 pub mod schedule;
+pub mod status;
 pub mod traits;
 
 // GUI-only modules
@@ -19,44 +23,87 @@ pub mod widgets;
 
 // Re-export commonly used types
 pub use analytics::{
+    BusynessLevel,
     // Comparison types
     ComparisonMode,
     DayAnalysis,
+    // Predictions
+    DetailedPrediction,
     HourlyComparison,
     // Insights
     Insight,
     InsightCategory,
+    Language,
     // Statistical analysis
     OccupancyStats,
     PeriodComparison,
+    QuieterSide,
     // Peak and quiet time analysis
     TimePeriod,
     TrendDirection,
+    TrendThresholds,
+    WeekdayFilter,
+    WeeklySummary,
     analyze_days,
+    average_by_hour,
+    average_by_hour_with_filter,
     // Comparison functions
     build_hourly_comparisons,
     // Core prediction functions
     calculate_predictions,
+    calculate_predictions_detailed,
     calculate_predictions_with_clock,
+    calculate_predictions_with_horizon,
     calculate_stats,
+    calculate_stats_weighted,
     compare_periods,
+    detect_anomalies,
+    detect_level_shift,
     determine_trend,
+    determine_trend_with_thresholds,
+    find_best_time_for_weekday,
     find_best_time_today,
+    find_best_time_today_detailed,
+    find_best_time_today_detailed_with_clock,
     find_best_time_today_with_clock,
     find_peak_hours,
+    find_peak_hours_with_min,
+    find_peak_windows,
     find_quiet_hours,
+    find_quiet_hours_with_min,
     find_quiet_windows,
+    find_quiet_windows_local,
     generate_insights,
+    generate_insights_lang,
+    infer_open_hours,
+    insights_to_json,
     midnight_utc,
+    predictive_quiet_alert,
+    probability_below,
+    probability_heatmap,
+    quieter_per_slot,
+    recommend_alternative_slot,
+    smooth_moving_average,
     // Utility functions
     weekday_name,
+    weekday_name_lang,
     weekday_short,
+    weekday_short_lang,
+    weekly_summary,
 };
-pub use api::{GymApiClient, GymResponse};
+pub use api::{ApiFormat, GymApiClient, GymResponse, OccupancyParser};
 pub use config::AppConfig;
-pub use db::{Database, HourlyAverage, OccupancyLog};
+pub use db::{
+    Database, DbNotificationStore, ExportColumn, ExportOptions, HourlyAverage, OccupancyLog,
+};
 pub use repair::{DataRepairer, RepairProgress, RepairSummary};
-pub use schedule::{GymSchedule, is_bavarian_holiday};
-pub use traits::{Clock, MockClock, MockNotifier, Notifier, SystemClock};
+pub use schedule::{
+    GermanState, GymSchedule, is_bavarian_holiday, is_public_holiday, load_holidays_from_ics,
+};
+pub use traits::{
+    AlertCrossings, AlertSound, AsyncNotifier, Clock, EmailNotifier, LoggingNotifier, MockClock,
+    MockNotifier, MockSoundSink, NotificationGate, NotificationStore, Notifier, NotifyFuture,
+    NtfyNotifier, SoundSink, SystemClock, ThresholdAlerts, WebhookNotifier, notify_all,
+};
 #[cfg(feature = "gui")]
-pub use traits::{CombinedNotifier, SystemNotifier};
+pub use traits::{CombinedNotifier, SystemNotifier, SystemSoundSink};
@@ -1,16 +1,27 @@
 #[cfg(feature = "gui")]
 mod app;
 
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use hardy_monitor::{api, config::AppConfig, db, schedule::GymSchedule};
+use hardy_monitor::{
+    AsyncNotifier, EmailNotifier, GymSchedule, NtfyNotifier, SystemClock, WebhookNotifier, api,
+    config::AppConfig,
+    daemon::{self, DaemonSettings, LiveFeed, PredictiveAlert, fetch_and_store},
+    db,
+    digest::{self, DigestSchedule},
+    notify_all,
+    status::{self, DaemonStatus},
+    weekly_summary,
+};
+use tokio::sync::watch;
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
 #[cfg(feature = "gui")]
-use hardy_monitor::{CombinedNotifier, SystemClock};
+use hardy_monitor::{CombinedNotifier, DbNotificationStore, LoggingNotifier, SystemSoundSink};
 #[cfg(feature = "gui")]
 use image::GenericImageView;
 #[cfg(feature = "gui")]
@@ -101,19 +112,68 @@ fn run_daemon(rt: tokio::runtime::Runtime, config: Arc<AppConfig>) -> Result<()>
 
         // Connect to database
         tracing::info!("Connecting to database...");
-        let database = db::Database::new(&config.database.url).await?;
+        let database = db::Database::from_config(&config.database).await?;
         tracing::info!("Database connected successfully");
 
-        // Create API client
-        let api_client = api::GymApiClient::new(config.gym.api_url.clone(), &config.network)?;
-        tracing::info!("API client initialized");
-
-        // Create schedule for working hours check
-        let schedule = GymSchedule::new(&config.schedule);
+        // Create one API client per gym and the schedule for working hours check
+        let mut settings = DaemonSettings::new(config.clone())?;
+        tracing::info!("API clients initialized for {} gym(s)", settings.gyms.len());
         tracing::info!("Schedule configured: weekday {}-{}, weekend {}-{}",
             config.schedule.weekday.open_hour, config.schedule.weekday.close_hour,
             config.schedule.weekend.open_hour, config.schedule.weekend.close_hour);
 
+        // Reloaded configs, for the background tasks
+        let (config_tx, config_rx) = watch::channel(config.clone());
+        if config.database.retention_days > 0 {
+            tokio::spawn(prune_periodically(database.clone(), config_rx.clone()));
+        }
+        // Gym ids with an averages refresh task, which outlives config reloads
+        let mut refreshed_gyms = HashSet::new();
+        spawn_averages_refresh(&settings, &database, &mut refreshed_gyms);
+        let daemon_status = DaemonStatus::new(&config.gym.entries());
+        if let Some(port) = config.daemon.status_port {
+            let (daemon_status, database) = (daemon_status.clone(), database.clone());
+            let bind = config.daemon.status_bind;
+            let schedule = settings.schedule.clone();
+            tokio::spawn(async move {
+                if let Err(e) = status::serve(bind, port, daemon_status, database, schedule).await {
+                    tracing::error!("Status endpoint stopped: {:#}", e);
+                }
+            });
+        }
+        if let Some(weekday) = config.daemon.digest_weekday {
+            let notifiers = network_notifiers(&config);
+            if notifiers.is_empty() {
+                tracing::warn!(
+                    "Weekly digest disabled: no ntfy topic, webhook or email configured"
+                );
+            } else {
+                tokio::spawn(send_digests_periodically(
+                    database.clone(),
+                    config_rx.clone(),
+                    weekday,
+                    notifiers,
+                ));
+            }
+        }
+        if config.notifications.enabled && config.notifications.predictive_alerts {
+            let notifiers = network_notifiers(&config);
+            if notifiers.is_empty() {
+                tracing::warn!(
+                    "Predictive alerts disabled: no ntfy topic, webhook or email configured"
+                );
+            } else {
+                tokio::spawn(send_predictive_alerts_periodically(
+                    database.clone(),
+                    config_rx.clone(),
+                    notifiers,
+                ));
+            }
+        }
+        let mut reload = ReloadSignal::new()?;
+        // Stored readings are published here for in-process subscribers
+        let live_feed = LiveFeed::new();
+
         // Wait until the next full minute before starting
         let now = chrono::Utc::now();
         let seconds_until_next_minute = 60 - (now.timestamp() % 60);
@@ -129,39 +189,407 @@ fn run_daemon(rt: tokio::runtime::Runtime, config: Arc<AppConfig>) -> Result<()>
 
         let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        // Consecutive identical readings per gym id
+        let mut stale_detectors: HashMap<String, api::StaleDetector> = HashMap::new();
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = reload.recv() => {
+                    if reload_settings(&mut settings) {
+                        spawn_averages_refresh(&settings, &database, &mut refreshed_gyms);
+                        daemon_status.set_gyms(&settings.config.gym.entries());
+                        config_tx.send_replace(settings.config.clone());
+                    }
+                    continue;
+                }
+            }
 
-            // Skip fetching when gym is closed
+            // Sleep until opening when gym is closed
             let now_local = chrono::Local::now();
-            if !schedule.is_open(&now_local) {
-                tracing::debug!("Gym is closed at {}, skipping fetch", now_local.format("%H:%M"));
+            let is_open = settings.schedule.is_open(&now_local);
+            daemon_status.set_open(is_open);
+            if !is_open {
+                match settings.schedule.next_open_time(&now_local) {
+                    Some(open_at) => {
+                        tracing::info!(
+                            "Gym is closed, sleeping until {}",
+                            open_at.format("%a %H:%M")
+                        );
+                        let wait = (open_at - now_local).to_std().unwrap_or_default();
+                        tokio::select! {
+                            _ = tokio::time::sleep(wait) => {}
+                            _ = reload.recv() => {
+                                if reload_settings(&mut settings) {
+                                    spawn_averages_refresh(
+                                        &settings,
+                                        &database,
+                                        &mut refreshed_gyms,
+                                    );
+                                    daemon_status.set_gyms(&settings.config.gym.entries());
+                                    config_tx.send_replace(settings.config.clone());
+                                }
+                            }
+                        }
+                    }
+                    None => {
+                        tracing::debug!(
+                            "Gym is closed at {}, skipping fetch",
+                            now_local.format("%H:%M")
+                        );
+                    }
+                }
                 continue;
             }
 
-            match fetch_and_store(&api_client, &database).await {
-                Ok(percentage) => {
-                    tracing::info!("Recorded occupancy: {:.1}%", percentage);
+            for (entry, api_client) in &settings.gyms {
+                // Records are tagged with each gym's id
+                let gym_db = database.clone().with_gym(entry.id.clone());
+                let stale = stale_detectors.entry(entry.id.clone()).or_default();
+                match fetch_and_store(
+                    api_client,
+                    &gym_db,
+                    stale,
+                    &settings.config.gym,
+                    &entry.name,
+                    &live_feed,
+                )
+                .await
+                {
+                    Ok(Some(percentage)) => {
+                        tracing::info!("Recorded occupancy for {}: {:.1}%", entry.name, percentage);
+                        daemon_status.record_fetch(&entry.id, percentage, chrono::Utc::now());
+                    }
+                    Ok(None) => {
+                        tracing::debug!("Skipped stale reading for {}", entry.name);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to fetch/store data for {}: {}", entry.name, e);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Reload the config file, keeping the current settings if it is invalid
+///
+/// Returns whether the new settings were applied.
+fn reload_settings(settings: &mut DaemonSettings) -> bool {
+    tracing::info!("Reloading configuration");
+    let previous = settings.config.clone();
+    match AppConfig::load().and_then(|config| settings.reload(config)) {
+        Ok(()) => {
+            tracing::info!("Configuration reloaded for {} gym(s)", settings.gyms.len());
+            let (new, old) = (&settings.config, &previous);
+            if new.refresh.data_fetch_interval_secs != old.refresh.data_fetch_interval_secs {
+                tracing::warn!("refresh.data_fetch_interval_secs only changes after a restart");
+            }
+            if new.daemon.digest_weekday != old.daemon.digest_weekday
+                || new.notifications.enabled != old.notifications.enabled
+                || new.notifications.predictive_alerts != old.notifications.predictive_alerts
+            {
+                tracing::warn!(
+                    "Enabling or disabling digests and predictive alerts only takes effect after \
+                     a restart"
+                );
+            }
+            if new.notifications.ntfy_topic != old.notifications.ntfy_topic
+                || new.notifications.webhook_url != old.notifications.webhook_url
+                || new.notifications.email != old.notifications.email
+            {
+                tracing::warn!("Daemon notification channels only change after a restart");
+            }
+            true
+        }
+        Err(e) => {
+            tracing::error!("Failed to reload configuration: {:#}", e);
+            false
+        }
+    }
+}
+
+/// Start refreshing the averages cache of configured gyms that have no task yet
+///
+/// Tasks of gyms removed from the config keep running until a restart.
+fn spawn_averages_refresh(
+    settings: &DaemonSettings,
+    database: &db::Database,
+    refreshed: &mut HashSet<String>,
+) {
+    for (entry, _) in &settings.gyms {
+        if refreshed.insert(entry.id.clone()) {
+            let gym_db = database.clone().with_gym(entry.id.clone());
+            tokio::spawn(refresh_averages_periodically(gym_db));
+        }
+    }
+}
+
+/// Resolves on each SIGHUP; never on platforms without signals
+struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignal {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            hangup: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .context("Failed to listen for SIGHUP")?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.hangup.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Delete records past the retention period at the configured interval
+///
+/// The pruned gyms follow config reloads; the database settings are kept on
+/// reload, so retention and interval stay as configured at startup.
+async fn prune_periodically(database: db::Database, configs: watch::Receiver<Arc<AppConfig>>) {
+    let settings = configs.borrow().database.clone();
+    let retention = chrono::Duration::days(settings.retention_days as i64);
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        settings.prune_interval_hours.max(1) * 3600,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        let cutoff = chrono::Utc::now() - retention;
+        let mut removed = 0;
+        let gyms = configs.borrow().gym.entries();
+        for entry in gyms {
+            let gym_db = database.clone().with_gym(entry.id.clone());
+            match gym_db.prune_before(cutoff).await {
+                Ok(count) => {
+                    tracing::info!(
+                        "Pruned {} records of {} older than {}",
+                        count,
+                        entry.name,
+                        cutoff.date_naive()
+                    );
+                    removed += count;
                 }
                 Err(e) => {
-                    tracing::error!("Failed to fetch/store data: {}", e);
+                    tracing::error!("Failed to prune old records of {}: {}", entry.name, e);
                 }
             }
         }
-    })
+        if settings.vacuum_min_rows > 0
+            && removed >= settings.vacuum_min_rows
+            && let Err(e) = database.vacuum().await
+        {
+            tracing::warn!("Failed to vacuum database: {}", e);
+        }
+    }
+}
+
+/// Keep the hourly averages cache up to date, rebuilding it fully on startup
+async fn refresh_averages_periodically(database: db::Database) {
+    let mut interval = tokio::time::interval(Duration::from_secs(3600));
+    let mut since = chrono::DateTime::<chrono::Utc>::UNIX_EPOCH;
+
+    loop {
+        interval.tick().await;
+
+        let now = chrono::Utc::now();
+        match database.refresh_hourly_averages(since).await {
+            Ok(hours) => {
+                tracing::debug!("Refreshed {} hours of cached averages", hours);
+                since = now - chrono::Duration::hours(1);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to refresh hourly averages: {}", e);
+            }
+        }
+    }
+}
+
+/// The network notification channels configured for the daemon
+fn network_notifiers(config: &AppConfig) -> Vec<Arc<dyn AsyncNotifier>> {
+    let mut notifiers: Vec<Arc<dyn AsyncNotifier>> = Vec::new();
+    if let Some(topic) = &config.notifications.ntfy_topic {
+        notifiers.push(Arc::new(NtfyNotifier::new(topic)));
+    }
+    if let Some(url) = &config.notifications.webhook_url {
+        match WebhookNotifier::new(url.as_str(), &config.network) {
+            Ok(webhook) => notifiers.push(Arc::new(webhook)),
+            Err(e) => tracing::warn!("Webhook notifications disabled: {:#}", e),
+        }
+    }
+    if let Some(email) = &config.notifications.email {
+        match EmailNotifier::new(email) {
+            Ok(email) => notifiers.push(Arc::new(email)),
+            Err(e) => tracing::warn!("Email notifications disabled: {:#}", e),
+        }
+    }
+    notifiers
 }
 
-/// Fetch current occupancy and store in database
-async fn fetch_and_store(
-    api_client: &api::GymApiClient,
+/// Send each gym's weekly digest when it is due, checking once a minute
+///
+/// The gyms, schedule and digest hour follow config reloads. Sends are logged,
+/// so reloading the digests from the log keeps each one's last send.
+async fn send_digests_periodically(
+    database: db::Database,
+    mut configs: watch::Receiver<Arc<AppConfig>>,
+    weekday: chrono::Weekday,
+    notifiers: Vec<Arc<dyn AsyncNotifier>>,
+) {
+    let clock = Arc::new(SystemClock);
+    let config = configs.borrow_and_update().clone();
+    let mut digests = load_digests(&database, &config, weekday, &clock).await;
+
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+
+        if configs.has_changed().unwrap_or(false) {
+            let config = configs.borrow_and_update().clone();
+            digests = load_digests(&database, &config, weekday, &clock).await;
+        }
+        for (gym_db, title, schedule) in &mut digests {
+            if !schedule.is_due() {
+                continue;
+            }
+            let body = match weekly_digest_body(gym_db).await {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::warn!("Failed to build {}: {:#}", title, e);
+                    continue;
+                }
+            };
+            // Not retried on delivery errors, so a digest never repeats
+            schedule.mark_sent();
+            if let Err(e) = gym_db
+                .insert_notification(chrono::Utc::now(), title, &body)
+                .await
+            {
+                tracing::warn!("Failed to record {}: {:#}", title, e);
+            }
+            match notify_all(&notifiers, title, &body).await {
+                Ok(()) => tracing::info!("Sent {}", title),
+                Err(e) => tracing::error!("Failed to send {}: {:#}", title, e),
+            }
+        }
+    }
+}
+
+/// The digest of each configured gym, with its last send read from the log
+async fn load_digests(
     database: &db::Database,
-) -> Result<f64> {
-    let response = api_client.fetch_occupancy().await?;
-    let percentage = response.occupancy_percentage()?;
-    let timestamp = chrono::Utc::now();
-    database.insert_record(timestamp, percentage).await?;
-    Ok(percentage)
+    config: &AppConfig,
+    weekday: chrono::Weekday,
+    clock: &Arc<SystemClock>,
+) -> Vec<(db::Database, String, DigestSchedule)> {
+    let gym_schedule = GymSchedule::new(&config.schedule);
+    let mut digests = Vec::new();
+    for entry in config.gym.entries() {
+        let title = digest::digest_title(&entry.name);
+        // Sends are logged, so a restarted daemon knows this week's went out
+        let last_sent = database
+            .last_notification_at(&title)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read last digest for {}: {:#}", entry.name, e);
+                None
+            });
+        let schedule = DigestSchedule::new(
+            weekday,
+            config.daemon.digest_hour,
+            last_sent,
+            gym_schedule.clone(),
+            clock.clone(),
+        );
+        digests.push((database.clone().with_gym(entry.id.clone()), title, schedule));
+    }
+    digests
+}
+
+/// Alert ahead of each gym's predicted quiet period, checking once a minute
+///
+/// The baselines are reloaded hourly, like the averages cache they read, and
+/// whenever the config is reloaded. The gyms, schedule and alert settings
+/// follow config reloads.
+async fn send_predictive_alerts_periodically(
+    database: db::Database,
+    mut configs: watch::Receiver<Arc<AppConfig>>,
+    notifiers: Vec<Arc<dyn AsyncNotifier>>,
+) {
+    let mut config = configs.borrow_and_update().clone();
+    let mut schedule = GymSchedule::new(&config.schedule);
+    // Keyed by gym id, so gyms kept across a reload keep their state
+    let mut baselines = HashMap::new();
+    let mut alerts: HashMap<String, PredictiveAlert> = HashMap::new();
+
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    let mut ticks = 0u64;
+    loop {
+        interval.tick().await;
+
+        let mut reload_baselines = ticks.is_multiple_of(60);
+        ticks += 1;
+        if configs.has_changed().unwrap_or(false) {
+            config = configs.borrow_and_update().clone();
+            schedule = GymSchedule::new(&config.schedule);
+            reload_baselines = true;
+        }
+        let settings = &config.notifications;
+        for entry in config.gym.entries() {
+            let gym_db = database.clone().with_gym(entry.id.clone());
+            if reload_baselines || !baselines.contains_key(&entry.id) {
+                match daemon::load_prediction_baseline(
+                    &gym_db,
+                    config.analytics.prediction_window_days,
+                    config.analytics.adaptive_baseline,
+                    &SystemClock,
+                )
+                .await
+                {
+                    Ok(averages) => {
+                        baselines.insert(entry.id.clone(), averages);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to load baseline for {}: {:#}", entry.name, e);
+                        baselines.entry(entry.id.clone()).or_default();
+                    }
+                }
+            }
+            let alert = alerts.entry(entry.id.clone()).or_default();
+            let Some(body) = alert.check(
+                &baselines[&entry.id],
+                &schedule,
+                settings.threshold_percent,
+                settings.predictive_lead_minutes,
+                &SystemClock,
+            ) else {
+                continue;
+            };
+            let title = format!("{} quiet soon", entry.name);
+            match notify_all(&notifiers, &title, &body).await {
+                Ok(()) => tracing::info!("Sent predictive alert for {}", entry.name),
+                Err(e) => tracing::error!("Failed to send predictive alert: {:#}", e),
+            }
+        }
+    }
+}
+
+/// Summarize the past seven days against the seven before
+async fn weekly_digest_body(database: &db::Database) -> Result<String> {
+    let now = chrono::Utc::now();
+    let week_ago = now - chrono::Duration::weeks(1);
+    let current = database.get_averages_range(week_ago, now).await?;
+    let previous = database
+        .get_averages_range(week_ago - chrono::Duration::weeks(1), week_ago)
+        .await?;
+    Ok(digest::digest_body(&weekly_summary(&current, &previous)))
 }
 
 /// Run in GUI mode - desktop application (read-only, no API fetching)
@@ -169,15 +597,18 @@ async fn fetch_and_store(
 fn run_gui(rt: tokio::runtime::Runtime, config: Arc<AppConfig>) -> Result<()> {
     let database = rt.block_on(async {
         tracing::info!("Connecting to database...");
-        let database = db::Database::new(&config.database.url).await?;
+        let database = db::Database::from_config(&config.database).await?;
         tracing::info!("Database connected successfully");
-        Ok::<_, anyhow::Error>(database)
+        // The GUI shows the first configured gym
+        let gym_id = config.gym.entries()[0].id.clone();
+        Ok::<_, anyhow::Error>(database.with_gym(gym_id))
     })?;
 
     let icon = load_icon();
 
     let window_width = config.window.width;
     let window_height = config.window.height;
+    let rt_handle = rt.handle().clone();
 
     let app = iced::application(
         move || {
@@ -201,7 +632,23 @@ fn run_gui(rt: tokio::runtime::Runtime, config: Arc<AppConfig>) -> Result<()> {
                 .build()
                 .expect("Failed to build tray icon");
 
-            let notifier = CombinedNotifier::new(config.notifications.ntfy_topic.clone());
+            let mut combined = CombinedNotifier::new(config.notifications.ntfy_topic.clone());
+            if let Some(url) = &config.notifications.webhook_url {
+                match WebhookNotifier::new(url.as_str(), &config.network) {
+                    Ok(webhook) => combined = combined.with_webhook(webhook),
+                    Err(e) => tracing::warn!("Webhook notifications disabled: {:#}", e),
+                }
+            }
+            if let Some(email) = &config.notifications.email {
+                match EmailNotifier::new(email) {
+                    Ok(email) => combined = combined.with_email(email),
+                    Err(e) => tracing::warn!("Email notifications disabled: {:#}", e),
+                }
+            }
+            let notifier = LoggingNotifier::new(
+                combined,
+                DbNotificationStore::new(database.clone(), rt_handle.clone()),
+            );
 
             HardyMonitorApp::new(
                 database.clone(),
@@ -209,6 +656,7 @@ fn run_gui(rt: tokio::runtime::Runtime, config: Arc<AppConfig>) -> Result<()> {
                 config.clone(),
                 Arc::new(SystemClock),
                 Arc::new(notifier),
+                Arc::new(SystemSoundSink),
             )
         },
         update,
@@ -2,6 +2,9 @@
 
 use chrono::{DateTime, Utc};
 
+#[cfg(feature = "gui")]
+use crate::widgets::history_chart::ConfidencePoint;
+
 /// Method used for generating a prediction
 #[derive(Debug, Clone, PartialEq)]
 pub enum PredictionMethod {
@@ -82,6 +85,17 @@ impl PredictionWithConfidence {
     pub fn to_simple(&self) -> (DateTime<Utc>, f64) {
         (self.timestamp, self.predicted_value)
     }
+
+    /// Convert to a point of the history chart's confidence band
+    #[cfg(feature = "gui")]
+    pub fn to_band(&self) -> ConfidencePoint {
+        ConfidencePoint {
+            timestamp: self.timestamp,
+            value: self.predicted_value,
+            low: self.confidence_low,
+            high: self.confidence_high,
+        }
+    }
 }
 
 #[cfg(test)]
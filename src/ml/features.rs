@@ -5,9 +5,11 @@
 use std::collections::{HashMap, VecDeque};
 use std::f64::consts::PI;
 
-use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, IsoWeek, Local, NaiveDate, NaiveDateTime, Timelike, Utc, Weekday,
+};
 
-use crate::db::HourlyAverage;
+use crate::db::{HourlyAverage, OccupancyLog};
 use crate::schedule::{is_bavarian_holiday, GymSchedule};
 
 /// Features extracted for a single prediction
@@ -32,11 +34,18 @@ pub struct PredictionFeatures {
     pub day_avg_so_far: f64,
     pub prev_day_avg: f64,
 
+    // Same local hour in prior weeks
+    pub same_slot_last_week: f64,
+    pub same_slot_two_weeks_ago: f64,
+
     // Categorical/seasonal
     pub is_weekend: f64,
     pub is_holiday: f64,
     pub week_of_year_sin: f64,
     pub week_of_year_cos: f64,
+    pub month_sin: f64,
+    pub month_cos: f64,
+    pub is_january: f64,
 
     // Prediction horizon
     pub hours_ahead: f64,
@@ -57,16 +66,25 @@ impl PredictionFeatures {
             self.recent_trend,
             self.day_avg_so_far,
             self.prev_day_avg,
+            self.same_slot_last_week,
+            self.same_slot_two_weeks_ago,
             self.is_weekend,
             self.is_holiday,
             self.week_of_year_sin,
             self.week_of_year_cos,
+            self.month_sin,
+            self.month_cos,
+            self.is_january,
             self.hours_ahead,
         ]
     }
 
     /// Number of features
-    pub const NUM_FEATURES: usize = 16;
+    pub const NUM_FEATURES: usize = 21;
+
+    /// Version of the feature layout, bumped whenever features are added,
+    /// removed or reordered
+    pub const FEATURE_VERSION: u32 = 3;
 
     /// Feature names for debugging/logging
     pub fn feature_names() -> Vec<&'static str> {
@@ -82,10 +100,15 @@ impl PredictionFeatures {
             "recent_trend",
             "day_avg_so_far",
             "prev_day_avg",
+            "same_slot_last_week",
+            "same_slot_two_weeks_ago",
             "is_weekend",
             "is_holiday",
             "week_of_year_sin",
             "week_of_year_cos",
+            "month_sin",
+            "month_cos",
+            "is_january",
             "hours_ahead",
         ]
     }
@@ -99,11 +122,40 @@ pub struct SlotStats {
     pub sample_count: i64,
 }
 
+impl SlotStats {
+    /// Mean and sample standard deviation of `values`
+    fn from_values(values: &[f64]) -> Self {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            sample_count: values.len() as i64,
+        }
+    }
+}
+
+/// Days of hourly history kept for the prior-week lag features
+const SLOT_HISTORY_DAYS: i64 = 15;
+
+/// Readings a (month, weekday, hour) slot needs before its statistics are
+/// used instead of the all-year ones
+const MIN_MONTHLY_SLOT_SAMPLES: i64 = 60;
+
 /// Extracts features from raw occupancy data
 #[derive(Debug, Clone)]
 pub struct FeatureExtractor {
     /// Historical statistics by (weekday, hour)
     historical_stats: HashMap<(u32, u32), SlotStats>,
+    /// Statistics of raw readings by local (month, weekday, hour)
+    monthly_stats: HashMap<(u32, u32, u32), SlotStats>,
+    /// Running (sum, count) of observations by local hour
+    slot_history: HashMap<NaiveDateTime, (f64, u32)>,
 }
 
 impl FeatureExtractor {
@@ -111,9 +163,42 @@ impl FeatureExtractor {
     pub fn new() -> Self {
         Self {
             historical_stats: HashMap::new(),
+            monthly_stats: HashMap::new(),
+            slot_history: HashMap::new(),
+        }
+    }
+
+    /// Record an observation for the prior-week lag features
+    ///
+    /// Hours more than two weeks older than the newest one are dropped.
+    pub fn record_observation(&mut self, timestamp: DateTime<Utc>, percentage: f64) {
+        let slot = local_hour_start(&timestamp.with_timezone(&Local));
+        if !self.slot_history.contains_key(&slot) {
+            let cutoff = slot - chrono::Duration::days(SLOT_HISTORY_DAYS);
+            self.slot_history.retain(|&s, _| s >= cutoff);
+        }
+
+        let entry = self.slot_history.entry(slot).or_insert((0.0, 0));
+        entry.0 += percentage;
+        entry.1 += 1;
+    }
+
+    /// Record occupancy logs for the prior-week lag features
+    pub fn update_slot_history(&mut self, logs: &[OccupancyLog]) {
+        for log in logs {
+            if let Some(timestamp) = log.datetime() {
+                self.record_observation(timestamp, log.percentage);
+            }
         }
     }
 
+    /// Average occupancy recorded for the local hour starting at `slot`
+    fn slot_average(&self, slot: NaiveDateTime) -> Option<f64> {
+        self.slot_history
+            .get(&slot)
+            .map(|&(sum, count)| sum / count as f64)
+    }
+
     /// Update historical statistics from baseline data
     pub fn update_historical_stats(&mut self, baseline: &[HourlyAverage]) {
         self.historical_stats.clear();
@@ -122,29 +207,55 @@ impl FeatureExtractor {
         let mut groups: HashMap<(u32, u32), Vec<f64>> = HashMap::new();
 
         for avg in baseline {
-            let key = (avg.weekday, avg.hour);
+            let key = (avg.weekday as u32, avg.hour as u32);
             groups.entry(key).or_default().push(avg.avg_percentage);
         }
 
         for (key, values) in groups {
-            let mean = values.iter().sum::<f64>() / values.len() as f64;
-            let variance = if values.len() > 1 {
-                values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
-            } else {
-                0.0
-            };
-
-            self.historical_stats.insert(
-                key,
-                SlotStats {
-                    mean,
-                    std_dev: variance.sqrt(),
-                    sample_count: values.len() as i64,
-                },
-            );
+            self.historical_stats
+                .insert(key, SlotStats::from_values(&values));
+        }
+    }
+
+    /// Update month-segmented statistics from raw occupancy logs
+    ///
+    /// Readings are grouped by local month, weekday and hour, so seasonal
+    /// shifts (January resolutions, August vacations) get their own baseline.
+    pub fn update_monthly_stats(&mut self, logs: &[OccupancyLog]) {
+        self.monthly_stats.clear();
+
+        let mut groups: HashMap<(u32, u32, u32), Vec<f64>> = HashMap::new();
+        for log in logs {
+            if let Some(timestamp) = log.datetime() {
+                let local = timestamp.with_timezone(&Local);
+                let key = (
+                    local.month(),
+                    local.weekday().num_days_from_monday(),
+                    local.hour(),
+                );
+                groups.entry(key).or_default().push(log.percentage);
+            }
+        }
+
+        for (key, values) in groups {
+            self.monthly_stats
+                .insert(key, SlotStats::from_values(&values));
         }
     }
 
+    /// Get statistics for a slot in a specific month (1-12), if it has
+    /// enough readings to be trusted
+    pub fn get_monthly_slot_stats(
+        &self,
+        month: u32,
+        weekday: u32,
+        hour: u32,
+    ) -> Option<&SlotStats> {
+        self.monthly_stats
+            .get(&(month, weekday, hour))
+            .filter(|s| s.sample_count >= MIN_MONTHLY_SLOT_SAMPLES)
+    }
+
     /// Get the standard deviation for a specific slot
     pub fn get_slot_std(&self, weekday: u32, hour: u32) -> Option<f64> {
         self.historical_stats
@@ -169,23 +280,24 @@ impl FeatureExtractor {
         let local_time = target_time.with_timezone(&Local);
         let hour = local_time.hour();
         let weekday = local_time.weekday().num_days_from_monday();
-        let week_of_year = local_time.iso_week().week();
 
         // Cyclical encoding for time
         let (hour_sin, hour_cos) = cyclical_encode(hour as f64, 24.0);
         let (weekday_sin, weekday_cos) = cyclical_encode(weekday as f64, 7.0);
-        let (week_of_year_sin, week_of_year_cos) = cyclical_encode(week_of_year as f64, 52.0);
+        let (week_of_year_sin, week_of_year_cos) = encode_iso_week(local_time.iso_week());
+        let month = local_time.month();
+        let (month_sin, month_cos) = cyclical_encode((month - 1) as f64, 12.0);
 
-        // Historical baseline for this slot
+        // Historical baseline for this slot, preferring the target's month
         let (historical_avg, historical_std) = self
-            .historical_stats
-            .get(&(weekday, hour))
+            .get_monthly_slot_stats(month, weekday, hour)
+            .or_else(|| self.historical_stats.get(&(weekday, hour)))
             .map(|s| (s.mean, s.std_dev))
             .or_else(|| {
                 // Fallback to baseline if stats not computed
                 baseline
                     .iter()
-                    .find(|b| b.weekday == weekday && b.hour == hour)
+                    .find(|b| b.weekday == weekday as i32 && b.hour == hour as i32)
                     .map(|b| (b.avg_percentage, 10.0)) // Default std
             })
             .unwrap_or((50.0, 15.0)); // Global default
@@ -196,6 +308,15 @@ impl FeatureExtractor {
         // Day-level features
         let (day_avg_so_far, prev_day_avg) = self.extract_day_features(recent_data, &local_time);
 
+        // Same slot in prior weeks, falling back to the slot baseline
+        let slot = local_hour_start(&local_time);
+        let same_slot_last_week = self
+            .slot_average(slot - chrono::Duration::weeks(1))
+            .unwrap_or(historical_avg);
+        let same_slot_two_weeks_ago = self
+            .slot_average(slot - chrono::Duration::weeks(2))
+            .unwrap_or(historical_avg);
+
         // Categorical features
         let is_weekend = if weekday >= 5 { 1.0 } else { 0.0 };
         let is_holiday = if is_bavarian_holiday(local_time.date_naive()) {
@@ -203,6 +324,7 @@ impl FeatureExtractor {
         } else {
             0.0
         };
+        let is_january = if month == 1 { 1.0 } else { 0.0 };
 
         PredictionFeatures {
             hour_sin,
@@ -216,10 +338,15 @@ impl FeatureExtractor {
             recent_trend,
             day_avg_so_far,
             prev_day_avg,
+            same_slot_last_week,
+            same_slot_two_weeks_ago,
             is_weekend,
             is_holiday,
             week_of_year_sin,
             week_of_year_cos,
+            month_sin,
+            month_cos,
+            is_january,
             hours_ahead: hours_ahead as f64,
         }
     }
@@ -336,6 +463,15 @@ impl Default for FeatureExtractor {
     }
 }
 
+/// Start of the local wall-clock hour containing `local_time`
+fn local_hour_start(local_time: &DateTime<Local>) -> NaiveDateTime {
+    let naive = local_time.naive_local();
+    naive
+        .date()
+        .and_hms_opt(naive.hour(), 0, 0)
+        .unwrap_or(naive)
+}
+
 /// Cyclical encoding for periodic features
 /// Returns (sin, cos) encoding to preserve continuity
 fn cyclical_encode(value: f64, period: f64) -> (f64, f64) {
@@ -343,10 +479,29 @@ fn cyclical_encode(value: f64, period: f64) -> (f64, f64) {
     (angle.sin(), angle.cos())
 }
 
+/// Number of ISO weeks (52 or 53) in an ISO week-numbering year
+fn iso_weeks_in_year(year: i32) -> u32 {
+    if NaiveDate::from_isoywd_opt(year, 53, Weekday::Mon).is_some() {
+        53
+    } else {
+        52
+    }
+}
+
+/// Cyclical encoding of an ISO week over the actual length of its year,
+/// so the last week of a year is adjacent to week 1 of the next
+fn encode_iso_week(week: IsoWeek) -> (f64, f64) {
+    cyclical_encode(
+        (week.week() - 1) as f64,
+        iso_weeks_in_year(week.year()) as f64,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
+    use chrono::TimeZone;
 
     #[test]
     fn test_cyclical_encoding_continuity() {
@@ -379,6 +534,106 @@ mod tests {
         assert_relative_eq!(cos_6, 0.0, epsilon = 1e-10);
     }
 
+    #[test]
+    fn test_iso_weeks_in_year() {
+        assert_eq!(iso_weeks_in_year(2020), 53);
+        assert_eq!(iso_weeks_in_year(2021), 52);
+        assert_eq!(iso_weeks_in_year(2026), 53);
+    }
+
+    #[test]
+    fn test_week_encoding_53_week_year_boundary() {
+        // 2020-12-31 is in ISO week 53 of 2020, 2021-01-04 in week 1 of 2021
+        let last = NaiveDate::from_ymd_opt(2020, 12, 31).unwrap().iso_week();
+        let first = NaiveDate::from_ymd_opt(2021, 1, 4).unwrap().iso_week();
+        assert_eq!(last.week(), 53);
+        assert_eq!(first.week(), 1);
+
+        let (sin_last, cos_last) = encode_iso_week(last);
+        let (sin_first, cos_first) = encode_iso_week(first);
+        let distance = ((sin_last - sin_first).powi(2) + (cos_last - cos_first).powi(2)).sqrt();
+
+        // One step on a 53-point circle
+        assert_relative_eq!(distance, 2.0 * (PI / 53.0).sin(), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn test_week_encoding_wraps_smoothly() {
+        // Consecutive weeks are equally spaced, including across year ends
+        let start = NaiveDate::from_ymd_opt(2020, 11, 2).unwrap();
+        let encoded: Vec<_> = (0..20)
+            .map(|w| encode_iso_week((start + chrono::Duration::weeks(w)).iso_week()))
+            .collect();
+
+        for pair in encoded.windows(2) {
+            let ((s1, c1), (s2, c2)) = (pair[0], pair[1]);
+            let distance = ((s1 - s2).powi(2) + (c1 - c2).powi(2)).sqrt();
+            assert!(distance > 0.1 && distance < 0.13, "Distance was {}", distance);
+        }
+    }
+
+    #[test]
+    fn test_month_features() {
+        let extractor = FeatureExtractor::new();
+        let at = |month| {
+            let target = Local
+                .with_ymd_and_hms(2024, month, 10, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc);
+            extractor.extract(target, 1, &VecDeque::new(), &[], &GymSchedule::default())
+        };
+
+        let (january, july, december) = (at(1), at(7), at(12));
+        assert_eq!(january.is_january, 1.0);
+        assert_eq!(july.is_january, 0.0);
+        assert_relative_eq!(january.month_cos, 1.0, epsilon = 1e-10);
+        assert_relative_eq!(july.month_cos, -1.0, epsilon = 1e-10);
+
+        // December is one step from January on the 12-month circle
+        let distance = ((december.month_sin - january.month_sin).powi(2)
+            + (december.month_cos - january.month_cos).powi(2))
+        .sqrt();
+        assert_relative_eq!(distance, 2.0 * (PI / 12.0).sin(), epsilon = 1e-10);
+    }
+
+    fn logs_at(local: DateTime<Local>, count: usize, percentage: f64) -> Vec<OccupancyLog> {
+        (0..count)
+            .map(|i| OccupancyLog {
+                id: i as i64,
+                timestamp: (local + chrono::Duration::seconds(i as i64))
+                    .with_timezone(&Utc)
+                    .to_rfc3339(),
+                percentage,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_monthly_stats_override_all_year_baseline() {
+        let mut extractor = FeatureExtractor::new();
+        extractor.update_historical_stats(&[HourlyAverage {
+            weekday: 3, // Thursday
+            hour: 18,
+            avg_percentage: 50.0,
+            sample_count: 10,
+        }]);
+        // Busy Thursday evenings in January, too few readings in August
+        let january = Local.with_ymd_and_hms(2024, 1, 11, 18, 0, 0).unwrap();
+        let august = Local.with_ymd_and_hms(2024, 8, 15, 18, 0, 0).unwrap();
+        let mut logs = logs_at(january, MIN_MONTHLY_SLOT_SAMPLES as usize, 80.0);
+        logs.extend(logs_at(august, 5, 10.0));
+        extractor.update_monthly_stats(&logs);
+
+        let extract = |local: DateTime<Local>| {
+            let target = (local + chrono::Duration::weeks(1)).with_timezone(&Utc);
+            extractor.extract(target, 1, &VecDeque::new(), &[], &GymSchedule::default())
+        };
+
+        assert_relative_eq!(extract(january).historical_avg, 80.0, epsilon = 1e-10);
+        assert_relative_eq!(extract(august).historical_avg, 50.0, epsilon = 1e-10);
+        assert!(extractor.get_monthly_slot_stats(8, 3, 18).is_none());
+    }
+
     #[test]
     fn test_feature_extractor_creation() {
         let extractor = FeatureExtractor::new();
@@ -461,10 +716,15 @@ mod tests {
             recent_trend: 2.0,
             day_avg_so_far: 42.0,
             prev_day_avg: 55.0,
+            same_slot_last_week: 47.0,
+            same_slot_two_weeks_ago: 44.0,
             is_weekend: 0.0,
             is_holiday: 0.0,
             week_of_year_sin: 0.5,
             week_of_year_cos: 0.866,
+            month_sin: 0.0,
+            month_cos: 1.0,
+            is_january: 1.0,
             hours_ahead: 1.0,
         };
 
@@ -478,6 +738,65 @@ mod tests {
         assert_eq!(names.len(), PredictionFeatures::NUM_FEATURES);
     }
 
+    #[test]
+    fn test_same_slot_features_use_prior_weeks() {
+        let mut extractor = FeatureExtractor::new();
+        let target = Local
+            .with_ymd_and_hms(2024, 6, 20, 18, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        extractor.record_observation(target - chrono::Duration::weeks(2), 30.0);
+        extractor.record_observation(
+            target - chrono::Duration::weeks(1) + chrono::Duration::minutes(10),
+            70.0,
+        );
+        extractor.record_observation(
+            target - chrono::Duration::weeks(1) + chrono::Duration::minutes(40),
+            80.0,
+        );
+        // A neighbouring hour does not leak into the slot
+        extractor.record_observation(
+            target - chrono::Duration::weeks(1) - chrono::Duration::hours(1),
+            10.0,
+        );
+
+        let features = extractor.extract(target, 1, &VecDeque::new(), &[], &GymSchedule::default());
+
+        assert_relative_eq!(features.same_slot_last_week, 75.0, epsilon = 1e-10);
+        assert_relative_eq!(features.same_slot_two_weeks_ago, 30.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn test_same_slot_features_fall_back_to_historical_avg() {
+        let mut extractor = FeatureExtractor::new();
+        let target = Local
+            .with_ymd_and_hms(2024, 6, 20, 18, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        extractor.update_historical_stats(&[HourlyAverage {
+            weekday: 3, // Thursday
+            hour: 18,
+            avg_percentage: 62.0,
+            sample_count: 10,
+        }]);
+        extractor.record_observation(target - chrono::Duration::weeks(1), 40.0);
+
+        let features = extractor.extract(target, 1, &VecDeque::new(), &[], &GymSchedule::default());
+
+        assert_relative_eq!(features.same_slot_last_week, 40.0, epsilon = 1e-10);
+        assert_relative_eq!(features.same_slot_two_weeks_ago, 62.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn test_slot_history_drops_old_hours() {
+        let mut extractor = FeatureExtractor::new();
+        let start = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        extractor.record_observation(start, 50.0);
+        extractor.record_observation(start + chrono::Duration::days(20), 50.0);
+
+        assert_eq!(extractor.slot_history.len(), 1);
+    }
+
     #[test]
     fn test_extract_momentum_empty() {
         let extractor = FeatureExtractor::new();
@@ -493,8 +812,8 @@ mod tests {
     #[test]
     fn test_weekend_detection() {
         // Saturday is weekday 5 (Mon=0, Sat=5, Sun=6)
-        assert!(5 >= 5); // Saturday
-        assert!(6 >= 5); // Sunday
-        assert!(!(4 >= 5)); // Friday
+        assert!(Weekday::Sat.num_days_from_monday() >= 5);
+        assert!(Weekday::Sun.num_days_from_monday() >= 5);
+        assert!(Weekday::Fri.num_days_from_monday() < 5);
     }
 }
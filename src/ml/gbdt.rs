@@ -0,0 +1,605 @@
+//! Gradient boosted regression trees
+//!
+//! Boosting over shallow regression trees, minimizing squared error or the
+//! pinball loss of a quantile. Features are bucketed into at most `MAX_BINS`
+//! quantile bins up front, so finding a split costs one histogram pass per
+//! node instead of a sort per feature.
+
+use super::model::TrainingError;
+
+/// Maximum number of bins per feature
+const MAX_BINS: usize = 64;
+
+/// Loss minimized by boosting
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Loss {
+    /// Predicts the conditional mean
+    #[default]
+    SquaredError,
+    /// Predicts the given conditional quantile (0-1), e.g. 0.9 for an upper
+    /// bound that 90% of observations stay below
+    Quantile(f64),
+}
+
+impl Loss {
+    /// Negative gradient of the loss for one residual
+    fn gradient(self, residual: f64) -> f64 {
+        match self {
+            Loss::SquaredError => residual,
+            Loss::Quantile(alpha) if residual > 0.0 => alpha,
+            Loss::Quantile(alpha) => alpha - 1.0,
+        }
+    }
+
+    /// Value minimizing the loss over `residuals`, each paired with its
+    /// sample weight
+    fn optimum(self, residuals: &mut [(f64, f64)]) -> f64 {
+        match self {
+            Loss::SquaredError => {
+                let total: f64 = residuals.iter().map(|(_, weight)| weight).sum();
+                residuals
+                    .iter()
+                    .map(|(residual, weight)| residual * weight)
+                    .sum::<f64>()
+                    / total
+            }
+            Loss::Quantile(alpha) if residuals.iter().all(|(_, w)| *w == residuals[0].1) => {
+                let mut values: Vec<f64> =
+                    residuals.iter().map(|(residual, _)| *residual).collect();
+                quantile(&mut values, alpha)
+            }
+            Loss::Quantile(alpha) => weighted_quantile(residuals, alpha),
+        }
+    }
+}
+
+/// The `alpha` quantile of `values`, interpolating between neighbours
+fn quantile(values: &mut [f64], alpha: f64) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let position = alpha.clamp(0.0, 1.0) * (values.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - lower as f64;
+    values[lower] + (values[upper] - values[lower]) * fraction
+}
+
+/// The smallest value whose share of the total weight reaches `alpha`
+fn weighted_quantile(values: &mut [(f64, f64)], alpha: f64) -> f64 {
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total: f64 = values.iter().map(|(_, weight)| weight).sum();
+    let target = alpha.clamp(0.0, 1.0) * total;
+    let mut cumulative = 0.0;
+    for &(value, weight) in values.iter() {
+        cumulative += weight;
+        if cumulative >= target {
+            return value;
+        }
+    }
+    values[values.len() - 1].0
+}
+
+/// Hyperparameters for boosting
+#[derive(Debug, Clone, Copy)]
+pub struct BoostingParams {
+    /// Loss to minimize
+    pub loss: Loss,
+    /// Number of trees
+    pub n_estimators: usize,
+    /// Shrinkage applied to each tree's output
+    pub learning_rate: f64,
+    /// Maximum depth of each tree
+    pub max_depth: usize,
+    /// Nodes with fewer samples become leaves
+    pub min_samples_split: usize,
+    /// Minimum number of samples in each leaf
+    pub min_samples_leaf: usize,
+}
+
+impl Default for BoostingParams {
+    fn default() -> Self {
+        Self {
+            loss: Loss::SquaredError,
+            n_estimators: 100,
+            learning_rate: 0.1,
+            max_depth: 6,
+            min_samples_split: 5,
+            min_samples_leaf: 2,
+        }
+    }
+}
+
+/// A fitted ensemble of regression trees
+#[derive(Debug, Clone)]
+pub struct GradientBoostedTrees {
+    /// Mean of the training targets, the starting prediction
+    base: f64,
+    learning_rate: f64,
+    trees: Vec<RegressionTree>,
+    /// Total squared-error reduction of the splits on each feature
+    split_gains: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct RegressionTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf(f64),
+    /// Samples with `value <= threshold` go left
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// Features bucketed by quantile cut points
+struct BinnedFeatures {
+    /// Ascending cut points per feature; bin `b` holds values in
+    /// `(cuts[b - 1], cuts[b]]`
+    cuts: Vec<Vec<f64>>,
+    /// Bin index per feature and sample
+    bins: Vec<Vec<u8>>,
+}
+
+impl BinnedFeatures {
+    fn new(rows: &[Vec<f64>], n_features: usize) -> Self {
+        let mut cuts = Vec::with_capacity(n_features);
+        let mut bins = Vec::with_capacity(n_features);
+
+        for feature in 0..n_features {
+            let mut values: Vec<f64> = rows.iter().map(|row| row[feature]).collect();
+            values.sort_by(f64::total_cmp);
+            values.dedup();
+
+            // Midpoints between distinct values, thinned to quantiles
+            let step = values.len().div_ceil(MAX_BINS).max(1);
+            let feature_cuts: Vec<f64> = values
+                .windows(2)
+                .enumerate()
+                .filter(|(i, _)| (i + 1) % step == 0)
+                .map(|(_, pair)| (pair[0] + pair[1]) / 2.0)
+                .collect();
+
+            bins.push(
+                rows.iter()
+                    .map(|row| feature_cuts.partition_point(|&cut| cut < row[feature]) as u8)
+                    .collect(),
+            );
+            cuts.push(feature_cuts);
+        }
+
+        Self { cuts, bins }
+    }
+}
+
+/// Best split found for a node
+struct Split {
+    feature: usize,
+    bin: usize,
+    gain: f64,
+}
+
+impl GradientBoostedTrees {
+    /// Fit the ensemble to `rows` (one feature vector per sample) and
+    /// `targets`.
+    pub fn fit(
+        rows: &[Vec<f64>],
+        targets: &[f64],
+        params: &BoostingParams,
+    ) -> Result<Self, TrainingError> {
+        Self::fit_weighted(rows, targets, &vec![1.0; targets.len()], params)
+    }
+
+    /// Fit the ensemble with a weight per sample.
+    ///
+    /// A sample of weight 2 counts like two copies of it in the leaf values
+    /// and split gains. `min_samples_leaf` still counts samples, not weight.
+    pub fn fit_weighted(
+        rows: &[Vec<f64>],
+        targets: &[f64],
+        weights: &[f64],
+        params: &BoostingParams,
+    ) -> Result<Self, TrainingError> {
+        if rows.is_empty() {
+            return Err(TrainingError::InsufficientData(0));
+        }
+        if rows.len() != targets.len() {
+            return Err(TrainingError::MismatchedLengths {
+                features: rows.len(),
+                targets: targets.len(),
+            });
+        }
+
+        if weights.len() != targets.len() {
+            return Err(TrainingError::ArrayError(format!(
+                "Got {} weights for {} samples",
+                weights.len(),
+                targets.len()
+            )));
+        }
+        if weights.iter().any(|w| !w.is_finite() || *w <= 0.0) {
+            return Err(TrainingError::FitError(
+                "Sample weights must be positive".to_string(),
+            ));
+        }
+
+        let n_features = rows[0].len();
+        if rows.iter().any(|row| row.len() != n_features) {
+            return Err(TrainingError::ArrayError(
+                "Feature vectors have different lengths".to_string(),
+            ));
+        }
+
+        if let Loss::Quantile(alpha) = params.loss
+            && !(0.0..=1.0).contains(&alpha)
+        {
+            return Err(TrainingError::FitError(format!(
+                "Quantile must be between 0 and 1, got {}",
+                alpha
+            )));
+        }
+
+        let binned = BinnedFeatures::new(rows, n_features);
+        let mut weighted_targets: Vec<(f64, f64)> = targets
+            .iter()
+            .copied()
+            .zip(weights.iter().copied())
+            .collect();
+        let base = params.loss.optimum(&mut weighted_targets);
+        let mut predictions = vec![base; targets.len()];
+        let mut split_gains = vec![0.0; n_features];
+        let mut trees = Vec::with_capacity(params.n_estimators);
+
+        for _ in 0..params.n_estimators {
+            let residuals: Vec<f64> = targets
+                .iter()
+                .zip(&predictions)
+                .map(|(target, prediction)| target - prediction)
+                .collect();
+            let gradients: Vec<f64> = residuals
+                .iter()
+                .map(|&residual| params.loss.gradient(residual))
+                .collect();
+
+            let mut builder = TreeBuilder {
+                binned: &binned,
+                residuals: &residuals,
+                gradients: &gradients,
+                weights,
+                params,
+                nodes: Vec::new(),
+                split_gains: &mut split_gains,
+            };
+            let mut indices: Vec<usize> = (0..rows.len()).collect();
+            builder.build(&mut indices, 0);
+            let tree = RegressionTree {
+                nodes: builder.nodes,
+            };
+
+            for (prediction, row) in predictions.iter_mut().zip(rows) {
+                *prediction += params.learning_rate * tree.predict(row);
+            }
+            trees.push(tree);
+        }
+
+        Ok(Self {
+            base,
+            learning_rate: params.learning_rate,
+            trees,
+            split_gains,
+        })
+    }
+
+    /// Predict the target for one feature vector.
+    pub fn predict(&self, row: &[f64]) -> f64 {
+        self.base
+            + self.learning_rate * self.trees.iter().map(|tree| tree.predict(row)).sum::<f64>()
+    }
+
+    /// Share of the total split gain contributed by each feature.
+    pub fn feature_importance(&self) -> Vec<f64> {
+        let total: f64 = self.split_gains.iter().sum();
+        if total <= 0.0 {
+            return vec![0.0; self.split_gains.len()];
+        }
+        self.split_gains.iter().map(|gain| gain / total).collect()
+    }
+
+    /// Number of trees in the ensemble.
+    pub fn n_trees(&self) -> usize {
+        self.trees.len()
+    }
+}
+
+impl RegressionTree {
+    fn predict(&self, row: &[f64]) -> f64 {
+        let mut index = 0;
+        loop {
+            match self.nodes[index] {
+                Node::Leaf(value) => return value,
+                Node::Split {
+                    feature,
+                    threshold,
+                    left,
+                    right,
+                } => {
+                    index = if row[feature] <= threshold {
+                        left
+                    } else {
+                        right
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Grows one tree on the current residuals
+struct TreeBuilder<'a> {
+    binned: &'a BinnedFeatures,
+    residuals: &'a [f64],
+    /// Splits are chosen to fit these negative gradients
+    gradients: &'a [f64],
+    weights: &'a [f64],
+    params: &'a BoostingParams,
+    nodes: Vec<Node>,
+    split_gains: &'a mut [f64],
+}
+
+impl TreeBuilder<'_> {
+    /// Build the subtree for `indices`, returning its node index.
+    fn build(&mut self, indices: &mut [usize], depth: usize) -> usize {
+        let sum: f64 = indices
+            .iter()
+            .map(|&i| self.weights[i] * self.gradients[i])
+            .sum();
+        let weight: f64 = indices.iter().map(|&i| self.weights[i]).sum();
+        let mut leaf_residuals: Vec<(f64, f64)> = indices
+            .iter()
+            .map(|&i| (self.residuals[i], self.weights[i]))
+            .collect();
+        let leaf = Node::Leaf(self.params.loss.optimum(&mut leaf_residuals));
+
+        let split = if depth < self.params.max_depth
+            && indices.len() >= self.params.min_samples_split.max(2)
+        {
+            self.best_split(indices, sum, weight)
+        } else {
+            None
+        };
+        let Some(split) = split else {
+            self.nodes.push(leaf);
+            return self.nodes.len() - 1;
+        };
+
+        self.split_gains[split.feature] += split.gain;
+
+        // Move the samples of the left child to the front
+        let feature_bins = &self.binned.bins[split.feature];
+        let mut mid = 0;
+        for i in 0..indices.len() {
+            if feature_bins[indices[i]] as usize <= split.bin {
+                indices.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(leaf);
+        let (left_indices, right_indices) = indices.split_at_mut(mid);
+        let left = self.build(left_indices, depth + 1);
+        let right = self.build(right_indices, depth + 1);
+        self.nodes[index] = Node::Split {
+            feature: split.feature,
+            threshold: self.binned.cuts[split.feature][split.bin],
+            left,
+            right,
+        };
+        index
+    }
+
+    /// Find the split with the largest reduction in weighted squared error.
+    ///
+    /// `sum` is the weighted sum of the gradients and `weight` the total
+    /// weight of `indices`.
+    fn best_split(&self, indices: &[usize], sum: f64, weight: f64) -> Option<Split> {
+        let n = indices.len();
+        let min_leaf = self.params.min_samples_leaf.max(1);
+        let parent_score = sum * sum / weight;
+        let mut best: Option<Split> = None;
+
+        for (feature, cuts) in self.binned.cuts.iter().enumerate() {
+            if cuts.is_empty() {
+                continue;
+            }
+
+            let mut counts = vec![0usize; cuts.len() + 1];
+            let mut sums = vec![0.0; cuts.len() + 1];
+            let mut weights = vec![0.0; cuts.len() + 1];
+            for &i in indices {
+                let bin = self.binned.bins[feature][i] as usize;
+                counts[bin] += 1;
+                sums[bin] += self.weights[i] * self.gradients[i];
+                weights[bin] += self.weights[i];
+            }
+
+            let mut left_n = 0;
+            let mut left_sum = 0.0;
+            let mut left_weight = 0.0;
+            for bin in 0..cuts.len() {
+                left_n += counts[bin];
+                left_sum += sums[bin];
+                left_weight += weights[bin];
+                let right_n = n - left_n;
+                if left_n < min_leaf || right_n < min_leaf {
+                    continue;
+                }
+
+                let right_sum = sum - left_sum;
+                let right_weight = weight - left_weight;
+                let gain = left_sum * left_sum / left_weight + right_sum * right_sum / right_weight
+                    - parent_score;
+                if gain > 1e-12 && best.as_ref().is_none_or(|b| gain > b.gain) {
+                    best = Some(Split { feature, bin, gain });
+                }
+            }
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mse(model: &GradientBoostedTrees, rows: &[Vec<f64>], targets: &[f64]) -> f64 {
+        rows.iter()
+            .zip(targets)
+            .map(|(row, target)| (model.predict(row) - target).powi(2))
+            .sum::<f64>()
+            / rows.len() as f64
+    }
+
+    #[test]
+    fn test_fit_empty_data() {
+        let result = GradientBoostedTrees::fit(&[], &[], &BoostingParams::default());
+        assert!(matches!(result, Err(TrainingError::InsufficientData(0))));
+    }
+
+    #[test]
+    fn test_fit_mismatched_lengths() {
+        let rows = vec![vec![1.0], vec![2.0]];
+        let result = GradientBoostedTrees::fit(&rows, &[1.0], &BoostingParams::default());
+        assert!(matches!(
+            result,
+            Err(TrainingError::MismatchedLengths { .. })
+        ));
+    }
+
+    #[test]
+    fn test_constant_target_predicts_constant() {
+        let rows: Vec<Vec<f64>> = (0..50).map(|i| vec![i as f64]).collect();
+        let targets = vec![42.0; 50];
+
+        let model = GradientBoostedTrees::fit(&rows, &targets, &BoostingParams::default()).unwrap();
+
+        assert!((model.predict(&[10.0]) - 42.0).abs() < 1e-9);
+        assert!(model.feature_importance().iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_fits_step_function() {
+        let rows: Vec<Vec<f64>> = (0..200).map(|i| vec![i as f64, (i % 7) as f64]).collect();
+        let targets: Vec<f64> = (0..200)
+            .map(|i| if i < 100 { 10.0 } else { 90.0 })
+            .collect();
+
+        let model = GradientBoostedTrees::fit(&rows, &targets, &BoostingParams::default()).unwrap();
+
+        assert!(mse(&model, &rows, &targets) < 1.0);
+        assert!(model.predict(&[20.0, 3.0]) < 20.0);
+        assert!(model.predict(&[180.0, 3.0]) > 80.0);
+        // Only the first feature carries signal
+        assert!(model.feature_importance()[0] > 0.9);
+        assert_eq!(model.n_trees(), 100);
+    }
+
+    #[test]
+    fn test_quantile_loss_brackets_targets() {
+        // Targets spread evenly over 0..100 regardless of the feature
+        let rows: Vec<Vec<f64>> = (0..500).map(|i| vec![(i % 5) as f64]).collect();
+        let targets: Vec<f64> = (0..500).map(|i| ((i * 37) % 100) as f64).collect();
+        let fit = |alpha| {
+            let params = BoostingParams {
+                loss: Loss::Quantile(alpha),
+                min_samples_leaf: 50,
+                ..Default::default()
+            };
+            GradientBoostedTrees::fit(&rows, &targets, &params).unwrap()
+        };
+
+        let low = fit(0.1).predict(&[2.0]);
+        let high = fit(0.9).predict(&[2.0]);
+
+        assert!((low - 10.0).abs() < 5.0, "low quantile {low}");
+        assert!((high - 90.0).abs() < 5.0, "high quantile {high}");
+    }
+
+    #[test]
+    fn test_quantile_out_of_range_is_rejected() {
+        let params = BoostingParams {
+            loss: Loss::Quantile(1.5),
+            ..Default::default()
+        };
+        let result = GradientBoostedTrees::fit(&[vec![1.0]], &[1.0], &params);
+        assert!(matches!(result, Err(TrainingError::FitError(_))));
+    }
+
+    #[test]
+    fn test_quantile_interpolates() {
+        let mut values = vec![40.0, 10.0, 30.0, 20.0];
+        assert_eq!(quantile(&mut values, 0.0), 10.0);
+        assert_eq!(quantile(&mut values, 0.5), 25.0);
+        assert_eq!(quantile(&mut values, 1.0), 40.0);
+    }
+
+    #[test]
+    fn test_weighted_quantile() {
+        let mut values = vec![(40.0, 1.0), (10.0, 1.0), (30.0, 6.0), (20.0, 2.0)];
+        assert_eq!(weighted_quantile(&mut values, 0.0), 10.0);
+        assert_eq!(weighted_quantile(&mut values, 0.5), 30.0);
+        assert_eq!(weighted_quantile(&mut values, 1.0), 40.0);
+    }
+
+    #[test]
+    fn test_weights_pull_leaf_towards_heavy_samples() {
+        // No feature signal, so the model predicts one weighted mean
+        let rows: Vec<Vec<f64>> = vec![vec![0.0]; 40];
+        let targets: Vec<f64> = (0..40).map(|i| if i < 20 { 10.0 } else { 50.0 }).collect();
+        let weights: Vec<f64> = (0..40).map(|i| if i < 20 { 1.0 } else { 3.0 }).collect();
+
+        let model = GradientBoostedTrees::fit_weighted(
+            &rows,
+            &targets,
+            &weights,
+            &BoostingParams::default(),
+        )
+        .unwrap();
+
+        assert!((model.predict(&[0.0]) - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_non_positive_weights_are_rejected() {
+        let rows = vec![vec![1.0], vec![2.0]];
+        let params = BoostingParams::default();
+
+        for weights in [[1.0, 0.0], [1.0, -1.0], [1.0, f64::NAN]] {
+            let result = GradientBoostedTrees::fit_weighted(&rows, &[1.0, 2.0], &weights, &params);
+            assert!(matches!(result, Err(TrainingError::FitError(_))));
+        }
+    }
+
+    #[test]
+    fn test_min_samples_leaf_limits_splits() {
+        let rows: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64]).collect();
+        let targets: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let params = BoostingParams {
+            n_estimators: 1,
+            learning_rate: 1.0,
+            min_samples_leaf: 10,
+            ..Default::default()
+        };
+
+        let model = GradientBoostedTrees::fit(&rows, &targets, &params).unwrap();
+
+        // No split can leave 10 samples on both sides, so the tree is a leaf
+        assert!((model.predict(&[0.0]) - 4.5).abs() < 1e-9);
+        assert!((model.predict(&[9.0]) - 4.5).abs() < 1e-9);
+    }
+}
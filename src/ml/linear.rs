@@ -0,0 +1,208 @@
+//! Linear regression by least squares
+//!
+//! Solves the normal equations of the (optionally centered) features with
+//! Gaussian elimination. A small ridge term keeps constant or collinear
+//! features from making the system singular; their coefficients end up near
+//! zero instead.
+
+use super::model::TrainingError;
+
+/// Ridge term added to the diagonal of the normal equations
+const RIDGE: f64 = 1e-6;
+
+/// A fitted linear model
+#[derive(Debug, Clone)]
+pub struct LinearRegression {
+    coefficients: Vec<f64>,
+    intercept: f64,
+}
+
+impl LinearRegression {
+    /// Fit `targets` as a weighted linear combination of `rows`, plus an
+    /// intercept if `fit_intercept` is set.
+    pub fn fit_weighted(
+        rows: &[Vec<f64>],
+        targets: &[f64],
+        weights: &[f64],
+        fit_intercept: bool,
+    ) -> Result<Self, TrainingError> {
+        if rows.is_empty() {
+            return Err(TrainingError::InsufficientData(0));
+        }
+        if rows.len() != targets.len() {
+            return Err(TrainingError::MismatchedLengths {
+                features: rows.len(),
+                targets: targets.len(),
+            });
+        }
+        if weights.len() != targets.len() {
+            return Err(TrainingError::ArrayError(format!(
+                "Got {} weights for {} samples",
+                weights.len(),
+                targets.len()
+            )));
+        }
+        if weights.iter().any(|w| !w.is_finite() || *w <= 0.0) {
+            return Err(TrainingError::FitError(
+                "Sample weights must be positive".to_string(),
+            ));
+        }
+
+        let n_features = rows[0].len();
+        if rows.iter().any(|row| row.len() != n_features) {
+            return Err(TrainingError::ArrayError(
+                "Feature vectors have different lengths".to_string(),
+            ));
+        }
+
+        // Weighted means to center on, so the intercept needs no column
+        let total_weight: f64 = weights.iter().sum();
+        let (x_mean, y_mean) = if fit_intercept {
+            let mut x_mean = vec![0.0; n_features];
+            for (row, weight) in rows.iter().zip(weights) {
+                for (mean, value) in x_mean.iter_mut().zip(row) {
+                    *mean += weight * value / total_weight;
+                }
+            }
+            let y_mean = targets
+                .iter()
+                .zip(weights)
+                .map(|(target, weight)| weight * target)
+                .sum::<f64>()
+                / total_weight;
+            (x_mean, y_mean)
+        } else {
+            (vec![0.0; n_features], 0.0)
+        };
+
+        // Normal equations (X'WX) b = X'Wy on the centered data
+        let mut gram = vec![vec![0.0; n_features]; n_features];
+        let mut moment = vec![0.0; n_features];
+        for ((row, target), weight) in rows.iter().zip(targets).zip(weights) {
+            let centered: Vec<f64> = row.iter().zip(&x_mean).map(|(x, m)| x - m).collect();
+            let y = target - y_mean;
+            for (i, gram_row) in gram.iter_mut().enumerate() {
+                moment[i] += weight * centered[i] * y;
+                for (cell, x) in gram_row.iter_mut().zip(&centered) {
+                    *cell += weight * centered[i] * x;
+                }
+            }
+        }
+        for (i, gram_row) in gram.iter_mut().enumerate() {
+            gram_row[i] += RIDGE * total_weight;
+        }
+
+        let coefficients = solve(gram, moment)?;
+        let intercept = y_mean
+            - coefficients
+                .iter()
+                .zip(&x_mean)
+                .map(|(c, m)| c * m)
+                .sum::<f64>();
+
+        Ok(Self {
+            coefficients,
+            intercept,
+        })
+    }
+
+    /// Predict the target for one feature vector.
+    pub fn predict(&self, row: &[f64]) -> f64 {
+        self.intercept
+            + self
+                .coefficients
+                .iter()
+                .zip(row)
+                .map(|(c, x)| c * x)
+                .sum::<f64>()
+    }
+
+    /// Coefficient of each feature.
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Result<Vec<f64>, TrainingError> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap_or(col);
+        if a[pivot][col].abs() < 1e-12 {
+            return Err(TrainingError::FitError(
+                "Normal equations are non-invertible".to_string(),
+            ));
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            if factor == 0.0 {
+                continue;
+            }
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovers_exact_linear_relation() {
+        let rows: Vec<Vec<f64>> = (0..50)
+            .map(|i| vec![i as f64, ((i * 7) % 11) as f64])
+            .collect();
+        let targets: Vec<f64> = rows.iter().map(|r| 3.0 + 2.0 * r[0] - 0.5 * r[1]).collect();
+
+        let model = LinearRegression::fit_weighted(&rows, &targets, &[1.0; 50], true).unwrap();
+
+        assert!((model.coefficients()[0] - 2.0).abs() < 1e-4);
+        assert!((model.coefficients()[1] + 0.5).abs() < 1e-4);
+        assert!((model.intercept() - 3.0).abs() < 1e-3);
+        assert!((model.predict(&[10.0, 4.0]) - 21.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_constant_feature_does_not_make_fit_singular() {
+        let rows: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64, 1.0]).collect();
+        let targets: Vec<f64> = (0..20).map(|i| 5.0 + i as f64).collect();
+
+        let model = LinearRegression::fit_weighted(&rows, &targets, &[1.0; 20], true).unwrap();
+
+        assert!(model.coefficients()[1].abs() < 1e-6);
+        assert!((model.predict(&[4.0, 1.0]) - 9.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_weights_pull_fit_towards_heavy_samples() {
+        // Two clusters of the same feature value with different targets
+        let rows = vec![vec![1.0]; 4];
+        let targets = [10.0, 10.0, 50.0, 50.0];
+        let weights = [1.0, 1.0, 3.0, 3.0];
+
+        let model = LinearRegression::fit_weighted(&rows, &targets, &weights, true).unwrap();
+
+        assert!((model.predict(&[1.0]) - 40.0).abs() < 1e-6);
+    }
+}
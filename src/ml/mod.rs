@@ -5,6 +5,8 @@
 
 pub mod confidence;
 pub mod features;
+pub mod gbdt;
+pub mod linear;
 pub mod model;
 pub mod persistence;
 pub mod training;
@@ -12,20 +14,22 @@ pub mod training;
 use std::collections::VecDeque;
 use std::path::PathBuf;
 
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use serde::Deserialize;
 
-use crate::db::HourlyAverage;
+use crate::db::{Database, HourlyAverage, OccupancyLog};
 use crate::schedule::GymSchedule;
 use crate::traits::Clock;
 
 pub use confidence::{PredictionMethod, PredictionWithConfidence};
 pub use features::{FeatureExtractor, PredictionFeatures};
-pub use model::TrainedModel;
+pub use model::{EvaluationMetrics, ModelKind, ModelParams, QuantileModels, TrainedModel};
 pub use persistence::PersistedModel;
 pub use training::TrainingResult;
 
-/// Configuration for the ML prediction system
-#[derive(Debug, Clone)]
+/// Configuration for the ML prediction system, read from the `[ml]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct MlConfig {
     /// Whether ML predictions are enabled
     pub enabled: bool,
@@ -41,6 +45,20 @@ pub struct MlConfig {
     pub model_path: Option<PathBuf>,
     /// Whether to fall back to simple averages if ML fails
     pub fallback_on_error: bool,
+    /// Round prediction timestamps to local hours instead of UTC hours
+    pub round_to_local_hour: bool,
+    /// Regression model to train; linear is a cheaper fallback
+    pub model_kind: ModelKind,
+    /// Quantiles (low, high) of the models trained for prediction intervals;
+    /// `None` derives intervals from historical variance instead
+    pub interval_quantiles: Option<(f64, f64)>,
+    /// Number of cross-validation folds evaluated during training (0 = off)
+    pub cv_folds: usize,
+    /// Age in days at which a training sample counts half as much as the
+    /// newest one; `None` weights all samples equally
+    pub half_life_days: Option<f64>,
+    /// Hyperparameters of the boosted trees
+    pub model_params: ModelParams,
 }
 
 impl Default for MlConfig {
@@ -53,6 +71,12 @@ impl Default for MlConfig {
             min_samples_for_training: 500,
             model_path: None,
             fallback_on_error: true,
+            round_to_local_hour: true,
+            model_kind: ModelKind::GradientBoostedTrees,
+            interval_quantiles: Some((0.1, 0.9)),
+            cv_folds: 5,
+            half_life_days: Some(28.0),
+            model_params: ModelParams::default(),
         }
     }
 }
@@ -61,6 +85,8 @@ impl Default for MlConfig {
 pub struct OccupancyPredictor {
     /// Trained ML model (if available)
     model: Option<TrainedModel>,
+    /// Models bounding the prediction interval (if trained)
+    quantile_models: Option<QuantileModels>,
     /// Feature extractor for building prediction inputs
     feature_extractor: FeatureExtractor,
     /// Recent occupancy data for momentum features
@@ -76,6 +102,7 @@ impl OccupancyPredictor {
     pub fn new(config: MlConfig) -> Self {
         Self {
             model: None,
+            quantile_models: None,
             feature_extractor: FeatureExtractor::new(),
             recent_data: VecDeque::with_capacity(180), // 3 hours at 1-min intervals
             last_training: None,
@@ -100,18 +127,52 @@ impl OccupancyPredictor {
     }
 
     /// Update the trained model
+    ///
+    /// Quantile models trained alongside the previous model are dropped.
     pub fn set_model(&mut self, model: TrainedModel, trained_at: DateTime<Utc>) {
         self.model = Some(model);
+        self.quantile_models = None;
         self.last_training = Some(trained_at);
     }
 
-    /// Add a recent occupancy observation for momentum features
+    /// Use quantile models for the prediction intervals of ML predictions
+    pub fn set_quantile_models(&mut self, models: QuantileModels) {
+        self.quantile_models = Some(models);
+    }
+
+    /// Add a recent occupancy observation for momentum and prior-week features
     pub fn add_observation(&mut self, timestamp: DateTime<Utc>, percentage: f64) {
         // Keep only the last 3 hours of data
         while self.recent_data.len() >= 180 {
             self.recent_data.pop_front();
         }
         self.recent_data.push_back((timestamp, percentage));
+        self.feature_extractor
+            .record_observation(timestamp, percentage);
+    }
+
+    /// Load the last 3 hours of observations from the database, replacing
+    /// any held in memory, so momentum features survive a restart
+    ///
+    /// Returns the number of observations loaded.
+    pub async fn warm_start_from_db(
+        &mut self,
+        db: &Database,
+        clock: &dyn Clock,
+    ) -> anyhow::Result<usize> {
+        let now = clock.now_utc();
+        let logs = db
+            .get_history_range(now - chrono::Duration::hours(3), now)
+            .await?;
+
+        self.recent_data.clear();
+        for log in &logs {
+            if let Some(timestamp) = log.datetime() {
+                self.add_observation(timestamp, log.percentage);
+            }
+        }
+
+        Ok(self.recent_data.len())
     }
 
     /// Update feature extractor with new baseline data
@@ -119,7 +180,18 @@ impl OccupancyPredictor {
         self.feature_extractor.update_historical_stats(baseline);
     }
 
+    /// Update the month-segmented baselines from raw occupancy logs
+    ///
+    /// Slots with enough readings in the target's month take precedence over
+    /// the all-year baseline, so predictions follow seasonal shifts.
+    pub fn update_monthly_baseline(&mut self, logs: &[OccupancyLog]) {
+        self.feature_extractor.update_monthly_stats(logs);
+    }
+
     /// Generate predictions for the next N hours
+    ///
+    /// Hours without an ML prediction or a baseline slot are left out rather
+    /// than guessed.
     pub fn predict(
         &self,
         baseline: &[HourlyAverage],
@@ -138,30 +210,68 @@ impl OccupancyPredictor {
                 continue;
             }
 
-            let prediction = self.predict_single(target_time, hours_ahead, baseline, schedule);
-            predictions.push(prediction);
+            if let Some(prediction) =
+                self.predict_single(target_time, hours_ahead, baseline, schedule)
+            {
+                predictions.push(prediction);
+            }
         }
 
         predictions
     }
 
-    /// Generate a single prediction for a target time
+    /// Generate a prediction for a specific future time
+    ///
+    /// Returns `None` if `target` is not in the future, the gym is closed
+    /// then, or there is no data to predict from.
+    pub fn predict_at(
+        &self,
+        target: DateTime<Utc>,
+        baseline: &[HourlyAverage],
+        schedule: &GymSchedule,
+        clock: &dyn Clock,
+    ) -> Option<PredictionWithConfidence> {
+        let now = clock.now_utc();
+        if target <= now || !schedule.is_open(&target.with_timezone(&chrono::Local)) {
+            return None;
+        }
+
+        // Round partial hours up, matching the whole-hour steps of `predict`
+        let hours_ahead = ((target - now).num_minutes() + 59) / 60;
+
+        self.predict_single(target, hours_ahead, baseline, schedule)
+    }
+
+    /// Generate predictions for the next N hours, keeping only those with a
+    /// confidence score of at least `min_confidence`
+    pub fn high_confidence_predictions(
+        &self,
+        baseline: &[HourlyAverage],
+        schedule: &GymSchedule,
+        clock: &dyn Clock,
+        min_confidence: f64,
+    ) -> Vec<PredictionWithConfidence> {
+        filter_by_confidence(self.predict(baseline, schedule, clock), min_confidence)
+    }
+
+    /// Generate a single prediction for a target time, or `None` if there is
+    /// no data for it
     fn predict_single(
         &self,
         target_time: DateTime<Utc>,
         hours_ahead: i64,
         baseline: &[HourlyAverage],
         schedule: &GymSchedule,
-    ) -> PredictionWithConfidence {
+    ) -> Option<PredictionWithConfidence> {
         // Try ML prediction first if available
-        if self.can_use_ml() {
-            if let Some(pred) = self.ml_predict(target_time, hours_ahead, baseline, schedule) {
-                return pred;
-            }
+        if self.can_use_ml()
+            && let Some(pred) = self.ml_predict(target_time, hours_ahead, baseline, schedule)
+        {
+            return Some(pred);
         }
 
         // Fall back to simple historical average
-        self.fallback_predict(target_time, baseline)
+        self.fallback_predict(target_time, baseline, schedule)
     }
 
     /// ML-based prediction
@@ -186,12 +296,18 @@ impl OccupancyPredictor {
         // Get prediction from model
         let predicted_value = model.predict(&features)?;
 
-        // Calculate confidence based on historical variance and horizon
-        let (confidence_low, confidence_high, confidence_score) =
-            self.calculate_confidence(target_time, predicted_value, hours_ahead);
+        // Take the interval from the quantile models, falling back to
+        // historical variance and horizon
+        let (confidence_low, confidence_high, confidence_score) = self
+            .quantile_models
+            .as_ref()
+            .and_then(|models| quantile_confidence(models, &features, predicted_value))
+            .unwrap_or_else(|| {
+                self.calculate_confidence(target_time, predicted_value, hours_ahead)
+            });
 
         Some(PredictionWithConfidence {
-            timestamp: normalize_timestamp(target_time),
+            timestamp: self.normalize(target_time, schedule),
             predicted_value: predicted_value.clamp(0.0, 100.0),
             confidence_low,
             confidence_high,
@@ -203,38 +319,44 @@ impl OccupancyPredictor {
     }
 
     /// Fallback prediction using simple historical average
+    ///
+    /// Returns `None` if neither a monthly nor a baseline slot matches, so no
+    /// occupancy is made up. Only the interval width has a default.
     fn fallback_predict(
         &self,
         target_time: DateTime<Utc>,
         baseline: &[HourlyAverage],
-    ) -> PredictionWithConfidence {
+        schedule: &GymSchedule,
+    ) -> Option<PredictionWithConfidence> {
         let target_weekday = target_time.weekday().num_days_from_monday();
         let target_hour = target_time.hour();
 
-        let (predicted_value, confidence_low, confidence_high) = baseline
-            .iter()
-            .find(|avg| avg.weekday == target_weekday && avg.hour == target_hour)
-            .map(|avg| {
-                let std_dev = self
-                    .feature_extractor
-                    .get_slot_std(target_weekday, target_hour)
-                    .unwrap_or(10.0);
-                (
-                    avg.avg_percentage,
-                    (avg.avg_percentage - std_dev).clamp(0.0, 100.0),
-                    (avg.avg_percentage + std_dev).clamp(0.0, 100.0),
-                )
-            })
-            .unwrap_or((50.0, 30.0, 70.0)); // Default if no data
+        let (predicted_value, std_dev) = self
+            .monthly_slot_stats(target_time)
+            .map(|stats| (stats.mean, stats.std_dev))
+            .or_else(|| {
+                baseline
+                    .iter()
+                    .find(|avg| {
+                        avg.weekday == target_weekday as i32 && avg.hour == target_hour as i32
+                    })
+                    .map(|avg| {
+                        let std_dev = self
+                            .feature_extractor
+                            .get_slot_std(target_weekday, target_hour)
+                            .unwrap_or(10.0);
+                        (avg.avg_percentage, std_dev)
+                    })
+            })?;
 
-        PredictionWithConfidence {
-            timestamp: normalize_timestamp(target_time),
+        Some(PredictionWithConfidence {
+            timestamp: self.normalize(target_time, schedule),
             predicted_value,
-            confidence_low,
-            confidence_high,
+            confidence_low: (predicted_value - std_dev).clamp(0.0, 100.0),
+            confidence_high: (predicted_value + std_dev).clamp(0.0, 100.0),
             confidence_score: 0.5, // Lower confidence for fallback
             method: PredictionMethod::HistoricalAverage,
-        }
+        })
     }
 
     /// Calculate confidence intervals for a prediction
@@ -247,10 +369,12 @@ impl OccupancyPredictor {
         let weekday = target_time.weekday().num_days_from_monday();
         let hour = target_time.hour();
 
-        // Get historical standard deviation for this slot
+        // Get historical standard deviation for this slot, preferring the
+        // target's month
         let base_std = self
-            .feature_extractor
-            .get_slot_std(weekday, hour)
+            .monthly_slot_stats(target_time)
+            .map(|stats| stats.std_dev)
+            .or_else(|| self.feature_extractor.get_slot_std(weekday, hour))
             .unwrap_or(15.0);
 
         // Increase uncertainty with prediction horizon
@@ -266,6 +390,28 @@ impl OccupancyPredictor {
         (confidence_low, confidence_high, confidence_score)
     }
 
+    /// Month-segmented statistics for the local slot of `target_time`
+    fn monthly_slot_stats(&self, target_time: DateTime<Utc>) -> Option<&features::SlotStats> {
+        let local = target_time.with_timezone(&chrono::Local);
+        self.feature_extractor.get_monthly_slot_stats(
+            local.month(),
+            local.weekday().num_days_from_monday(),
+            local.hour(),
+        )
+    }
+
+    /// Normalize a prediction timestamp according to the configuration
+    fn normalize(&self, dt: DateTime<Utc>, schedule: &GymSchedule) -> DateTime<Utc> {
+        if self.config.round_to_local_hour {
+            match schedule.timezone() {
+                Some(tz) => normalize_timestamp_in(dt, &tz),
+                None => normalize_timestamp_in(dt, &chrono::Local),
+            }
+        } else {
+            normalize_timestamp(dt)
+        }
+    }
+
     /// Get the configuration
     pub fn config(&self) -> &MlConfig {
         &self.config
@@ -282,6 +428,36 @@ impl OccupancyPredictor {
     }
 }
 
+/// Confidence interval and score from quantile model predictions
+fn quantile_confidence(
+    models: &QuantileModels,
+    features: &PredictionFeatures,
+    predicted_value: f64,
+) -> Option<(f64, f64, f64)> {
+    let (low, high) = models.predict(features)?;
+
+    // Independently trained quantiles may cross or miss the point estimate
+    let confidence_low = low.min(high).min(predicted_value).clamp(0.0, 100.0);
+    let confidence_high = low.max(high).max(predicted_value).clamp(0.0, 100.0);
+
+    // Same scale as the heuristic, with the half-width in place of the std
+    let half_width = (confidence_high - confidence_low) / 2.0;
+    let confidence_score = (1.0 / (1.0 + half_width / 20.0)).clamp(0.0, 1.0);
+
+    Some((confidence_low, confidence_high, confidence_score))
+}
+
+/// Keep only predictions with a confidence score of at least `min_confidence`
+fn filter_by_confidence(
+    predictions: Vec<PredictionWithConfidence>,
+    min_confidence: f64,
+) -> Vec<PredictionWithConfidence> {
+    predictions
+        .into_iter()
+        .filter(|p| p.confidence_score >= min_confidence)
+        .collect()
+}
+
 /// Normalize a timestamp to the start of the hour
 fn normalize_timestamp(dt: DateTime<Utc>) -> DateTime<Utc> {
     dt.with_minute(0)
@@ -290,9 +466,23 @@ fn normalize_timestamp(dt: DateTime<Utc>) -> DateTime<Utc> {
         .unwrap_or(dt)
 }
 
+/// Normalize a timestamp to the start of the hour in the given timezone
+///
+/// In zones with a non-whole-hour offset (e.g. +5:30), the UTC hour boundary
+/// falls on :30 local time, so rounding has to happen in local time.
+fn normalize_timestamp_in<Tz: TimeZone>(dt: DateTime<Utc>, tz: &Tz) -> DateTime<Utc> {
+    dt.with_timezone(tz)
+        .with_minute(0)
+        .and_then(|d| d.with_second(0))
+        .and_then(|d| d.with_nanosecond(0))
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or(dt)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ml::model::ModelBuilder;
     use crate::traits::MockClock;
     use chrono::TimeZone;
 
@@ -325,6 +515,39 @@ mod tests {
         assert_eq!(predictor.recent_data.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_warm_start_from_db_loads_recent_window() {
+        let _ = dotenvy::dotenv();
+        let Ok(db_url) = std::env::var("DATABASE_URL") else {
+            eprintln!("Skipping test: DATABASE_URL not set");
+            return;
+        };
+        let db = Database::new(&db_url)
+            .await
+            .unwrap()
+            .with_gym("test-warm-start");
+
+        // Four hours at one-minute intervals, one more than the window holds
+        let now = Utc.with_ymd_and_hms(2002, 10, 7, 14, 0, 0).unwrap();
+        let records: Vec<_> = (0..=240)
+            .map(|i| (now - chrono::Duration::minutes(240 - i), i as f64 / 4.0))
+            .collect();
+        db.insert_records(&records).await.unwrap();
+
+        let mut predictor = OccupancyPredictor::new(MlConfig::default());
+        let loaded = predictor
+            .warm_start_from_db(&db, &MockClock::new(now))
+            .await
+            .unwrap();
+
+        assert_eq!(loaded, 180);
+        assert_eq!(predictor.recent_data.len(), 180);
+        let timestamps: Vec<_> = predictor.recent_data.iter().map(|(t, _)| *t).collect();
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(timestamps[0] > now - chrono::Duration::hours(3));
+        assert_eq!(timestamps[179], now);
+    }
+
     #[test]
     fn test_normalize_timestamp() {
         let dt = Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 45).unwrap();
@@ -335,6 +558,45 @@ mod tests {
         assert_eq!(normalized.hour(), 10);
     }
 
+    #[test]
+    fn test_normalize_timestamp_half_hour_offset() {
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+
+        // 10:45 UTC is 16:15 local, which should round to 16:00 local
+        let dt = Utc.with_ymd_and_hms(2024, 6, 17, 10, 45, 0).unwrap();
+        let normalized = normalize_timestamp_in(dt, &ist);
+
+        let local = normalized.with_timezone(&ist);
+        assert_eq!(local.hour(), 16);
+        assert_eq!(local.minute(), 0);
+        // Which is 10:30 UTC, not the UTC hour boundary
+        assert_eq!(
+            normalized,
+            Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_timestamp_half_hour_offset_before_utc_boundary() {
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+
+        // 10:15 UTC is 15:45 local, which should round to 15:00 local
+        let dt = Utc.with_ymd_and_hms(2024, 6, 17, 10, 15, 0).unwrap();
+        let normalized = normalize_timestamp_in(dt, &ist);
+
+        assert_eq!(normalized.with_timezone(&ist).minute(), 0);
+        assert_eq!(
+            normalized,
+            Utc.with_ymd_and_hms(2024, 6, 17, 9, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_timestamp_utc_offset_matches_utc_rounding() {
+        let dt = Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 45).unwrap();
+        assert_eq!(normalize_timestamp_in(dt, &Utc), normalize_timestamp(dt));
+    }
+
     #[test]
     fn test_fallback_prediction() {
         let config = MlConfig::default();
@@ -348,12 +610,295 @@ mod tests {
         }];
 
         let target = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap(); // Monday
-        let pred = predictor.fallback_predict(target, &baseline);
+        let pred = predictor
+            .fallback_predict(target, &baseline, &GymSchedule::default())
+            .unwrap();
 
         assert_eq!(pred.predicted_value, 45.0);
         assert!(matches!(pred.method, PredictionMethod::HistoricalAverage));
     }
 
+    #[test]
+    fn test_prediction_timestamp_rounds_in_schedule_zone() {
+        let predictor = OccupancyPredictor::new(MlConfig::default());
+        let schedule = GymSchedule::new(&crate::config::ScheduleConfig {
+            timezone: "Asia/Kolkata".into(),
+            ..Default::default()
+        });
+        let baseline = vec![HourlyAverage {
+            weekday: 0, // Monday
+            hour: 10,
+            avg_percentage: 45.0,
+            sample_count: 100,
+        }];
+
+        // 10:45 UTC is 16:15 in Kolkata (+5:30), so the hour starts 10:30 UTC
+        let target = Utc.with_ymd_and_hms(2024, 6, 17, 10, 45, 0).unwrap();
+        let pred = predictor
+            .fallback_predict(target, &baseline, &schedule)
+            .unwrap();
+
+        assert_eq!(
+            pred.timestamp,
+            Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fallback_prediction_without_baseline_slot_is_none() {
+        let predictor = OccupancyPredictor::new(MlConfig::default());
+        let baseline = vec![HourlyAverage {
+            weekday: 0, // Monday
+            hour: 10,
+            avg_percentage: 45.0,
+            sample_count: 100,
+        }];
+
+        // Monday 14:00 has no baseline slot
+        let target = Utc.with_ymd_and_hms(2024, 6, 17, 14, 0, 0).unwrap();
+        assert!(
+            predictor
+                .fallback_predict(target, &baseline, &GymSchedule::default())
+                .is_none()
+        );
+
+        // Nor does any hour when the baseline is empty
+        let clock = MockClock::new(target);
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        assert!(predictor.predict(&[], &schedule, &clock).is_empty());
+        assert!(
+            predictor
+                .predict_at(target + chrono::Duration::hours(2), &[], &schedule, &clock)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_fallback_prediction_prefers_monthly_baseline() {
+        let mut predictor = OccupancyPredictor::new(MlConfig::default());
+        let baseline = vec![HourlyAverage {
+            weekday: 0, // Monday
+            hour: 10,
+            avg_percentage: 45.0,
+            sample_count: 100,
+        }];
+
+        // Readings from January Monday mornings only
+        let january = chrono::Local
+            .with_ymd_and_hms(2024, 1, 8, 10, 0, 0)
+            .unwrap();
+        let logs: Vec<OccupancyLog> = (0..120)
+            .map(|i| OccupancyLog {
+                id: i,
+                timestamp: (january + chrono::Duration::seconds(i * 20))
+                    .with_timezone(&Utc)
+                    .to_rfc3339(),
+                percentage: 70.0,
+            })
+            .collect();
+        predictor.update_monthly_baseline(&logs);
+
+        let in_january = (january + chrono::Duration::weeks(2)).with_timezone(&Utc);
+        let in_june = chrono::Local
+            .with_ymd_and_hms(2024, 6, 17, 10, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let predicted = |target| {
+            predictor
+                .fallback_predict(target, &baseline, &GymSchedule::default())
+                .map(|p| p.predicted_value)
+        };
+        assert_eq!(predicted(in_january), Some(70.0));
+        assert_ne!(predicted(in_june), Some(70.0));
+    }
+
+    fn prediction_with_score(hours_ahead: i64, confidence_score: f64) -> PredictionWithConfidence {
+        let base = Utc.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap();
+        PredictionWithConfidence::new(
+            base + chrono::Duration::hours(hours_ahead),
+            50.0,
+            40.0,
+            60.0,
+            confidence_score,
+            PredictionMethod::MachineLearning {
+                confidence: confidence_score,
+            },
+        )
+    }
+
+    #[test]
+    fn test_filter_by_confidence() {
+        let predictions = vec![
+            prediction_with_score(1, 0.9),
+            prediction_with_score(2, 0.4),
+            prediction_with_score(3, 0.75),
+            prediction_with_score(4, 0.2),
+        ];
+
+        let filtered = filter_by_confidence(predictions, 0.7);
+
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|p| p.confidence_score >= 0.7));
+        assert_eq!(filtered[0].confidence_score, 0.9);
+        assert_eq!(filtered[1].confidence_score, 0.75);
+    }
+
+    #[test]
+    fn test_high_confidence_predictions_filters_fallback() {
+        let predictor = OccupancyPredictor::new(MlConfig::default());
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 17, 10, 30, 0).unwrap());
+        // Every Monday hour, so no prediction is dropped for lack of data
+        let baseline: Vec<HourlyAverage> = (0..24)
+            .map(|hour| HourlyAverage {
+                weekday: 0,
+                hour,
+                avg_percentage: 40.0,
+                sample_count: 100,
+            })
+            .collect();
+
+        // Without a model every prediction is a fallback with confidence 0.5
+        let all = predictor.high_confidence_predictions(&baseline, &schedule, &clock, 0.5);
+        let strict = predictor.high_confidence_predictions(&baseline, &schedule, &clock, 0.6);
+
+        assert_eq!(
+            all.len(),
+            predictor.predict(&baseline, &schedule, &clock).len()
+        );
+        assert!(!all.is_empty());
+        assert!(strict.is_empty());
+    }
+
+    /// Predictor trained on hourly data that is calm on weekdays and noisy
+    /// on weekends
+    fn train_weekend_noise_predictor(schedule: &GymSchedule) -> OccupancyPredictor {
+        let extractor = FeatureExtractor::new();
+        let start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..(56 * 24) {
+            let time = start + chrono::Duration::hours(i);
+            let feature = extractor.extract(time, 0, &VecDeque::new(), &[], schedule);
+            let amplitude = if feature.is_weekend == 1.0 { 30.0 } else { 2.0 };
+            let noise = ((i * 7919 + 13) % 201) as f64 / 100.0 - 1.0;
+            features.push(feature);
+            targets.push(50.0 + amplitude * noise);
+        }
+
+        let builder = ModelBuilder::new();
+        let model = builder.train(&features, &targets).unwrap();
+        let quantiles = builder
+            .clone()
+            .min_samples_leaf(20)
+            .train_quantiles(&features, &targets, (0.1, 0.9))
+            .unwrap();
+
+        let mut predictor = OccupancyPredictor::new(MlConfig::default());
+        predictor.set_model(model, start);
+        predictor.set_quantile_models(quantiles);
+        predictor
+    }
+
+    #[test]
+    fn test_quantile_interval_widens_for_noisier_slots() {
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        let predictor = train_weekend_noise_predictor(&schedule);
+
+        // Wednesday and Saturday at noon
+        let weekday = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 12, 11, 0, 0).unwrap());
+        let weekend = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 15, 11, 0, 0).unwrap());
+        let calm = &predictor.predict(&[], &schedule, &weekday)[0];
+        let noisy = &predictor.predict(&[], &schedule, &weekend)[0];
+
+        assert!(calm.method.is_ml());
+        assert!(
+            noisy.interval_width() > 2.0 * calm.interval_width(),
+            "weekend {:.1} vs weekday {:.1}",
+            noisy.interval_width(),
+            calm.interval_width()
+        );
+        assert!(noisy.confidence_score < calm.confidence_score);
+    }
+
+    #[test]
+    fn test_set_model_drops_stale_quantile_models() {
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        let mut predictor = train_weekend_noise_predictor(&schedule);
+        assert!(predictor.quantile_models.is_some());
+
+        let start = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let features: Vec<_> = (0..20)
+            .map(|i| {
+                let time = start + chrono::Duration::hours(i);
+                FeatureExtractor::new().extract(time, 0, &VecDeque::new(), &[], &schedule)
+            })
+            .collect();
+        let model = ModelBuilder::new().train(&features, &[50.0; 20]).unwrap();
+        predictor.set_model(model, Utc::now());
+
+        assert!(predictor.quantile_models.is_none());
+    }
+
+    #[test]
+    fn test_predict_at_specific_future_time() {
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        let predictor = train_weekend_noise_predictor(&schedule);
+        // Thursday morning, planning a Saturday afternoon visit
+        let clock = MockClock::new(
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 13, 9, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+        );
+        let target = chrono::Local
+            .with_ymd_and_hms(2024, 6, 15, 15, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let prediction = predictor
+            .predict_at(target, &[], &schedule, &clock)
+            .unwrap();
+
+        assert!(prediction.method.is_ml());
+        assert_eq!(prediction.timestamp, target);
+        assert!((20.0..=80.0).contains(&prediction.predicted_value));
+        assert!(prediction.confidence_low <= prediction.confidence_high);
+    }
+
+    #[test]
+    fn test_predict_at_closed_or_past_time() {
+        let predictor = OccupancyPredictor::new(MlConfig::default());
+        // Weekend hours end at 14:00
+        let schedule = GymSchedule::new_for_test(6, 22, 9, 14);
+        let clock = MockClock::new(
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 13, 9, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+        );
+        let saturday_afternoon = chrono::Local
+            .with_ymd_and_hms(2024, 6, 15, 15, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let wednesday_noon = chrono::Local
+            .with_ymd_and_hms(2024, 6, 12, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert!(
+            predictor
+                .predict_at(saturday_afternoon, &[], &schedule, &clock)
+                .is_none()
+        );
+        assert!(
+            predictor
+                .predict_at(wednesday_noon, &[], &schedule, &clock)
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_config_defaults() {
         let config = MlConfig::default();
@@ -364,5 +909,39 @@ mod tests {
         assert_eq!(config.prediction_horizon_hours, 6);
         assert_eq!(config.min_samples_for_training, 500);
         assert!(config.fallback_on_error);
+        assert!(config.round_to_local_hour);
+        assert_eq!(config.model_kind, ModelKind::GradientBoostedTrees);
+        assert_eq!(config.interval_quantiles, Some((0.1, 0.9)));
+        assert_eq!(config.cv_folds, 5);
+        assert_eq!(config.half_life_days, Some(28.0));
+        assert_eq!(config.model_params, ModelParams::default());
+    }
+
+    #[test]
+    fn test_config_from_toml_ml_section() {
+        let toml = r#"
+            [ml]
+            model_kind = "gradient_boosted_trees"
+            cv_folds = 3
+            half_life_days = 14.0
+
+            [ml.model_params]
+            learning_rate = 0.05
+            max_depth = 4
+        "#;
+        let settings = config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap();
+
+        let config: MlConfig = settings.get("ml").unwrap();
+
+        assert_eq!(config.cv_folds, 3);
+        assert_eq!(config.half_life_days, Some(14.0));
+        assert_eq!(config.model_params.learning_rate, 0.05);
+        assert_eq!(config.model_params.max_depth, 4);
+        // Unset values keep their defaults
+        assert_eq!(config.model_params.n_estimators, 100);
+        assert_eq!(config.training_window_days, 56);
     }
 }
@@ -1,21 +1,101 @@
-//! ML model wrapper for Linear Regression
+//! ML model wrapper for gradient boosted trees and linear regression
 
 use chrono::{DateTime, Utc};
-use linfa::prelude::*;
-use linfa_linear::LinearRegression;
-use ndarray::{Array1, Array2};
+use serde::{Deserialize, Serialize};
 
 use super::features::PredictionFeatures;
+use super::gbdt::{BoostingParams, GradientBoostedTrees, Loss};
+use super::linear::LinearRegression;
+
+/// Kind of regression model to train
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Gradient boosted decision trees, which capture nonlinear patterns
+    /// such as morning and evening peaks
+    #[default]
+    GradientBoostedTrees,
+    /// Linear regression, cheap and kept as a fallback
+    Linear,
+}
+
+impl ModelKind {
+    /// Model type name recorded in persisted model summaries
+    pub fn name(self) -> &'static str {
+        match self {
+            ModelKind::GradientBoostedTrees => "GradientBoostedTrees",
+            ModelKind::Linear => "LinearRegression",
+        }
+    }
+}
+
+/// Tunable hyperparameters of the gradient boosted trees
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ModelParams {
+    /// Number of boosting rounds
+    pub n_estimators: usize,
+    /// Shrinkage applied to each tree's output
+    pub learning_rate: f64,
+    /// Maximum depth of each tree
+    pub max_depth: usize,
+    /// Nodes with fewer samples become leaves
+    pub min_samples_split: usize,
+    /// Minimum number of samples in each leaf; larger values regularize
+    pub min_samples_leaf: usize,
+}
+
+impl Default for ModelParams {
+    fn default() -> Self {
+        Self {
+            n_estimators: 100,
+            learning_rate: 0.1,
+            max_depth: 6,
+            min_samples_split: 5,
+            min_samples_leaf: 2,
+        }
+    }
+}
+
+/// The fitted regressor behind a trained model
+#[derive(Debug)]
+enum Regressor {
+    Linear(LinearRegression),
+    Boosted(GradientBoostedTrees),
+}
+
+impl Regressor {
+    fn predict(&self, features: &[PredictionFeatures]) -> Vec<f64> {
+        match self {
+            Regressor::Linear(model) => features
+                .iter()
+                .map(|f| model.predict(&f.to_vec()))
+                .collect(),
+            Regressor::Boosted(model) => features
+                .iter()
+                .map(|f| model.predict(&f.to_vec()))
+                .collect(),
+        }
+    }
+}
 
 /// A trained ML model for occupancy prediction
 #[derive(Debug)]
 pub struct TrainedModel {
-    /// The underlying linear regression model
-    model: linfa_linear::FittedLinearRegression<f64>,
+    /// The underlying regression model
+    model: Regressor,
     /// Training mean squared error
     pub training_mse: f64,
+    /// Training mean absolute error in percentage points
+    pub training_mae: f64,
+    /// Training coefficient of determination
+    pub training_r2: f64,
     /// Validation mean squared error (if available)
     pub validation_mse: Option<f64>,
+    /// Validation mean absolute error in percentage points (if available)
+    pub validation_mae: Option<f64>,
+    /// Validation coefficient of determination (if available)
+    pub validation_r2: Option<f64>,
     /// Number of samples used for training
     pub training_samples: usize,
     /// Timestamp when model was created
@@ -24,29 +104,55 @@ pub struct TrainedModel {
 
 impl TrainedModel {
     /// Create a new trained model
-    pub fn new(
-        model: linfa_linear::FittedLinearRegression<f64>,
-        training_mse: f64,
-        validation_mse: Option<f64>,
+    fn new(
+        model: Regressor,
+        training: EvaluationMetrics,
         training_samples: usize,
         created_at: DateTime<Utc>,
     ) -> Self {
         Self {
             model,
-            training_mse,
-            validation_mse,
+            training_mse: training.mse,
+            training_mae: training.mae,
+            training_r2: training.r2,
+            validation_mse: None,
+            validation_mae: None,
+            validation_r2: None,
             training_samples,
             created_at,
         }
     }
 
+    /// Error metrics on the training set
+    pub fn training_metrics(&self) -> EvaluationMetrics {
+        EvaluationMetrics {
+            mse: self.training_mse,
+            mae: self.training_mae,
+            r2: self.training_r2,
+        }
+    }
+
+    /// Error metrics on the validation set (if available)
+    pub fn validation_metrics(&self) -> Option<EvaluationMetrics> {
+        Some(EvaluationMetrics {
+            mse: self.validation_mse?,
+            mae: self.validation_mae?,
+            r2: self.validation_r2?,
+        })
+    }
+
+    fn set_validation_metrics(&mut self, metrics: EvaluationMetrics) {
+        self.validation_mse = Some(metrics.mse);
+        self.validation_mae = Some(metrics.mae);
+        self.validation_r2 = Some(metrics.r2);
+    }
+
     /// Predict occupancy for a single feature vector
     pub fn predict(&self, features: &PredictionFeatures) -> Option<f64> {
-        let feature_vec = features.to_vec();
-        let array = Array2::from_shape_vec((1, feature_vec.len()), feature_vec).ok()?;
-
-        let predictions = self.model.predict(&array);
-        predictions.first().copied()
+        self.model
+            .predict(std::slice::from_ref(features))
+            .first()
+            .copied()
     }
 
     /// Predict occupancy for multiple feature vectors
@@ -55,51 +161,117 @@ impl TrainedModel {
             return Vec::new();
         }
 
-        let n_samples = features.len();
-        let n_features = PredictionFeatures::NUM_FEATURES;
-
-        let flat: Vec<f64> = features.iter().flat_map(|f| f.to_vec()).collect();
+        self.model.predict(features)
+    }
 
-        match Array2::from_shape_vec((n_samples, n_features), flat) {
-            Ok(array) => self.model.predict(&array).to_vec(),
-            Err(_) => Vec::new(),
+    /// Kind of the underlying model
+    pub fn kind(&self) -> ModelKind {
+        match self.model {
+            Regressor::Linear(_) => ModelKind::Linear,
+            Regressor::Boosted(_) => ModelKind::GradientBoostedTrees,
         }
     }
 
     /// Get model information as a string
     pub fn info(&self) -> String {
         format!(
-            "TrainedModel(samples={}, train_mse={:.2}, val_mse={}, created={})",
+            "TrainedModel(kind={}, samples={}, train_mse={:.2}, train_mae={:.2}, val_mse={}, \
+             val_mae={}, created={})",
+            self.kind().name(),
             self.training_samples,
             self.training_mse,
+            self.training_mae,
             self.validation_mse
                 .map(|v| format!("{:.2}", v))
                 .unwrap_or_else(|| "N/A".to_string()),
+            self.validation_mae
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_else(|| "N/A".to_string()),
             self.created_at.format("%Y-%m-%d %H:%M")
         )
     }
 
-    /// Get the model coefficients
-    pub fn coefficients(&self) -> &Array1<f64> {
-        self.model.params()
+    /// Get the model coefficients (linear models only)
+    pub fn coefficients(&self) -> Option<&[f64]> {
+        match &self.model {
+            Regressor::Linear(model) => Some(model.coefficients()),
+            Regressor::Boosted(_) => None,
+        }
     }
 
-    /// Get the model intercept
-    pub fn intercept(&self) -> f64 {
-        self.model.intercept()
+    /// Get the model intercept (linear models only)
+    pub fn intercept(&self) -> Option<f64> {
+        match &self.model {
+            Regressor::Linear(model) => Some(model.intercept()),
+            Regressor::Boosted(_) => None,
+        }
+    }
+
+    /// Features paired with their share of the model's importance, most
+    /// important first.
+    ///
+    /// Linear models use normalized coefficient magnitudes, tree models the
+    /// share of split gain. The shares sum to 1.0 unless the model ignores
+    /// every feature.
+    pub fn feature_importance(&self) -> Vec<(&'static str, f64)> {
+        let mut importance: Vec<_> = PredictionFeatures::feature_names()
+            .into_iter()
+            .zip(self.importance_by_feature())
+            .collect();
+        importance.sort_by(|a, b| b.1.total_cmp(&a.1));
+        importance
+    }
+
+    /// Normalized importance in `PredictionFeatures` order
+    pub(super) fn importance_by_feature(&self) -> Vec<f64> {
+        match &self.model {
+            Regressor::Linear(model) => {
+                let magnitudes: Vec<f64> = model.coefficients().iter().map(|c| c.abs()).collect();
+                let total: f64 = magnitudes.iter().sum();
+                if total > 0.0 {
+                    magnitudes.iter().map(|m| m / total).collect()
+                } else {
+                    vec![0.0; magnitudes.len()]
+                }
+            }
+            Regressor::Boosted(model) => model.feature_importance(),
+        }
+    }
+}
+
+/// Lower and upper quantile models bounding a prediction interval
+#[derive(Debug)]
+pub struct QuantileModels {
+    /// Predicts the lower quantile
+    pub low: TrainedModel,
+    /// Predicts the upper quantile
+    pub high: TrainedModel,
+}
+
+impl QuantileModels {
+    /// Predict the `(low, high)` interval for a feature vector
+    pub fn predict(&self, features: &PredictionFeatures) -> Option<(f64, f64)> {
+        Some((self.low.predict(features)?, self.high.predict(features)?))
     }
 }
 
 /// Builder for training a model
+#[derive(Debug, Clone)]
 pub struct ModelBuilder {
-    /// Whether to fit intercept
+    /// Which model to train
+    kind: ModelKind,
+    /// Whether to fit intercept (linear only)
     fit_intercept: bool,
+    /// Tree hyperparameters (gradient boosting only)
+    boosting: BoostingParams,
 }
 
 impl Default for ModelBuilder {
     fn default() -> Self {
         Self {
+            kind: ModelKind::default(),
             fit_intercept: true,
+            boosting: BoostingParams::default(),
         }
     }
 }
@@ -110,32 +282,72 @@ impl ModelBuilder {
         Self::default()
     }
 
-    /// Set whether to fit intercept
+    /// Set which model to train
+    pub fn kind(mut self, kind: ModelKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set whether to fit intercept (ignored for trees)
     pub fn fit_intercept(mut self, fit: bool) -> Self {
         self.fit_intercept = fit;
         self
     }
 
-    /// Provided for API compatibility - ignored for linear regression
-    pub fn max_depth(self, _depth: usize) -> Self {
+    /// Set the number of boosting rounds (ignored for linear regression)
+    pub fn n_estimators(mut self, n: usize) -> Self {
+        self.boosting.n_estimators = n;
+        self
+    }
+
+    /// Set the boosting learning rate (ignored for linear regression)
+    pub fn learning_rate(mut self, rate: f64) -> Self {
+        self.boosting.learning_rate = rate;
+        self
+    }
+
+    /// Set the maximum tree depth (ignored for linear regression)
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.boosting.max_depth = depth;
         self
     }
 
-    /// Provided for API compatibility - ignored for linear regression
-    pub fn min_samples_split(self, _samples: usize) -> Self {
+    /// Set the minimum samples to split a node (ignored for linear regression)
+    pub fn min_samples_split(mut self, samples: usize) -> Self {
+        self.boosting.min_samples_split = samples;
         self
     }
 
-    /// Provided for API compatibility - ignored for linear regression
-    pub fn min_samples_leaf(self, _samples: usize) -> Self {
+    /// Set the minimum samples per leaf (ignored for linear regression)
+    pub fn min_samples_leaf(mut self, samples: usize) -> Self {
+        self.boosting.min_samples_leaf = samples;
         self
     }
 
+    /// Set all tree hyperparameters at once (ignored for linear regression)
+    pub fn params(self, params: &ModelParams) -> Self {
+        self.n_estimators(params.n_estimators)
+            .learning_rate(params.learning_rate)
+            .max_depth(params.max_depth)
+            .min_samples_split(params.min_samples_split)
+            .min_samples_leaf(params.min_samples_leaf)
+    }
+
     /// Train a model on the provided data
     pub fn train(
         &self,
         features: &[PredictionFeatures],
         targets: &[f64],
+    ) -> Result<TrainedModel, TrainingError> {
+        self.train_weighted(features, targets, &vec![1.0; targets.len()])
+    }
+
+    /// Train a model with a positive weight per sample
+    pub fn train_weighted(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        weights: &[f64],
     ) -> Result<TrainedModel, TrainingError> {
         if features.is_empty() || targets.is_empty() {
             return Err(TrainingError::InsufficientData(0));
@@ -148,31 +360,79 @@ impl ModelBuilder {
             });
         }
 
-        let n_samples = features.len();
-        let n_features = PredictionFeatures::NUM_FEATURES;
+        if weights.len() != targets.len() {
+            return Err(TrainingError::ArrayError(format!(
+                "Got {} weights for {} samples",
+                weights.len(),
+                targets.len()
+            )));
+        }
 
-        // Convert to ndarray
-        let flat_features: Vec<f64> = features.iter().flat_map(|f| f.to_vec()).collect();
+        let n_samples = features.len();
+        let model = match self.kind {
+            ModelKind::GradientBoostedTrees => self.fit_boosted(features, targets, weights)?,
+            ModelKind::Linear => self.fit_linear(features, targets, weights)?,
+        };
 
-        let x = Array2::from_shape_vec((n_samples, n_features), flat_features)
-            .map_err(|e| TrainingError::ArrayError(e.to_string()))?;
+        // Evaluate on the training set
+        let predictions = model.predict(features);
+        let metrics = EvaluationMetrics::evaluate(&predictions, targets);
 
-        let y = Array1::from_vec(targets.to_vec());
+        Ok(TrainedModel::new(model, metrics, n_samples, Utc::now()))
+    }
 
-        // Create dataset
-        let dataset = Dataset::new(x, y);
+    fn fit_boosted(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        weights: &[f64],
+    ) -> Result<Regressor, TrainingError> {
+        let rows: Vec<Vec<f64>> = features.iter().map(|f| f.to_vec()).collect();
+        let model = GradientBoostedTrees::fit_weighted(&rows, targets, weights, &self.boosting)?;
+        Ok(Regressor::Boosted(model))
+    }
 
-        // Configure and train linear regression
-        let model = LinearRegression::default()
-            .with_intercept(self.fit_intercept)
-            .fit(&dataset)
-            .map_err(|e: linfa_linear::LinearError<f64>| TrainingError::FitError(e.to_string()))?;
+    fn fit_linear(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        weights: &[f64],
+    ) -> Result<Regressor, TrainingError> {
+        let rows: Vec<Vec<f64>> = features.iter().map(|f| f.to_vec()).collect();
+        let model = LinearRegression::fit_weighted(&rows, targets, weights, self.fit_intercept)?;
+        Ok(Regressor::Linear(model))
+    }
 
-        // Calculate training MSE
-        let predictions = model.predict(dataset.records());
-        let mse = calculate_mse(&predictions.to_vec(), targets);
+    /// Train gradient boosted trees predicting the `alpha` quantile (0-1)
+    /// instead of the mean
+    pub fn train_quantile(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        alpha: f64,
+    ) -> Result<TrainedModel, TrainingError> {
+        let builder = ModelBuilder {
+            kind: ModelKind::GradientBoostedTrees,
+            fit_intercept: self.fit_intercept,
+            boosting: BoostingParams {
+                loss: Loss::Quantile(alpha),
+                ..self.boosting
+            },
+        };
+        builder.train(features, targets)
+    }
 
-        Ok(TrainedModel::new(model, mse, None, n_samples, Utc::now()))
+    /// Train lower and upper quantile models for prediction intervals
+    pub fn train_quantiles(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        (low, high): (f64, f64),
+    ) -> Result<QuantileModels, TrainingError> {
+        Ok(QuantileModels {
+            low: self.train_quantile(features, targets, low)?,
+            high: self.train_quantile(features, targets, high)?,
+        })
     }
 
     /// Train with validation split
@@ -181,6 +441,24 @@ impl ModelBuilder {
         features: &[PredictionFeatures],
         targets: &[f64],
         validation_split: f64,
+    ) -> Result<TrainedModel, TrainingError> {
+        self.train_with_validation_weighted(
+            features,
+            targets,
+            &vec![1.0; targets.len()],
+            validation_split,
+        )
+    }
+
+    /// Train with validation split and a weight per sample
+    ///
+    /// The validation metrics stay unweighted.
+    pub fn train_with_validation_weighted(
+        &self,
+        features: &[PredictionFeatures],
+        targets: &[f64],
+        weights: &[f64],
+        validation_split: f64,
     ) -> Result<TrainedModel, TrainingError> {
         if features.len() < 10 {
             return Err(TrainingError::InsufficientData(features.len()));
@@ -194,17 +472,43 @@ impl ModelBuilder {
         let val_targets = &targets[split_idx..];
 
         // Train on training set
-        let mut model = self.train(train_features, train_targets)?;
+        let mut model = self.train_weighted(
+            train_features,
+            train_targets,
+            // Too few weights fail the length check in `train_weighted`
+            weights.get(..split_idx).unwrap_or(weights),
+        )?;
 
         // Evaluate on validation set
         let val_predictions = model.predict_batch(val_features);
-        let val_mse = calculate_mse(&val_predictions, val_targets);
-        model.validation_mse = Some(val_mse);
+        model.set_validation_metrics(EvaluationMetrics::evaluate(&val_predictions, val_targets));
 
         Ok(model)
     }
 }
 
+/// Error metrics of a model on one data set
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EvaluationMetrics {
+    /// Mean squared error
+    pub mse: f64,
+    /// Mean absolute error in percentage points
+    pub mae: f64,
+    /// Coefficient of determination (1.0 = perfect fit)
+    pub r2: f64,
+}
+
+impl EvaluationMetrics {
+    /// Compare predictions against targets
+    pub fn evaluate(predictions: &[f64], targets: &[f64]) -> Self {
+        Self {
+            mse: calculate_mse(predictions, targets),
+            mae: calculate_mae(predictions, targets),
+            r2: calculate_r2(predictions, targets),
+        }
+    }
+}
+
 /// Calculate mean squared error
 fn calculate_mse(predictions: &[f64], targets: &[f64]) -> f64 {
     if predictions.is_empty() || predictions.len() != targets.len() {
@@ -220,6 +524,43 @@ fn calculate_mse(predictions: &[f64], targets: &[f64]) -> f64 {
     sum_sq_error / predictions.len() as f64
 }
 
+/// Calculate mean absolute error
+fn calculate_mae(predictions: &[f64], targets: &[f64]) -> f64 {
+    if predictions.is_empty() || predictions.len() != targets.len() {
+        return f64::MAX;
+    }
+
+    let sum_abs_error: f64 = predictions
+        .iter()
+        .zip(targets.iter())
+        .map(|(p, t)| (p - t).abs())
+        .sum();
+
+    sum_abs_error / predictions.len() as f64
+}
+
+/// Calculate the coefficient of determination
+///
+/// Constant targets give 1.0 for an exact fit and 0.0 otherwise.
+fn calculate_r2(predictions: &[f64], targets: &[f64]) -> f64 {
+    if predictions.is_empty() || predictions.len() != targets.len() {
+        return f64::MIN;
+    }
+
+    let mean = targets.iter().sum::<f64>() / targets.len() as f64;
+    let ss_res: f64 = predictions
+        .iter()
+        .zip(targets.iter())
+        .map(|(p, t)| (t - p).powi(2))
+        .sum();
+    let ss_tot: f64 = targets.iter().map(|t| (t - mean).powi(2)).sum();
+
+    if ss_tot == 0.0 {
+        return if ss_res == 0.0 { 1.0 } else { 0.0 };
+    }
+    1.0 - ss_res / ss_tot
+}
+
 /// Errors that can occur during model training
 #[derive(Debug, Clone)]
 pub enum TrainingError {
@@ -280,10 +621,15 @@ mod tests {
                     recent_trend: -10.0 + (t % 20.0),
                     day_avg_so_far: 30.0 + (t % 45.0),
                     prev_day_avg: 45.0 + ((t * 0.7) % 25.0),
+                    same_slot_last_week: 40.0 + ((t * 1.7) % 20.0),
+                    same_slot_two_weeks_ago: 42.0 + ((t * 2.3) % 18.0),
                     is_weekend: if (i % 7) >= 5 { 1.0 } else { 0.0 },
                     is_holiday: if i % 30 == 0 { 1.0 } else { 0.0 },
                     week_of_year_sin: (t * 0.02).sin() + noise1,
                     week_of_year_cos: (t * 0.021).cos() + noise2,
+                    month_sin: (t * 0.005).sin() + noise1,
+                    month_cos: (t * 0.0051).cos() + noise2,
+                    is_january: if (i / 30) % 12 == 0 { 1.0 } else { 0.0 },
                     hours_ahead: 1.0 + (i % 6) as f64,
                 }
             })
@@ -294,6 +640,7 @@ mod tests {
     fn test_model_builder_default() {
         let builder = ModelBuilder::default();
         assert!(builder.fit_intercept);
+        assert_eq!(builder.kind, ModelKind::GradientBoostedTrees);
     }
 
     #[test]
@@ -303,6 +650,32 @@ mod tests {
         assert!(!builder.fit_intercept);
     }
 
+    #[test]
+    fn test_different_params_fit_different_models() {
+        let features = create_test_features(200);
+        let targets: Vec<f64> = features.iter().map(|f| f.historical_avg).collect();
+        let shallow = ModelParams {
+            n_estimators: 5,
+            max_depth: 1,
+            ..Default::default()
+        };
+
+        let default_model = ModelBuilder::new()
+            .params(&ModelParams::default())
+            .train(&features, &targets)
+            .unwrap();
+        let shallow_model = ModelBuilder::new()
+            .params(&shallow)
+            .train(&features, &targets)
+            .unwrap();
+
+        assert_ne!(
+            default_model.predict_batch(&features),
+            shallow_model.predict_batch(&features)
+        );
+        assert!(shallow_model.training_mse > default_model.training_mse);
+    }
+
     #[test]
     fn test_train_empty_data() {
         let builder = ModelBuilder::new();
@@ -364,7 +737,7 @@ mod tests {
         let prediction = model.predict(test_feature);
 
         assert!(prediction.is_some());
-        // Linear regression might predict outside 0-100 range
+        // The model might predict outside 0-100 range
         // That's OK, we clamp in the predictor
     }
 
@@ -393,6 +766,48 @@ mod tests {
         assert!((mse - 4.0).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_evaluation_metrics() {
+        let predictions = vec![10.0, 20.0, 30.0];
+        let targets = vec![12.0, 18.0, 32.0];
+
+        let metrics = EvaluationMetrics::evaluate(&predictions, &targets);
+
+        assert!((metrics.mse - 4.0).abs() < 1e-10);
+        assert!((metrics.mae - 2.0).abs() < 1e-10);
+        // ss_res = 4 + 4 + 4 = 12
+        let mean: f64 = 62.0 / 3.0;
+        let ss_tot: f64 = targets.iter().map(|t| (t - mean).powi(2)).sum();
+        assert!((metrics.r2 - (1.0 - 12.0 / ss_tot)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_perfect_fit_metrics() {
+        let targets = vec![15.0, 40.0, 85.0, 60.0];
+
+        let metrics = EvaluationMetrics::evaluate(&targets, &targets);
+
+        assert_eq!(metrics.mse, 0.0);
+        assert_eq!(metrics.mae, 0.0);
+        assert_eq!(metrics.r2, 1.0);
+    }
+
+    #[test]
+    fn test_perfect_fit_model_reports_zero_mae() {
+        let features = create_test_features(100);
+        let targets = vec![55.0; 100];
+
+        let model = ModelBuilder::new()
+            .train_with_validation(&features, &targets, 0.2)
+            .unwrap();
+
+        assert_eq!(model.training_mae, 0.0);
+        assert_eq!(model.training_r2, 1.0);
+        let validation = model.validation_metrics().unwrap();
+        assert_eq!(validation.mae, 0.0);
+        assert_eq!(validation.r2, 1.0);
+    }
+
     #[test]
     fn test_model_info() {
         let features = create_test_features(50);
@@ -411,10 +826,91 @@ mod tests {
         let features = create_test_features(100);
         let targets: Vec<f64> = features.iter().map(|f| f.historical_avg).collect();
 
-        let builder = ModelBuilder::new();
+        let builder = ModelBuilder::new().kind(ModelKind::Linear);
         let model = builder.train(&features, &targets).unwrap();
 
-        let coeffs = model.coefficients();
+        let coeffs = model.coefficients().expect("Linear model has coefficients");
         assert_eq!(coeffs.len(), PredictionFeatures::NUM_FEATURES);
     }
+
+    #[test]
+    fn test_train_quantiles_bracket_mean_model() {
+        let features = create_test_features(300);
+        // Spread that no feature explains
+        let targets: Vec<f64> = (0..300)
+            .map(|i| 30.0 + ((i * 7919 + 13) % 201) as f64 / 5.0)
+            .collect();
+
+        let builder = ModelBuilder::new().min_samples_leaf(30);
+        let mean = builder.train(&features, &targets).unwrap();
+        let quantiles = builder
+            .train_quantiles(&features, &targets, (0.1, 0.9))
+            .unwrap();
+
+        let sample = &features[150];
+        let (low, high) = quantiles.predict(sample).unwrap();
+        let predicted = mean.predict(sample).unwrap();
+        assert!(
+            low < predicted && predicted < high,
+            "{low} {predicted} {high}"
+        );
+        assert_eq!(quantiles.low.kind(), ModelKind::GradientBoostedTrees);
+    }
+
+    #[test]
+    fn test_feature_importance_ranks_only_signal_first() {
+        // Only historical_avg drives the target
+        let features = create_test_features(200);
+        let targets: Vec<f64> = features
+            .iter()
+            .map(|f| 1.5 * f.historical_avg + 10.0)
+            .collect();
+
+        for kind in [ModelKind::GradientBoostedTrees, ModelKind::Linear] {
+            let model = ModelBuilder::new()
+                .kind(kind)
+                .train(&features, &targets)
+                .unwrap();
+            let importance = model.feature_importance();
+
+            assert_eq!(importance.len(), PredictionFeatures::NUM_FEATURES);
+            assert_eq!(
+                importance[0].0, "historical_avg",
+                "{kind:?}: {importance:?}"
+            );
+            assert!(importance.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+            let total: f64 = importance.iter().map(|(_, share)| share).sum();
+            assert!((total - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_trees_beat_linear_on_nonlinear_target() {
+        // Double peak over recent_trend, which no linear fit can follow
+        let features = create_test_features(600);
+        let targets: Vec<f64> = features
+            .iter()
+            .map(|f| {
+                let x = f.recent_trend;
+                20.0 + 60.0 * (-(x - 5.0).powi(2) / 8.0).exp()
+                    + 60.0 * (-(x + 5.0).powi(2) / 8.0).exp()
+            })
+            .collect();
+
+        let linear = ModelBuilder::new()
+            .kind(ModelKind::Linear)
+            .train_with_validation(&features, &targets, 0.2)
+            .unwrap();
+        let trees = ModelBuilder::new()
+            .kind(ModelKind::GradientBoostedTrees)
+            .train_with_validation(&features, &targets, 0.2)
+            .unwrap();
+
+        let linear_mse = linear.validation_mse.unwrap();
+        let trees_mse = trees.validation_mse.unwrap();
+        assert!(
+            trees_mse < linear_mse / 2.0,
+            "trees MSE {trees_mse:.2} should beat linear MSE {linear_mse:.2}"
+        );
+    }
 }
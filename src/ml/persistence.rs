@@ -6,23 +6,28 @@ use std::path::Path;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::features::SlotStats;
+use super::features::{PredictionFeatures, SlotStats};
+use super::model::EvaluationMetrics;
 
 /// Serializable model metadata and statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistedModel {
     /// Version for backward compatibility
     pub version: u32,
+    /// Feature layout version the model was trained with
+    pub feature_version: u32,
+    /// Number of features the model was trained with
+    pub num_features: usize,
     /// When the model was trained
     pub created_at: DateTime<Utc>,
     /// Number of days of data used for training
     pub training_window_days: i64,
     /// Number of samples used for training
     pub training_samples: usize,
-    /// Training MSE
-    pub training_mse: f64,
-    /// Validation MSE (if available)
-    pub validation_mse: Option<f64>,
+    /// Error metrics on the training set
+    pub training_metrics: EvaluationMetrics,
+    /// Error metrics on the validation set (if available)
+    pub validation_metrics: Option<EvaluationMetrics>,
     /// Historical statistics for each (weekday, hour) slot
     pub slot_stats: Vec<SerializedSlotStats>,
     /// Serialized model weights/parameters
@@ -66,24 +71,26 @@ pub struct ModelSummary {
 
 impl PersistedModel {
     /// Current version number
-    pub const CURRENT_VERSION: u32 = 1;
+    pub const CURRENT_VERSION: u32 = 2;
 
-    /// Create a new persisted model record
+    /// Create a new persisted model record for the current feature layout
     pub fn new(
         training_window_days: i64,
         training_samples: usize,
-        training_mse: f64,
-        validation_mse: Option<f64>,
+        training_metrics: EvaluationMetrics,
+        validation_metrics: Option<EvaluationMetrics>,
         slot_stats: Vec<SerializedSlotStats>,
         model_summary: ModelSummary,
     ) -> Self {
         Self {
             version: Self::CURRENT_VERSION,
+            feature_version: PredictionFeatures::FEATURE_VERSION,
+            num_features: PredictionFeatures::NUM_FEATURES,
             created_at: Utc::now(),
             training_window_days,
             training_samples,
-            training_mse,
-            validation_mse,
+            training_metrics,
+            validation_metrics,
             slot_stats,
             model_summary,
         }
@@ -125,9 +132,26 @@ impl PersistedModel {
             });
         }
 
+        model.check_features()?;
+
         Ok(model)
     }
 
+    /// Check that the model was trained on the current feature layout
+    pub fn check_features(&self) -> Result<(), PersistenceError> {
+        if self.feature_version != PredictionFeatures::FEATURE_VERSION
+            || self.num_features != PredictionFeatures::NUM_FEATURES
+        {
+            return Err(PersistenceError::FeatureMismatch {
+                expected_version: PredictionFeatures::FEATURE_VERSION,
+                found_version: self.feature_version,
+                expected_features: PredictionFeatures::NUM_FEATURES,
+                found_features: self.num_features,
+            });
+        }
+        Ok(())
+    }
+
     /// Check if the persisted model is stale
     pub fn is_stale(&self, max_age_hours: i64) -> bool {
         let age = Utc::now() - self.created_at;
@@ -140,9 +164,9 @@ impl PersistedModel {
             "Model v{}: {} samples, train_mse={:.2}, val_mse={}, created {}",
             self.version,
             self.training_samples,
-            self.training_mse,
-            self.validation_mse
-                .map(|v| format!("{:.2}", v))
+            self.training_metrics.mse,
+            self.validation_metrics
+                .map(|v| format!("{:.2}", v.mse))
                 .unwrap_or_else(|| "N/A".to_string()),
             self.created_at.format("%Y-%m-%d %H:%M UTC")
         )
@@ -162,6 +186,13 @@ pub enum PersistenceError {
     DeserializeError(String),
     /// Version mismatch
     VersionMismatch { expected: u32, found: u32 },
+    /// Model trained on a different feature layout
+    FeatureMismatch {
+        expected_version: u32,
+        found_version: u32,
+        expected_features: usize,
+        found_features: usize,
+    },
 }
 
 impl std::fmt::Display for PersistenceError {
@@ -178,6 +209,19 @@ impl std::fmt::Display for PersistenceError {
                     expected, found
                 )
             }
+            PersistenceError::FeatureMismatch {
+                expected_version,
+                found_version,
+                expected_features,
+                found_features,
+            } => {
+                write!(
+                    f,
+                    "Model was trained on feature layout v{} with {} features, but this build \
+                     uses v{} with {} features; retrain the model",
+                    found_version, found_features, expected_version, expected_features
+                )
+            }
         }
     }
 }
@@ -193,8 +237,16 @@ mod tests {
         PersistedModel::new(
             28,
             1000,
-            5.5,
-            Some(6.2),
+            EvaluationMetrics {
+                mse: 5.5,
+                mae: 1.8,
+                r2: 0.9,
+            },
+            Some(EvaluationMetrics {
+                mse: 6.2,
+                mae: 2.0,
+                r2: 0.85,
+            }),
             vec![
                 SerializedSlotStats {
                     weekday: 0,
@@ -225,8 +277,9 @@ mod tests {
 
         assert_eq!(model.version, PersistedModel::CURRENT_VERSION);
         assert_eq!(model.training_samples, 1000);
-        assert_eq!(model.training_mse, 5.5);
-        assert_eq!(model.validation_mse, Some(6.2));
+        assert_eq!(model.training_metrics.mse, 5.5);
+        assert_eq!(model.validation_metrics.map(|m| m.mse), Some(6.2));
+        assert_eq!(model.num_features, PredictionFeatures::NUM_FEATURES);
         assert_eq!(model.slot_stats.len(), 2);
     }
 
@@ -241,11 +294,49 @@ mod tests {
         let loaded = PersistedModel::load(&path).unwrap();
 
         assert_eq!(loaded.version, model.version);
+        assert_eq!(loaded.feature_version, model.feature_version);
+        assert_eq!(loaded.num_features, model.num_features);
+        assert_eq!(loaded.created_at, model.created_at);
         assert_eq!(loaded.training_samples, model.training_samples);
-        assert_eq!(loaded.training_mse, model.training_mse);
+        assert_eq!(loaded.training_metrics, model.training_metrics);
+        assert_eq!(loaded.validation_metrics, model.validation_metrics);
         assert_eq!(loaded.slot_stats.len(), model.slot_stats.len());
     }
 
+    #[test]
+    fn test_load_rejects_mismatched_feature_count() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+
+        let mut model = create_test_model();
+        model.num_features = PredictionFeatures::NUM_FEATURES - 2;
+        model.save(&path).unwrap();
+
+        let result = PersistedModel::load(&path);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::FeatureMismatch { found_features, .. })
+                if found_features == PredictionFeatures::NUM_FEATURES - 2
+        ));
+        assert!(result.unwrap_err().to_string().contains("retrain"));
+    }
+
+    #[test]
+    fn test_load_rejects_older_feature_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+
+        let mut model = create_test_model();
+        model.feature_version = PredictionFeatures::FEATURE_VERSION - 1;
+        model.save(&path).unwrap();
+
+        assert!(matches!(
+            PersistedModel::load(&path),
+            Err(PersistenceError::FeatureMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_load_nonexistent() {
         let path = Path::new("/nonexistent/path/model.bin");
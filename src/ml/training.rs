@@ -9,7 +9,10 @@ use crate::schedule::GymSchedule;
 use crate::traits::Clock;
 
 use super::features::{FeatureExtractor, PredictionFeatures};
-use super::model::{ModelBuilder, TrainedModel, TrainingError};
+use super::model::{
+    EvaluationMetrics, ModelBuilder, ModelKind, ModelParams, QuantileModels, TrainedModel,
+    TrainingError,
+};
 use super::persistence::{ModelSummary, PersistedModel, SerializedSlotStats};
 use super::MlConfig;
 
@@ -18,12 +21,155 @@ use super::MlConfig;
 pub struct TrainingResult {
     /// The trained model
     pub model: TrainedModel,
+    /// Error metrics on the training set
+    pub training_metrics: EvaluationMetrics,
+    /// Error metrics on the validation set
+    pub validation_metrics: Option<EvaluationMetrics>,
+    /// Models bounding the prediction interval, if configured
+    pub quantile_models: Option<QuantileModels>,
+    /// Mean validation MSE across cross-validation folds, if configured
+    pub cv_mse_mean: Option<f64>,
+    /// Standard deviation of the validation MSE across folds
+    pub cv_mse_std: Option<f64>,
     /// Feature extractor with updated stats
     pub feature_extractor: FeatureExtractor,
     /// Persisted model metadata (for saving)
     pub persisted: PersistedModel,
 }
 
+/// Quantile models need larger leaves than the mean model, or they end up
+/// fitting individual observations and report too narrow an interval
+const QUANTILE_MIN_SAMPLES_LEAF: usize = 20;
+
+/// Train the interval models configured by `interval_quantiles`
+fn train_quantile_models(
+    builder: &ModelBuilder,
+    features: &[PredictionFeatures],
+    targets: &[f64],
+    config: &MlConfig,
+) -> Result<Option<QuantileModels>, TrainingError> {
+    let Some(quantiles) = config.interval_quantiles else {
+        return Ok(None);
+    };
+    let min_leaf = QUANTILE_MIN_SAMPLES_LEAF.min(features.len() / 2).max(1);
+    builder
+        .clone()
+        .min_samples_leaf(min_leaf)
+        .train_quantiles(features, targets, quantiles)
+        .map(Some)
+}
+
+/// Validation MSE of each of `k` folds, training on the remaining folds
+///
+/// Folds are contiguous blocks, so with time-ordered samples each fold is
+/// validated on a period the model did not see.
+pub fn cross_validate(
+    builder: &ModelBuilder,
+    features: &[PredictionFeatures],
+    targets: &[f64],
+    k: usize,
+) -> Result<Vec<f64>, TrainingError> {
+    if features.len() != targets.len() {
+        return Err(TrainingError::MismatchedLengths {
+            features: features.len(),
+            targets: targets.len(),
+        });
+    }
+    if k < 2 {
+        return Err(TrainingError::FitError(format!(
+            "Cross-validation needs at least 2 folds, got {}",
+            k
+        )));
+    }
+    if features.len() < 2 * k {
+        return Err(TrainingError::InsufficientData(features.len()));
+    }
+
+    let n = features.len();
+    (0..k)
+        .map(|fold| {
+            let start = fold * n / k;
+            let end = (fold + 1) * n / k;
+
+            let train_features: Vec<PredictionFeatures> = features[..start]
+                .iter()
+                .chain(&features[end..])
+                .cloned()
+                .collect();
+            let train_targets: Vec<f64> = targets[..start]
+                .iter()
+                .chain(&targets[end..])
+                .copied()
+                .collect();
+
+            let model = builder.train(&train_features, &train_targets)?;
+            let predictions = model.predict_batch(&features[start..end]);
+            Ok(EvaluationMetrics::evaluate(&predictions, &targets[start..end]).mse)
+        })
+        .collect()
+}
+
+/// Mean and standard deviation of the fold MSEs configured by `cv_folds`
+fn cross_validation_summary(
+    builder: &ModelBuilder,
+    features: &[PredictionFeatures],
+    targets: &[f64],
+    config: &MlConfig,
+) -> Result<(Option<f64>, Option<f64>), TrainingError> {
+    if config.cv_folds < 2 {
+        return Ok((None, None));
+    }
+
+    let fold_mse = cross_validate(builder, features, targets, config.cv_folds)?;
+    let (mean, std_dev) = mean_and_std(&fold_mse);
+    Ok((Some(mean), Some(std_dev)))
+}
+
+/// Exponential time-decay weight of each sample, 1.0 for the newest one and
+/// halving every `half_life_days` before it
+pub fn decay_weights(timestamps: &[DateTime<Utc>], half_life_days: f64) -> Vec<f64> {
+    let Some(newest) = timestamps.iter().max() else {
+        return Vec::new();
+    };
+    timestamps
+        .iter()
+        .map(|timestamp| {
+            let age_days = (*newest - *timestamp).num_seconds() as f64 / 86_400.0;
+            // Keep very old samples above zero, which the fit rejects
+            0.5f64
+                .powf(age_days / half_life_days)
+                .max(f64::MIN_POSITIVE)
+        })
+        .collect()
+}
+
+/// Sample weights for the logs `TrainingDataPreparer::prepare` turns into
+/// samples, decayed by `half_life_days` if configured
+fn sample_weights(logs: &[OccupancyLog], config: &MlConfig) -> Vec<f64> {
+    let timestamps: Vec<DateTime<Utc>> = logs.iter().filter_map(|log| log.datetime()).collect();
+    match config.half_life_days {
+        Some(half_life_days) if half_life_days > 0.0 => decay_weights(&timestamps, half_life_days),
+        _ => vec![1.0; timestamps.len()],
+    }
+}
+
+/// Mean and population standard deviation
+fn mean_and_std(values: &[f64]) -> (f64, f64) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    (mean, variance.sqrt())
+}
+
+/// Summarize a trained model for persistence
+fn model_summary(model: &TrainedModel, params: &ModelParams) -> ModelSummary {
+    let kind = model.kind();
+    ModelSummary {
+        model_type: kind.name().to_string(),
+        max_depth: (kind == ModelKind::GradientBoostedTrees).then_some(params.max_depth),
+        feature_importance: Some(model.importance_by_feature()),
+    }
+}
+
 /// Prepare training data from database records
 pub struct TrainingDataPreparer {
     config: MlConfig,
@@ -48,6 +194,7 @@ impl TrainingDataPreparer {
 
         let mut feature_extractor = FeatureExtractor::new();
         feature_extractor.update_historical_stats(baseline);
+        feature_extractor.update_monthly_stats(logs);
 
         let mut features = Vec::with_capacity(logs.len());
         let mut targets = Vec::with_capacity(logs.len());
@@ -65,6 +212,7 @@ impl TrainingDataPreparer {
                 recent_window.pop_front();
             }
             recent_window.push_back((timestamp, log.percentage));
+            feature_extractor.record_observation(timestamp, log.percentage);
 
             // Extract features for this record
             // We use hours_ahead=0 for training data (actual observation)
@@ -112,22 +260,30 @@ pub async fn train_model<C: Clock>(
     // Prepare training data
     let preparer = TrainingDataPreparer::new(config.clone());
     let (features, targets) = preparer.prepare(&logs, &baseline, schedule)?;
+    let weights = sample_weights(&logs, config);
 
     // Train model with validation
-    let builder = ModelBuilder::new().max_depth(10).min_samples_split(5).min_samples_leaf(2);
+    let builder = ModelBuilder::new()
+        .kind(config.model_kind)
+        .params(&config.model_params);
 
-    let model = builder.train_with_validation(&features, &targets, 0.2)?;
+    let model = builder.train_with_validation_weighted(&features, &targets, &weights, 0.2)?;
+    let quantile_models = train_quantile_models(&builder, &features, &targets, config)?;
+    let (cv_mse_mean, cv_mse_std) =
+        cross_validation_summary(&builder, &features, &targets, config)?;
 
     // Create feature extractor with stats
     let mut feature_extractor = FeatureExtractor::new();
     feature_extractor.update_historical_stats(&baseline);
+    feature_extractor.update_monthly_stats(&logs);
+    feature_extractor.update_slot_history(&logs);
 
     // Create persisted model metadata
     let slot_stats: Vec<SerializedSlotStats> = baseline
         .iter()
         .map(|avg| SerializedSlotStats {
-            weekday: avg.weekday,
-            hour: avg.hour,
+            weekday: avg.weekday as u32,
+            hour: avg.hour as u32,
             mean: avg.avg_percentage,
             std_dev: 10.0, // Default, could be computed
             sample_count: avg.sample_count,
@@ -137,17 +293,18 @@ pub async fn train_model<C: Clock>(
     let persisted = PersistedModel::new(
         config.training_window_days,
         model.training_samples,
-        model.training_mse,
-        model.validation_mse,
+        model.training_metrics(),
+        model.validation_metrics(),
         slot_stats,
-        ModelSummary {
-            model_type: "LinearRegression".to_string(),
-            max_depth: Some(10),
-            feature_importance: None,
-        },
+        model_summary(&model, &config.model_params),
     );
 
     Ok(TrainingResult {
+        training_metrics: model.training_metrics(),
+        validation_metrics: model.validation_metrics(),
+        quantile_models,
+        cv_mse_mean,
+        cv_mse_std,
         model,
         feature_extractor,
         persisted,
@@ -168,22 +325,30 @@ pub fn train_model_sync(
     // Prepare training data
     let preparer = TrainingDataPreparer::new(config.clone());
     let (features, targets) = preparer.prepare(logs, baseline, schedule)?;
+    let weights = sample_weights(logs, config);
 
     // Train model
-    let builder = ModelBuilder::new().max_depth(10).min_samples_split(5).min_samples_leaf(2);
+    let builder = ModelBuilder::new()
+        .kind(config.model_kind)
+        .params(&config.model_params);
 
-    let model = builder.train_with_validation(&features, &targets, 0.2)?;
+    let model = builder.train_with_validation_weighted(&features, &targets, &weights, 0.2)?;
+    let quantile_models = train_quantile_models(&builder, &features, &targets, config)?;
+    let (cv_mse_mean, cv_mse_std) =
+        cross_validation_summary(&builder, &features, &targets, config)?;
 
     // Create feature extractor
     let mut feature_extractor = FeatureExtractor::new();
     feature_extractor.update_historical_stats(baseline);
+    feature_extractor.update_monthly_stats(logs);
+    feature_extractor.update_slot_history(logs);
 
     // Create persisted model metadata
     let slot_stats: Vec<SerializedSlotStats> = baseline
         .iter()
         .map(|avg| SerializedSlotStats {
-            weekday: avg.weekday,
-            hour: avg.hour,
+            weekday: avg.weekday as u32,
+            hour: avg.hour as u32,
             mean: avg.avg_percentage,
             std_dev: 10.0,
             sample_count: avg.sample_count,
@@ -193,17 +358,18 @@ pub fn train_model_sync(
     let persisted = PersistedModel::new(
         config.training_window_days,
         model.training_samples,
-        model.training_mse,
-        model.validation_mse,
+        model.training_metrics(),
+        model.validation_metrics(),
         slot_stats,
-        ModelSummary {
-            model_type: "LinearRegression".to_string(),
-            max_depth: Some(10),
-            feature_importance: None,
-        },
+        model_summary(&model, &config.model_params),
     );
 
     Ok(TrainingResult {
+        training_metrics: model.training_metrics(),
+        validation_metrics: model.validation_metrics(),
+        quantile_models,
+        cv_mse_mean,
+        cv_mse_std,
         model,
         feature_extractor,
         persisted,
@@ -213,7 +379,7 @@ pub fn train_model_sync(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Timelike};
 
     fn create_test_logs(n: usize) -> Vec<OccupancyLog> {
         let base_time = Utc.with_ymd_and_hms(2024, 6, 1, 6, 0, 0).unwrap();
@@ -308,7 +474,11 @@ mod tests {
         match result {
             Ok(training_result) => {
                 assert!(training_result.model.training_samples >= 100);
-                assert!(training_result.persisted.training_mse >= 0.0);
+                assert!(training_result.persisted.training_metrics.mse >= 0.0);
+                assert!(training_result.training_metrics.mae >= 0.0);
+                assert!(training_result.validation_metrics.is_some());
+                assert!(training_result.quantile_models.is_some());
+                assert!(training_result.cv_mse_mean.is_some());
             }
             Err(TrainingError::FitError(msg)) if msg.contains("non-invertible") => {
                 // This can happen with synthetic data due to feature collinearity
@@ -319,6 +489,149 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cross_validate_uniform_data_beats_mean() {
+        let base_time = Utc.with_ymd_and_hms(2024, 6, 1, 6, 0, 0).unwrap();
+        // Occupancy scattered uniformly in [40, 60] regardless of time
+        let logs: Vec<OccupancyLog> = (0..600)
+            .map(|i| OccupancyLog {
+                id: i as i64,
+                timestamp: (base_time + Duration::hours(i as i64)).to_rfc3339(),
+                percentage: 40.0 + ((i * 7919 + 13) % 201) as f64 / 10.0,
+            })
+            .collect();
+        let config = MlConfig {
+            min_samples_for_training: 100,
+            ..Default::default()
+        };
+        let (features, targets) = TrainingDataPreparer::new(config)
+            .prepare(&logs, &create_test_baseline(), &GymSchedule::default())
+            .unwrap();
+        let builder = ModelBuilder::new().min_samples_leaf(2);
+
+        let fold_mse = cross_validate(&builder, &features, &targets, 5).unwrap();
+
+        // Every held-out fold beats predicting the overall mean. The first
+        // fold lacks prior-week history, so its error is the largest.
+        assert_eq!(fold_mse.len(), 5);
+        let (_, target_std) = mean_and_std(&targets);
+        for mse in &fold_mse {
+            assert!(
+                *mse < target_std * target_std,
+                "fold MSE {} not below target variance; folds: {:?}",
+                mse,
+                fold_mse
+            );
+        }
+    }
+
+    #[test]
+    fn test_cross_validate_rejects_bad_fold_counts() {
+        let logs = create_test_logs(200);
+        let config = MlConfig {
+            min_samples_for_training: 100,
+            ..Default::default()
+        };
+        let (features, targets) = TrainingDataPreparer::new(config)
+            .prepare(&logs, &create_test_baseline(), &GymSchedule::default())
+            .unwrap();
+        let builder = ModelBuilder::new();
+
+        assert!(matches!(
+            cross_validate(&builder, &features, &targets, 1),
+            Err(TrainingError::FitError(_))
+        ));
+        assert!(matches!(
+            cross_validate(&builder, &features[..5], &targets[..5], 5),
+            Err(TrainingError::InsufficientData(5))
+        ));
+    }
+
+    #[test]
+    fn test_decay_weights_halve_per_half_life() {
+        let newest = Utc.with_ymd_and_hms(2024, 6, 29, 12, 0, 0).unwrap();
+        let timestamps = [
+            newest - Duration::days(28),
+            newest,
+            newest - Duration::days(14),
+        ];
+
+        let weights = decay_weights(&timestamps, 14.0);
+
+        assert_eq!(weights.len(), 3);
+        assert!((weights[0] - 0.25).abs() < 1e-12);
+        assert!((weights[1] - 1.0).abs() < 1e-12);
+        assert!((weights[2] - 0.5).abs() < 1e-12);
+        assert!(decay_weights(&[], 14.0).is_empty());
+    }
+
+    #[test]
+    fn test_decayed_model_tracks_regime_change() {
+        let base_time = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let days = 56;
+        let timestamps: Vec<DateTime<Utc>> = (0..days * 24)
+            .map(|i| base_time + Duration::hours(i))
+            .collect();
+        // Same daily shape throughout, 25 points busier in the second half
+        let targets: Vec<f64> = (0..days * 24)
+            .map(|i| {
+                let hour = (i % 24) as f64;
+                let shift = if i >= days * 12 { 25.0 } else { 0.0 };
+                30.0 + 15.0 * (hour / 24.0 * std::f64::consts::TAU).sin() + shift + (i % 5) as f64
+            })
+            .collect();
+        // Only the hour is known, so the model cannot tell the regimes apart
+        let features: Vec<PredictionFeatures> = timestamps
+            .iter()
+            .map(|timestamp| {
+                let angle = timestamp.hour() as f64 / 24.0 * std::f64::consts::TAU;
+                PredictionFeatures {
+                    hour_sin: angle.sin(),
+                    hour_cos: angle.cos(),
+                    weekday_sin: 0.0,
+                    weekday_cos: 1.0,
+                    historical_avg: 0.0,
+                    historical_std: 0.0,
+                    recent_avg_1h: 0.0,
+                    recent_avg_3h: 0.0,
+                    recent_trend: 0.0,
+                    day_avg_so_far: 0.0,
+                    prev_day_avg: 0.0,
+                    same_slot_last_week: 0.0,
+                    same_slot_two_weeks_ago: 0.0,
+                    is_weekend: 0.0,
+                    is_holiday: 0.0,
+                    week_of_year_sin: 0.0,
+                    week_of_year_cos: 1.0,
+                    month_sin: 0.0,
+                    month_cos: 1.0,
+                    is_january: 0.0,
+                    hours_ahead: 0.0,
+                }
+            })
+            .collect();
+        let weights = decay_weights(&timestamps, 7.0);
+        let builder = ModelBuilder::new();
+
+        let unweighted = builder.train(&features, &targets).unwrap();
+        let decayed = builder
+            .train_weighted(&features, &targets, &weights)
+            .unwrap();
+
+        // Error on the last week
+        let recent = features.len() - 7 * 24;
+        let recent_mse = |model: &TrainedModel| {
+            let predictions = model.predict_batch(&features[recent..]);
+            EvaluationMetrics::evaluate(&predictions, &targets[recent..]).mse
+        };
+        assert!(
+            recent_mse(&decayed) < recent_mse(&unweighted) / 2.0,
+            "decayed MSE {} vs unweighted {}",
+            recent_mse(&decayed),
+            recent_mse(&unweighted)
+        );
+    }
+
     #[test]
     fn test_train_model_sync_insufficient_data() {
         let config = MlConfig {
@@ -2,14 +2,18 @@
 //!
 //! This module provides functionality to repair gaps in occupancy data:
 //! - Fill missing minute-by-minute data with linear interpolation (gaps up to 5
-//!   minutes)
+//!   minutes by default)
 //! - Normalize values outside opening hours to 0
 //! - Ensure end-of-day closure entries exist at close_hour:01
+//!
+//! The affected records are backed up before each run, so a repair can be
+//! undone with [`DataRepairer::undo`].
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Timelike, Utc};
+use futures::stream::{self, StreamExt};
 use tokio::sync::mpsc;
 
 use crate::{
@@ -17,21 +21,30 @@ use crate::{
     schedule::GymSchedule,
 };
 
-/// Maximum gap in minutes that will be filled with interpolation.
-const MAX_GAP_MINUTES: i64 = 5;
+/// Default maximum gap in minutes that will be filled with interpolation.
+pub const DEFAULT_MAX_INTERPOLATION_GAP_MINUTES: i64 = 5;
+
+/// Default number of days repaired at the same time.
+pub const DEFAULT_REPAIR_CONCURRENCY: usize = 4;
 
-/// Progress update for a repair job.
+/// Progress update for a repair job, sent after each day.
 #[derive(Debug, Clone)]
 pub struct RepairProgress {
+    /// Day that was just repaired or skipped, in order of completion
     pub current_day: NaiveDate,
     pub total_days: u32,
+    /// Days repaired or skipped so far, including `current_day`
     pub processed_days: u32,
 }
 
 /// Summary of a completed repair job.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RepairSummary {
+    /// Id of the pre-repair backup, for [`DataRepairer::undo`]
+    pub repair_id: i64,
     pub days_processed: u32,
+    /// Days skipped because they were already repaired after they ended
+    pub days_skipped: u32,
     pub gaps_filled: u32,
     pub records_zeroed: u32,
     pub end_entries_added: u32,
@@ -49,110 +62,219 @@ struct DayRepairResult {
 pub struct DataRepairer {
     db: Arc<Database>,
     schedule: GymSchedule,
+    skip_repaired: bool,
+    max_interpolation_gap_minutes: i64,
+    concurrency: usize,
 }
 
 impl DataRepairer {
     /// Create a new DataRepairer.
     pub fn new(db: Arc<Database>, schedule: GymSchedule) -> Self {
-        Self { db, schedule }
+        Self {
+            db,
+            schedule,
+            skip_repaired: false,
+            max_interpolation_gap_minutes: DEFAULT_MAX_INTERPOLATION_GAP_MINUTES,
+            concurrency: DEFAULT_REPAIR_CONCURRENCY,
+        }
+    }
+
+    /// Skip days that were already repaired after they ended.
+    ///
+    /// Days repaired while still in progress are repaired again, since more
+    /// data may have arrived since.
+    pub fn with_skip_repaired(mut self, skip: bool) -> Self {
+        self.skip_repaired = skip;
+        self
+    }
+
+    /// Fill gaps of up to `minutes` with interpolation instead of the default
+    /// of 5. Larger gaps are left untouched.
+    pub fn with_max_interpolation_gap_minutes(mut self, minutes: i64) -> Self {
+        self.max_interpolation_gap_minutes = minutes;
+        self
+    }
+
+    /// Repair up to `days` days at the same time instead of the default of
+    /// 4. Days are independent, so only the order of the writes changes.
+    pub fn with_concurrency(mut self, days: usize) -> Self {
+        self.concurrency = days.max(1);
+        self
     }
 
     /// Repair data for a date range.
     ///
     /// This will:
     /// 1. Zero out records outside opening hours
-    /// 2. Fill gaps up to 5 minutes with linear interpolation
+    /// 2. Fill gaps up to the maximum interpolation gap with linear
+    ///    interpolation
     /// 3. Add end-of-day entries at close_hour:01 if missing
+    ///
+    /// Days are repaired concurrently, see [`Self::with_concurrency`]. A
+    /// [`RepairProgress`] is sent on `progress_tx` as each day finishes, so
+    /// its days may arrive out of order. The repair carries on if the
+    /// receiver is dropped. On an error, days still in progress are
+    /// abandoned.
+    ///
+    /// Afterwards the hourly averages cache is refreshed from `start` on.
     pub async fn repair_date_range(
         &self,
         start: NaiveDate,
         end: NaiveDate,
-        progress_tx: Option<mpsc::UnboundedSender<RepairProgress>>,
+        progress_tx: Option<mpsc::Sender<RepairProgress>>,
     ) -> Result<RepairSummary> {
+        let (range_start, range_end) = local_range(&self.schedule, start, end)?;
+        let repair_id = self.db.backup_records(range_start, range_end).await?;
+
         let mut summary = RepairSummary {
+            repair_id,
             days_processed: 0,
+            days_skipped: 0,
             gaps_filled: 0,
             records_zeroed: 0,
             end_entries_added: 0,
         };
 
+        let repair_log: HashMap<NaiveDate, DateTime<Utc>> = if self.skip_repaired {
+            self.db
+                .get_repair_log(start, end)
+                .await?
+                .into_iter()
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
         let total_days = (end - start).num_days() as u32 + 1;
-        let mut current = start;
+        let repair_log = &repair_log;
+
+        // Each day yields its repair result, or `None` if it was skipped
+        let mut days = stream::iter(start.iter_days().take_while(|day| *day <= end))
+            .map(move |day| async move {
+                if repair_log.get(&day).is_some_and(|repaired_at| {
+                    repaired_after_day_end(&self.schedule, day, *repaired_at)
+                }) {
+                    return Ok((day, None));
+                }
 
-        while current <= end {
-            // Send progress update
-            if let Some(ref tx) = progress_tx {
-                let _ = tx.send(RepairProgress {
-                    current_day: current,
-                    total_days,
-                    processed_days: summary.days_processed,
-                });
+                let result = self.repair_day(day).await?;
+                self.db.mark_day_repaired(day, Utc::now()).await?;
+                Ok::<_, anyhow::Error>((day, Some(result)))
+            })
+            .buffer_unordered(self.concurrency);
+
+        while let Some(outcome) = days.next().await {
+            let (day, result) = outcome?;
+            match result {
+                Some(result) => {
+                    summary.days_processed += 1;
+                    summary.gaps_filled += result.gaps_filled;
+                    summary.records_zeroed += result.records_zeroed;
+                    if result.end_entry_added {
+                        summary.end_entries_added += 1;
+                    }
+                }
+                None => summary.days_skipped += 1,
             }
 
-            let result = self.repair_day(current).await?;
-
-            summary.days_processed += 1;
-            summary.gaps_filled += result.gaps_filled;
-            summary.records_zeroed += result.records_zeroed;
-            if result.end_entry_added {
-                summary.end_entries_added += 1;
+            // A closed channel only means nobody is watching any more
+            if let Some(ref tx) = progress_tx {
+                let _ = tx
+                    .send(RepairProgress {
+                        current_day: day,
+                        total_days,
+                        processed_days: summary.days_processed + summary.days_skipped,
+                    })
+                    .await;
             }
+        }
 
-            current += Duration::days(1);
+        // Repaired days change the cached hourly averages
+        if summary.days_processed > 0 {
+            self.db.refresh_hourly_averages(range_start).await?;
         }
 
         Ok(summary)
     }
 
+    /// Restore the records a repair run changed to their pre-repair state.
+    ///
+    /// The restored days are dropped from the repair log, so a later run does
+    /// not skip them.
+    pub async fn undo(&self, repair_id: i64) -> Result<()> {
+        let (range_start, range_end) = self.db.restore_backup(repair_id).await?;
+
+        self.db
+            .clear_repair_log(
+                self.schedule.wall_time(&range_start).date(),
+                self.schedule.wall_time(&range_end).date(),
+            )
+            .await?;
+        self.db.refresh_hourly_averages(range_start).await?;
+
+        Ok(())
+    }
+
     /// Repair data for a single day.
     async fn repair_day(&self, date: NaiveDate) -> Result<DayRepairResult> {
         let mut result = DayRepairResult::default();
 
         // Get opening hours for this day
-        let open_hour = self.schedule.get_open_hour(date);
+        let windows = self.schedule.open_windows(date);
         let close_hour = self.schedule.get_close_hour(date);
 
         // Load all records for the day
-        let records = self.db.get_records_for_date(date).await?;
+        let records = self.db.get_records_for_date(date, &self.schedule).await?;
 
         // Step A: Zero records outside opening hours
-        result.records_zeroed = self
-            .zero_outside_hours(&records, date, open_hour, close_hour)
-            .await?;
+        result.records_zeroed = self.zero_outside_hours(&records, date, &windows).await?;
 
         // Step B: Fill gaps with interpolation
         // Reload records after zeroing (to get updated values)
-        let records = self.db.get_records_for_date(date).await?;
-        result.gaps_filled = self
-            .fill_gaps(&records, date, open_hour, close_hour)
-            .await?;
+        let records = self.db.get_records_for_date(date, &self.schedule).await?;
+        let mut pending = interpolate_gaps(
+            &self.schedule,
+            &records,
+            date,
+            &windows,
+            self.max_interpolation_gap_minutes,
+        )?;
+        result.gaps_filled = pending.len() as u32;
+
+        // Step C: Ensure end-of-day entry exists, unless the gym was closed
+        if !windows.is_empty()
+            && let Some(entry) = end_of_day_entry(&self.schedule, &records, date, close_hour)?
+        {
+            pending.push(entry);
+            result.end_entry_added = true;
+        }
 
-        // Step C: Ensure end-of-day entry exists
-        result.end_entry_added = self.ensure_end_of_day_entry(date, close_hour).await?;
+        // Write the day's new records in one transaction
+        self.db.insert_records(&pending).await?;
 
         Ok(result)
     }
 
-    /// Zero out records that fall outside the opening hours.
+    /// Zero out records that fall outside all opening windows.
     async fn zero_outside_hours(
         &self,
         records: &[OccupancyLog],
         date: NaiveDate,
-        open_hour: u32,
-        close_hour: u32,
+        windows: &[(u32, u32)],
     ) -> Result<u32> {
         let mut zeroed_count = 0;
-        let local_tz = Local;
 
-        // Opening time is open_hour:00, closing time is close_hour:00
-        let open_time = NaiveTime::from_hms_opt(open_hour, 0, 0).unwrap();
-        let close_time = NaiveTime::from_hms_opt(close_hour, 0, 0).unwrap();
+        // Each window runs from open_hour:00 to close_hour:00, in seconds of day
+        let window_seconds: Vec<(u32, u32)> = windows
+            .iter()
+            .map(|&(open, close)| (open * 3600, close * 3600))
+            .collect();
 
         for record in records {
             if let Some(utc_dt) = record.datetime() {
-                let local_dt = utc_dt.with_timezone(&local_tz);
-                let local_date = local_dt.date_naive();
-                let local_time = local_dt.time();
+                let local_dt = self.schedule.wall_time(&utc_dt);
+                let local_date = local_dt.date();
+                let local_seconds = local_dt.time().num_seconds_from_midnight();
 
                 // Only process records from the target date
                 if local_date != date {
@@ -160,7 +282,9 @@ impl DataRepairer {
                 }
 
                 // Check if outside opening hours and not already zero
-                let is_outside = local_time < open_time || local_time > close_time;
+                let is_outside = !window_seconds
+                    .iter()
+                    .any(|(open, close)| local_seconds >= *open && local_seconds <= *close);
                 if is_outside && record.percentage != 0.0 {
                     self.db.update_percentage(record.id, 0.0).await?;
                     zeroed_count += 1;
@@ -170,131 +294,230 @@ impl DataRepairer {
 
         Ok(zeroed_count)
     }
+}
 
-    /// Fill gaps in the data with linear interpolation.
-    async fn fill_gaps(
-        &self,
-        records: &[OccupancyLog],
-        date: NaiveDate,
-        open_hour: u32,
-        close_hour: u32,
-    ) -> Result<u32> {
-        let mut filled_count = 0;
-        let local_tz = Local;
-
-        // Build a list of (minute_of_day, percentage) for records on this date
-        let mut data_points: Vec<(i64, f64)> = Vec::new();
-
-        for record in records {
-            if let Some(utc_dt) = record.datetime() {
-                let local_dt = utc_dt.with_timezone(&local_tz);
-                let local_date = local_dt.date_naive();
-
-                if local_date == date {
-                    let minute_of_day = local_dt.hour() as i64 * 60 + local_dt.minute() as i64;
-                    data_points.push((minute_of_day, record.percentage));
-                }
+/// Interpolated records for gaps of up to `max_gap_minutes` in the data.
+///
+/// Minutes are counted on the schedule's wall clock.
+fn interpolate_gaps(
+    schedule: &GymSchedule,
+    records: &[OccupancyLog],
+    date: NaiveDate,
+    windows: &[(u32, u32)],
+    max_gap_minutes: i64,
+) -> Result<Vec<(DateTime<Utc>, f64)>> {
+    // Build a list of (minute_of_day, percentage) for records on this date
+    let mut data_points: Vec<(i64, f64)> = Vec::new();
+
+    for record in records {
+        if let Some(utc_dt) = record.datetime() {
+            let local_dt = schedule.wall_time(&utc_dt);
+            let local_date = local_dt.date();
+
+            if local_date == date {
+                let minute_of_day = local_dt.hour() as i64 * 60 + local_dt.minute() as i64;
+                data_points.push((minute_of_day, record.percentage));
             }
         }
+    }
 
-        // Sort by minute of day
-        data_points.sort_by_key(|(m, _)| *m);
+    // Sort by minute of day
+    data_points.sort_by_key(|(m, _)| *m);
 
-        if data_points.len() < 2 {
-            return Ok(0);
-        }
+    if data_points.len() < 2 {
+        return Ok(Vec::new());
+    }
 
-        // Opening and closing in minutes of day
-        let open_minute = open_hour as i64 * 60;
-        let close_minute = close_hour as i64 * 60;
-
-        // Find gaps and interpolate
-        let mut inserts: Vec<(DateTime<Utc>, f64)> = Vec::new();
-
-        for i in 0..data_points.len() - 1 {
-            let (m1, v1) = data_points[i];
-            let (m2, v2) = data_points[i + 1];
-
-            let gap_minutes = m2 - m1;
-
-            // Only fill gaps that are:
-            // 1. Within opening hours
-            // 2. Greater than 1 minute (missing data)
-            // 3. Less than or equal to MAX_GAP_MINUTES
-            if gap_minutes > 1 && gap_minutes <= MAX_GAP_MINUTES {
-                // Check if the gap is within opening hours
-                if m1 >= open_minute && m2 <= close_minute {
-                    // Linear interpolation for each missing minute
-                    for m in (m1 + 1)..m2 {
-                        let t = (m - m1) as f64 / gap_minutes as f64;
-                        let interpolated = v1 + t * (v2 - v1);
-
-                        // Convert minute of day back to timestamp
-                        let hour = (m / 60) as u32;
-                        let minute = (m % 60) as u32;
-                        let local_time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
-                        let local_dt = local_tz
-                            .from_local_datetime(&date.and_time(local_time))
-                            .single()
-                            .context("Invalid local datetime for interpolation")?;
-                        let utc_dt = local_dt.with_timezone(&Utc);
-
-                        inserts.push((utc_dt, interpolated));
-                        filled_count += 1;
-                    }
+    // Opening windows in minutes of day
+    let window_minutes: Vec<(i64, i64)> = windows
+        .iter()
+        .map(|&(open, close)| (open as i64 * 60, close as i64 * 60))
+        .collect();
+
+    // Find gaps and interpolate
+    let mut inserts: Vec<(DateTime<Utc>, f64)> = Vec::new();
+
+    for i in 0..data_points.len() - 1 {
+        let (m1, v1) = data_points[i];
+        let (m2, v2) = data_points[i + 1];
+
+        let gap_minutes = m2 - m1;
+
+        // Only fill gaps that are:
+        // 1. Within a single opening window
+        // 2. Greater than 1 minute (missing data)
+        // 3. Less than or equal to max_gap_minutes
+        if gap_minutes > 1 && gap_minutes <= max_gap_minutes {
+            // Check if the gap is within an opening window
+            if window_minutes
+                .iter()
+                .any(|(open, close)| m1 >= *open && m2 <= *close)
+            {
+                // Linear interpolation for each missing minute
+                for m in (m1 + 1)..m2 {
+                    let t = (m - m1) as f64 / gap_minutes as f64;
+                    let interpolated = v1 + t * (v2 - v1);
+
+                    // Convert minute of day back to timestamp
+                    let hour = (m / 60) as u32;
+                    let minute = (m % 60) as u32;
+                    let local_time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+                    let utc_dt = schedule
+                        .wall_time_to_utc(&date.and_time(local_time))
+                        .context("Invalid local datetime for interpolation")?;
+
+                    inserts.push((utc_dt, interpolated));
                 }
             }
         }
-
-        // Batch insert the interpolated values
-        if !inserts.is_empty() {
-            self.db.batch_insert(inserts).await?;
-        }
-
-        Ok(filled_count)
     }
 
-    /// Ensure an end-of-day entry exists at close_hour:01.
-    async fn ensure_end_of_day_entry(&self, date: NaiveDate, close_hour: u32) -> Result<bool> {
-        let local_tz = Local;
-
-        // End of day time is close_hour:01
-        let end_time = NaiveTime::from_hms_opt(close_hour, 1, 0).unwrap();
-        let local_dt = local_tz
-            .from_local_datetime(&date.and_time(end_time))
-            .single()
-            .context("Invalid local datetime for end of day entry")?;
-        let utc_dt = local_dt.with_timezone(&Utc);
-
-        // Check if an entry already exists at this time
-        let records = self.db.get_records_for_date(date).await?;
-
-        let exists = records.iter().any(|r| {
-            if let Some(dt) = r.datetime() {
-                let local = dt.with_timezone(&local_tz);
-                local.date_naive() == date && local.hour() == close_hour && local.minute() == 1
-            } else {
-                false
-            }
-        });
+    Ok(inserts)
+}
 
-        if !exists {
-            self.db.insert_at_timestamp(utc_dt, 0.0).await?;
-            Ok(true)
+/// End-of-day entry at close_hour:01 on the schedule's wall clock, unless
+/// `records` already has one.
+fn end_of_day_entry(
+    schedule: &GymSchedule,
+    records: &[OccupancyLog],
+    date: NaiveDate,
+    close_hour: u32,
+) -> Result<Option<(DateTime<Utc>, f64)>> {
+    // End of day time is close_hour:01
+    let end_time = NaiveTime::from_hms_opt(close_hour, 1, 0).unwrap();
+    let utc_dt = schedule
+        .wall_time_to_utc(&date.and_time(end_time))
+        .context("Invalid local datetime for end of day entry")?;
+
+    // Check if an entry already exists at this time
+    let exists = records.iter().any(|r| {
+        if let Some(dt) = r.datetime() {
+            let local = schedule.wall_time(&dt);
+            local.date() == date && local.hour() == close_hour && local.minute() == 1
         } else {
-            Ok(false)
+            false
         }
+    });
+
+    Ok((!exists).then_some((utc_dt, 0.0)))
+}
+
+/// UTC bounds of the days from `start` to `end`, both inclusive, in the
+/// schedule's time zone.
+fn local_range(
+    schedule: &GymSchedule,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let midnight = |date: NaiveDate| {
+        schedule
+            .day_start(date)
+            .context("Invalid local datetime for repair range")
+    };
+
+    Ok((
+        midnight(start)?,
+        midnight(end + Duration::days(1))? - Duration::nanoseconds(1),
+    ))
+}
+
+/// Whether a repair at `repaired_at` happened after `date` ended in the
+/// schedule's time zone.
+///
+/// Only such repairs saw the complete day, so later runs can skip it.
+fn repaired_after_day_end(
+    schedule: &GymSchedule,
+    date: NaiveDate,
+    repaired_at: DateTime<Utc>,
+) -> bool {
+    match schedule.day_start(date + Duration::days(1)) {
+        Some(day_end) => repaired_at >= day_end,
+        None => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::{Local, TimeZone};
+
     use super::*;
 
+    /// Records at 10:00 and `gap` minutes later on 2024-06-17, in local time.
+    fn records_with_gap(gap: i64) -> Vec<OccupancyLog> {
+        let start = Local.with_ymd_and_hms(2024, 6, 17, 10, 0, 0).unwrap();
+        [(start, 20.0), (start + Duration::minutes(gap), 40.0)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (time, percentage))| OccupancyLog {
+                id: i as i64,
+                timestamp: time.with_timezone(&Utc).to_rfc3339(),
+                percentage,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolate_gaps_fills_short_gap() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 17).unwrap();
+        let records = records_with_gap(4);
+
+        let inserts = interpolate_gaps(
+            &GymSchedule::default(),
+            &records,
+            date,
+            &[(6, 22)],
+            DEFAULT_MAX_INTERPOLATION_GAP_MINUTES,
+        )
+        .unwrap();
+
+        let values: Vec<f64> = inserts.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, vec![25.0, 30.0, 35.0]);
+    }
+
+    #[test]
+    fn test_interpolate_gaps_respects_max_gap() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 17).unwrap();
+        let records = records_with_gap(9);
+
+        let default = interpolate_gaps(
+            &GymSchedule::default(),
+            &records,
+            date,
+            &[(6, 22)],
+            DEFAULT_MAX_INTERPOLATION_GAP_MINUTES,
+        )
+        .unwrap();
+        let widened =
+            interpolate_gaps(&GymSchedule::default(), &records, date, &[(6, 22)], 10).unwrap();
+
+        assert!(default.is_empty());
+        assert_eq!(widened.len(), 8);
+    }
+
+    #[test]
+    fn test_repaired_after_day_end() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let local = |d: u32, h: u32| {
+            Local
+                .with_ymd_and_hms(2024, 6, d, h, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        let schedule = GymSchedule::default();
+
+        // Repaired the next morning: complete
+        assert!(repaired_after_day_end(&schedule, date, local(16, 8)));
+        // Repaired during the day itself: more data may have arrived since
+        assert!(!repaired_after_day_end(&schedule, date, local(15, 20)));
+    }
+
     #[test]
     fn test_repair_summary_default() {
         let summary = RepairSummary {
+            repair_id: 1,
             days_processed: 0,
+            days_skipped: 0,
             gaps_filled: 0,
             records_zeroed: 0,
             end_entries_added: 0,
@@ -1,51 +1,162 @@
-use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
+use std::{collections::HashSet, path::Path};
 
-use crate::config::ScheduleConfig;
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::{
+    config::{ScheduleConfig, ScheduleHours},
+    traits::Clock,
+};
+
+/// German state whose public holidays apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GermanState {
+    /// Nationwide holidays only
+    National,
+    #[default]
+    Bavaria,
+    BadenWuerttemberg,
+    Berlin,
+    Saxony,
+}
 
 /// Gym schedule with configurable opening hours.
 #[derive(Debug, Clone)]
 pub struct GymSchedule {
-    weekday_open: u32,
-    weekday_close: u32,
-    weekend_open: u32,
-    weekend_close: u32,
+    weekday: ScheduleHours,
+    weekend: ScheduleHours,
+    /// Per-day hours (Monday first), overriding the weekday/weekend split
+    days: Option<[ScheduleHours; 7]>,
+    holidays: GermanState,
+    /// Zone the opening hours are given in; `None` for the system zone
+    timezone: Option<Tz>,
+    /// Dates the gym stays closed all day
+    closed_dates: HashSet<NaiveDate>,
+    /// Extra holidays, e.g. from an ICS calendar, treated like public holidays
+    extra_holidays: HashSet<NaiveDate>,
 }
 
 impl GymSchedule {
     pub fn new(config: &ScheduleConfig) -> Self {
         Self {
-            weekday_open: config.weekday.open_hour,
-            weekday_close: config.weekday.close_hour,
-            weekend_open: config.weekend.open_hour,
-            weekend_close: config.weekend.close_hour,
+            weekday: config.weekday.clone(),
+            weekend: config.weekend.clone(),
+            days: config.days.clone(),
+            holidays: config.holidays,
+            timezone: parse_timezone(&config.timezone),
+            closed_dates: config.closed_dates.iter().copied().collect(),
+            extra_holidays: config
+                .holiday_calendar
+                .as_deref()
+                .map(load_calendar_or_warn)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Add holidays on top of the built-in ones for the configured state.
+    pub fn with_holidays(mut self, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.extra_holidays.extend(holidays);
+        self
+    }
+
+    /// Zone the opening hours are given in; `None` for the system zone.
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone
+    }
+
+    /// Current time in the schedule's time zone.
+    pub fn now_local(&self, clock: &dyn Clock) -> DateTime<FixedOffset> {
+        match self.timezone {
+            Some(tz) => clock.now_in(tz).fixed_offset(),
+            None => clock.now_utc().with_timezone(&Local).fixed_offset(),
         }
     }
 
-    /// Check if the gym is currently open.
-    pub fn is_open(&self, time: &DateTime<Local>) -> bool {
-        let date = time.date_naive();
-        let hour = time.hour();
-        let minute = time.minute();
+    /// Wall-clock time of `time` in the schedule's time zone.
+    pub fn wall_time<Z: TimeZone>(&self, time: &DateTime<Z>) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) => time.with_timezone(&tz).naive_local(),
+            None => time.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// Earliest instant showing `naive` on the schedule's wall clock, or
+    /// `None` if a DST change skips it.
+    pub fn wall_time_to_utc(&self, naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.timezone {
+            Some(tz) => tz
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+            None => Local
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+        }
+    }
+
+    /// Start of `date` in the schedule's time zone.
+    pub fn day_start(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        self.wall_time_to_utc(&date.and_hms_opt(0, 0, 0)?)
+    }
+
+    /// Earliest instant showing `naive` on the schedule's wall clock.
+    fn wall_time_to_local(&self, naive: &NaiveDateTime) -> Option<DateTime<Local>> {
+        self.wall_time_to_utc(naive)
+            .map(|dt| dt.with_timezone(&Local))
+    }
+
+    /// Hours that apply on a date.
+    ///
+    /// With a per-day table, public holidays use Sunday's hours; otherwise
+    /// weekends and public holidays use the weekend hours.
+    fn hours_for(&self, date: NaiveDate) -> &ScheduleHours {
+        let holiday = is_public_holiday(date, self.holidays) || self.extra_holidays.contains(&date);
 
-        if is_bavarian_holiday(date) || date.weekday().number_from_monday() > 5 {
-            // Weekend or Holiday
-            (self.weekend_open..self.weekend_close).contains(&hour)
-                || (hour == self.weekend_close && minute == 0)
+        if let Some(days) = &self.days {
+            if holiday {
+                &days[6]
+            } else {
+                &days[date.weekday().num_days_from_monday() as usize]
+            }
+        } else if holiday || date.weekday().number_from_monday() > 5 {
+            &self.weekend
         } else {
-            // Regular Weekday
-            (self.weekday_open..self.weekday_close).contains(&hour)
-                || (hour == self.weekday_close && minute == 0)
+            &self.weekday
         }
     }
+
+    /// Opening windows on a date as `(open_hour, close_hour)` pairs.
+    ///
+    /// Empty on configured closure dates.
+    pub fn open_windows(&self, date: NaiveDate) -> Vec<(u32, u32)> {
+        if self.closed_dates.contains(&date) {
+            return Vec::new();
+        }
+        self.hours_for(date).open_windows()
+    }
+
+    /// Check if the gym is open at `time`, read in the schedule's time zone.
+    pub fn is_open<Z: TimeZone>(&self, time: &DateTime<Z>) -> bool {
+        let wall = self.wall_time(time);
+        self.open_windows(wall.date())
+            .into_iter()
+            .any(|window| window_contains(window, &wall))
+    }
 }
 
 impl Default for GymSchedule {
+    /// Default hours in the system time zone.
     fn default() -> Self {
         Self {
-            weekday_open: 6,
-            weekday_close: 23,
-            weekend_open: 9,
-            weekend_close: 21,
+            timezone: None,
+            ..Self::new(&ScheduleConfig::default())
         }
     }
 }
@@ -60,79 +171,343 @@ impl GymSchedule {
         weekend_close: u32,
     ) -> Self {
         Self {
-            weekday_open,
-            weekday_close,
-            weekend_open,
-            weekend_close,
+            weekday: ScheduleHours {
+                open_hour: weekday_open,
+                close_hour: weekday_close,
+                windows: None,
+            },
+            weekend: ScheduleHours {
+                open_hour: weekend_open,
+                close_hour: weekend_close,
+                windows: None,
+            },
+            days: None,
+            holidays: GermanState::default(),
+            timezone: None,
+            closed_dates: HashSet::new(),
+            extra_holidays: HashSet::new(),
         }
     }
 
     /// Get the opening hour for a specific date.
     pub fn get_open_hour(&self, date: NaiveDate) -> u32 {
-        if is_bavarian_holiday(date) || date.weekday().number_from_monday() > 5 {
-            self.weekend_open
-        } else {
-            self.weekday_open
-        }
+        let hours = self.hours_for(date);
+        hours
+            .open_windows()
+            .iter()
+            .map(|(open, _)| *open)
+            .min()
+            .unwrap_or(hours.open_hour)
     }
 
     /// Get the closing hour for a specific date.
     pub fn get_close_hour(&self, date: NaiveDate) -> u32 {
-        if is_bavarian_holiday(date) || date.weekday().number_from_monday() > 5 {
-            self.weekend_close
-        } else {
-            self.weekday_close
+        let hours = self.hours_for(date);
+        hours
+            .open_windows()
+            .iter()
+            .map(|(_, close)| *close)
+            .max()
+            .unwrap_or(hours.close_hour)
+    }
+
+    /// Find the next moment after `from` at which the gym opens or closes.
+    ///
+    /// Looks up to a week ahead and returns `None` if the state never changes
+    /// in that window (e.g. a gym that is open around the clock).
+    pub fn next_transition<Z: TimeZone>(&self, from: &DateTime<Z>) -> Option<DateTime<Local>> {
+        let currently_open = self.is_open(from);
+        self.boundaries_after(from)
+            .find(|candidate| self.is_open(candidate) != currently_open)
+    }
+
+    /// Next moment after `from` at which a closed gym opens.
+    ///
+    /// If the gym is open at `from`, this is the opening after the upcoming
+    /// closure. Looks up to a week ahead, like [`Self::next_transition`].
+    pub fn next_open_time(&self, from: &DateTime<Local>) -> Option<DateTime<Local>> {
+        self.boundaries_after(from)
+            .find(|candidate| self.is_open(candidate) && !self.was_open_before(candidate))
+    }
+
+    /// Next moment after `from` at which an open gym closes.
+    ///
+    /// If the gym is closed at `from`, this is the closing after the next
+    /// opening. Looks up to a week ahead, like [`Self::next_transition`].
+    pub fn next_close_time(&self, from: &DateTime<Local>) -> Option<DateTime<Local>> {
+        self.boundaries_after(from)
+            .find(|candidate| !self.is_open(candidate) && self.was_open_before(candidate))
+    }
+
+    /// Whether the gym was open in the minute before `time`.
+    fn was_open_before(&self, time: &DateTime<Local>) -> bool {
+        self.is_open(&(*time - Duration::minutes(1)))
+    }
+
+    /// Opening (:00) and closing (:01) instants of each window over the week
+    /// after `from`, in order.
+    fn boundaries_after<'a, Z: TimeZone>(
+        &'a self,
+        from: &'a DateTime<Z>,
+    ) -> impl Iterator<Item = DateTime<Local>> + 'a {
+        let start = self.wall_time(from).date();
+
+        (0..=7)
+            .flat_map(move |offset| {
+                let date = start + Duration::days(offset);
+                // Closing is inclusive of the :00 minute, so the gym is closed from :01 on
+                self.open_windows(date)
+                    .into_iter()
+                    .flat_map(move |(open, close)| {
+                        [date.and_hms_opt(open, 0, 0), date.and_hms_opt(close, 1, 0)]
+                    })
+            })
+            .flatten()
+            .filter_map(|naive| self.wall_time_to_local(&naive))
+            .filter(move |candidate| candidate > from)
+    }
+
+    /// Minutes left until the current opening window closes, or `None` if the
+    /// gym is closed. Partial minutes round up, so 22:45:30 with a 23:00 close
+    /// gives 15.
+    pub fn minutes_until_close<Z: TimeZone>(&self, time: &DateTime<Z>) -> Option<u32> {
+        let wall = self.wall_time(time);
+        let date = wall.date();
+        let close_hour = self
+            .open_windows(date)
+            .into_iter()
+            .filter(|&window| window_contains(window, &wall))
+            .map(|(_, close)| close)
+            .max()?;
+
+        let close = date.and_hms_opt(0, 0, 0)? + Duration::hours(close_hour as i64);
+        let seconds = (close - wall).num_seconds().max(0);
+        Some(((seconds + 59) / 60) as u32)
+    }
+
+    /// Check whether the gym opened between two clock reads.
+    ///
+    /// Used to trigger an immediate fetch at opening instead of waiting for the
+    /// next aligned poll.
+    pub fn opened_between(&self, previous: &DateTime<Local>, now: &DateTime<Local>) -> bool {
+        !self.is_open(previous)
+            && self.is_open(now)
+            && self
+                .next_transition(previous)
+                .is_some_and(|transition| transition <= *now)
+    }
+}
+
+/// Whether `time` falls in an `(open_hour, close_hour)` window. Closing is
+/// inclusive of the :00 minute.
+fn window_contains((open, close): (u32, u32), time: &NaiveDateTime) -> bool {
+    let hour = time.hour();
+    (open..close).contains(&hour) || (hour == close && time.minute() == 0)
+}
+
+/// Parse an IANA time zone name; `"local"` selects the system zone.
+fn parse_timezone(name: &str) -> Option<Tz> {
+    if name.eq_ignore_ascii_case("local") {
+        return None;
+    }
+    match name.parse::<Tz>() {
+        Ok(tz) => Some(tz),
+        Err(_) => {
+            tracing::warn!("Unknown schedule timezone {:?}, using system time", name);
+            None
+        }
+    }
+}
+
+/// Yearly ICS recurrences without COUNT or UNTIL are expanded this many years.
+const ICS_RECURRENCE_YEARS: u32 = 100;
+
+/// Load all-day holidays from an iCalendar (`.ics`) file.
+///
+/// Timed events are ignored. Yearly recurrences (`RRULE:FREQ=YEARLY`) are
+/// expanded, honouring `COUNT` and `UNTIL`.
+pub fn load_holidays_from_ics(path: &Path) -> Result<HashSet<NaiveDate>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read holiday calendar {}", path.display()))?;
+    Ok(parse_ics_holidays(&content))
+}
+
+/// Load a holiday calendar, logging and ignoring any error.
+fn load_calendar_or_warn(path: &Path) -> HashSet<NaiveDate> {
+    load_holidays_from_ics(path).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring holiday calendar: {:#}", e);
+        HashSet::new()
+    })
+}
+
+/// All-day dates of the VEVENTs in ICS `content`.
+fn parse_ics_holidays(content: &str) -> HashSet<NaiveDate> {
+    let mut holidays = HashSet::new();
+    let mut event: Option<IcsEvent> = None;
+
+    for line in unfold_ics_lines(content) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Drop parameters such as `;VALUE=DATE`
+        let name = name.split(';').next().unwrap_or(name).to_ascii_uppercase();
+        let value = value.trim();
+
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => event = Some(IcsEvent::default()),
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(finished) = event.take() {
+                    holidays.extend(finished.dates());
+                }
+            }
+            _ => {
+                let Some(current) = event.as_mut() else {
+                    continue;
+                };
+                match name.as_str() {
+                    "DTSTART" => current.start = parse_ics_date(value),
+                    "DTEND" => current.end = parse_ics_date(value),
+                    "RRULE" => current.rrule = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    holidays
+}
+
+/// Join folded ICS lines: a line starting with a space or tab continues the
+/// previous one.
+fn unfold_ics_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parse an ICS `DATE` value (`YYYYMMDD`); date-times yield `None`.
+fn parse_ics_date(value: &str) -> Option<NaiveDate> {
+    if value.len() != 8 {
+        return None;
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d").ok()
+}
+
+/// The parts of a VEVENT needed to list its days.
+#[derive(Debug, Default)]
+struct IcsEvent {
+    start: Option<NaiveDate>,
+    /// Exclusive end date
+    end: Option<NaiveDate>,
+    rrule: Option<String>,
+}
+
+impl IcsEvent {
+    /// Days covered by the event, with yearly recurrences expanded.
+    fn dates(&self) -> Vec<NaiveDate> {
+        let Some(start) = self.start else {
+            return Vec::new();
+        };
+        let span = self.end.map_or(1, |end| (end - start).num_days().max(1));
+        let occurrences = match &self.rrule {
+            Some(rule) => yearly_occurrences(start, rule),
+            None => vec![start],
+        };
+
+        occurrences
+            .into_iter()
+            .flat_map(|day| (0..span).map(move |offset| day + Duration::days(offset)))
+            .collect()
+    }
+}
+
+/// Occurrences of an RRULE starting at `start`. Only `FREQ=YEARLY` is
+/// expanded; other frequencies yield just `start`.
+fn yearly_occurrences(start: NaiveDate, rule: &str) -> Vec<NaiveDate> {
+    let mut yearly = false;
+    let mut count = None;
+    let mut until = None;
+
+    for part in rule.split(';') {
+        match part.split_once('=') {
+            Some(("FREQ", freq)) => yearly = freq.eq_ignore_ascii_case("YEARLY"),
+            Some(("COUNT", n)) => count = n.parse::<u32>().ok(),
+            Some(("UNTIL", date)) => until = date.get(..8).and_then(parse_ics_date),
+            _ => {}
         }
     }
+
+    if !yearly {
+        return vec![start];
+    }
+
+    // Feb 29 only recurs in leap years
+    (0..count.unwrap_or(ICS_RECURRENCE_YEARS))
+        .filter_map(|n| start.with_year(start.year() + n as i32))
+        .take_while(|date| until.is_none_or(|until| *date <= until))
+        .collect()
 }
 
 /// Check if a date is a Bavarian public holiday.
 pub fn is_bavarian_holiday(date: NaiveDate) -> bool {
-    let (d, m) = (date.day(), date.month());
-    let year = date.year();
+    is_public_holiday(date, GermanState::Bavaria)
+}
+
+/// Check if a date is a public holiday in the given state.
+pub fn is_public_holiday(date: NaiveDate, state: GermanState) -> bool {
+    is_national_holiday(date) || is_state_holiday(date, state)
+}
 
-    // 1. Fixed Holidays (Bavaria)
-    match (m, d) {
+/// Holidays observed in every state.
+fn is_national_holiday(date: NaiveDate) -> bool {
+    match (date.month(), date.day()) {
         (1, 1) => return true,   // New Year
-        (1, 6) => return true,   // Epiphany
         (5, 1) => return true,   // Labour Day
-        (8, 15) => return true,  // Assumption Day
         (10, 3) => return true,  // German Unity Day
-        (11, 1) => return true,  // All Saints' Day
         (12, 25) => return true, // Christmas Day
         (12, 26) => return true, // 2nd Day of Christmas
+        // Reformation Day was a one-off nationwide holiday for its 500th anniversary
+        (10, 31) if date.year() == 2017 => return true,
         _ => {}
     }
 
-    // 2. Variable Holidays (Easter based)
-    // We calculate Easter Sunday for the given year to find variable holidays
-    if let Some(easter) = easter_date(year) {
-        let ordinal = date.ordinal();
-        let easter_ordinal = easter.ordinal();
+    // Good Friday, Easter Monday, Ascension Day, Whit Monday
+    matches!(days_after_easter(date), Some(-2 | 1 | 39 | 50))
+}
 
-        // Good Friday: -2 days
-        if ordinal == easter_ordinal - 2 {
-            return true;
-        }
-        // Easter Monday: +1 day
-        if ordinal == easter_ordinal + 1 {
-            return true;
-        }
-        // Ascension Day: +39 days
-        if ordinal == easter_ordinal + 39 {
-            return true;
-        }
-        // Whit Monday: +50 days
-        if ordinal == easter_ordinal + 50 {
-            return true;
+/// Holidays specific to a state, on top of the national ones.
+fn is_state_holiday(date: NaiveDate, state: GermanState) -> bool {
+    let (m, d) = (date.month(), date.day());
+    let epiphany = (m, d) == (1, 6);
+    let all_saints = (m, d) == (11, 1);
+    let reformation = (m, d) == (10, 31);
+    let corpus_christi = days_after_easter(date) == Some(60);
+
+    match state {
+        GermanState::National => false,
+        GermanState::Bavaria => {
+            let assumption = (m, d) == (8, 15);
+            epiphany || corpus_christi || assumption || all_saints
         }
-        // Corpus Christi: +60 days
-        if ordinal == easter_ordinal + 60 {
-            return true;
+        GermanState::BadenWuerttemberg => epiphany || corpus_christi || all_saints,
+        // International Women's Day since 2019
+        GermanState::Berlin => (m, d) == (3, 8) && date.year() >= 2019,
+        GermanState::Saxony => {
+            // Day of Repentance and Prayer: the Wednesday before November 23
+            let repentance = m == 11 && (16..=22).contains(&d) && date.weekday() == Weekday::Wed;
+            reformation || repentance
         }
     }
+}
 
-    false
+/// Signed number of days from Easter Sunday of the same year.
+fn days_after_easter(date: NaiveDate) -> Option<i64> {
+    easter_date(date.year()).map(|easter| (date - easter).num_days())
 }
 
 /// Calculate Easter date using the Anonymous Gregorian algorithm.
@@ -158,9 +533,10 @@ fn easter_date(year: i32) -> Option<NaiveDate> {
 
 #[cfg(test)]
 mod tests {
-    use chrono::{NaiveDate, TimeZone};
+    use chrono::{NaiveDate, TimeZone, Utc};
 
     use super::*;
+    use crate::traits::MockClock;
 
     // ==================== Easter Date Tests ====================
 
@@ -281,6 +657,190 @@ mod tests {
         ));
     }
 
+    // ==================== State Holiday Tests ====================
+
+    #[test]
+    fn test_reformation_day_only_in_some_states() {
+        let reformation = NaiveDate::from_ymd_opt(2024, 10, 31).unwrap();
+
+        assert!(is_public_holiday(reformation, GermanState::Saxony));
+        assert!(!is_public_holiday(reformation, GermanState::Bavaria));
+        assert!(!is_public_holiday(
+            reformation,
+            GermanState::BadenWuerttemberg
+        ));
+        assert!(!is_public_holiday(reformation, GermanState::Berlin));
+        assert!(!is_public_holiday(reformation, GermanState::National));
+    }
+
+    #[test]
+    fn test_reformation_day_2017_nationwide() {
+        let reformation = NaiveDate::from_ymd_opt(2017, 10, 31).unwrap();
+        assert!(is_public_holiday(reformation, GermanState::National));
+        assert!(is_public_holiday(reformation, GermanState::Bavaria));
+    }
+
+    #[test]
+    fn test_national_holidays_apply_everywhere() {
+        // Good Friday 2024, German Unity Day
+        for date in [
+            NaiveDate::from_ymd_opt(2024, 3, 29).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 10, 3).unwrap(),
+        ] {
+            for state in [
+                GermanState::National,
+                GermanState::Bavaria,
+                GermanState::BadenWuerttemberg,
+                GermanState::Berlin,
+                GermanState::Saxony,
+            ] {
+                assert!(is_public_holiday(date, state), "{date} in {state:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_corpus_christi_by_state() {
+        let corpus_christi = NaiveDate::from_ymd_opt(2024, 5, 30).unwrap();
+
+        assert!(is_public_holiday(corpus_christi, GermanState::Bavaria));
+        assert!(is_public_holiday(
+            corpus_christi,
+            GermanState::BadenWuerttemberg
+        ));
+        assert!(!is_public_holiday(corpus_christi, GermanState::Berlin));
+        assert!(!is_public_holiday(corpus_christi, GermanState::National));
+    }
+
+    #[test]
+    fn test_berlin_womens_day() {
+        let womens_day = NaiveDate::from_ymd_opt(2024, 3, 8).unwrap();
+
+        assert!(is_public_holiday(womens_day, GermanState::Berlin));
+        assert!(!is_public_holiday(womens_day, GermanState::Bavaria));
+        assert!(!is_public_holiday(
+            NaiveDate::from_ymd_opt(2018, 3, 8).unwrap(),
+            GermanState::Berlin
+        ));
+    }
+
+    #[test]
+    fn test_saxony_repentance_day() {
+        // Wednesday before November 23
+        let repentance = NaiveDate::from_ymd_opt(2024, 11, 20).unwrap();
+
+        assert!(is_public_holiday(repentance, GermanState::Saxony));
+        assert!(!is_public_holiday(repentance, GermanState::Bavaria));
+        assert!(!is_public_holiday(
+            NaiveDate::from_ymd_opt(2024, 11, 13).unwrap(),
+            GermanState::Saxony
+        ));
+    }
+
+    #[test]
+    fn test_schedule_uses_configured_state() {
+        let config = ScheduleConfig {
+            holidays: GermanState::Saxony,
+            ..Default::default()
+        };
+        let saxony = GymSchedule::new(&config);
+        let bavaria = GymSchedule::default();
+
+        // Thursday, 2024-10-31 (Reformation Day)
+        let reformation = NaiveDate::from_ymd_opt(2024, 10, 31).unwrap();
+        assert_eq!(saxony.get_open_hour(reformation), 9);
+        assert_eq!(bavaria.get_open_hour(reformation), 6);
+    }
+
+    // ==================== Closure Date Tests ====================
+
+    #[test]
+    fn test_closed_date_overrides_opening_hours() {
+        // 2024-06-11 is a Tuesday
+        let closed = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+        let schedule = GymSchedule::new(&ScheduleConfig {
+            timezone: "local".into(),
+            closed_dates: vec![closed],
+            ..Default::default()
+        });
+        let noon = make_local_datetime(2024, 6, 11, 12, 0);
+
+        assert!(GymSchedule::default().is_open(&noon));
+        assert!(!schedule.is_open(&noon));
+        assert!(schedule.open_windows(closed).is_empty());
+        assert_eq!(schedule.minutes_until_close(&noon), None);
+        // The following Wednesday keeps its normal hours
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 12, 12, 0)));
+    }
+
+    // ==================== ICS Calendar Tests ====================
+
+    const HOLIDAY_ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Gym anniversary\r
+DTSTART;VALUE=DATE:20240611\r
+DTEND;VALUE=DATE:20240612\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Local festival\r
+DTSTART;VALUE=DATE:20220725\r
+RRULE:FREQ=YEARLY;\r
+ COUNT=5\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Staff meeting\r
+DTSTART:20240612T090000Z\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_parse_ics_fixed_and_yearly_events() {
+        let holidays = parse_ics_holidays(HOLIDAY_ICS);
+
+        assert!(holidays.contains(&NaiveDate::from_ymd_opt(2024, 6, 11).unwrap()));
+        for year in 2022..=2026 {
+            assert!(holidays.contains(&NaiveDate::from_ymd_opt(year, 7, 25).unwrap()));
+        }
+        // COUNT=5 stops after 2026; timed events are not holidays
+        assert!(!holidays.contains(&NaiveDate::from_ymd_opt(2027, 7, 25).unwrap()));
+        assert!(!holidays.contains(&NaiveDate::from_ymd_opt(2024, 6, 12).unwrap()));
+        assert_eq!(holidays.len(), 6);
+    }
+
+    #[test]
+    fn test_yearly_rule_honours_until() {
+        let start = NaiveDate::from_ymd_opt(2020, 12, 24).unwrap();
+        let dates = yearly_occurrences(start, "FREQ=YEARLY;UNTIL=20221231T000000Z");
+        assert_eq!(dates.len(), 3);
+        assert_eq!(
+            dates.last(),
+            Some(&NaiveDate::from_ymd_opt(2022, 12, 24).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_load_holidays_from_ics_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("holidays.ics");
+        std::fs::write(&path, HOLIDAY_ICS).unwrap();
+
+        let holidays = load_holidays_from_ics(&path).unwrap();
+        assert_eq!(holidays, parse_ics_holidays(HOLIDAY_ICS));
+        assert!(load_holidays_from_ics(&dir.path().join("missing.ics")).is_err());
+    }
+
+    #[test]
+    fn test_calendar_holidays_use_weekend_hours() {
+        // 2024-06-11 is a Tuesday
+        let date = NaiveDate::from_ymd_opt(2024, 6, 11).unwrap();
+        let schedule = GymSchedule::default().with_holidays(parse_ics_holidays(HOLIDAY_ICS));
+
+        assert_eq!(schedule.get_open_hour(date), 9);
+        assert_eq!(GymSchedule::default().get_open_hour(date), 6);
+    }
+
     // ==================== GymSchedule Tests ====================
 
     fn make_local_datetime(
@@ -298,10 +858,10 @@ mod tests {
     #[test]
     fn test_schedule_default_values() {
         let schedule = GymSchedule::default();
-        assert_eq!(schedule.weekday_open, 6);
-        assert_eq!(schedule.weekday_close, 23);
-        assert_eq!(schedule.weekend_open, 9);
-        assert_eq!(schedule.weekend_close, 21);
+        assert_eq!(schedule.weekday.open_hour, 6);
+        assert_eq!(schedule.weekday.close_hour, 23);
+        assert_eq!(schedule.weekend.open_hour, 9);
+        assert_eq!(schedule.weekend.close_hour, 21);
     }
 
     #[test]
@@ -372,6 +932,305 @@ mod tests {
         assert!(schedule.is_open(&time));
     }
 
+    // ==================== Per-Day Schedule Tests ====================
+
+    fn per_day_schedule() -> GymSchedule {
+        let hours = |open_hour, close_hour| ScheduleHours {
+            open_hour,
+            close_hour,
+            windows: None,
+        };
+        GymSchedule::new(&ScheduleConfig {
+            days: Some([
+                hours(6, 23),
+                hours(6, 23),
+                hours(6, 23),
+                hours(6, 23),
+                hours(6, 22),
+                hours(8, 20),
+                hours(10, 18),
+            ]),
+            timezone: "local".into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_per_day_saturday_differs_from_sunday() {
+        let schedule = per_day_schedule();
+
+        // 2024-06-15 is a Saturday, 2024-06-16 a Sunday
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 15, 9, 0)));
+        assert!(!schedule.is_open(&make_local_datetime(2024, 6, 16, 9, 0)));
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 15, 19, 0)));
+        assert!(!schedule.is_open(&make_local_datetime(2024, 6, 16, 19, 0)));
+    }
+
+    #[test]
+    fn test_per_day_hours_lookup() {
+        let schedule = per_day_schedule();
+
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 14).unwrap();
+        let saturday = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let sunday = NaiveDate::from_ymd_opt(2024, 6, 16).unwrap();
+        assert_eq!(schedule.get_close_hour(friday), 22);
+        assert_eq!(schedule.get_open_hour(saturday), 8);
+        assert_eq!(schedule.get_close_hour(saturday), 20);
+        assert_eq!(schedule.get_open_hour(sunday), 10);
+        assert_eq!(schedule.get_close_hour(sunday), 18);
+    }
+
+    #[test]
+    fn test_per_day_holiday_uses_sunday_hours() {
+        let schedule = per_day_schedule();
+
+        // Christmas 2024 is a Wednesday
+        let christmas = NaiveDate::from_ymd_opt(2024, 12, 25).unwrap();
+        assert_eq!(schedule.get_open_hour(christmas), 10);
+        assert_eq!(schedule.get_close_hour(christmas), 18);
+    }
+
+    #[test]
+    fn test_per_day_next_transition() {
+        let schedule = per_day_schedule();
+
+        // Saturday after closing: next opening is Sunday at 10:00
+        let from = make_local_datetime(2024, 6, 15, 21, 0);
+        let next = schedule.next_transition(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 16, 10, 0));
+    }
+
+    // ==================== Midday Closure Tests ====================
+
+    fn midday_closure_schedule() -> GymSchedule {
+        let mut schedule = GymSchedule::new_for_test(6, 23, 9, 21);
+        schedule.weekday.windows = Some(vec![(6, 13), (15, 23)]);
+        schedule
+    }
+
+    #[test]
+    fn test_midday_closure_is_closed() {
+        let schedule = midday_closure_schedule();
+
+        // Wednesday 2024-06-12
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 12, 12, 0)));
+        assert!(!schedule.is_open(&make_local_datetime(2024, 6, 12, 14, 0)));
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 12, 16, 0)));
+    }
+
+    #[test]
+    fn test_midday_closure_boundaries() {
+        let schedule = midday_closure_schedule();
+
+        // Closing is inclusive of :00, reopening at 15:00
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 12, 13, 0)));
+        assert!(!schedule.is_open(&make_local_datetime(2024, 6, 12, 13, 1)));
+        assert!(!schedule.is_open(&make_local_datetime(2024, 6, 12, 14, 59)));
+        assert!(schedule.is_open(&make_local_datetime(2024, 6, 12, 15, 0)));
+    }
+
+    #[test]
+    fn test_midday_closure_open_and_close_hours() {
+        let schedule = midday_closure_schedule();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 12).unwrap();
+
+        assert_eq!(schedule.get_open_hour(date), 6);
+        assert_eq!(schedule.get_close_hour(date), 23);
+        assert_eq!(schedule.open_windows(date), vec![(6, 13), (15, 23)]);
+    }
+
+    #[test]
+    fn test_midday_closure_transitions() {
+        let schedule = midday_closure_schedule();
+
+        let next = schedule
+            .next_transition(&make_local_datetime(2024, 6, 12, 12, 0))
+            .unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 12, 13, 1));
+
+        let next = schedule
+            .next_transition(&make_local_datetime(2024, 6, 12, 14, 0))
+            .unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 12, 15, 0));
+    }
+
+    #[test]
+    fn test_minutes_until_midday_closure() {
+        let schedule = midday_closure_schedule();
+
+        let time = make_local_datetime(2024, 6, 12, 12, 30);
+        assert_eq!(schedule.minutes_until_close(&time), Some(30));
+        let time = make_local_datetime(2024, 6, 12, 14, 0);
+        assert_eq!(schedule.minutes_until_close(&time), None);
+    }
+
+    // ==================== Transition Tests ====================
+
+    #[test]
+    fn test_next_open_time_across_weekend() {
+        let schedule = GymSchedule::default();
+
+        // Saturday 2024-06-15 after the 21:00 close opens Sunday at 09:00
+        let from = make_local_datetime(2024, 6, 15, 22, 0);
+        let next = schedule.next_open_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 16, 9, 0));
+
+        // Sunday evening opens Monday at 06:00
+        let from = make_local_datetime(2024, 6, 16, 21, 30);
+        let next = schedule.next_open_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 17, 6, 0));
+    }
+
+    #[test]
+    fn test_next_open_time_on_holiday() {
+        let schedule = GymSchedule::default();
+
+        // Christmas 2024 is a Wednesday: weekend hours, so not open at 06:00
+        let from = make_local_datetime(2024, 12, 25, 5, 0);
+        let next = schedule.next_open_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 12, 25, 9, 0));
+
+        // After closing on Christmas, the 26th is a holiday too
+        let from = make_local_datetime(2024, 12, 25, 22, 0);
+        let next = schedule.next_open_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 12, 26, 9, 0));
+    }
+
+    #[test]
+    fn test_next_open_time_while_open() {
+        let schedule = GymSchedule::default();
+
+        // Open on Wednesday: next opening is Thursday morning
+        let from = make_local_datetime(2024, 6, 12, 10, 0);
+        let next = schedule.next_open_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 13, 6, 0));
+    }
+
+    #[test]
+    fn test_next_close_time_while_open() {
+        let schedule = GymSchedule::default();
+
+        let from = make_local_datetime(2024, 6, 12, 10, 0);
+        let next = schedule.next_close_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 12, 23, 1));
+    }
+
+    #[test]
+    fn test_next_close_time_while_closed() {
+        let schedule = GymSchedule::default();
+
+        // Closed Saturday night: next close is Sunday evening
+        let from = make_local_datetime(2024, 6, 15, 22, 0);
+        let next = schedule.next_close_time(&from).unwrap();
+        assert_eq!(next, make_local_datetime(2024, 6, 16, 21, 1));
+    }
+
+    #[test]
+    fn test_next_open_and_close_always_open() {
+        let schedule = GymSchedule::new_for_test(0, 24, 0, 24);
+        let from = make_local_datetime(2024, 6, 12, 10, 0);
+
+        assert!(schedule.next_open_time(&from).is_none());
+        assert!(schedule.next_close_time(&from).is_none());
+    }
+
+    #[test]
+    fn test_next_transition_when_closed() {
+        let schedule = GymSchedule::default();
+        // Monday at 05:30, opens at 06:00
+        let time = make_local_datetime(2024, 2, 12, 5, 30);
+        assert_eq!(
+            schedule.next_transition(&time),
+            Some(make_local_datetime(2024, 2, 12, 6, 0))
+        );
+    }
+
+    #[test]
+    fn test_next_transition_when_open() {
+        let schedule = GymSchedule::default();
+        // Monday at 10:00, closes after 23:00
+        let time = make_local_datetime(2024, 2, 12, 10, 0);
+        assert_eq!(
+            schedule.next_transition(&time),
+            Some(make_local_datetime(2024, 2, 12, 23, 1))
+        );
+    }
+
+    #[test]
+    fn test_next_transition_after_close_rolls_to_next_day() {
+        let schedule = GymSchedule::default();
+        // Friday at 23:30, next opening is Saturday 09:00 (weekend hours)
+        let time = make_local_datetime(2024, 2, 16, 23, 30);
+        assert_eq!(
+            schedule.next_transition(&time),
+            Some(make_local_datetime(2024, 2, 17, 9, 0))
+        );
+    }
+
+    #[test]
+    fn test_opened_between_crossing_open_boundary() {
+        let schedule = GymSchedule::default();
+        // Two clock reads straddling the 06:00 weekday opening
+        let previous = make_local_datetime(2024, 2, 12, 5, 59);
+        let now = make_local_datetime(2024, 2, 12, 6, 0);
+        assert!(schedule.opened_between(&previous, &now));
+    }
+
+    #[test]
+    fn test_opened_between_no_crossing() {
+        let schedule = GymSchedule::default();
+        // Both reads before opening
+        let previous = make_local_datetime(2024, 2, 12, 5, 30);
+        let now = make_local_datetime(2024, 2, 12, 5, 59);
+        assert!(!schedule.opened_between(&previous, &now));
+
+        // Both reads while open
+        let previous = make_local_datetime(2024, 2, 12, 10, 0);
+        let now = make_local_datetime(2024, 2, 12, 10, 1);
+        assert!(!schedule.opened_between(&previous, &now));
+    }
+
+    #[test]
+    fn test_opened_between_ignores_closing() {
+        let schedule = GymSchedule::default();
+        // Crossing the closing boundary must not request a fetch
+        let previous = make_local_datetime(2024, 2, 12, 23, 0);
+        let now = make_local_datetime(2024, 2, 12, 23, 1);
+        assert!(!schedule.opened_between(&previous, &now));
+    }
+
+    #[test]
+    fn test_minutes_until_close_before_closing() {
+        let schedule = GymSchedule::default();
+        // Monday at 22:45, closes at 23:00
+        let time = make_local_datetime(2024, 2, 12, 22, 45);
+        assert_eq!(schedule.minutes_until_close(&time), Some(15));
+    }
+
+    #[test]
+    fn test_minutes_until_close_at_closing() {
+        let schedule = GymSchedule::default();
+        let time = make_local_datetime(2024, 2, 12, 23, 0);
+        assert_eq!(schedule.minutes_until_close(&time), Some(0));
+    }
+
+    #[test]
+    fn test_minutes_until_close_when_closed() {
+        let schedule = GymSchedule::default();
+        let before_open = make_local_datetime(2024, 2, 12, 5, 30);
+        let after_close = make_local_datetime(2024, 2, 12, 23, 30);
+        assert_eq!(schedule.minutes_until_close(&before_open), None);
+        assert_eq!(schedule.minutes_until_close(&after_close), None);
+    }
+
+    #[test]
+    fn test_minutes_until_close_weekend_hours() {
+        let schedule = GymSchedule::default();
+        // Saturday at 20:00, closes at 21:00
+        let time = make_local_datetime(2024, 2, 17, 20, 0);
+        assert_eq!(schedule.minutes_until_close(&time), Some(60));
+    }
+
     // ==================== DST Transition Tests ====================
     // Germany DST: Last Sunday in March (2:00→3:00) and October (3:00→2:00)
 
@@ -479,6 +1338,48 @@ mod tests {
         assert!(schedule.is_open(&at_closing)); // At exactly 21:00 with minute=0
     }
 
+    // ==================== Time Zone Tests ====================
+
+    fn berlin_schedule() -> GymSchedule {
+        GymSchedule::new(&ScheduleConfig {
+            timezone: "Europe/Berlin".into(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_now_local_applies_berlin_dst() {
+        let schedule = berlin_schedule();
+
+        let winter = MockClock::new(Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap());
+        assert_eq!(schedule.now_local(&winter).hour(), 11);
+
+        let summer = MockClock::new(Utc.with_ymd_and_hms(2024, 7, 15, 10, 0, 0).unwrap());
+        assert_eq!(schedule.now_local(&summer).hour(), 12);
+    }
+
+    #[test]
+    fn test_is_open_converts_to_schedule_zone() {
+        let schedule = berlin_schedule();
+
+        // Monday 05:30 UTC is 06:30 in Berlin, after the 06:00 opening
+        let early = Utc.with_ymd_and_hms(2024, 1, 15, 5, 30, 0).unwrap();
+        assert!(schedule.is_open(&early));
+        // Monday 22:30 UTC is 23:30 in Berlin, after the 23:00 closing
+        let late = Utc.with_ymd_and_hms(2024, 1, 15, 22, 30, 0).unwrap();
+        assert!(!schedule.is_open(&late));
+    }
+
+    #[test]
+    fn test_unknown_timezone_falls_back_to_local() {
+        let schedule = GymSchedule::new(&ScheduleConfig {
+            timezone: "Mars/Olympus_Mons".into(),
+            ..Default::default()
+        });
+        let time = make_local_datetime(2024, 1, 15, 6, 30);
+        assert!(schedule.is_open(&time));
+    }
+
     // ==================== Property-Based Tests ====================
 
     #[cfg(test)]
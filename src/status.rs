@@ -0,0 +1,370 @@
+//! Daemon Status Module
+//!
+//! Tracks the fetch loop's progress and serves it as JSON on `GET /health`,
+//! so a headless daemon can be monitored. The HTTP handling is deliberately
+//! minimal: one request per connection, no keep-alive.
+
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{config::GymEntry, db::Database, schedule::GymSchedule, traits::SystemClock};
+
+/// Time a client gets to send its request line.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Status of one monitored gym.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GymHealth {
+    pub id: String,
+    pub name: String,
+    /// Time of the last successfully stored reading
+    pub last_fetch: Option<DateTime<Utc>>,
+    pub last_percentage: Option<f64>,
+    /// Records stored since local midnight; `None` if the count failed
+    pub records_today: Option<i64>,
+}
+
+/// Body of the `/health` response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    /// Whether the schedule had the gym open at the last fetch tick
+    pub gym_open: bool,
+    pub gyms: Vec<GymHealth>,
+}
+
+impl HealthReport {
+    /// Healthy unless a database query failed.
+    pub fn is_healthy(&self) -> bool {
+        self.gyms.iter().all(|gym| gym.records_today.is_some())
+    }
+}
+
+/// Fetch loop state shared with the status endpoint.
+#[derive(Debug, Clone)]
+pub struct DaemonStatus {
+    inner: Arc<Mutex<HealthReport>>,
+}
+
+impl DaemonStatus {
+    /// Start tracking the given gyms, with nothing fetched yet.
+    pub fn new(gyms: &[GymEntry]) -> Self {
+        let gyms = gyms.iter().map(unfetched).collect();
+        Self {
+            inner: Arc::new(Mutex::new(HealthReport {
+                gym_open: false,
+                gyms,
+            })),
+        }
+    }
+
+    /// Track a reloaded gym list, keeping the state of gyms still in it.
+    pub fn set_gyms(&self, gyms: &[GymEntry]) {
+        let mut report = self.inner.lock().unwrap();
+        let previous = std::mem::take(&mut report.gyms);
+        report.gyms = gyms
+            .iter()
+            .map(|entry| {
+                let health = previous.iter().find(|gym| gym.id == entry.id);
+                health.map_or_else(
+                    || unfetched(entry),
+                    |gym| GymHealth {
+                        name: entry.name.clone(),
+                        ..gym.clone()
+                    },
+                )
+            })
+            .collect();
+    }
+
+    pub fn set_open(&self, open: bool) {
+        self.inner.lock().unwrap().gym_open = open;
+    }
+
+    /// Record a successfully stored reading.
+    pub fn record_fetch(&self, gym_id: &str, percentage: f64, at: DateTime<Utc>) {
+        let mut report = self.inner.lock().unwrap();
+        if let Some(gym) = report.gyms.iter_mut().find(|gym| gym.id == gym_id) {
+            gym.last_fetch = Some(at);
+            gym.last_percentage = Some(percentage);
+        }
+    }
+
+    /// Current state, without today's record counts.
+    pub fn snapshot(&self) -> HealthReport {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// Current state with each gym's records counted since `since`.
+    pub async fn report(&self, database: &Database, since: DateTime<Utc>) -> HealthReport {
+        let mut report = self.snapshot();
+        for gym in &mut report.gyms {
+            gym.records_today = match database
+                .clone()
+                .with_gym(gym.id.clone())
+                .count_records_since(since)
+                .await
+            {
+                Ok(count) => Some(count),
+                Err(e) => {
+                    tracing::warn!(
+                        "Health check failed to count records for {}: {:#}",
+                        gym.id,
+                        e
+                    );
+                    None
+                }
+            };
+        }
+        report
+    }
+}
+
+fn unfetched(entry: &GymEntry) -> GymHealth {
+    GymHealth {
+        id: entry.id.clone(),
+        name: entry.name.clone(),
+        last_fetch: None,
+        last_percentage: None,
+        records_today: Some(0),
+    }
+}
+
+/// A complete HTTP response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    /// Serialize as an HTTP/1.1 response that closes the connection.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let reason = match self.status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Error",
+        };
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: \
+             {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+/// Response for a request line other than `GET /health`, or `None` for the
+/// health check itself.
+pub fn route(request_line: &str) -> Option<HttpResponse> {
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    // Ignore any query string
+    let path = path.split('?').next().unwrap_or("");
+
+    if path != "/health" {
+        Some(HttpResponse::error(404, "not found"))
+    } else if method != "GET" {
+        Some(HttpResponse::error(405, "method not allowed"))
+    } else {
+        None
+    }
+}
+
+/// The `/health` response for a report: 200 when healthy, 503 otherwise.
+pub fn health_response(report: &HealthReport) -> HttpResponse {
+    HttpResponse {
+        status: if report.is_healthy() { 200 } else { 503 },
+        body: serde_json::to_string(report).unwrap_or_default(),
+    }
+}
+
+/// Serve the status endpoint on `bind` until the task is dropped.
+///
+/// Records are counted from midnight in the schedule's time zone.
+pub async fn serve(
+    bind: IpAddr,
+    port: u16,
+    status: DaemonStatus,
+    database: Database,
+    schedule: GymSchedule,
+) -> Result<()> {
+    let listener = TcpListener::bind((bind, port))
+        .await
+        .with_context(|| format!("Failed to bind status endpoint to {}:{}", bind, port))?;
+    tracing::info!("Status endpoint listening on {}:{}", bind, port);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to accept status connection: {}", e);
+                continue;
+            }
+        };
+        let (status, database, schedule) = (status.clone(), database.clone(), schedule.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &status, &database, &schedule).await {
+                tracing::debug!("Status request failed: {:#}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    status: &DaemonStatus,
+    database: &Database,
+    schedule: &GymSchedule,
+) -> Result<()> {
+    let mut buf = [0u8; 1024];
+    let read = tokio::time::timeout(READ_TIMEOUT, stream.read(&mut buf))
+        .await
+        .context("Timed out reading request")??;
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let request_line = request.lines().next().unwrap_or("");
+
+    let response = match route(request_line) {
+        Some(response) => response,
+        None => {
+            let today = schedule.now_local(&SystemClock).date_naive();
+            let since = schedule
+                .day_start(today)
+                .context("Invalid local datetime for start of day")?;
+            health_response(&status.report(database, since).await)
+        }
+    };
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn entries() -> Vec<GymEntry> {
+        vec![
+            GymEntry {
+                id: "ffb".to_string(),
+                name: "Fürstenfeldbruck".to_string(),
+                api_url: "https://example.com/ffb".to_string(),
+            },
+            GymEntry {
+                id: "gap".to_string(),
+                name: "Garmisch".to_string(),
+                api_url: "https://example.com/gap".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_health_response_reports_seeded_state() {
+        let status = DaemonStatus::new(&entries());
+        let at = Utc.with_ymd_and_hms(2024, 6, 12, 14, 3, 0).unwrap();
+        status.set_open(true);
+        status.record_fetch("ffb", 42.5, at);
+
+        let mut report = status.snapshot();
+        report.gyms[0].records_today = Some(311);
+        let response = health_response(&report);
+
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["gym_open"], true);
+        assert_eq!(body["gyms"][0]["id"], "ffb");
+        assert_eq!(body["gyms"][0]["last_fetch"], "2024-06-12T14:03:00Z");
+        assert_eq!(body["gyms"][0]["last_percentage"], 42.5);
+        assert_eq!(body["gyms"][0]["records_today"], 311);
+        // Nothing fetched yet for the second gym
+        assert!(body["gyms"][1]["last_fetch"].is_null());
+        assert!(body["gyms"][1]["last_percentage"].is_null());
+    }
+
+    #[test]
+    fn test_health_response_unhealthy_when_count_failed() {
+        let status = DaemonStatus::new(&entries());
+        let mut report = status.snapshot();
+        report.gyms[1].records_today = None;
+
+        assert_eq!(health_response(&report).status, 503);
+    }
+
+    #[test]
+    fn test_record_fetch_ignores_unknown_gym() {
+        let status = DaemonStatus::new(&entries());
+        status.record_fetch("elsewhere", 10.0, Utc::now());
+
+        assert!(
+            status
+                .snapshot()
+                .gyms
+                .iter()
+                .all(|g| g.last_fetch.is_none())
+        );
+    }
+
+    #[test]
+    fn test_set_gyms_keeps_state_of_remaining_gyms() {
+        let status = DaemonStatus::new(&entries());
+        let at = Utc.with_ymd_and_hms(2024, 6, 12, 14, 3, 0).unwrap();
+        status.record_fetch("gap", 42.5, at);
+
+        let mut reloaded = entries();
+        reloaded[0].id = "muc".to_string();
+        status.set_gyms(&reloaded);
+
+        let gyms = status.snapshot().gyms;
+        let ids: Vec<_> = gyms.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(ids, ["muc", "gap"]);
+        assert!(gyms[0].last_fetch.is_none());
+        assert_eq!(gyms[1].last_fetch, Some(at));
+        assert_eq!(gyms[1].last_percentage, Some(42.5));
+    }
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("GET /health HTTP/1.1"), None);
+        assert_eq!(route("GET /health?verbose=1 HTTP/1.1"), None);
+        assert_eq!(route("GET / HTTP/1.1").unwrap().status, 404);
+        assert_eq!(route("POST /health HTTP/1.1").unwrap().status, 405);
+        assert_eq!(route("").unwrap().status, 404);
+    }
+
+    #[test]
+    fn test_response_bytes_have_content_length() {
+        let response = HttpResponse {
+            status: 200,
+            body: "{}".to_string(),
+        };
+        let text = String::from_utf8(response.to_bytes()).unwrap();
+
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 2\r\n"));
+        assert!(text.ends_with("\r\n\r\n{}"));
+    }
+}
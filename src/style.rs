@@ -1,22 +1,80 @@
-use iced::Color;
+use iced::{Color, Theme};
 
-// Background colors
-pub const BG_DARK: Color = Color::from_rgb(0.04, 0.04, 0.06);
-pub const BG_CARD: Color = Color::from_rgb(0.09, 0.11, 0.15);
+/// UI colors for one theme.
+///
+/// Views and widgets resolve the active palette from the iced [`Theme`] with
+/// [`Palette::of`] instead of using fixed colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    // Background colors
+    pub background: Color,
+    pub card: Color,
 
-// Stroke/border colors
-pub const STROKE_DIM: Color = Color::from_rgb(0.2, 0.22, 0.28);
+    // Stroke/border colors
+    pub stroke: Color,
 
-// Accent colors
-pub const ACCENT_BLUE: Color = Color::from_rgb(0.35, 0.65, 0.95);
-pub const ACCENT_CYAN: Color = Color::from_rgb(0.2, 0.9, 0.9);
-pub const ACCENT_GREEN: Color = Color::from_rgb(0.2, 0.85, 0.5);
-pub const ACCENT_ORANGE: Color = Color::from_rgb(1.0, 0.6, 0.2);
-pub const ACCENT_RED: Color = Color::from_rgb(1.0, 0.35, 0.35);
+    // Accent colors
+    pub accent_blue: Color,
+    pub accent_cyan: Color,
+    pub accent_green: Color,
+    pub accent_orange: Color,
+    pub accent_red: Color,
 
-// Text colors
-pub const TEXT_BRIGHT: Color = Color::from_rgb(0.96, 0.97, 0.99);
-pub const TEXT_MUTED: Color = Color::from_rgb(0.6, 0.63, 0.7);
+    // Text colors
+    pub text: Color,
+    pub text_muted: Color,
 
-// Overlay colors
-pub const TOOLTIP_BG: Color = Color::from_rgba(0.09, 0.11, 0.15, 0.95);
+    // Overlay colors
+    pub tooltip_bg: Color,
+}
+
+impl Palette {
+    pub const DARK: Self = Self {
+        background: Color::from_rgb(0.04, 0.04, 0.06),
+        card: Color::from_rgb(0.09, 0.11, 0.15),
+        stroke: Color::from_rgb(0.2, 0.22, 0.28),
+        accent_blue: Color::from_rgb(0.35, 0.65, 0.95),
+        accent_cyan: Color::from_rgb(0.2, 0.9, 0.9),
+        accent_green: Color::from_rgb(0.2, 0.85, 0.5),
+        accent_orange: Color::from_rgb(1.0, 0.6, 0.2),
+        accent_red: Color::from_rgb(1.0, 0.35, 0.35),
+        text: Color::from_rgb(0.96, 0.97, 0.99),
+        text_muted: Color::from_rgb(0.6, 0.63, 0.7),
+        tooltip_bg: Color::from_rgba(0.09, 0.11, 0.15, 0.95),
+    };
+    /// Darker accents than [`Palette::DARK`] keep contrast on light
+    /// backgrounds.
+    pub const LIGHT: Self = Self {
+        background: Color::from_rgb(0.95, 0.96, 0.97),
+        card: Color::from_rgb(1.0, 1.0, 1.0),
+        stroke: Color::from_rgb(0.8, 0.82, 0.86),
+        accent_blue: Color::from_rgb(0.15, 0.45, 0.85),
+        accent_cyan: Color::from_rgb(0.0, 0.58, 0.64),
+        accent_green: Color::from_rgb(0.1, 0.62, 0.35),
+        accent_orange: Color::from_rgb(0.88, 0.48, 0.05),
+        accent_red: Color::from_rgb(0.85, 0.2, 0.2),
+        text: Color::from_rgb(0.1, 0.11, 0.14),
+        text_muted: Color::from_rgb(0.42, 0.45, 0.52),
+        tooltip_bg: Color::from_rgba(1.0, 1.0, 1.0, 0.95),
+    };
+
+    /// The palette matching the brightness of `theme`.
+    pub fn of(theme: &Theme) -> Self {
+        if theme.extended_palette().is_dark {
+            Self::DARK
+        } else {
+            Self::LIGHT
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_follows_theme_brightness() {
+        assert_eq!(Palette::of(&Theme::Dark), Palette::DARK);
+        assert_eq!(Palette::of(&Theme::Light), Palette::LIGHT);
+    }
+}
@@ -3,11 +3,28 @@
 //! This module provides traits for:
 //! - `Clock`: Abstracting time access for deterministic testing
 //! - `Notifier`: Abstracting system notifications for testing
+//! - `AsyncNotifier`: Network notifiers that send without blocking a thread
+//! - `NotificationStore`: Abstracting the notification audit log
+//! - `SoundSink`: Abstracting audio output for alert sounds
 
-use std::sync::{Arc, Mutex};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use anyhow::Result;
-use chrono::{DateTime, Local, Utc};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use lettre::{
+    Message, SmtpTransport, Transport,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+
+use crate::config::{EmailConfig, NetworkConfig};
 
 // ==================== Clock Trait ====================
 
@@ -21,6 +38,12 @@ pub trait Clock: Send + Sync {
 
     /// Get the current time in the local timezone.
     fn now_local(&self) -> DateTime<Local>;
+
+    /// Get the current time in the given timezone, e.g. the gym's, regardless
+    /// of the machine's local zone.
+    fn now_in(&self, tz: Tz) -> DateTime<Tz> {
+        self.now_utc().with_timezone(&tz)
+    }
 }
 
 /// System clock implementation using real time.
@@ -51,6 +74,11 @@ impl MockClock {
         }
     }
 
+    /// Create a new mock clock frozen at the given zoned time.
+    pub fn new_zoned<Z: TimeZone>(time: DateTime<Z>) -> Self {
+        Self::new(time.with_timezone(&Utc))
+    }
+
     /// Set the mock clock to a new time.
     pub fn set_time(&self, time: DateTime<Utc>) {
         *self.utc_time.lock().unwrap() = time;
@@ -59,7 +87,7 @@ impl MockClock {
     /// Advance the clock by a duration.
     pub fn advance(&self, duration: chrono::Duration) {
         let mut time = self.utc_time.lock().unwrap();
-        *time = *time + duration;
+        *time += duration;
     }
 }
 
@@ -101,11 +129,276 @@ impl Notifier for SystemNotifier {
     }
 }
 
-/// Combined notifier that sends to both desktop and ntfy.sh.
 #[cfg(feature = "gui")]
+impl AsyncNotifier for SystemNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let (title, body) = (title.to_string(), body.to_string());
+            // notify-rust blocks until the notification server answers
+            tokio::task::spawn_blocking(move || SystemNotifier.notify(&title, &body)).await?
+        })
+    }
+}
+
+// ==================== Async Notifier Trait ====================
+
+/// Future returned by `AsyncNotifier::notify_async`.
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Async counterpart of `Notifier` for channels that go over the network.
+///
+/// Returns a boxed future so notifiers can be combined as `dyn AsyncNotifier`.
+pub trait AsyncNotifier: Send + Sync {
+    /// Send a notification with the given title and body.
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a>;
+}
+
+/// Send through all `notifiers` concurrently.
+///
+/// Every send runs to completion; the first error is returned.
+pub async fn notify_all(
+    notifiers: &[Arc<dyn AsyncNotifier>],
+    title: &str,
+    body: &str,
+) -> Result<()> {
+    let mut sends = tokio::task::JoinSet::new();
+    for notifier in notifiers {
+        let notifier = notifier.clone();
+        let (title, body) = (title.to_string(), body.to_string());
+        sends.spawn(async move { notifier.notify_async(&title, &body).await });
+    }
+
+    let mut result = Ok(());
+    while let Some(joined) = sends.join_next().await {
+        let sent = joined.map_err(anyhow::Error::from).and_then(|sent| sent);
+        if result.is_ok() {
+            result = sent;
+        }
+    }
+    result
+}
+
+// ==================== Network Notifiers ====================
+
+/// Notifier that publishes to an ntfy.sh topic for phone notifications.
 #[derive(Debug, Clone)]
+pub struct NtfyNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl NtfyNotifier {
+    /// Create a notifier for the given ntfy.sh topic.
+    pub fn new(topic: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            url: format!("https://ntfy.sh/{}", topic),
+            client,
+        }
+    }
+}
+
+impl AsyncNotifier for NtfyNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .body(format!("{}\n{}", title, body))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .context("Failed to send ntfy notification")?;
+            Ok(())
+        })
+    }
+}
+
+/// Hands a prepared email to its transport.
+type EmailSender = Arc<dyn Fn(&Message) -> Result<()> + Send + Sync>;
+
+/// Notifier that sends each notification as an email over SMTP.
+pub struct EmailNotifier {
+    from: Mailbox,
+    to: Mailbox,
+    send: EmailSender,
+}
+
+impl EmailNotifier {
+    /// Create an email notifier for the configured SMTP server.
+    ///
+    /// Connection and authentication failures are reported by `notify`.
+    pub fn new(config: &EmailConfig) -> Result<Self> {
+        let builder = if config.smtp_port == 465 {
+            SmtpTransport::relay(&config.smtp_host)
+        } else {
+            SmtpTransport::starttls_relay(&config.smtp_host)
+        }
+        .context("Invalid SMTP host")?;
+
+        let mut builder = builder
+            .port(config.smtp_port)
+            .timeout(Some(Duration::from_secs(10)));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        let transport = builder.build();
+
+        Self::with_sender(config, move |message| {
+            transport
+                .send(message)
+                .map(|_| ())
+                .context("Failed to send email")
+        })
+    }
+
+    /// Create an email notifier that hands messages to `send` instead of an
+    /// SMTP server.
+    pub fn with_sender(
+        config: &EmailConfig,
+        send: impl Fn(&Message) -> Result<()> + Send + Sync + 'static,
+    ) -> Result<Self> {
+        Ok(Self {
+            from: config
+                .from
+                .parse()
+                .context("Invalid email sender address")?,
+            to: config
+                .to
+                .parse()
+                .context("Invalid email recipient address")?,
+            send: Arc::new(send),
+        })
+    }
+
+    /// Build the email for a notification.
+    fn message(&self, title: &str, body: &str) -> Result<Message> {
+        Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(title)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string())
+            .context("Failed to build email")
+    }
+}
+
+impl fmt::Debug for EmailNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmailNotifier")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn notify(&self, title: &str, body: &str) -> Result<()> {
+        let message = self.message(title, body)?;
+        (self.send)(&message)
+    }
+}
+
+impl AsyncNotifier for EmailNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let message = self.message(title, body)?;
+            let send = self.send.clone();
+            // The SMTP transport is blocking
+            tokio::task::spawn_blocking(move || send(&message)).await?
+        })
+    }
+}
+
+/// Notifier that POSTs each notification as JSON to a webhook URL.
+///
+/// The payload is `{"title", "body", "timestamp"}`, which Discord and Slack
+/// incoming webhooks accept.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+    timeout: Duration,
+    connect_timeout: Duration,
+}
+
+impl WebhookNotifier {
+    /// Create a webhook notifier using the network timeouts from config.
+    pub fn new(url: impl Into<String>, network: &NetworkConfig) -> Result<Self> {
+        let timeout = Duration::from_secs(network.request_timeout_secs);
+        let connect_timeout = Duration::from_secs(network.connect_timeout_secs);
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .connect_timeout(connect_timeout)
+            .build()
+            .context("Failed to build webhook client")?;
+
+        Ok(Self {
+            url: url.into(),
+            client,
+            timeout,
+            connect_timeout,
+        })
+    }
+
+    /// The JSON body posted for a notification.
+    fn payload(title: &str, body: &str) -> serde_json::Value {
+        serde_json::json!({
+            "title": title,
+            "body": body,
+            "timestamp": Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Blocking send, for callers outside an async runtime.
+impl Notifier for WebhookNotifier {
+    fn notify(&self, title: &str, body: &str) -> Result<()> {
+        // Built per call, since a blocking client must not be dropped on the
+        // async runtime the notifier may be created on
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .context("Failed to build webhook client")?;
+
+        client
+            .post(&self.url)
+            .json(&Self::payload(title, body))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .context("Failed to send webhook notification")?;
+        Ok(())
+    }
+}
+
+impl AsyncNotifier for WebhookNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .json(&Self::payload(title, body))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .context("Failed to send webhook notification")?;
+            Ok(())
+        })
+    }
+}
+
+/// Combined notifier that shows a desktop notification and fans out to the
+/// configured network notifiers (ntfy.sh, a webhook and email).
+///
+/// The desktop notification is one channel among the others: a failure there
+/// does not stop the rest from being sent.
+#[cfg(feature = "gui")]
+#[derive(Clone)]
 pub struct CombinedNotifier {
-    ntfy_topic: Option<String>,
+    channels: Vec<Arc<dyn AsyncNotifier>>,
 }
 
 #[cfg(feature = "gui")]
@@ -115,43 +408,231 @@ impl CombinedNotifier {
     /// # Arguments
     /// * `ntfy_topic` - Optional ntfy.sh topic name for phone notifications
     pub fn new(ntfy_topic: Option<String>) -> Self {
-        Self { ntfy_topic }
+        let mut channels: Vec<Arc<dyn AsyncNotifier>> = vec![Arc::new(SystemNotifier)];
+        if let Some(topic) = ntfy_topic {
+            channels.push(Arc::new(NtfyNotifier::new(&topic)));
+        }
+
+        Self { channels }
+    }
+
+    /// Also POST every notification to a webhook.
+    pub fn with_webhook(mut self, webhook: WebhookNotifier) -> Self {
+        self.channels.push(Arc::new(webhook));
+        self
+    }
+
+    /// Also send every notification by email.
+    pub fn with_email(mut self, email: EmailNotifier) -> Self {
+        self.channels.push(Arc::new(email));
+        self
     }
 }
 
 #[cfg(feature = "gui")]
-impl Notifier for CombinedNotifier {
-    fn notify(&self, title: &str, body: &str) -> Result<()> {
-        // Send desktop notification
-        notify_rust::Notification::new()
-            .summary(title)
-            .body(body)
-            .appname("Hardy Monitor")
-            .show()?;
+impl fmt::Debug for CombinedNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CombinedNotifier")
+            .field("channels", &self.channels.len())
+            .finish()
+    }
+}
 
-        // Send ntfy.sh notification if configured
-        if let Some(ref topic) = self.ntfy_topic {
-            let url = format!("https://ntfy.sh/{}", topic);
-            let message = format!("{}\n{}", title, body);
-
-            // Spawn async task to send ntfy notification (fire and forget)
-            let url_clone = url.clone();
-            let message_clone = message.clone();
-            std::thread::spawn(move || {
-                // Use blocking reqwest to avoid async complexity
-                if let Ok(client) = reqwest::blocking::Client::builder()
-                    .timeout(std::time::Duration::from_secs(10))
-                    .build()
-                {
-                    let _ = client.post(&url_clone).body(message_clone).send();
-                }
-            });
-        }
+#[cfg(feature = "gui")]
+impl AsyncNotifier for CombinedNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(notify_all(&self.channels, title, body))
+    }
+}
+
+// ==================== Alert Sound ====================
+
+/// Trait for abstracting audio output.
+///
+/// `play` blocks until the sound has finished.
+pub trait SoundSink: Send + Sync {
+    /// Play the alert sound once.
+    fn play(&self) -> Result<()>;
+}
+
+/// Sound sink that plays the bundled alert sound on the default output
+/// device.
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Default)]
+pub struct SystemSoundSink;
 
+#[cfg(feature = "gui")]
+impl SoundSink for SystemSoundSink {
+    fn play(&self) -> Result<()> {
+        const ALERT_WAV: &[u8] = include_bytes!("../assets/alert.wav");
+
+        // The stream must outlive playback
+        let (_stream, handle) =
+            rodio::OutputStream::try_default().context("Failed to open audio output")?;
+        let sink = rodio::Sink::try_new(&handle).context("Failed to create audio sink")?;
+        let source = rodio::Decoder::new(std::io::Cursor::new(ALERT_WAV))
+            .context("Failed to decode alert sound")?;
+        sink.append(source);
+        sink.sleep_until_end();
         Ok(())
     }
 }
 
+/// Plays the alert sound when enabled in config.
+///
+/// Playback is best-effort: errors are logged and never reach the caller, so
+/// a missing audio device cannot break a notification.
+#[derive(Clone)]
+pub struct AlertSound {
+    sink: Arc<dyn SoundSink>,
+    enabled: bool,
+}
+
+impl AlertSound {
+    /// Create an alert sound playing through `sink` if `enabled`.
+    pub fn new(sink: Arc<dyn SoundSink>, enabled: bool) -> Self {
+        Self { sink, enabled }
+    }
+
+    /// Play the sound on a blocking thread, if enabled.
+    pub async fn play(&self) {
+        if !self.enabled {
+            return;
+        }
+        let sink = self.sink.clone();
+        match tokio::task::spawn_blocking(move || sink.play()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("Failed to play alert sound: {:#}", e),
+            Err(e) => tracing::warn!("Alert sound task failed: {}", e),
+        }
+    }
+}
+
+// ==================== Notification Cooldown ====================
+
+/// Suppresses notifications fired within a cooldown of the previous one.
+///
+/// Keeps alerts from repeating while occupancy oscillates around the threshold.
+#[derive(Clone)]
+pub struct NotificationGate {
+    cooldown: chrono::Duration,
+    clock: Arc<dyn Clock>,
+    last_sent: Option<DateTime<Utc>>,
+}
+
+impl NotificationGate {
+    /// Create a gate that lets one notification through per `cooldown_secs`.
+    pub fn new(cooldown_secs: u64, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            cooldown: chrono::Duration::seconds(cooldown_secs as i64),
+            clock,
+            last_sent: None,
+        }
+    }
+
+    /// Whether a notification may be sent now. Allowed sends start a new
+    /// cooldown.
+    pub fn allow(&mut self) -> bool {
+        let now = self.clock.now_utc();
+        if self
+            .last_sent
+            .is_some_and(|last| now - last < self.cooldown)
+        {
+            return false;
+        }
+        self.last_sent = Some(now);
+        true
+    }
+}
+
+// ==================== Threshold Alerts ====================
+
+/// Which threshold alerts a reading triggered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlertCrossings {
+    /// Occupancy dropped below the low threshold
+    pub empty: bool,
+    /// Occupancy rose above the high threshold
+    pub busy: bool,
+}
+
+/// Debounce state for the "empty" and "busy" threshold alerts.
+///
+/// Each alert fires once when a reading crosses into its range and re-arms
+/// once a reading leaves it again. The two track independently.
+#[derive(Debug, Clone, Default)]
+pub struct ThresholdAlerts {
+    was_below: bool,
+    was_above: bool,
+}
+
+impl ThresholdAlerts {
+    /// Record a reading and report which alerts it crossed into.
+    pub fn update(&mut self, percentage: f64, low: f64, high: f64) -> AlertCrossings {
+        let is_below = percentage < low;
+        let is_above = percentage > high;
+        let crossings = AlertCrossings {
+            empty: is_below && !self.was_below,
+            busy: is_above && !self.was_above,
+        };
+        self.was_below = is_below;
+        self.was_above = is_above;
+        crossings
+    }
+
+    /// Adopt the current reading without firing, e.g. when alerts are enabled.
+    pub fn reset(&mut self, percentage: Option<f64>, low: f64, high: f64) {
+        self.was_below = percentage.is_some_and(|p| p < low);
+        self.was_above = percentage.is_some_and(|p| p > high);
+    }
+}
+
+// ==================== Notification Audit ====================
+
+/// Trait for persisting sent notifications.
+///
+/// Implemented by the database layer; tests can inject an in-memory store.
+pub trait NotificationStore: Send + Sync {
+    /// Record a notification with the given title and body.
+    fn record(&self, title: &str, body: &str) -> Result<()>;
+}
+
+/// Notifier decorator that records every notification before delegating.
+///
+/// Wrap the outermost notifier (e.g. a `CombinedNotifier`) so a single audit
+/// trail covers every delivery channel.
+#[derive(Debug, Clone)]
+pub struct LoggingNotifier<N, S> {
+    inner: N,
+    store: S,
+}
+
+impl<N, S: NotificationStore> LoggingNotifier<N, S> {
+    /// Create a new logging notifier around `inner`.
+    pub fn new(inner: N, store: S) -> Self {
+        Self { inner, store }
+    }
+}
+
+impl<N: Notifier, S: NotificationStore> Notifier for LoggingNotifier<N, S> {
+    fn notify(&self, title: &str, body: &str) -> Result<()> {
+        // A failing audit log must never suppress the actual alert
+        if let Err(e) = self.store.record(title, body) {
+            tracing::warn!("Failed to record notification: {}", e);
+        }
+        self.inner.notify(title, body)
+    }
+}
+
+impl<N: AsyncNotifier, S: NotificationStore> AsyncNotifier for LoggingNotifier<N, S> {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        if let Err(e) = self.store.record(title, body) {
+            tracing::warn!("Failed to record notification: {}", e);
+        }
+        self.inner.notify_async(title, body)
+    }
+}
+
 /// Mock notifier for testing that records all notifications.
 #[derive(Debug, Clone, Default)]
 pub struct MockNotifier {
@@ -195,6 +676,50 @@ impl Notifier for MockNotifier {
     }
 }
 
+impl AsyncNotifier for MockNotifier {
+    fn notify_async<'a>(&'a self, title: &'a str, body: &'a str) -> NotifyFuture<'a> {
+        Box::pin(async move { self.notify(title, body) })
+    }
+}
+
+/// Mock sound sink for testing that counts playback attempts.
+#[derive(Debug, Clone, Default)]
+pub struct MockSoundSink {
+    plays: Arc<Mutex<usize>>,
+    fail: bool,
+}
+
+impl MockSoundSink {
+    /// Create a new mock sound sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a mock sound sink whose playback always fails, like a machine
+    /// without an audio device.
+    pub fn failing() -> Self {
+        Self {
+            fail: true,
+            ..Self::default()
+        }
+    }
+
+    /// Get the number of playback attempts.
+    pub fn play_count(&self) -> usize {
+        *self.plays.lock().unwrap()
+    }
+}
+
+impl SoundSink for MockSoundSink {
+    fn play(&self) -> Result<()> {
+        *self.plays.lock().unwrap() += 1;
+        if self.fail {
+            anyhow::bail!("No audio output device");
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::TimeZone;
@@ -232,6 +757,36 @@ mod tests {
         assert_eq!(clock.now_utc(), time2);
     }
 
+    #[test]
+    fn test_mock_clock_now_in_applies_offset() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 15, 10, 0, 0).unwrap());
+        let berlin = clock.now_in(chrono_tz::Europe::Berlin);
+
+        // Summer time: UTC+2
+        assert_eq!(berlin.naive_local().to_string(), "2024-06-15 12:00:00");
+        assert_eq!(berlin, clock.now_utc());
+
+        clock.set_time(Utc.with_ymd_and_hms(2024, 1, 15, 23, 30, 0).unwrap());
+        let berlin = clock.now_in(chrono_tz::Europe::Berlin);
+
+        // Winter time: UTC+1, past midnight
+        assert_eq!(berlin.naive_local().to_string(), "2024-01-16 00:30:00");
+    }
+
+    #[test]
+    fn test_mock_clock_new_zoned_freezes_instant() {
+        let zoned = chrono_tz::Europe::Berlin
+            .with_ymd_and_hms(2024, 6, 15, 12, 0, 0)
+            .unwrap();
+        let clock = MockClock::new_zoned(zoned);
+
+        assert_eq!(
+            clock.now_utc(),
+            Utc.with_ymd_and_hms(2024, 6, 15, 10, 0, 0).unwrap()
+        );
+        assert_eq!(clock.now_in(chrono_tz::Europe::Berlin), zoned);
+    }
+
     #[test]
     fn test_mock_clock_advance() {
         let start = Utc.with_ymd_and_hms(2024, 6, 15, 10, 0, 0).unwrap();
@@ -268,6 +823,296 @@ mod tests {
         );
     }
 
+    /// In-memory store for verifying what the logging notifier persisted.
+    #[derive(Default)]
+    struct MemoryStore {
+        rows: Mutex<Vec<(String, String)>>,
+    }
+
+    impl NotificationStore for &MemoryStore {
+        fn record(&self, title: &str, body: &str) -> Result<()> {
+            self.rows
+                .lock()
+                .unwrap()
+                .push((title.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    struct FailingStore;
+
+    impl NotificationStore for FailingStore {
+        fn record(&self, _title: &str, _body: &str) -> Result<()> {
+            anyhow::bail!("store unavailable")
+        }
+    }
+
+    #[test]
+    fn test_logging_notifier_records_and_forwards() {
+        let inner = MockNotifier::new();
+        let store = MemoryStore::default();
+        let notifier = LoggingNotifier::new(inner.clone(), &store);
+
+        notifier.notify("Gym is quiet", "Occupancy at 20%").unwrap();
+
+        assert_eq!(
+            inner.get_notifications(),
+            vec![("Gym is quiet".to_string(), "Occupancy at 20%".to_string())]
+        );
+        assert_eq!(
+            *store.rows.lock().unwrap(),
+            vec![("Gym is quiet".to_string(), "Occupancy at 20%".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_logging_notifier_forwards_when_store_fails() {
+        let inner = MockNotifier::new();
+        let notifier = LoggingNotifier::new(inner.clone(), FailingStore);
+
+        assert!(notifier.notify("Title", "Body").is_ok());
+        assert_eq!(inner.notification_count(), 1);
+    }
+
+    fn email_config() -> EmailConfig {
+        EmailConfig {
+            smtp_host: "localhost".to_string(),
+            smtp_port: 1,
+            username: None,
+            password: None,
+            from: "Hardy Monitor <alerts@example.com>".to_string(),
+            to: "me@example.com".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_email_notifier_formats_message() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let outbox = sent.clone();
+        let notifier = EmailNotifier::with_sender(&email_config(), move |message| {
+            outbox
+                .lock()
+                .unwrap()
+                .push(String::from_utf8(message.formatted())?);
+            Ok(())
+        })
+        .unwrap();
+
+        notifier.notify("Gym is quiet", "Occupancy at 20%").unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("From: \"Hardy Monitor\" <alerts@example.com>"));
+        assert!(sent[0].contains("To: me@example.com"));
+        assert!(sent[0].contains("Subject: Gym is quiet"));
+        assert!(sent[0].contains("Content-Type: text/plain"));
+        assert!(sent[0].ends_with("Occupancy at 20%"));
+    }
+
+    #[test]
+    fn test_email_notifier_surfaces_send_errors() {
+        let notifier = EmailNotifier::with_sender(&email_config(), |_| {
+            anyhow::bail!("535 authentication failed")
+        })
+        .unwrap();
+
+        let err = notifier.notify("Title", "Body").unwrap_err();
+
+        assert!(err.to_string().contains("authentication failed"));
+    }
+
+    #[test]
+    fn test_email_notifier_reports_unreachable_server() {
+        // Nothing listens on port 1, so the connection is refused
+        let notifier = EmailNotifier::new(&email_config()).unwrap();
+
+        assert!(notifier.notify("Title", "Body").is_err());
+    }
+
+    #[test]
+    fn test_email_notifier_rejects_invalid_address() {
+        let config = EmailConfig {
+            to: "not an address".to_string(),
+            ..email_config()
+        };
+
+        assert!(EmailNotifier::new(&config).is_err());
+    }
+
+    /// Waits for every other notifier sharing the barrier, so sequential
+    /// sends never finish.
+    struct BarrierNotifier(Arc<tokio::sync::Barrier>);
+
+    impl AsyncNotifier for BarrierNotifier {
+        fn notify_async<'a>(&'a self, _title: &'a str, _body: &'a str) -> NotifyFuture<'a> {
+            Box::pin(async move {
+                self.0.wait().await;
+                Ok(())
+            })
+        }
+    }
+
+    struct FailingNotifier;
+
+    impl AsyncNotifier for FailingNotifier {
+        fn notify_async<'a>(&'a self, _title: &'a str, _body: &'a str) -> NotifyFuture<'a> {
+            Box::pin(async { anyhow::bail!("channel unavailable") })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_notifier_records_async_calls() {
+        let notifier = MockNotifier::new();
+
+        notifier.notify_async("Title", "Body").await.unwrap();
+
+        assert_eq!(
+            notifier.get_notifications(),
+            vec![("Title".to_string(), "Body".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_sends_concurrently() {
+        let barrier = Arc::new(tokio::sync::Barrier::new(2));
+        let mock = MockNotifier::new();
+        let notifiers: Vec<Arc<dyn AsyncNotifier>> = vec![
+            Arc::new(BarrierNotifier(barrier.clone())),
+            Arc::new(BarrierNotifier(barrier)),
+            Arc::new(mock.clone()),
+        ];
+
+        let sent = tokio::time::timeout(
+            Duration::from_secs(5),
+            notify_all(&notifiers, "Title", "Body"),
+        )
+        .await;
+
+        assert!(sent.expect("sends should not wait on each other").is_ok());
+        assert_eq!(mock.notification_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_reports_error_after_all_sends() {
+        let mock = MockNotifier::new();
+        let notifiers: Vec<Arc<dyn AsyncNotifier>> =
+            vec![Arc::new(FailingNotifier), Arc::new(mock.clone())];
+
+        let err = notify_all(&notifiers, "Title", "Body").await.unwrap_err();
+
+        assert!(err.to_string().contains("channel unavailable"));
+        assert!(mock.was_called());
+    }
+
+    #[tokio::test]
+    async fn test_logging_notifier_records_async_notifications() {
+        let inner = MockNotifier::new();
+        let store = MemoryStore::default();
+        let notifier = LoggingNotifier::new(inner.clone(), &store);
+
+        notifier.notify_async("Title", "Body").await.unwrap();
+
+        assert_eq!(inner.notification_count(), 1);
+        assert_eq!(store.rows.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_alert_sound_plays_only_when_enabled() {
+        let sink = MockSoundSink::new();
+
+        AlertSound::new(Arc::new(sink.clone()), false).play().await;
+        assert_eq!(sink.play_count(), 0);
+
+        AlertSound::new(Arc::new(sink.clone()), true).play().await;
+        assert_eq!(sink.play_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_alert_sound_swallows_playback_errors() {
+        let sink = MockSoundSink::failing();
+
+        // Must complete without panicking
+        AlertSound::new(Arc::new(sink.clone()), true).play().await;
+        assert_eq!(sink.play_count(), 1);
+    }
+
+    #[test]
+    fn test_notification_gate_suppresses_within_cooldown() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 15, 10, 0, 0).unwrap());
+        let mut gate = NotificationGate::new(900, Arc::new(clock.clone()));
+        let notifier = MockNotifier::new();
+
+        for _ in 0..2 {
+            if gate.allow() {
+                notifier.notify("Title", "Body").unwrap();
+            }
+            clock.advance(chrono::Duration::minutes(10));
+        }
+        assert_eq!(notifier.notification_count(), 1);
+
+        // 20 minutes after the first alert the cooldown has passed
+        assert!(gate.allow());
+    }
+
+    #[test]
+    fn test_notification_gate_without_cooldown() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 6, 15, 10, 0, 0).unwrap());
+        let mut gate = NotificationGate::new(0, Arc::new(clock));
+
+        assert!(gate.allow());
+        assert!(gate.allow());
+    }
+
+    #[test]
+    fn test_threshold_alerts_track_independently() {
+        let mut alerts = ThresholdAlerts::default();
+        let update = |alerts: &mut ThresholdAlerts, p| alerts.update(p, 30.0, 75.0);
+
+        // Quiet, then crowded, then quiet again: each side fires once per crossing
+        assert_eq!(
+            update(&mut alerts, 20.0),
+            AlertCrossings {
+                empty: true,
+                busy: false
+            }
+        );
+        assert_eq!(update(&mut alerts, 25.0), AlertCrossings::default());
+        assert_eq!(
+            update(&mut alerts, 80.0),
+            AlertCrossings {
+                empty: false,
+                busy: true
+            }
+        );
+        assert_eq!(update(&mut alerts, 90.0), AlertCrossings::default());
+        assert_eq!(update(&mut alerts, 50.0), AlertCrossings::default());
+        assert_eq!(
+            update(&mut alerts, 10.0),
+            AlertCrossings {
+                empty: true,
+                busy: false
+            }
+        );
+        assert_eq!(
+            update(&mut alerts, 76.0),
+            AlertCrossings {
+                empty: false,
+                busy: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_threshold_alerts_reset_does_not_fire() {
+        let mut alerts = ThresholdAlerts::default();
+
+        alerts.reset(Some(80.0), 30.0, 75.0);
+        assert_eq!(alerts.update(85.0, 30.0, 75.0), AlertCrossings::default());
+
+        alerts.reset(None, 30.0, 75.0);
+        assert!(alerts.update(85.0, 30.0, 75.0).busy);
+    }
+
     #[test]
     fn test_mock_notifier_clear() {
         let notifier = MockNotifier::new();
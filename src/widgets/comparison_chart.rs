@@ -1,43 +1,113 @@
 use iced::{
     Color, Point, Rectangle, Renderer, Size, Theme, mouse,
-    widget::canvas::{self, Path, Stroke, Text},
+    widget::canvas::{self, Action, Path, Stroke, Text},
 };
 
-use crate::{analytics::HourlyComparison, style};
+use super::heatmap::local_offset_seconds;
+use crate::{analytics::HourlyComparison, style::Palette};
 
+/// Change per local hour of day as a diverging bar chart around zero.
 pub struct ComparisonChartWidget<'a> {
     pub data: &'a [HourlyComparison],
     pub cache: &'a canvas::Cache,
+    /// Tooltip of the hovered bar, redrawn when the cursor moves to another
     pub tooltip_cache: &'a canvas::Cache,
 }
 
+const PAD_LEFT: f32 = 45.0;
+const PAD_RIGHT: f32 = 10.0;
+const PAD_TOP: f32 = 10.0;
+const PAD_BOTTOM: f32 = 25.0;
+
+/// Smallest half-range of the y-axis in percentage points.
+const MIN_SCALE: f64 = 5.0;
+
+/// Mean absolute change per local hour of day, across all weekdays.
+///
+/// `offset_seconds` is the local offset from UTC that the comparison's UTC
+/// hours are shifted by. Hours without data in either period are `None`.
+pub fn hourly_deltas(data: &[HourlyComparison], offset_seconds: i64) -> [Option<f64>; 24] {
+    let mut sums = [(0.0, 0usize); 24];
+    for comparison in data {
+        let local_seconds = comparison.hour as i64 * 3600 + offset_seconds;
+        let hour = (local_seconds.rem_euclid(24 * 3600) / 3600) as usize;
+        sums[hour].0 += comparison.absolute_change;
+        sums[hour].1 += 1;
+    }
+    sums.map(|(sum, count)| (count > 0).then(|| sum / count as f64))
+}
+
+/// Local hour of the bar under `point` in a chart of the given size.
+fn bar_at(point: Point, size: Size) -> Option<usize> {
+    let bar_w = (size.width - PAD_LEFT - PAD_RIGHT) / 24.0;
+    if bar_w <= 0.0 || point.x < PAD_LEFT || point.y < PAD_TOP {
+        return None;
+    }
+    let hour = ((point.x - PAD_LEFT) / bar_w).floor() as usize;
+    (hour < 24 && point.y <= size.height - PAD_BOTTOM).then_some(hour)
+}
+
+/// Color of a change, with increases (busier) in red and decreases in green.
+fn change_color(palette: &Palette, change: f64) -> Color {
+    if change > 0.0 {
+        palette.accent_red
+    } else {
+        palette.accent_green
+    }
+}
+
 impl<'a, Message> canvas::Program<Message> for ComparisonChartWidget<'a> {
-    type State = ();
+    /// Hovered local hour
+    type State = Option<usize>;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: &iced::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<Action<Message>> {
+        if let iced::Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) =
+            event
+        {
+            let hovered = cursor
+                .position_in(bounds)
+                .and_then(|point| bar_at(point, bounds.size()));
+            if hovered != *state {
+                *state = hovered;
+                self.tooltip_cache.clear();
+                return Some(Action::request_redraw());
+            }
+        }
+        None
+    }
 
     fn draw(
         &self,
-        _: &Self::State,
+        state: &Self::State,
         renderer: &Renderer,
-        _: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
-        cursor: mouse::Cursor,
+        _cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
-        let pad_left = 50.0;
-        let pad_right = 20.0;
-        let pad_top = 30.0;
-        let pad_bottom = 40.0;
+        let palette = Palette::of(theme);
+        let deltas = hourly_deltas(self.data, local_offset_seconds());
+        let w = bounds.width - PAD_LEFT - PAD_RIGHT;
+        let h = bounds.height - PAD_TOP - PAD_BOTTOM;
+        let bar_w = w / 24.0;
+        let zero_y = PAD_TOP + h / 2.0;
+        let scale = deltas
+            .iter()
+            .flatten()
+            .fold(MIN_SCALE, |max, d| max.max(d.abs()));
+        let to_height = |delta: f64| (delta / scale) as f32 * h / 2.0;
 
-        let chart_width = bounds.width - pad_left - pad_right;
-        let chart_height = bounds.height - pad_top - pad_bottom;
-
-        // Draw the main chart (cached)
         let chart_geo = self.cache.draw(renderer, bounds.size(), |frame| {
-            if self.data.is_empty() {
-                // Draw "Insufficient data" message
+            if deltas.iter().all(Option::is_none) {
                 frame.fill_text(Text {
                     content: "Insufficient data for comparison".to_string(),
                     position: Point::new(bounds.width / 2.0, bounds.height / 2.0),
-                    color: style::TEXT_MUTED,
+                    color: palette.text_muted,
                     size: 16.0.into(),
                     align_x: iced::alignment::Horizontal::Center.into(),
                     align_y: iced::alignment::Vertical::Center,
@@ -46,236 +116,145 @@ impl<'a, Message> canvas::Program<Message> for ComparisonChartWidget<'a> {
                 return;
             }
 
-            // Calculate max value for scaling
-            let max_val = self
-                .data
-                .iter()
-                .map(|d| d.current_avg.max(d.previous_avg))
-                .fold(0.0f64, |a, b| a.max(b))
-                .max(10.0); // Minimum scale of 10%
-
-            let num_bars = self.data.len();
-            let group_width = chart_width / num_bars as f32;
-            let bar_width = (group_width * 0.35).min(20.0);
-            let bar_gap = 2.0;
-
-            // Draw Y-axis labels
-            for i in 0..=4 {
-                let y_val = (max_val / 4.0) * i as f64;
-                let y_pos = pad_top + chart_height - (chart_height * (y_val / max_val) as f32);
-
+            // Y-Axis: -scale, 0 and +scale
+            for factor in [-1.0, 0.0, 1.0] {
+                let y = zero_y - to_height(scale * factor);
+                frame.stroke(
+                    &Path::line(Point::new(PAD_LEFT, y), Point::new(PAD_LEFT + w, y)),
+                    Stroke::default()
+                        .with_color(palette.stroke)
+                        .with_width(if factor == 0.0 { 1.5 } else { 1.0 }),
+                );
                 frame.fill_text(Text {
-                    content: format!("{:.0}%", y_val),
-                    position: Point::new(pad_left - 8.0, y_pos),
-                    color: style::TEXT_MUTED,
+                    content: format!("{:+.0}", scale * factor),
+                    position: Point::new(PAD_LEFT - 5.0, y),
+                    color: palette.text_muted,
                     size: 10.0.into(),
                     align_x: iced::alignment::Horizontal::Right.into(),
                     align_y: iced::alignment::Vertical::Center,
                     ..Default::default()
                 });
-
-                // Grid line
-                let line = Path::line(
-                    Point::new(pad_left, y_pos),
-                    Point::new(bounds.width - pad_right, y_pos),
-                );
-                frame.stroke(
-                    &line,
-                    Stroke::default()
-                        .with_color(Color::from_rgba(1.0, 1.0, 1.0, 0.1))
-                        .with_width(1.0),
-                );
             }
 
-            // Draw bars and X-axis labels
-            let mut last_drawn_hour: Option<u32> = None;
-
-            for (i, comparison) in self.data.iter().enumerate() {
-                let group_x = pad_left + i as f32 * group_width + group_width / 2.0;
-
-                // Previous period bar (muted)
-                let prev_height = (comparison.previous_avg / max_val) as f32 * chart_height;
-                if prev_height > 0.0 {
-                    let prev_bar = Path::rounded_rectangle(
-                        Point::new(group_x - bar_width - bar_gap / 2.0, pad_top + chart_height - prev_height),
-                        Size::new(bar_width, prev_height),
-                        2.0.into(),
+            for (hour, delta) in deltas.iter().enumerate() {
+                let x = PAD_LEFT + hour as f32 * bar_w;
+                if let Some(delta) = *delta {
+                    let bar_h = to_height(delta);
+                    let top = if bar_h > 0.0 { zero_y - bar_h } else { zero_y };
+                    frame.fill(
+                        &Path::rectangle(
+                            Point::new(x + 2.0, top),
+                            Size::new((bar_w - 4.0).max(1.0), bar_h.abs()),
+                        ),
+                        change_color(&palette, delta),
                     );
-                    frame.fill(&prev_bar, Color::from_rgba(0.5, 0.5, 0.6, 0.5));
                 }
 
-                // Current period bar (colored by change)
-                let curr_height = (comparison.current_avg / max_val) as f32 * chart_height;
-                if curr_height > 0.0 {
-                    let color = get_change_color(comparison.percent_change);
-                    let curr_bar = Path::rounded_rectangle(
-                        Point::new(group_x + bar_gap / 2.0, pad_top + chart_height - curr_height),
-                        Size::new(bar_width, curr_height),
-                        2.0.into(),
-                    );
-                    frame.fill(&curr_bar, color);
-                }
-
-                // X-axis labels (show hour, avoid clutter)
-                let should_draw_label = match last_drawn_hour {
-                    None => true,
-                    Some(last) => {
-                        // Draw if hour changed and enough space
-                        comparison.hour != last && (i == 0 || i % 2 == 0 || num_bars < 24)
-                    }
-                };
-
-                if should_draw_label {
+                if hour % 3 == 0 {
                     frame.fill_text(Text {
-                        content: format!("{:02}", comparison.hour),
-                        position: Point::new(group_x, bounds.height - pad_bottom + 15.0),
-                        color: style::TEXT_MUTED,
+                        content: format!("{:02}", hour),
+                        position: Point::new(x + bar_w / 2.0, bounds.height - 10.0),
+                        color: palette.text_muted,
                         size: 10.0.into(),
                         align_x: iced::alignment::Horizontal::Center.into(),
                         align_y: iced::alignment::Vertical::Center,
                         ..Default::default()
                     });
-                    last_drawn_hour = Some(comparison.hour);
                 }
             }
+        });
 
-            // Draw legend
-            let legend_y = 10.0;
-            let legend_x = bounds.width - pad_right - 150.0;
-
-            // Previous period legend
-            let prev_box = Path::rounded_rectangle(
-                Point::new(legend_x, legend_y),
-                Size::new(12.0, 12.0),
-                2.0.into(),
-            );
-            frame.fill(&prev_box, Color::from_rgba(0.5, 0.5, 0.6, 0.5));
-            frame.fill_text(Text {
-                content: "Previous".to_string(),
-                position: Point::new(legend_x + 16.0, legend_y + 6.0),
-                color: style::TEXT_MUTED,
-                size: 10.0.into(),
-                align_y: iced::alignment::Vertical::Center,
-                ..Default::default()
-            });
-
-            // Current period legend
-            let curr_box = Path::rounded_rectangle(
-                Point::new(legend_x + 70.0, legend_y),
-                Size::new(12.0, 12.0),
-                2.0.into(),
+        // Tooltip (cleared by `update` when the hovered bar changes)
+        let tooltip_geo = self.tooltip_cache.draw(renderer, bounds.size(), |frame| {
+            let Some(hour) = *state else {
+                return;
+            };
+            let Some(delta) = deltas[hour] else {
+                return;
+            };
+
+            let content = format!("{:02}:00 — {:+.1} pts", hour, delta);
+            let size = Size::new(content.chars().count() as f32 * 6.5 + 16.0, 24.0);
+            let bar_x = PAD_LEFT + hour as f32 * bar_w;
+            let x = if bar_x + bar_w + 4.0 + size.width > bounds.width {
+                bar_x - 4.0 - size.width
+            } else {
+                bar_x + bar_w + 4.0
+            };
+            let pos = Point::new(x.max(0.0), PAD_TOP);
+
+            let tooltip_bg = Path::rounded_rectangle(pos, size, 4.0.into());
+            frame.fill(&tooltip_bg, palette.card);
+            frame.stroke(
+                &tooltip_bg,
+                Stroke::default().with_color(palette.stroke).with_width(1.0),
             );
-            frame.fill(&curr_box, style::ACCENT_BLUE);
             frame.fill_text(Text {
-                content: "Current".to_string(),
-                position: Point::new(legend_x + 86.0, legend_y + 6.0),
-                color: style::TEXT_MUTED,
-                size: 10.0.into(),
+                content,
+                position: Point::new(pos.x + size.width / 2.0, pos.y + size.height / 2.0),
+                color: change_color(&palette, delta),
+                size: 12.0.into(),
+                align_x: iced::alignment::Horizontal::Center.into(),
                 align_y: iced::alignment::Vertical::Center,
                 ..Default::default()
             });
         });
 
-        // Draw tooltip (dynamic)
-        self.tooltip_cache.clear();
-
-        let tooltip_geo = self.tooltip_cache.draw(renderer, bounds.size(), |frame| {
-            if self.data.is_empty() {
-                return;
-            }
-
-            if let Some(cursor_pos) = cursor.position_in(bounds) {
-                let num_bars = self.data.len();
-                let group_width = chart_width / num_bars as f32;
-
-                // Check if cursor is in chart area
-                if cursor_pos.x > pad_left
-                    && cursor_pos.x < bounds.width - pad_right
-                    && cursor_pos.y > pad_top
-                    && cursor_pos.y < bounds.height - pad_bottom
-                {
-                    let bar_index = ((cursor_pos.x - pad_left) / group_width) as usize;
-
-                    if bar_index < self.data.len() {
-                        let comparison = &self.data[bar_index];
-                        let days = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
-                        let day_name = days.get(comparison.weekday as usize).unwrap_or(&"?");
+        vec![chart_geo, tooltip_geo]
+    }
+}
 
-                        let text = format!(
-                            "{} {:02}:00\nPrev: {:.1}%\nCurr: {:.1}%\nChange: {:+.1}%",
-                            day_name,
-                            comparison.hour,
-                            comparison.previous_avg,
-                            comparison.current_avg,
-                            comparison.percent_change
-                        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison(weekday: i32, hour: i32, baseline: f64, current: f64) -> HourlyComparison {
+        HourlyComparison {
+            weekday,
+            hour,
+            baseline_avg: baseline,
+            current_avg: current,
+            absolute_change: current - baseline,
+            percent_change: (current - baseline) / baseline * 100.0,
+            baseline_samples: 10,
+            current_samples: 10,
+        }
+    }
 
-                        // Calculate tooltip position
-                        let tooltip_width = 100.0;
-                        let tooltip_height = 70.0;
-                        let mut tooltip_x = cursor_pos.x + 15.0;
-                        let mut tooltip_y = cursor_pos.y - tooltip_height - 10.0;
+    #[test]
+    fn test_hourly_deltas_average_across_weekdays() {
+        let data = [
+            comparison(0, 14, 40.0, 50.0),
+            comparison(3, 14, 40.0, 44.0),
+            comparison(5, 9, 30.0, 20.0),
+        ];
 
-                        // Keep tooltip in bounds
-                        if tooltip_x + tooltip_width > bounds.width {
-                            tooltip_x = cursor_pos.x - tooltip_width - 15.0;
-                        }
-                        if tooltip_y < 0.0 {
-                            tooltip_y = cursor_pos.y + 15.0;
-                        }
+        let deltas = hourly_deltas(&data, 0);
 
-                        // Background
-                        let tooltip_bg = Path::rounded_rectangle(
-                            Point::new(tooltip_x, tooltip_y),
-                            Size::new(tooltip_width, tooltip_height),
-                            6.0.into(),
-                        );
-                        frame.fill(&tooltip_bg, style::BG_CARD);
-                        frame.stroke(
-                            &tooltip_bg,
-                            Stroke::default()
-                                .with_color(style::STROKE_DIM)
-                                .with_width(1.0),
-                        );
+        assert_eq!(deltas[14], Some(7.0));
+        assert_eq!(deltas[9], Some(-10.0));
+        assert_eq!(deltas.iter().flatten().count(), 2);
+    }
 
-                        // Text lines
-                        let lines: Vec<&str> = text.lines().collect();
-                        for (i, line) in lines.iter().enumerate() {
-                            let color = if i == 0 {
-                                style::TEXT_BRIGHT
-                            } else if line.contains("Change") {
-                                get_change_color(comparison.percent_change)
-                            } else {
-                                style::TEXT_MUTED
-                            };
+    #[test]
+    fn test_hourly_deltas_shift_to_local_hours() {
+        let data = [comparison(0, 23, 40.0, 50.0)];
 
-                            frame.fill_text(Text {
-                                content: line.to_string(),
-                                position: Point::new(tooltip_x + 8.0, tooltip_y + 12.0 + i as f32 * 14.0),
-                                color,
-                                size: 11.0.into(),
-                                ..Default::default()
-                            });
-                        }
-                    }
-                }
-            }
-        });
+        // UTC+2: 23:00 UTC is 01:00 local
+        let deltas = hourly_deltas(&data, 7200);
 
-        vec![chart_geo, tooltip_geo]
+        assert_eq!(deltas[1], Some(10.0));
+        assert_eq!(deltas[23], None);
     }
-}
 
-/// Get color based on percent change
-fn get_change_color(percent_change: f64) -> Color {
-    if percent_change > 5.0 {
-        // Busier - red/orange tones
-        Color::from_rgb(0.9, 0.4, 0.3)
-    } else if percent_change < -5.0 {
-        // Quieter - green tones
-        Color::from_rgb(0.3, 0.8, 0.5)
-    } else {
-        // Stable - blue
-        style::ACCENT_BLUE
+    #[test]
+    fn test_bar_at_maps_pixels_to_hours() {
+        // 24 bars of 10px between the paddings
+        let size = Size::new(PAD_LEFT + 240.0 + PAD_RIGHT, 100.0);
+
+        assert_eq!(bar_at(Point::new(PAD_LEFT + 5.0, 50.0), size), Some(0));
+        assert_eq!(bar_at(Point::new(PAD_LEFT + 145.0, 50.0), size), Some(14));
+        assert_eq!(bar_at(Point::new(10.0, 50.0), size), None);
+        assert_eq!(bar_at(Point::new(PAD_LEFT + 145.0, 95.0), size), None);
     }
 }
@@ -3,7 +3,7 @@ use iced::{
     widget::canvas::{self, Path, Stroke, Text},
 };
 
-use crate::style;
+use crate::{analytics::BusynessLevel, style::Palette};
 
 pub struct GaugeWidget<'a> {
     pub percentage: f64,
@@ -14,25 +14,35 @@ pub struct GaugeWidget<'a> {
 }
 
 /// Determine the status text based on percentage and thresholds.
+///
+/// The gauge shows three states, so a full gym reads as crowded.
 pub fn get_status_text(percentage: f64, low_threshold: f64, high_threshold: f64) -> &'static str {
-    if percentage < low_threshold {
-        "Not Busy"
-    } else if percentage < high_threshold {
-        "Moderate"
-    } else {
-        "Crowded"
+    match BusynessLevel::from_percentage(percentage, low_threshold, high_threshold) {
+        BusynessLevel::Full => BusynessLevel::Busy.label(),
+        level => level.label(),
     }
 }
 
 /// Determine the color based on percentage and thresholds.
-pub fn get_status_color(percentage: f64, low_threshold: f64, high_threshold: f64) -> Color {
-    if percentage < low_threshold {
-        style::ACCENT_GREEN
-    } else if percentage < high_threshold {
-        style::ACCENT_ORANGE
-    } else {
-        style::ACCENT_RED
-    }
+pub fn get_status_color(
+    palette: &Palette,
+    percentage: f64,
+    low_threshold: f64,
+    high_threshold: f64,
+) -> Color {
+    level_color(
+        palette,
+        BusynessLevel::from_percentage(percentage, low_threshold, high_threshold),
+    )
+}
+
+/// Color for a busyness level.
+pub fn level_color(palette: &Palette, level: BusynessLevel) -> Color {
+    [
+        palette.accent_green,
+        palette.accent_orange,
+        palette.accent_red,
+    ][level.color_index()]
 }
 
 impl<'a, Message> canvas::Program<Message> for GaugeWidget<'a> {
@@ -42,10 +52,11 @@ impl<'a, Message> canvas::Program<Message> for GaugeWidget<'a> {
         &self,
         _: &Self::State,
         renderer: &Renderer,
-        _: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
         _: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
+        let palette = Palette::of(theme);
         let geo = self.cache.draw(renderer, bounds.size(), |frame| {
             let center = frame.center();
             let radius = bounds.width.min(bounds.height) / 2.0 - 10.0;
@@ -63,7 +74,7 @@ impl<'a, Message> canvas::Program<Message> for GaugeWidget<'a> {
             frame.stroke(
                 &bg_arc,
                 Stroke::default()
-                    .with_color(style::STROKE_DIM)
+                    .with_color(palette.stroke)
                     .with_width(width),
             );
 
@@ -71,15 +82,19 @@ impl<'a, Message> canvas::Program<Message> for GaugeWidget<'a> {
                 frame.fill_text(Text {
                     content: "CLOSED".to_string(),
                     position: center,
-                    color: style::TEXT_MUTED,
+                    color: palette.text_muted,
                     size: 32.0.into(),
                     align_x: iced::alignment::Horizontal::Center.into(),
                     align_y: iced::alignment::Vertical::Center,
                     ..Default::default()
                 });
             } else {
-                let color =
-                    get_status_color(self.percentage, self.low_threshold, self.high_threshold);
+                let color = get_status_color(
+                    &palette,
+                    self.percentage,
+                    self.low_threshold,
+                    self.high_threshold,
+                );
 
                 // Foreground Arc
                 let angle = (self.percentage / 100.0 * 360.0).max(1.0);
@@ -103,7 +118,7 @@ impl<'a, Message> canvas::Program<Message> for GaugeWidget<'a> {
                 frame.fill_text(Text {
                     content: format!("{:.0}%", self.percentage),
                     position: center + Vector::new(0.0, -5.0),
-                    color: style::TEXT_BRIGHT,
+                    color: palette.text,
                     size: 48.0.into(),
                     align_x: iced::alignment::Horizontal::Center.into(),
                     align_y: iced::alignment::Vertical::Center,
@@ -183,32 +198,32 @@ mod tests {
 
     #[test]
     fn test_color_below_low_threshold() {
-        let color = get_status_color(20.0, LOW, HIGH);
-        assert_eq!(color, style::ACCENT_GREEN);
+        let color = get_status_color(&Palette::DARK, 20.0, LOW, HIGH);
+        assert_eq!(color, Palette::DARK.accent_green);
     }
 
     #[test]
     fn test_color_at_low_threshold() {
-        let color = get_status_color(40.0, LOW, HIGH);
-        assert_eq!(color, style::ACCENT_ORANGE);
+        let color = get_status_color(&Palette::DARK, 40.0, LOW, HIGH);
+        assert_eq!(color, Palette::DARK.accent_orange);
     }
 
     #[test]
     fn test_color_between_thresholds() {
-        let color = get_status_color(50.0, LOW, HIGH);
-        assert_eq!(color, style::ACCENT_ORANGE);
+        let color = get_status_color(&Palette::DARK, 50.0, LOW, HIGH);
+        assert_eq!(color, Palette::DARK.accent_orange);
     }
 
     #[test]
     fn test_color_at_high_threshold() {
-        let color = get_status_color(75.0, LOW, HIGH);
-        assert_eq!(color, style::ACCENT_RED);
+        let color = get_status_color(&Palette::DARK, 75.0, LOW, HIGH);
+        assert_eq!(color, Palette::DARK.accent_red);
     }
 
     #[test]
     fn test_color_above_high_threshold() {
-        let color = get_status_color(100.0, LOW, HIGH);
-        assert_eq!(color, style::ACCENT_RED);
+        let color = get_status_color(&Palette::DARK, 100.0, LOW, HIGH);
+        assert_eq!(color, Palette::DARK.accent_red);
     }
 
     #[test]
@@ -218,12 +233,12 @@ mod tests {
 
         for &val in &test_values {
             let text = get_status_text(val, LOW, HIGH);
-            let color = get_status_color(val, LOW, HIGH);
+            let color = get_status_color(&Palette::DARK, val, LOW, HIGH);
 
             match text {
-                "Not Busy" => assert_eq!(color, style::ACCENT_GREEN),
-                "Moderate" => assert_eq!(color, style::ACCENT_ORANGE),
-                "Crowded" => assert_eq!(color, style::ACCENT_RED),
+                "Not Busy" => assert_eq!(color, Palette::DARK.accent_green),
+                "Moderate" => assert_eq!(color, Palette::DARK.accent_orange),
+                "Crowded" => assert_eq!(color, Palette::DARK.accent_red),
                 _ => panic!("Unexpected status text: {}", text),
             }
         }
@@ -1,55 +1,186 @@
 use chrono::{Local, Offset};
 use iced::{
     Color, Point, Rectangle, Renderer, Size, Theme, mouse,
-    widget::canvas::{self, Path, Stroke, Text},
+    widget::canvas::{self, Action, Path, Stroke, Text},
 };
 
-use crate::{db::HourlyAverage, style};
+use crate::{
+    analytics::{BusynessLevel, probability_heatmap},
+    db::HourlyAverage,
+    style::Palette,
+    widgets::gauge::level_color,
+};
+
+/// What the heatmap cells represent.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HeatmapMode {
+    /// Average occupancy per slot
+    #[default]
+    Occupancy,
+    /// Probability that the slot is below the goal percentage
+    QuietProbability { goal: f64 },
+}
 
 pub struct HeatmapWidget<'a> {
     pub data: &'a [HourlyAverage],
+    pub mode: HeatmapMode,
+    /// Thresholds classifying occupancy cells, see [`BusynessLevel`]
+    pub low_threshold: f64,
+    pub high_threshold: f64,
     pub cache: &'a canvas::Cache,
-    pub tooltip_cache: &'a canvas::Cache, // Add this
+    /// Tooltip of the hovered cell, redrawn when the cursor moves to another
+    pub tooltip_cache: &'a canvas::Cache,
+}
+
+type ProbabilityGrid = [[Option<f64>; 24]; 7];
+
+const PAD_LEFT: f32 = 30.0;
+const PAD_BOTTOM: f32 = 20.0;
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Local `(weekday, hour)` cell under `point` in a heatmap of the given size.
+pub fn cell_at(point: Point, size: Size) -> Option<(usize, usize)> {
+    let cell_w = (size.width - PAD_LEFT) / 24.0;
+    let cell_h = (size.height - PAD_BOTTOM) / 7.0;
+    if cell_w <= 0.0 || cell_h <= 0.0 || point.x < PAD_LEFT || point.y < 0.0 {
+        return None;
+    }
+
+    let hour = ((point.x - PAD_LEFT) / cell_w).floor() as usize;
+    let weekday = (point.y / cell_h).floor() as usize;
+    (hour < 24 && weekday < 7).then_some((weekday, hour))
+}
+
+/// UTC `(weekday, hour)` slot shown in a local grid cell.
+fn utc_slot(weekday: usize, hour: usize, offset_seconds: i64) -> (i64, i64) {
+    let seconds_per_week = 7 * 24 * 3600;
+    let local_seconds = (weekday as i64 * 24 + hour as i64) * 3600;
+    let utc_seconds = (local_seconds - offset_seconds).rem_euclid(seconds_per_week);
+    ((utc_seconds / 3600) / 24, (utc_seconds / 3600) % 24)
+}
+
+/// Current offset of local time from UTC in seconds.
+pub(crate) fn local_offset_seconds() -> i64 {
+    Local::now().offset().fix().local_minus_utc() as i64
+}
+
+impl HeatmapWidget<'_> {
+    /// Probability grid for the current mode, if it needs one.
+    fn probabilities(&self) -> Option<ProbabilityGrid> {
+        match self.mode {
+            HeatmapMode::Occupancy => None,
+            HeatmapMode::QuietProbability { goal } => Some(probability_heatmap(self.data, goal)),
+        }
+    }
+
+    /// Average of a UTC slot, if there is data for it.
+    fn slot_average(&self, weekday: i64, hour: i64) -> Option<&HourlyAverage> {
+        self.data
+            .iter()
+            .find(|x| x.weekday == weekday as i32 && x.hour == hour as i32)
+    }
+
+    /// Value of a UTC slot as a percentage (occupancy or probability).
+    fn slot_value(
+        &self,
+        probabilities: Option<&ProbabilityGrid>,
+        weekday: i64,
+        hour: i64,
+    ) -> Option<f64> {
+        match probabilities {
+            Some(grid) => grid[weekday as usize][hour as usize].map(|p| p * 100.0),
+            None => self.slot_average(weekday, hour).map(|x| x.avg_percentage),
+        }
+    }
+
+    /// Cell color for a slot value, with "good" always green.
+    ///
+    /// Occupancy cells use the busyness level colors of the gauge.
+    fn cell_color(&self, palette: &Palette, value: Option<f64>) -> Color {
+        match (self.mode, value) {
+            (HeatmapMode::Occupancy, Some(v)) if v != 0.0 => level_color(
+                palette,
+                BusynessLevel::from_percentage(v, self.low_threshold, self.high_threshold),
+            ),
+            (HeatmapMode::QuietProbability { .. }, Some(p)) => calculate_gradient_color(100.0 - p),
+            _ => palette.background,
+        }
+    }
+
+    /// Tooltip text for a local grid cell, e.g. "Mon 14:00 — 42% (n=31)".
+    fn tooltip_text(&self, weekday: usize, hour: usize) -> Option<String> {
+        let (utc_weekday, utc_hour) = utc_slot(weekday, hour, local_offset_seconds());
+        let value = self.slot_value(self.probabilities().as_ref(), utc_weekday, utc_hour)?;
+        let samples = self
+            .slot_average(utc_weekday, utc_hour)
+            .map_or(0, |x| x.sample_count);
+        Some(format!(
+            "{} {:02}:00 — {:.0}% (n={})",
+            DAYS[weekday], hour, value, samples
+        ))
+    }
 }
 
 impl<'a, Message> canvas::Program<Message> for HeatmapWidget<'a> {
-    type State = ();
+    /// Hovered local `(weekday, hour)` cell
+    type State = Option<(usize, usize)>;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: &iced::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<Action<Message>> {
+        if let iced::Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) =
+            event
+        {
+            let hovered = cursor
+                .position_in(bounds)
+                .and_then(|point| cell_at(point, bounds.size()));
+            if hovered != *state {
+                *state = hovered;
+                self.tooltip_cache.clear();
+                return Some(Action::request_redraw());
+            }
+        }
+        None
+    }
 
     fn draw(
         &self,
-        _: &Self::State,
+        state: &Self::State,
         renderer: &Renderer,
-        _: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
-        cursor: mouse::Cursor,
+        _cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
-        let pad_left = 30.0;
-        let pad_bottom = 20.0;
-        let w = bounds.width - pad_left;
-        let h = bounds.height - pad_bottom;
-        let days = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        let palette = Palette::of(theme);
+        let w = bounds.width - PAD_LEFT;
+        let h = bounds.height - PAD_BOTTOM;
         let cell_w = w / 24.0;
         let cell_h = h / 7.0;
 
         // 1. Draw the Heatmap Grid (Cached)
         let grid_geo = self.cache.draw(renderer, bounds.size(), |frame| {
+            let probabilities = self.probabilities();
+
             // Get current offset to map Local Grid -> UTC Data
-            let offset_seconds = Local::now().offset().fix().local_minus_utc();
-            let seconds_per_week = 7 * 24 * 3600;
+            let offset_seconds = local_offset_seconds();
 
-            for (d_idx, day) in days.iter().enumerate() {
+            for (d_idx, day) in DAYS.iter().enumerate() {
                 // Day Label
                 frame.fill_text(Text {
                     content: day.to_string(),
                     position: Point::new(0.0, d_idx as f32 * cell_h + cell_h / 2.0),
-                    color: style::TEXT_MUTED,
+                    color: palette.text_muted,
                     size: 10.0.into(),
                     align_y: iced::alignment::Vertical::Center,
                     ..Default::default()
                 });
 
                 for hour in 0..24 {
-                    let x = pad_left + hour as f32 * cell_w;
+                    let x = PAD_LEFT + hour as f32 * cell_w;
                     let y = d_idx as f32 * cell_h;
 
                     let is_open_hour = if d_idx >= 5 {
@@ -67,30 +198,10 @@ impl<'a, Message> canvas::Program<Message> for HeatmapWidget<'a> {
                     if !is_open_hour {
                         frame.fill(&bg, Color::from_rgba(0.0, 0.0, 0.0, 0.3));
                     } else {
-                        // Calculate UTC indices
-                        let local_seconds = (d_idx as i64 * 24 + hour as i64) * 3600;
-                        let utc_seconds = local_seconds - offset_seconds as i64;
-                        let wrapped_utc = ((utc_seconds % seconds_per_week) + seconds_per_week)
-                            % seconds_per_week;
-
-                        let target_w = (wrapped_utc / 3600) / 24;
-                        let target_h = (wrapped_utc / 3600) % 24;
-
-                        let val = self
-                            .data
-                            .iter()
-                            .find(|x| x.weekday == target_w as i32 && x.hour == target_h as i32)
-                            .map(|x| x.avg_percentage)
-                            .unwrap_or(0.0);
-
-                        // Gradient Logic
-                        let color = if val == 0.0 {
-                            style::BG_DARK
-                        } else {
-                            calculate_gradient_color(val)
-                        };
-
-                        frame.fill(&bg, color);
+                        let (target_w, target_h) = utc_slot(d_idx, hour, offset_seconds);
+                        let val = self.slot_value(probabilities.as_ref(), target_w, target_h);
+
+                        frame.fill(&bg, self.cell_color(&palette, val));
                     }
 
                     // Hour Labels (Bottom)
@@ -98,7 +209,7 @@ impl<'a, Message> canvas::Program<Message> for HeatmapWidget<'a> {
                         frame.fill_text(Text {
                             content: format!("{:02}", hour),
                             position: Point::new(x + cell_w / 2.0, h + 10.0),
-                            color: style::TEXT_MUTED,
+                            color: palette.text_muted,
                             size: 10.0.into(),
                             align_x: iced::alignment::Horizontal::Center.into(),
                             align_y: iced::alignment::Vertical::Center,
@@ -109,65 +220,43 @@ impl<'a, Message> canvas::Program<Message> for HeatmapWidget<'a> {
             }
         });
 
-        // 2. Draw Tooltip (Dynamic - Uses separate cache, cleared every frame)
-        // We use a cache here because creating Geometry directly is not exposed easily.
-        // Clearing it ensures it updates with the cursor.
-        self.tooltip_cache.clear();
-
+        // 2. Draw Tooltip (cleared by `update` when the hovered cell changes)
         let overlay_geo = self.tooltip_cache.draw(renderer, bounds.size(), |frame| {
-            if let Some(cursor_pos) = cursor.position_in(bounds) {
-                // Check if inside grid area
-                if cursor_pos.x > pad_left && cursor_pos.y < h {
-                    let col = ((cursor_pos.x - pad_left) / cell_w).floor() as i64;
-                    let row = (cursor_pos.y / cell_h).floor() as i64;
-
-                    if col >= 0 && col < 24 && row >= 0 && row < 7 {
-                        // Resolve value again for tooltip
-                        let offset_seconds = Local::now().offset().fix().local_minus_utc();
-                        let seconds_per_week = 7 * 24 * 3600;
-
-                        let local_seconds = (row * 24 + col) * 3600;
-                        let utc_seconds = local_seconds - offset_seconds as i64;
-                        let wrapped_utc = ((utc_seconds % seconds_per_week) + seconds_per_week)
-                            % seconds_per_week;
-
-                        let target_w = (wrapped_utc / 3600) / 24;
-                        let target_h = (wrapped_utc / 3600) % 24;
-
-                        let val = self
-                            .data
-                            .iter()
-                            .find(|x| x.weekday == target_w as i32 && x.hour == target_h as i32)
-                            .map(|x| x.avg_percentage);
-
-                        if let Some(v) = val {
-                            let text = format!("{:.1}%", v);
-                            let pos = Point::new(cursor_pos.x + 10.0, cursor_pos.y - 20.0);
-
-                            // Background for tooltip
-                            let tooltip_bg =
-                                Path::rounded_rectangle(pos, Size::new(50.0, 24.0), 4.0.into());
-                            frame.fill(&tooltip_bg, style::BG_CARD);
-                            frame.stroke(
-                                &tooltip_bg,
-                                Stroke::default()
-                                    .with_color(style::STROKE_DIM)
-                                    .with_width(1.0),
-                            );
-
-                            frame.fill_text(Text {
-                                content: text,
-                                position: Point::new(pos.x + 25.0, pos.y + 12.0),
-                                color: style::TEXT_BRIGHT,
-                                size: 12.0.into(),
-                                align_x: iced::alignment::Horizontal::Center.into(),
-                                align_y: iced::alignment::Vertical::Center,
-                                ..Default::default()
-                            });
-                        }
-                    }
-                }
-            }
+            let Some((weekday, hour)) = *state else {
+                return;
+            };
+            let Some(text) = self.tooltip_text(weekday, hour) else {
+                return;
+            };
+
+            // Next to the cell, flipped to its left near the right edge
+            let size = Size::new(text.chars().count() as f32 * 6.5 + 16.0, 24.0);
+            let cell_x = PAD_LEFT + hour as f32 * cell_w;
+            let x = if cell_x + cell_w + 4.0 + size.width > bounds.width {
+                cell_x - 4.0 - size.width
+            } else {
+                cell_x + cell_w + 4.0
+            };
+            let y = (weekday as f32 * cell_h + (cell_h - size.height) / 2.0)
+                .clamp(0.0, (bounds.height - size.height).max(0.0));
+            let pos = Point::new(x.max(0.0), y);
+
+            let tooltip_bg = Path::rounded_rectangle(pos, size, 4.0.into());
+            frame.fill(&tooltip_bg, palette.card);
+            frame.stroke(
+                &tooltip_bg,
+                Stroke::default().with_color(palette.stroke).with_width(1.0),
+            );
+
+            frame.fill_text(Text {
+                content: text,
+                position: Point::new(pos.x + size.width / 2.0, pos.y + size.height / 2.0),
+                color: palette.text,
+                size: 12.0.into(),
+                align_x: iced::alignment::Horizontal::Center.into(),
+                align_y: iced::alignment::Vertical::Center,
+                ..Default::default()
+            });
         });
 
         vec![grid_geo, overlay_geo]
@@ -203,6 +292,65 @@ fn interpolate_color(c1: Color, c2: Color, factor: f32) -> Color {
 mod tests {
     use super::*;
 
+    // ==================== Hit Testing Tests ====================
+
+    /// 24 columns of 10px after the labels, 7 rows of 10px above them.
+    const SIZE: Size = Size::new(PAD_LEFT + 240.0, 70.0 + PAD_BOTTOM);
+
+    #[test]
+    fn test_cell_at_maps_pixels_to_slots() {
+        assert_eq!(cell_at(Point::new(PAD_LEFT + 0.5, 0.5), SIZE), Some((0, 0)));
+        assert_eq!(
+            cell_at(Point::new(PAD_LEFT + 145.0, 35.0), SIZE),
+            Some((3, 14))
+        );
+        assert_eq!(
+            cell_at(Point::new(PAD_LEFT + 239.9, 69.9), SIZE),
+            Some((6, 23))
+        );
+    }
+
+    #[test]
+    fn test_cell_at_ignores_labels_and_margins() {
+        // Day labels on the left, hour labels at the bottom
+        assert_eq!(cell_at(Point::new(10.0, 35.0), SIZE), None);
+        assert_eq!(cell_at(Point::new(PAD_LEFT + 145.0, 75.0), SIZE), None);
+    }
+
+    #[test]
+    fn test_utc_slot_wraps_around_the_week() {
+        // UTC+2: Monday 01:00 local is Sunday 23:00 UTC
+        assert_eq!(utc_slot(0, 1, 7200), (6, 23));
+        assert_eq!(utc_slot(3, 14, 7200), (3, 12));
+        // UTC-5: Sunday 22:00 local is Monday 03:00 UTC
+        assert_eq!(utc_slot(6, 22, -18000), (0, 3));
+    }
+
+    // ==================== cell_color Tests ====================
+
+    #[test]
+    fn test_occupancy_cells_use_busyness_levels() {
+        let cache = canvas::Cache::new();
+        let widget = HeatmapWidget {
+            data: &[],
+            mode: HeatmapMode::Occupancy,
+            low_threshold: 40.0,
+            high_threshold: 75.0,
+            cache: &cache,
+            tooltip_cache: &cache,
+        };
+        let palette = Palette::DARK;
+
+        for value in [10.0, 39.9, 40.0, 74.9, 75.0, 100.0] {
+            let level = BusynessLevel::from_percentage(value, 40.0, 75.0);
+            assert_eq!(
+                widget.cell_color(&palette, Some(value)),
+                level_color(&palette, level)
+            );
+        }
+        assert_eq!(widget.cell_color(&palette, None), palette.background);
+    }
+
     // ==================== interpolate_color Tests ====================
 
     #[test]
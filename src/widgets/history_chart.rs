@@ -1,10 +1,15 @@
 use chrono::{DateTime, Duration as ChronoDuration, Local, Timelike, Utc};
 use iced::{
     Color, Point, Rectangle, Renderer, Size, Theme, mouse,
-    widget::canvas::{self, Action, Frame, LineDash, Path, Stroke, Text},
+    widget::canvas::{self, Action, Frame, LineDash, Path, Stroke, Text, gradient},
 };
+use image::{Rgba, RgbaImage};
 
-use crate::{analytics::midnight_utc, db::OccupancyLog, style};
+use crate::{
+    analytics::{DetailedPrediction, midnight_utc},
+    db::OccupancyLog,
+    style::Palette,
+};
 
 // Interaction event to avoid circular dependency on Message
 #[derive(Debug, Clone, Copy)]
@@ -12,9 +17,40 @@ pub enum Interaction {
     Hovered,
 }
 
+/// A predicted value with its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidencePoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl From<&DetailedPrediction> for ConfidencePoint {
+    fn from(prediction: &DetailedPrediction) -> Self {
+        Self {
+            timestamp: prediction.timestamp,
+            value: prediction.percentage,
+            low: prediction.lower,
+            high: prediction.upper,
+        }
+    }
+}
+
+/// Vertical pixel span `(top, bottom)` of a confidence band in a plot area
+/// starting at `pad_top` with height `h`, clipped to the 0-100% range.
+fn band_y_span(low: f64, high: f64, pad_top: f32, h: f32) -> (f32, f32) {
+    let to_y = |v: f64| pad_top + h - (v.clamp(0.0, 100.0) / 100.0 * f64::from(h)) as f32;
+    (to_y(low.max(high)), to_y(low.min(high)))
+}
+
 pub struct HistoryChart<'a> {
     pub history: &'a [OccupancyLog],
+    /// Moving average drawn over the raw history; empty to hide it
+    pub smoothed: &'a [(DateTime<Utc>, f64)],
     pub predictions: &'a [(DateTime<Utc>, f64)],
+    /// Predictions drawn with a shaded confidence band; empty to hide it
+    pub confidence: &'a [ConfidencePoint],
     pub range_start: DateTime<Utc>,
     pub range_end: DateTime<Utc>,
     pub cache: &'a canvas::Cache,
@@ -42,10 +78,11 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
         &self,
         _: &Self::State,
         renderer: &Renderer,
-        _: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
+        let palette = Palette::of(theme);
         let geo = self.cache.draw(renderer, bounds.size(), |frame| {
             let pad_left = 35.0;
             let pad_bottom = 25.0;
@@ -61,14 +98,12 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                 let line = Path::line(Point::new(pad_left, y), Point::new(pad_left + w, y));
                 frame.stroke(
                     &line,
-                    Stroke::default()
-                        .with_color(style::STROKE_DIM)
-                        .with_width(1.0),
+                    Stroke::default().with_color(palette.stroke).with_width(1.0),
                 );
                 frame.fill_text(Text {
                     content: format!("{:.0}", pct),
                     position: Point::new(pad_left - 5.0, y),
-                    color: style::TEXT_MUTED,
+                    color: palette.text_muted,
                     size: 10.0.into(),
                     align_x: iced::alignment::Horizontal::Right.into(),
                     align_y: iced::alignment::Vertical::Center,
@@ -106,9 +141,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                     let x = pad_left + (offset / dur as f32) * w;
                     frame.stroke(
                         &Path::line(Point::new(x, pad_top), Point::new(x, pad_top + h)),
-                        Stroke::default()
-                            .with_color(style::STROKE_DIM)
-                            .with_width(0.5),
+                        Stroke::default().with_color(palette.stroke).with_width(0.5),
                     );
                     let label = if tick_interval < 86400 {
                         current.with_timezone(&Local).format("%H:%M").to_string()
@@ -118,7 +151,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                     frame.fill_text(Text {
                         content: label,
                         position: Point::new(x, bounds.height - 10.0),
-                        color: style::TEXT_MUTED,
+                        color: palette.text_muted,
                         size: 10.0.into(),
                         align_x: iced::alignment::Horizontal::Center.into(),
                         align_y: iced::alignment::Vertical::Bottom,
@@ -169,11 +202,84 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                 frame.stroke(
                     &line_path,
                     Stroke::default()
-                        .with_color(style::ACCENT_BLUE)
+                        .with_color(palette.accent_blue)
                         .with_width(2.0),
                 );
             }
 
+            // Draw Smoothed Overlay
+            let smoothed: Vec<_> = self
+                .smoothed
+                .iter()
+                .filter(|(d, _)| *d >= self.range_start && *d <= self.range_end)
+                .collect();
+            if let Some((first, rest)) = smoothed.split_first() {
+                let mut builder = canvas::path::Builder::new();
+                builder.move_to(to_pt(first.0, first.1));
+                for (d, v) in rest {
+                    builder.line_to(to_pt(*d, *v));
+                }
+                frame.stroke(
+                    &builder.build(),
+                    Stroke::default().with_color(palette.text).with_width(1.5),
+                );
+            }
+
+            // Draw Confidence Band
+            let band: Vec<_> = self
+                .confidence
+                .iter()
+                .filter(|p| p.timestamp >= self.range_start && p.timestamp <= self.range_end)
+                .collect();
+            if let Some(first) = band.first() {
+                let mut upper = Vec::with_capacity(band.len() + 1);
+                let mut lower = Vec::with_capacity(band.len() + 1);
+                let mut line = Vec::with_capacity(band.len() + 1);
+
+                // Fan out from the last reading, so the band starts where the data ends
+                if let Some((pt, dt)) = last_history_point
+                    && (first.timestamp - dt).num_hours() < 4
+                {
+                    upper.push(pt);
+                    lower.push(pt);
+                    line.push(pt);
+                }
+                for p in &band {
+                    let x = to_pt(p.timestamp, 0.0).x;
+                    let (top, bottom) = band_y_span(p.low, p.high, pad_top, h);
+                    upper.push(Point::new(x, top));
+                    lower.push(Point::new(x, bottom));
+                    line.push(to_pt(p.timestamp, p.value.clamp(0.0, 100.0)));
+                }
+
+                let mut area = canvas::path::Builder::new();
+                area.move_to(upper[0]);
+                for pt in upper.iter().skip(1).chain(lower.iter().rev()) {
+                    area.line_to(*pt);
+                }
+                area.close();
+
+                // Fade out as the forecast reaches further ahead
+                let start_x = upper[0].x;
+                let end_x = upper[upper.len() - 1].x.max(start_x + 1.0);
+                let fade = gradient::Linear::new(Point::new(start_x, 0.0), Point::new(end_x, 0.0))
+                    .add_stop(0.0, palette.accent_cyan.scale_alpha(0.35))
+                    .add_stop(1.0, palette.accent_cyan.scale_alpha(0.08));
+                frame.fill(&area.build(), fade);
+
+                let mut center = canvas::path::Builder::new();
+                center.move_to(line[0]);
+                for pt in &line[1..] {
+                    center.line_to(*pt);
+                }
+                frame.stroke(
+                    &center.build(),
+                    Stroke::default()
+                        .with_color(palette.accent_cyan)
+                        .with_width(1.5),
+                );
+            }
+
             // Draw Predictions
             if !self.predictions.is_empty() {
                 let mut builder = canvas::path::Builder::new();
@@ -200,7 +306,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                             } else {
                                 builder.line_to(pt);
                             }
-                            frame.fill(&Path::circle(pt, 3.0), style::ACCENT_CYAN);
+                            frame.fill(&Path::circle(pt, 3.0), palette.accent_cyan);
                         }
                     }
                 }
@@ -209,7 +315,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                     frame.stroke(
                         &builder.build(),
                         Stroke {
-                            style: style::ACCENT_CYAN.into(),
+                            style: palette.accent_cyan.into(),
                             width: 2.0,
                             line_dash: LineDash {
                                 segments: &[4.0, 6.0],
@@ -255,7 +361,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                             frame.stroke(
                                 &Path::line(Point::new(x, pad_top), Point::new(x, pad_top + h)),
                                 Stroke {
-                                    style: style::TEXT_BRIGHT.into(),
+                                    style: palette.text.into(),
                                     width: 1.0,
                                     line_dash: LineDash {
                                         segments: &[4.0, 4.0],
@@ -264,7 +370,7 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                                     ..Stroke::default()
                                 },
                             );
-                            frame.fill(&Path::circle(Point::new(x, y), 4.0), style::ACCENT_CYAN);
+                            frame.fill(&Path::circle(Point::new(x, y), 4.0), palette.accent_cyan);
 
                             // Tooltip
                             let text_str =
@@ -283,12 +389,12 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
                                     Size::new(box_w, box_h),
                                     4.0.into(),
                                 ),
-                                style::TOOLTIP_BG,
+                                palette.tooltip_bg,
                             );
                             frame.fill_text(Text {
                                 content: text_str,
                                 position: Point::new(box_x + box_w / 2.0, box_y + box_h / 2.0),
-                                color: style::TEXT_BRIGHT,
+                                color: palette.text,
                                 size: 12.0.into(),
                                 align_x: iced::alignment::Horizontal::Center.into(),
                                 align_y: iced::alignment::Vertical::Center,
@@ -303,3 +409,218 @@ impl<'a> canvas::Program<Interaction> for HistoryChart<'a> {
         geometries
     }
 }
+
+/// Off-screen counterpart of [`HistoryChart`] for image export.
+///
+/// Draws the grid, history, smoothed overlay and predictions like the canvas
+/// does, but without text labels since there is no font rasterizer here.
+pub struct HistoryChartImage<'a> {
+    pub history: &'a [OccupancyLog],
+    pub smoothed: &'a [(DateTime<Utc>, f64)],
+    pub predictions: &'a [(DateTime<Utc>, f64)],
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+}
+
+impl HistoryChartImage<'_> {
+    const PAD: f32 = 10.0;
+
+    /// Render the chart into an opaque RGBA image of the given size.
+    pub fn render(&self, palette: &Palette, width: u32, height: u32) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(width, height, to_rgba(palette.card));
+        let w = width as f32 - 2.0 * Self::PAD;
+        let h = height as f32 - 2.0 * Self::PAD;
+        let dur = (self.range_end - self.range_start).num_seconds() as f32;
+        if w <= 0.0 || h <= 0.0 || dur <= 0.0 {
+            return img;
+        }
+
+        let to_pt = |dt: DateTime<Utc>, val: f64| {
+            let offset = dt.signed_duration_since(self.range_start).num_seconds() as f32;
+            let x = Self::PAD + (offset / dur) * w;
+            let y = Self::PAD + h - (val.clamp(0.0, 100.0) as f32 / 100.0 * h);
+            (x, y)
+        };
+
+        // Y-Axis grid
+        for i in 0..=4 {
+            let y = Self::PAD + h - (i as f32 / 4.0 * h);
+            draw_line(
+                &mut img,
+                (Self::PAD, y),
+                (Self::PAD + w, y),
+                palette.stroke,
+                1.0,
+            );
+        }
+
+        // History with area fill
+        let points: Vec<_> = self
+            .history
+            .iter()
+            .filter_map(|l| l.datetime().map(|d| (d, l.percentage)))
+            .filter(|(d, _)| *d >= self.range_start && *d <= self.range_end)
+            .map(|(d, v)| to_pt(d, v))
+            .collect();
+        let fill = palette.accent_blue.scale_alpha(0.1);
+        for pair in points.windows(2) {
+            fill_under(&mut img, pair[0], pair[1], Self::PAD + h, fill);
+        }
+        draw_polyline(&mut img, &points, palette.accent_blue, 2.0);
+
+        // Smoothed overlay
+        let smoothed: Vec<_> = self
+            .smoothed
+            .iter()
+            .filter(|(d, _)| *d >= self.range_start && *d <= self.range_end)
+            .map(|(d, v)| to_pt(*d, *v))
+            .collect();
+        draw_polyline(&mut img, &smoothed, palette.text, 1.5);
+
+        // Predictions as dots
+        for (d, v) in self.predictions {
+            if *d >= self.range_start && *d <= self.range_end {
+                let (x, y) = to_pt(*d, *v);
+                draw_dot(&mut img, x, y, 3.0, palette.accent_cyan);
+            }
+        }
+
+        img
+    }
+}
+
+fn to_rgba(color: Color) -> Rgba<u8> {
+    Rgba(color.into_rgba8())
+}
+
+/// Alpha-blend `color` onto the pixel at (x, y), ignoring points outside.
+fn blend(img: &mut RgbaImage, x: f32, y: f32, color: Color) {
+    let (x, y) = (x.round(), y.round());
+    if x < 0.0 || y < 0.0 || x >= img.width() as f32 || y >= img.height() as f32 {
+        return;
+    }
+    let pixel = img.get_pixel_mut(x as u32, y as u32);
+    let src = color.into_rgba8();
+    let a = color.a;
+    for c in 0..3 {
+        pixel[c] = (src[c] as f32 * a + pixel[c] as f32 * (1.0 - a)).round() as u8;
+    }
+    pixel[3] = 255;
+}
+
+/// Draw an opaque line of roughly `width` pixels by stamping squares along it.
+fn draw_line(img: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Color, width: f32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as u32;
+    let half = (width / 2.0).floor() as i32;
+
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        for ox in -half..=half {
+            for oy in -half..=half {
+                blend(img, x + ox as f32, y + oy as f32, color);
+            }
+        }
+    }
+}
+
+fn draw_polyline(img: &mut RgbaImage, points: &[(f32, f32)], color: Color, width: f32) {
+    if let [only] = points {
+        draw_dot(img, only.0, only.1, width, color);
+    }
+    for pair in points.windows(2) {
+        draw_line(img, pair[0], pair[1], color, width);
+    }
+}
+
+fn draw_dot(img: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Color) {
+    let r = radius.ceil() as i32;
+    for ox in -r..=r {
+        for oy in -r..=r {
+            if (ox * ox + oy * oy) as f32 <= radius * radius {
+                blend(img, cx + ox as f32, cy + oy as f32, color);
+            }
+        }
+    }
+}
+
+/// Fill each pixel column between the segment and `baseline`.
+fn fill_under(img: &mut RgbaImage, from: (f32, f32), to: (f32, f32), baseline: f32, color: Color) {
+    let (start, end) = (from.0.round() as i64, to.0.round() as i64);
+    // Adjacent segments share their end column; fill it only once
+    for x in start..end.max(start + 1) {
+        let t = if end > start {
+            (x - start) as f32 / (end - start) as f32
+        } else {
+            0.0
+        };
+        let top = from.1 + (to.1 - from.1) * t;
+        let mut y = top.round();
+        while y <= baseline {
+            blend(img, x as f32, y, color);
+            y += 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn log(minute: u32, percentage: f64) -> OccupancyLog {
+        OccupancyLog {
+            id: 0,
+            timestamp: Utc
+                .with_ymd_and_hms(2024, 6, 12, 12, minute, 0)
+                .unwrap()
+                .to_rfc3339(),
+            percentage,
+        }
+    }
+
+    fn render(history: &[OccupancyLog], width: u32, height: u32) -> RgbaImage {
+        HistoryChartImage {
+            history,
+            smoothed: &[],
+            predictions: &[],
+            range_start: Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap(),
+            range_end: Utc.with_ymd_and_hms(2024, 6, 12, 13, 0, 0).unwrap(),
+        }
+        .render(&Palette::DARK, width, height)
+    }
+
+    #[test]
+    fn test_band_y_span_maps_percentages_to_pixels() {
+        // 100px plot below a 10px top padding: 0% at y=110, 100% at y=10
+        assert_eq!(band_y_span(20.0, 60.0, 10.0, 100.0), (50.0, 90.0));
+        // Swapped bounds describe the same band
+        assert_eq!(band_y_span(60.0, 20.0, 10.0, 100.0), (50.0, 90.0));
+    }
+
+    #[test]
+    fn test_band_y_span_clips_to_plot_area() {
+        assert_eq!(band_y_span(-15.0, 130.0, 10.0, 100.0), (10.0, 110.0));
+    }
+
+    #[test]
+    fn test_render_has_requested_dimensions() {
+        let history: Vec<_> = (0..60).map(|m| log(m, m as f64)).collect();
+        let img = render(&history, 800, 400);
+
+        assert_eq!(img.dimensions(), (800, 400));
+        let line = to_rgba(Palette::DARK.accent_blue);
+        assert!(img.pixels().any(|p| *p == line));
+    }
+
+    #[test]
+    fn test_render_without_data_is_background_and_grid() {
+        let img = render(&[], 200, 100);
+
+        let line = to_rgba(Palette::DARK.accent_blue);
+        assert!(img.pixels().all(|p| *p != line));
+        assert!(img.pixels().all(|p| p[3] == 255));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod comparison_chart;
 pub mod gauge;
 pub mod heatmap;
 pub mod history_chart;
+pub mod sparkline;
@@ -0,0 +1,173 @@
+use image::{Rgba, RgbaImage};
+
+use super::gauge::level_color;
+use crate::{analytics::BusynessLevel, style::Palette};
+
+/// Tray color for a busyness level.
+///
+/// The tray sits on the OS panel, not the window, so the colors come from the
+/// fixed dark palette regardless of the app theme.
+pub fn level_rgba(level: BusynessLevel) -> Rgba<u8> {
+    let color = level_color(&Palette::DARK, level);
+    Rgba([
+        (color.r * 255.0) as u8,
+        (color.g * 255.0) as u8,
+        (color.b * 255.0) as u8,
+        255,
+    ])
+}
+
+/// Tray color for a reading given the low and high occupancy thresholds.
+pub fn status_rgba(percentage: f64, low_threshold: f64, high_threshold: f64) -> Rgba<u8> {
+    level_rgba(BusynessLevel::from_percentage(
+        percentage,
+        low_threshold,
+        high_threshold,
+    ))
+}
+
+/// Render a filled circle of the given color into a square RGBA image.
+///
+/// Edge pixels are partially transparent so the dot stays round at tray
+/// icon sizes.
+pub fn render_status_dot(size: u32, color: Rgba<u8>) -> RgbaImage {
+    let center = size as f32 / 2.0;
+    let radius = center - 1.0;
+    RgbaImage::from_fn(size, size, |x, y| {
+        let dx = x as f32 + 0.5 - center;
+        let dy = y as f32 + 0.5 - center;
+        let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+        Rgba([
+            color[0],
+            color[1],
+            color[2],
+            (color[3] as f32 * coverage) as u8,
+        ])
+    })
+}
+
+/// Render a sparkline of occupancy percentages into an RGBA image.
+///
+/// Values are plotted left to right across the full width, with 0% at the
+/// bottom and 100% at the top. The background stays transparent so the image
+/// can be used directly as a tray icon.
+pub fn render_sparkline(values: &[f64], width: u32, height: u32, color: Rgba<u8>) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+    if values.is_empty() || width == 0 || height == 0 {
+        return img;
+    }
+
+    let max_x = (width - 1) as f32;
+    let max_y = (height - 1) as f32;
+    let step = if values.len() > 1 {
+        max_x / (values.len() - 1) as f32
+    } else {
+        0.0
+    };
+
+    let points: Vec<(f32, f32)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let x = i as f32 * step;
+            let y = (1.0 - (v.clamp(0.0, 100.0) / 100.0) as f32) * max_y;
+            (x, y)
+        })
+        .collect();
+
+    if let [only] = points.as_slice() {
+        draw_line(&mut img, *only, *only, color);
+    }
+    for pair in points.windows(2) {
+        draw_line(&mut img, pair[0], pair[1], color);
+    }
+
+    img
+}
+
+/// Draw a straight line by sampling one pixel per unit of the longer axis.
+fn draw_line(img: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Rgba<u8>) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as u32;
+
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let x = (from.0 + dx * t).round() as u32;
+        let y = (from.1 + dy * t).round() as u32;
+        img.put_pixel(x, y, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// Row of the first drawn pixel in the given column.
+    fn first_drawn_row(img: &RgbaImage, x: u32) -> Option<u32> {
+        (0..img.height()).find(|&y| img.get_pixel(x, y)[3] > 0)
+    }
+
+    #[test]
+    fn test_empty_series_is_transparent() {
+        let img = render_sparkline(&[], 16, 16, WHITE);
+        assert!(img.pixels().all(|p| p[3] == 0));
+    }
+
+    #[test]
+    fn test_single_value_draws_a_pixel() {
+        let img = render_sparkline(&[50.0], 16, 16, WHITE);
+        assert!(img.pixels().any(|p| p[3] > 0));
+    }
+
+    #[test]
+    fn test_rising_and_falling_series_differ() {
+        let rising = [10.0, 30.0, 50.0, 70.0, 90.0];
+        let falling = [90.0, 70.0, 50.0, 30.0, 10.0];
+
+        let rising_img = render_sparkline(&rising, 32, 32, WHITE);
+        let falling_img = render_sparkline(&falling, 32, 32, WHITE);
+
+        assert_ne!(rising_img.as_raw(), falling_img.as_raw());
+
+        // Rising starts low (near the bottom), falling starts high
+        let rising_start = first_drawn_row(&rising_img, 0).unwrap();
+        let falling_start = first_drawn_row(&falling_img, 0).unwrap();
+        assert!(rising_start > falling_start);
+    }
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        // Must not panic on values outside 0-100
+        let img = render_sparkline(&[-20.0, 150.0], 8, 8, WHITE);
+        assert_eq!(first_drawn_row(&img, 7), Some(0));
+        assert_eq!(first_drawn_row(&img, 0), Some(7));
+    }
+
+    #[test]
+    fn test_status_rgba_follows_thresholds() {
+        let quiet = level_rgba(BusynessLevel::Quiet);
+        let moderate = level_rgba(BusynessLevel::Moderate);
+        let busy = level_rgba(BusynessLevel::Busy);
+
+        assert_ne!(quiet, moderate);
+        assert_ne!(moderate, busy);
+        assert_eq!(status_rgba(10.0, 30.0, 70.0), quiet);
+        assert_eq!(status_rgba(30.0, 30.0, 70.0), moderate);
+        assert_eq!(status_rgba(69.9, 30.0, 70.0), moderate);
+        assert_eq!(status_rgba(70.0, 30.0, 70.0), busy);
+        assert_eq!(status_rgba(100.0, 30.0, 70.0), busy);
+        // Raising the thresholds turns the same reading green
+        assert_eq!(status_rgba(50.0, 60.0, 80.0), quiet);
+    }
+
+    #[test]
+    fn test_status_dot_is_round() {
+        let img = render_status_dot(32, WHITE);
+
+        assert_eq!(img.get_pixel(16, 16)[3], 255);
+        assert_eq!(img.get_pixel(0, 0)[3], 0);
+        assert_eq!(img.get_pixel(31, 31)[3], 0);
+    }
+}
@@ -3,10 +3,15 @@
 //! These tests use wiremock to simulate the gym API responses
 //! and verify correct parsing and error handling.
 
-use hardy_monitor::{api::GymApiClient, config::NetworkConfig};
+use std::collections::HashMap;
+
+use hardy_monitor::{
+    api::{ApiFormat, DEFAULT_USER_AGENT, GymApiClient},
+    config::{GymConfig, GymEntry, NetworkConfig},
+};
 use wiremock::{
     Mock, MockServer, ResponseTemplate,
-    matchers::{method, path},
+    matchers::{header, method, path},
 };
 
 /// Test successful API response parsing.
@@ -31,6 +36,7 @@ async fn test_fetch_occupancy_success() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client =
@@ -69,6 +75,7 @@ async fn test_fetch_occupancy_integer_value() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -98,6 +105,7 @@ async fn test_fetch_occupancy_zero() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -120,6 +128,7 @@ async fn test_fetch_occupancy_server_error() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -147,6 +156,7 @@ async fn test_fetch_occupancy_not_found() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -169,6 +179,7 @@ async fn test_fetch_occupancy_invalid_json() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -197,6 +208,7 @@ async fn test_fetch_occupancy_missing_fields() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -225,6 +237,7 @@ async fn test_fetch_occupancy_timeout() {
     let config = NetworkConfig {
         request_timeout_secs: 1,
         connect_timeout_secs: 1,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -250,6 +263,7 @@ async fn test_api_client_clone_and_concurrent_use() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -292,6 +306,7 @@ async fn test_fetch_occupancy_very_large_percentage() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -322,6 +337,7 @@ async fn test_fetch_occupancy_negative_percentage() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -352,6 +368,7 @@ async fn test_fetch_occupancy_unicode_in_name() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -382,6 +399,7 @@ async fn test_fetch_occupancy_decimal_as_integer() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -414,6 +432,7 @@ async fn test_fetch_occupancy_extra_fields() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -443,6 +462,7 @@ async fn test_fetch_occupancy_whitespace_numval_fails() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -470,6 +490,7 @@ async fn test_fetch_occupancy_rate_limited() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -504,6 +525,7 @@ async fn test_fetch_occupancy_very_small_decimal() {
     let config = NetworkConfig {
         request_timeout_secs: 10,
         connect_timeout_secs: 5,
+        ..Default::default()
     };
 
     let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
@@ -511,3 +533,221 @@ async fn test_fetch_occupancy_very_small_decimal() {
 
     assert!((response.occupancy_percentage().unwrap() - 0.001).abs() < 0.0001);
 }
+
+/// Test that transient 503 responses are retried until one succeeds.
+#[tokio::test]
+async fn test_fetch_occupancy_retries_service_unavailable() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"gym":1,"name":"Test","workload":"30%","numval":"30"}"#),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let config = NetworkConfig {
+        max_retries: 3,
+        retry_base_delay_ms: 10,
+        ..Default::default()
+    };
+
+    let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
+    let response = client
+        .fetch_occupancy()
+        .await
+        .expect("Third attempt should succeed");
+
+    assert_eq!(response.occupancy_percentage().unwrap(), 30.0);
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+}
+
+/// Test that client errors are not retried.
+#[tokio::test]
+async fn test_fetch_occupancy_does_not_retry_not_found() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let config = NetworkConfig {
+        max_retries: 3,
+        retry_base_delay_ms: 10,
+        ..Default::default()
+    };
+
+    let client = GymApiClient::new(mock_server.uri(), &config).unwrap();
+    assert!(client.fetch_occupancy().await.is_err());
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1, "404 should not be retried");
+}
+
+/// Test that two configured gyms get independent clients for their own URLs.
+#[tokio::test]
+async fn test_two_gyms_produce_independent_clients() {
+    let ffb_server = MockServer::start().await;
+    let gap_server = MockServer::start().await;
+
+    for (server, numval) in [(&ffb_server, "20.0"), (&gap_server, "80.0")] {
+        let body = format!(
+            r#"{{"gym": 1, "name": "Test Gym", "workload": "x", "numval": "{}"}}"#,
+            numval
+        );
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    let gym_config = GymConfig {
+        api_url: "http://unused.invalid".to_string(),
+        api_format: ApiFormat::Aidoo,
+        gyms: vec![
+            GymEntry {
+                id: "ffb".to_string(),
+                name: "Fürstenfeldbruck".to_string(),
+                api_url: ffb_server.uri(),
+            },
+            GymEntry {
+                id: "gap".to_string(),
+                name: "Garmisch".to_string(),
+                api_url: gap_server.uri(),
+            },
+        ],
+        stale_detection_count: 30,
+        skip_stale_readings: false,
+        capacity: None,
+    };
+
+    let clients = GymApiClient::for_gyms(&gym_config, &NetworkConfig::default())
+        .expect("Client creation should succeed");
+    assert_eq!(clients.len(), 2);
+
+    let mut results = Vec::new();
+    for (entry, client) in &clients {
+        let response = client
+            .fetch_occupancy()
+            .await
+            .expect("Fetch should succeed");
+        results.push((entry.id.as_str(), response.occupancy_percentage().unwrap()));
+    }
+
+    assert_eq!(results, vec![("ffb", 20.0), ("gap", 80.0)]);
+}
+
+/// Test that a client with a custom parser reads a non-Aidoo response.
+#[tokio::test]
+async fn test_fetch_percentage_with_count_capacity_parser() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(r#"{"count": 45, "capacity": 150}"#),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let client = GymApiClient::new(mock_server.uri(), &NetworkConfig::default())
+        .expect("Client creation should succeed")
+        .with_parser(ApiFormat::CountCapacity.parser());
+
+    let percentage = client
+        .fetch_percentage()
+        .await
+        .expect("Fetch should succeed");
+    assert!((percentage - 30.0).abs() < 1e-9);
+}
+
+/// Test that configured headers and bearer token are sent with the request.
+#[tokio::test]
+async fn test_fetch_occupancy_sends_configured_headers() {
+    let mock_server = MockServer::start().await;
+
+    let body = r#"{"gym": 1, "name": "Test Gym", "workload": "40%", "numval": "40.0"}"#;
+    Mock::given(method("GET"))
+        .and(header("X-Api-Key", "secret-key"))
+        .and(header("Authorization", "Bearer secret-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&mock_server)
+        .await;
+
+    let config = NetworkConfig {
+        headers: HashMap::from([("X-Api-Key".to_string(), "secret-key".to_string())]),
+        bearer_token: Some("secret-token".to_string()),
+        ..Default::default()
+    };
+    let client =
+        GymApiClient::new(mock_server.uri(), &config).expect("Client creation should succeed");
+
+    let response = client
+        .fetch_occupancy()
+        .await
+        .expect("Request with headers should match");
+    assert_eq!(response.occupancy_percentage().unwrap(), 40.0);
+
+    // Without the headers the mock does not match and wiremock answers 404
+    let bare_client = GymApiClient::new(mock_server.uri(), &NetworkConfig::default())
+        .expect("Client creation should succeed");
+    assert!(bare_client.fetch_occupancy().await.is_err());
+}
+
+/// Test that the configured User-Agent is sent with the request.
+#[tokio::test]
+async fn test_fetch_occupancy_sends_user_agent() {
+    let mock_server = MockServer::start().await;
+
+    let body = r#"{"gym": 1, "name": "Test Gym", "workload": "40%", "numval": "40.0"}"#;
+    Mock::given(method("GET"))
+        .and(header("user-agent", "hardy-monitor-test/1.0"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = NetworkConfig {
+        user_agent: Some("hardy-monitor-test/1.0".to_string()),
+        ..Default::default()
+    };
+    let client =
+        GymApiClient::new(mock_server.uri(), &config).expect("Client creation should succeed");
+
+    let response = client
+        .fetch_occupancy()
+        .await
+        .expect("Request with User-Agent should match");
+    assert_eq!(response.occupancy_percentage().unwrap(), 40.0);
+}
+
+/// Test that the default User-Agent identifies the monitor.
+#[tokio::test]
+async fn test_fetch_occupancy_sends_default_user_agent() {
+    let mock_server = MockServer::start().await;
+
+    let body = r#"{"gym": 1, "name": "Test Gym", "workload": "40%", "numval": "40.0"}"#;
+    Mock::given(method("GET"))
+        .and(header("user-agent", DEFAULT_USER_AGENT))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = GymApiClient::new(mock_server.uri(), &NetworkConfig::default())
+        .expect("Client creation should succeed");
+
+    assert!(client.fetch_occupancy().await.is_ok());
+}
@@ -23,11 +23,15 @@ fn create_test_schedule(
         weekday: ScheduleHours {
             open_hour: weekday_open,
             close_hour: weekday_close,
+            windows: None,
         },
         weekend: ScheduleHours {
             open_hour: weekend_open,
             close_hour: weekend_close,
+            windows: None,
         },
+        timezone: "local".into(),
+        ..Default::default()
     };
     GymSchedule::new(&config)
 }
@@ -0,0 +1,100 @@
+//! Integration tests for network notifiers.
+//!
+//! These tests use wiremock to stand in for webhook endpoints
+//! and verify the payload that is sent.
+
+use hardy_monitor::{AsyncNotifier, Notifier, WebhookNotifier, config::NetworkConfig};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{body_partial_json, header, method, path},
+};
+
+/// Test that notify POSTs the title, body and timestamp as JSON.
+#[tokio::test]
+async fn test_webhook_notifier_posts_json() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .and(header("content-type", "application/json"))
+        .and(body_partial_json(serde_json::json!({
+            "title": "Gym is quiet",
+            "body": "Occupancy at 20%",
+        })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let notifier = WebhookNotifier::new(
+        format!("{}/hook", mock_server.uri()),
+        &NetworkConfig::default(),
+    )
+    .unwrap();
+    let result = notifier
+        .notify_async("Gym is quiet", "Occupancy at 20%")
+        .await;
+
+    assert!(
+        result.is_ok(),
+        "Webhook notify should succeed: {:?}",
+        result
+    );
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let payload: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let timestamp = payload["timestamp"]
+        .as_str()
+        .expect("timestamp should be set");
+    assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+}
+
+/// Test that the blocking notify sends the same payload.
+#[tokio::test]
+async fn test_webhook_notifier_blocking_notify() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .and(body_partial_json(serde_json::json!({
+            "title": "Gym is quiet",
+            "body": "Occupancy at 20%",
+        })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let notifier = WebhookNotifier::new(
+        format!("{}/hook", mock_server.uri()),
+        &NetworkConfig::default(),
+    )
+    .unwrap();
+    // The blocking client must not run on the async runtime
+    let result =
+        tokio::task::spawn_blocking(move || notifier.notify("Gym is quiet", "Occupancy at 20%"))
+            .await
+            .unwrap();
+
+    assert!(
+        result.is_ok(),
+        "Webhook notify should succeed: {:?}",
+        result
+    );
+}
+
+/// Test that an error response is reported by notify.
+#[tokio::test]
+async fn test_webhook_notifier_surfaces_http_errors() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let notifier = WebhookNotifier::new(mock_server.uri(), &NetworkConfig::default()).unwrap();
+    let result = notifier.notify_async("Title", "Body").await;
+
+    assert!(result.is_err(), "404 response should fail notify");
+}